@@ -0,0 +1,99 @@
+//! Benchmarks for the per-page hot paths, over pages stored in `benches/pages`
+//! (cat is a large page, happiness a typical one, uncouthly a stub), with the
+//! small label schema in `tests/fixtures/labels.yaml`.
+//!
+//! Run with `cargo bench`; compare against a baseline with
+//! `cargo bench -- --save-baseline main` and `cargo bench -- --baseline main`.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Once;
+use wiktionary_scanner_rust::{
+    count_syllables_from_ipa, extract_morphology, init_form_of, init_ipa_inventory, init_labels, init_pos_map,
+    parse_page, parse_page_borrowed, PageData, WikitextParser,
+};
+
+const PAGES: &[&str] = &["cat", "happiness", "uncouthly"];
+
+const IPA: &[&str] = &["/kæt/", "/ˈhæpinəs/", "/ˌɛn.saɪ.kləˈpiː.di.ə/", "/ʌnˈkuːθli/"];
+
+const TEMPLATE_PARAMS: &str = "en|un-|[[couth|couthe]]|-ly|t1=not|t2={{m|en|known}}|pos3=adverb suffix";
+
+static SCHEMAS: Once = Once::new();
+
+fn init_schemas() {
+    SCHEMAS.call_once(|| {
+        let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        init_pos_map(Some(&manifest_dir.join("../../schema/pos.yaml"))).unwrap();
+        init_labels(Some(&manifest_dir.join("tests/fixtures/labels.yaml"))).unwrap();
+        init_ipa_inventory(Some(&manifest_dir.join("../../schema/ipa.yaml"))).unwrap();
+        init_form_of(Some(&manifest_dir.join("../../schema/form_of.yaml"))).unwrap();
+    });
+}
+
+/// A stored page, escaped the way the XML dump escapes page text
+fn page(title: &str) -> String {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join(format!("benches/pages/{}.wikitext", title));
+    fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn bench_parse_page(c: &mut Criterion) {
+    init_schemas();
+    let mut group = c.benchmark_group("parse_page");
+    for title in PAGES {
+        let text = page(title);
+        group.bench_function(*title, |b| b.iter(|| parse_page(black_box(title), black_box(&text))));
+    }
+    group.finish();
+}
+
+/// The batch pipeline's parse, whose entries borrow the page's shared fields
+fn bench_parse_page_borrowed(c: &mut Criterion) {
+    init_schemas();
+    let mut group = c.benchmark_group("parse_page_borrowed");
+    for title in PAGES {
+        let text = page(title);
+        let mut data = PageData::default();
+        group.bench_function(*title, |b| {
+            b.iter(|| parse_page_borrowed(black_box(title), black_box(&text), &mut data).len())
+        });
+    }
+    group.finish();
+}
+
+fn bench_extract_morphology(c: &mut Criterion) {
+    let mut group = c.benchmark_group("extract_morphology");
+    for title in PAGES {
+        let text = page(title);
+        group.bench_function(*title, |b| b.iter(|| extract_morphology(black_box(&text))));
+    }
+    group.finish();
+}
+
+fn bench_count_syllables(c: &mut Criterion) {
+    c.bench_function("count_syllables_from_ipa", |b| {
+        b.iter(|| IPA.iter().map(|ipa| count_syllables_from_ipa(black_box(ipa))).sum::<usize>())
+    });
+}
+
+fn bench_wikitext_parser(c: &mut Criterion) {
+    c.bench_function("WikitextParser::parse_params", |b| {
+        b.iter(|| WikitextParser::new(black_box(TEMPLATE_PARAMS)).parse_params())
+    });
+}
+
+criterion_group!(
+    benches,
+    bench_parse_page,
+    bench_parse_page_borrowed,
+    bench_extract_morphology,
+    bench_count_syllables,
+    bench_wikitext_parser
+);
+criterion_main!(benches);
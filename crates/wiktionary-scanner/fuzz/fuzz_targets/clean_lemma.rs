@@ -0,0 +1,9 @@
+//! Raw lemma parameters of inflection templates ("[[cat#Noun|cats]]")
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wiktionary_scanner_rust::clean_lemma;
+
+fuzz_target!(|raw: &str| {
+    let _ = clean_lemma(raw);
+});
@@ -0,0 +1,9 @@
+//! Etymology sections of whole pages
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wiktionary_scanner_rust::extract_morphology;
+
+fuzz_target!(|text: &str| {
+    let _ = extract_morphology(text);
+});
@@ -0,0 +1,9 @@
+//! Template parameters, as found between a template's braces on a user-edited page
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wiktionary_scanner_rust::WikitextParser;
+
+fuzz_target!(|params: &str| {
+    let _ = WikitextParser::new(params).parse_params();
+});
@@ -0,0 +1,99 @@
+#!/usr/bin/env python3
+"""
+Seed the fuzz corpora with real wikitext from a Wiktionary dump.
+
+Writes one file per seed, named by its SHA-1 like cargo-fuzz does:
+- fuzz/corpus/extract_morphology: English sections of pages
+- fuzz/corpus/parse_params: the inside of each template on those pages
+- fuzz/corpus/clean_lemma: lemma parameters of {{... of|en|...}} templates
+
+Usage:
+    python3 scripts/fuzz_seeds.py enwiktionary-latest-pages-articles.xml.bz2 --pages 2000
+    cargo +nightly fuzz run extract_morphology
+"""
+
+import argparse
+import bz2
+import hashlib
+import html
+import re
+import sys
+from pathlib import Path
+
+PAGE = re.compile(r"<page>.*?</page>", re.S)
+TEXT = re.compile(r"<text[^>]*>(.*?)</text>", re.S)
+NAMESPACE = re.compile(r"<ns>(\d+)</ns>")
+ENGLISH = re.compile(r"^==English==\n(.*?)(?=^==[^=]|\Z)", re.S | re.M)
+# Innermost templates only, which is enough for seeds
+TEMPLATE = re.compile(r"\{\{([^{}]*)\}\}")
+FORM_OF = re.compile(r"^[^|]* of\|en\|([^|]*)")
+
+
+def pages(path: Path):
+    """Yield the text of each mainspace page, unescaped."""
+    opener = bz2.open if path.suffix == ".bz2" else open
+    buffer = ""
+    with opener(path, "rt", encoding="utf-8") as dump:
+        for chunk in iter(lambda: dump.read(1 << 20), ""):
+            buffer += chunk
+            end = 0
+            for match in PAGE.finditer(buffer):
+                end = match.end()
+                page = match.group(0)
+                namespace = NAMESPACE.search(page)
+                text = TEXT.search(page)
+                if text and (namespace is None or namespace.group(1) == "0"):
+                    yield html.unescape(text.group(1))
+            buffer = buffer[end:]
+
+
+def write_seed(directory: Path, seed: str) -> bool:
+    data = seed.encode("utf-8")
+    path = directory / hashlib.sha1(data).hexdigest()
+    if path.exists():
+        return False
+    path.write_bytes(data)
+    return True
+
+
+def main() -> int:
+    parser = argparse.ArgumentParser(description=__doc__.split("\n\n")[0])
+    parser.add_argument("dump", type=Path, help="Wiktionary dump (.xml or .xml.bz2)")
+    parser.add_argument("--pages", type=int, default=1000, help="English pages to take seeds from")
+    parser.add_argument(
+        "--corpus",
+        type=Path,
+        default=Path(__file__).resolve().parent.parent / "fuzz" / "corpus",
+        help="Corpus root (default: fuzz/corpus)",
+    )
+    args = parser.parse_args()
+
+    corpora = {name: args.corpus / name for name in ("extract_morphology", "parse_params", "clean_lemma")}
+    for directory in corpora.values():
+        directory.mkdir(parents=True, exist_ok=True)
+
+    counts = dict.fromkeys(corpora, 0)
+    taken = 0
+    for text in pages(args.dump):
+        english = ENGLISH.search(text)
+        if english is None:
+            continue
+        section = english.group(1)
+        counts["extract_morphology"] += write_seed(corpora["extract_morphology"], section)
+        for template in TEMPLATE.finditer(section):
+            inner = template.group(1)
+            counts["parse_params"] += write_seed(corpora["parse_params"], inner)
+            lemma = FORM_OF.match(inner)
+            if lemma:
+                counts["clean_lemma"] += write_seed(corpora["clean_lemma"], lemma.group(1))
+        taken += 1
+        if taken >= args.pages:
+            break
+
+    for name, count in counts.items():
+        print(f"{name}: {count} new seeds")
+    return 0
+
+
+if __name__ == "__main__":
+    sys.exit(main())
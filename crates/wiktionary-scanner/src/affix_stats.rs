@@ -0,0 +1,188 @@
+//! Affix productivity report over scanner output.
+//!
+//! Reads the JSONL written by a scan, and for every prefix, suffix and
+//! interfix in the morphology fields counts the distinct words formed with
+//! it. Senses of the same word share morphology, so each word is counted
+//! once per affix.
+
+use crate::open_input;
+
+use clap::Args as ClapArgs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(ClapArgs, Debug)]
+pub struct AffixStatsArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Number of affixes to list per kind (0 = all)
+    #[arg(long, default_value_t = 50)]
+    pub top: usize,
+
+    /// Example words to show per affix
+    #[arg(long, default_value_t = 5)]
+    pub examples: usize,
+
+    /// Write one JSON object per affix instead of a text report
+    #[arg(long)]
+    pub json: bool,
+}
+
+/// Only the fields the report needs
+#[derive(Deserialize)]
+struct MorphologyRecord {
+    id: String,
+    morphology: Option<AffixFields>,
+}
+
+#[derive(Deserialize)]
+struct AffixFields {
+    #[serde(default)]
+    prefixes: Vec<String>,
+    #[serde(default)]
+    suffixes: Vec<String>,
+    #[serde(default)]
+    interfixes: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AffixCount {
+    pub kind: &'static str,
+    pub affix: String,
+    pub words: usize,
+    pub examples: Vec<String>,
+}
+
+/// Distinct words per (kind, affix); BTreeSet keeps examples alphabetical
+pub fn collect_affix_words<R: BufRead>(reader: R) -> io::Result<HashMap<(&'static str, String), BTreeSet<String>>> {
+    let mut words: HashMap<(&'static str, String), BTreeSet<String>> = HashMap::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: MorphologyRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let Some(morphology) = record.morphology else {
+            continue;
+        };
+
+        let affixes = [
+            ("prefix", morphology.prefixes),
+            ("suffix", morphology.suffixes),
+            ("interfix", morphology.interfixes),
+        ];
+        for (kind, list) in affixes {
+            for affix in list {
+                words.entry((kind, affix)).or_default().insert(record.id.clone());
+            }
+        }
+    }
+
+    Ok(words)
+}
+
+/// Rank affixes of each kind by the number of distinct words, most productive first
+pub fn rank_affixes(
+    words: HashMap<(&'static str, String), BTreeSet<String>>,
+    top: usize,
+    examples: usize,
+) -> Vec<AffixCount> {
+    let mut counts: Vec<AffixCount> = words
+        .into_iter()
+        .map(|((kind, affix), words)| AffixCount {
+            kind,
+            affix,
+            words: words.len(),
+            examples: words.into_iter().take(examples).collect(),
+        })
+        .collect();
+
+    let kind_order = |kind: &str| match kind {
+        "prefix" => 0,
+        "suffix" => 1,
+        _ => 2,
+    };
+    counts.sort_by(|a, b| {
+        kind_order(a.kind)
+            .cmp(&kind_order(b.kind))
+            .then(b.words.cmp(&a.words))
+            .then_with(|| a.affix.cmp(&b.affix))
+    });
+
+    if top > 0 {
+        let mut seen: HashMap<&'static str, usize> = HashMap::new();
+        counts.retain(|count| {
+            let n = seen.entry(count.kind).or_default();
+            *n += 1;
+            *n <= top
+        });
+    }
+
+    counts
+}
+
+pub fn run_affix_stats(args: &AffixStatsArgs) -> io::Result<()> {
+    let words = collect_affix_words(open_input(&args.input)?)?;
+    let counts = rank_affixes(words, args.top, args.examples);
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    if args.json {
+        for count in &counts {
+            writeln!(out, "{}", serde_json::to_string(count)?)?;
+        }
+        return Ok(());
+    }
+
+    let mut current_kind = "";
+    for count in &counts {
+        if count.kind != current_kind {
+            if !current_kind.is_empty() {
+                writeln!(out)?;
+            }
+            current_kind = count.kind;
+            writeln!(out, "{}es", count.kind)?;
+            writeln!(out, "{}", "=".repeat(60))?;
+        }
+        writeln!(out, "{:>8}  {:<16} {}", count.words, count.affix, count.examples.join(", "))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_distinct_words_per_affix() {
+        let jsonl = concat!(
+            r#"{"id":"unhappy","pos":"ADJ","wc":1,"morphology":{"prefixes":["un-"],"suffixes":[]}}"#, "\n",
+            r#"{"id":"unhappy","pos":"ADJ","wc":1,"morphology":{"prefixes":["un-"],"suffixes":[]}}"#, "\n",
+            r#"{"id":"undo","pos":"VRB","wc":1,"morphology":{"prefixes":["un-"],"suffixes":[]}}"#, "\n",
+            r#"{"id":"redo","pos":"VRB","wc":1,"morphology":{"prefixes":["re-"],"suffixes":[]}}"#, "\n",
+            r#"{"id":"kindness","pos":"NOU","wc":1,"morphology":{"prefixes":[],"suffixes":["-ness"]}}"#, "\n",
+            r#"{"id":"cat","pos":"NOU","wc":1}"#, "\n",
+        );
+        let words = collect_affix_words(jsonl.as_bytes()).unwrap();
+        let counts = rank_affixes(words, 0, 1);
+
+        let summary: Vec<(&str, &str, usize, Vec<String>)> = counts
+            .iter()
+            .map(|c| (c.kind, c.affix.as_str(), c.words, c.examples.clone()))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("prefix", "un-", 2, vec!["undo".to_string()]),
+                ("prefix", "re-", 1, vec!["redo".to_string()]),
+                ("suffix", "-ness", 1, vec!["kindness".to_string()]),
+            ]
+        );
+    }
+}
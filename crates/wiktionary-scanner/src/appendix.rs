@@ -0,0 +1,243 @@
+//! Opt-in extraction of English appendix pages.
+//!
+//! The main scan drops the Appendix namespace, but `Appendix:Glossary*` and
+//! `Appendix:English ...` pages hold supplementary terms (definition lists)
+//! and irregular form tables. With `--appendix-out` a second pass over the
+//! input collects them into a JSONL sidecar, one record per glossary term or
+//! table row.
+
+use crate::{create_output, open_inputs, scan_pages, unescape_xml, TEXT_PATTERN, TITLE_PATTERN};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::Serialize;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+lazy_static! {
+    // Link templates whose displayed text is the linked term: {{l|en|word}}, {{m|en|word}}
+    static ref LINK_TEMPLATE: Regex = Regex::new(r"\{\{(?:l|m|l-self|ll)\|[a-z-]+\|([^}|]+)[^}]*\}\}").unwrap();
+    // Glossary term templates: {{term|word}}, {{glossary|word}}
+    static ref TERM_TEMPLATE: Regex = Regex::new(r"\{\{(?:term|glossary|w)\|([^}|]+)[^}]*\}\}").unwrap();
+    static ref OTHER_TEMPLATE: Regex = Regex::new(r"\{\{[^{}]*\}\}").unwrap();
+    static ref REF_TAG: Regex = Regex::new(r"(?s)<ref[^>/]*>.*?</ref>|<ref[^>]*/>").unwrap();
+    static ref HTML_TAG: Regex = Regex::new(r"<[^>]+>").unwrap();
+    static ref LINK: Regex = Regex::new(r"\[\[(?:[^\]|]*\|)?([^\]]+)\]\]").unwrap();
+    static ref EMPHASIS: Regex = Regex::new(r"'{2,}").unwrap();
+}
+
+/// One item found on an appendix page
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppendixRecord {
+    /// A `; term` / `: definition` pair from a glossary
+    Term {
+        page: String,
+        term: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        definition: Option<String>,
+    },
+    /// One row of a wikitable, e.g. base / past / past participle
+    Forms { page: String, forms: Vec<String> },
+}
+
+pub fn is_wanted_appendix(title: &str) -> bool {
+    title.starts_with("Appendix:Glossary") || title.starts_with("Appendix:English ")
+}
+
+/// Replace link and term templates with the term they display, leaving
+/// other templates in place
+pub fn inline_link_templates(text: &str) -> String {
+    let text = LINK_TEMPLATE.replace_all(text, "$1");
+    TERM_TEMPLATE.replace_all(&text, "$1").into_owned()
+}
+
+/// Reduce a line of wikitext to its plain text
+pub fn clean_wikitext(text: &str) -> String {
+    let text = REF_TAG.replace_all(text, "");
+    let text = LINK_TEMPLATE.replace_all(&text, "$1");
+    let text = TERM_TEMPLATE.replace_all(&text, "$1");
+    let text = OTHER_TEMPLATE.replace_all(&text, "");
+    let text = LINK.replace_all(&text, "$1");
+    let text = HTML_TAG.replace_all(&text, "");
+    let text = EMPHASIS.replace_all(&text, "");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Split a table line into cells, dropping any `attr="..." |` prefix
+fn table_cells(line: &str) -> Vec<String> {
+    line.split("||")
+        .map(|cell| {
+            // An attribute prefix is a lone '|' outside of links and templates
+            let mut depth = 0i32;
+            let mut content_start = 0;
+            let bytes = cell.as_bytes();
+            for (i, &b) in bytes.iter().enumerate() {
+                match b {
+                    b'[' | b'{' => depth += 1,
+                    b']' | b'}' => depth -= 1,
+                    b'|' if depth == 0 => content_start = i + 1,
+                    _ => {}
+                }
+            }
+            clean_wikitext(&cell[content_start..])
+        })
+        .collect()
+}
+
+/// Extract glossary terms and table rows from one appendix page
+pub fn extract_appendix_records(title: &str, text: &str) -> Vec<AppendixRecord> {
+    let text = unescape_xml(text);
+    let mut records = Vec::new();
+    let mut in_table = false;
+    let mut row: Vec<String> = Vec::new();
+
+    let flush_row = |row: &mut Vec<String>, records: &mut Vec<AppendixRecord>| {
+        let forms: Vec<String> = row.drain(..).filter(|cell| !cell.is_empty()).collect();
+        if forms.len() >= 2 {
+            records.push(AppendixRecord::Forms { page: title.to_string(), forms });
+        }
+    };
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if line.starts_with("{|") {
+            in_table = true;
+            continue;
+        }
+        if in_table {
+            if line.starts_with("|}") {
+                flush_row(&mut row, &mut records);
+                in_table = false;
+            } else if line.starts_with("|-") {
+                flush_row(&mut row, &mut records);
+            } else if line.starts_with("|+") || line.starts_with('!') {
+                // Captions and header cells are not forms
+            } else if let Some(cells) = line.strip_prefix('|') {
+                row.extend(table_cells(cells));
+            }
+            continue;
+        }
+
+        if let Some(term) = line.strip_prefix(';') {
+            // "; term : definition" is also valid definition-list syntax
+            let (term, inline_def) = match term.split_once(" : ") {
+                Some((term, def)) => (term, Some(def)),
+                None => (term, None),
+            };
+            let term = clean_wikitext(term);
+            if !term.is_empty() {
+                records.push(AppendixRecord::Term {
+                    page: title.to_string(),
+                    term,
+                    definition: inline_def.map(clean_wikitext).filter(|d| !d.is_empty()),
+                });
+            }
+        } else if let Some(definition) = line.strip_prefix(':') {
+            // Attach the first definition line to the preceding term
+            if let Some(AppendixRecord::Term { definition: slot @ None, .. }) = records.last_mut() {
+                let definition = clean_wikitext(definition.trim_start_matches(':'));
+                if !definition.is_empty() {
+                    *slot = Some(definition);
+                }
+            }
+        }
+    }
+
+    records
+}
+
+/// Scan the inputs for wanted appendix pages and write their records as JSONL.
+/// Returns the number of pages and records written.
+pub fn run_appendix_pass(inputs: &[PathBuf], output: &Path, force: bool) -> io::Result<(usize, usize)> {
+    let (output, output_file) = create_output(output, force)?;
+    let mut writer = BufWriter::with_capacity(256 * 1024, output);
+    let mut pages = 0;
+    let mut written = 0;
+    let mut result = Ok(());
+
+    scan_pages(open_inputs(inputs)?, |page_xml| {
+        let title = match TITLE_PATTERN.captures(&page_xml) {
+            Some(cap) => unescape_xml(&cap[1]),
+            None => return true,
+        };
+        if !is_wanted_appendix(&title) {
+            return true;
+        }
+        let Some(text) = TEXT_PATTERN.captures(&page_xml).map(|cap| cap[1].to_string()) else {
+            return true;
+        };
+
+        pages += 1;
+        for record in extract_appendix_records(&title, &text) {
+            let line = serde_json::to_string(&record).map_err(io::Error::from);
+            if let Err(e) = line.and_then(|line| writeln!(writer, "{}", line)) {
+                result = Err(e);
+                return false;
+            }
+            written += 1;
+        }
+        true
+    })?;
+
+    result?;
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((pages, written))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn glossary_terms_and_form_tables() {
+        let text = "\
+==A==
+{{anchor|abbreviation}}
+; {{term|abbreviation}}
+: A shortened form of a [[word]] or [[phrase|phrases]].
+; '''acronym''' : An abbreviation pronounced as a word.
+{| class=&quot;wikitable&quot;
+! Base form !! Past simple !! Past participle
+|-
+| {{l|en|arise}} || {{l|en|arose}} || {{l|en|arisen}}
+|-
+| style=&quot;color:red&quot; | [[awake]]
+| [[awoke]]
+| [[awoken]]
+|}";
+        let records = extract_appendix_records("Appendix:Glossary", text);
+        let page = || "Appendix:Glossary".to_string();
+        let forms = |f: &[&str]| AppendixRecord::Forms {
+            page: page(),
+            forms: f.iter().map(|s| s.to_string()).collect(),
+        };
+        assert_eq!(
+            records,
+            vec![
+                AppendixRecord::Term {
+                    page: page(),
+                    term: "abbreviation".to_string(),
+                    definition: Some("A shortened form of a word or phrases.".to_string()),
+                },
+                AppendixRecord::Term {
+                    page: page(),
+                    term: "acronym".to_string(),
+                    definition: Some("An abbreviation pronounced as a word.".to_string()),
+                },
+                forms(&["arise", "arose", "arisen"]),
+                forms(&["awake", "awoke", "awoken"]),
+            ]
+        );
+    }
+
+    #[test]
+    fn only_glossary_and_english_appendices() {
+        assert!(is_wanted_appendix("Appendix:Glossary of chess"));
+        assert!(is_wanted_appendix("Appendix:English irregular verbs"));
+        assert!(!is_wanted_appendix("Appendix:Latin first declension"));
+    }
+}
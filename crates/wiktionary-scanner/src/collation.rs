@@ -0,0 +1,105 @@
+//! Dictionary-order sort keys.
+//!
+//! `sort_key` follows the Unicode Collation Algorithm's levels with the
+//! "shifted" treatment of punctuation, for English (root) order:
+//!
+//! 1. base letters and digits, lowercased, with æ → ae and the like;
+//!    spaces, apostrophes, hyphens and other punctuation are ignored
+//! 2. diacritics (cote < coté < côte)
+//! 3. case, lowercase first (polish < Polish)
+//! 4. the ignored punctuation (co-op < coop)
+//!
+//! Each level is written as printable ASCII and the levels are joined with
+//! spaces, which sort below everything in the first three levels, so
+//! comparing keys as byte strings gives dictionary order ("polish  1 ~~~~~~"
+//! for "Polish"). Keys are only for comparing; the last level can hold
+//! spaces of its own. Letters of other scripts keep their code point order
+//! after the Latin ones.
+
+use crate::normalize::{expand_letter, fold_ascii};
+
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Secondary weight of a letter without diacritics
+const PLAIN: u8 = 0;
+/// Secondary weight of a letter like ø that is spelled with another base letter
+const VARIANT: u8 = 0xff;
+
+pub fn sort_key(word: &str) -> String {
+    let mut primary = String::with_capacity(word.len());
+    let mut secondary: Vec<u8> = Vec::new();
+    let mut tertiary = String::new();
+    let mut quaternary = String::new();
+
+    for ch in word.nfd() {
+        if is_combining_mark(ch) {
+            if let Some(weight) = secondary.last_mut() {
+                *weight = (*weight).max(mark_weight(ch));
+            }
+            continue;
+        }
+        if !ch.is_alphanumeric() {
+            quaternary.push(variable_weight(ch));
+            continue;
+        }
+
+        let case = if ch.is_uppercase() { '1' } else { '0' };
+        let expansion = expand_letter(ch);
+        let base: String = match expansion {
+            Some(spelling) => spelling.to_lowercase(),
+            None => ch.to_lowercase().collect(),
+        };
+        for (i, base_ch) in base.chars().enumerate() {
+            primary.push(base_ch);
+            secondary.push(if expansion.is_some() && i == 0 { VARIANT } else { PLAIN });
+            tertiary.push(case);
+        }
+        quaternary.push('~');
+    }
+
+    // Trailing lowest weights can go, as keys of equal primaries have equal lengths
+    let secondary: String = secondary.iter().map(|weight| format!("{:02x}", weight)).collect();
+    let secondary = secondary.trim_end_matches('0');
+    let tertiary = tertiary.trim_end_matches('0');
+    format!("{} {} {} {}", primary, secondary, tertiary, quaternary)
+}
+
+/// Combining diacritics in block order (grave, acute, circumflex, ...), then
+/// any other mark
+fn mark_weight(mark: char) -> u8 {
+    match mark as u32 {
+        cp @ 0x0300..=0x036F => (cp - 0x02FF) as u8,
+        _ => 0xfe,
+    }
+}
+
+/// Punctuation as its ASCII form, so ’ counts as '; everything is below the
+/// '~' that marks a letter
+fn variable_weight(ch: char) -> char {
+    match fold_ascii(&ch.to_string()).and_then(|folded| folded.chars().next()) {
+        Some(folded) if folded != '~' => folded,
+        _ => '}',
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(words: &[&str]) -> Vec<String> {
+        let mut words: Vec<String> = words.iter().map(|w| w.to_string()).collect();
+        words.sort_by_key(|w| sort_key(w));
+        words
+    }
+
+    #[test]
+    fn dictionary_order() {
+        assert_eq!(sorted(&["Polish", "polish", "police"]), vec!["police", "polish", "Polish"]);
+        assert_eq!(sorted(&["côte", "coté", "cote", "côté"]), vec!["cote", "coté", "côte", "côté"]);
+        assert_eq!(sorted(&["coop", "co-op", "cooper"]), vec!["co-op", "coop", "cooper"]);
+        assert_eq!(sorted(&["can't", "canto", "cant"]), vec!["can't", "cant", "canto"]);
+        assert_eq!(sorted(&["aesthete", "æsthete", "aft"]), vec!["aesthete", "æsthete", "aft"]);
+        assert_eq!(sort_key("can\u{2019}t"), sort_key("can't"));
+    }
+}
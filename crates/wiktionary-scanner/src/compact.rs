@@ -0,0 +1,196 @@
+//! Short keys for smaller output, with `--compact-keys`.
+//!
+//! Field names are a large share of every line: a plain sense such as
+//! `{"id":"cat","pos":"NOU","wc":1,"nsyll":1,"categories":[...]}` spends about
+//! as many bytes on keys as on values. With `--compact-keys` every key is
+//! written as the one- or two-letter code in `KEYS` instead, and nothing else
+//! changes, so a reader that maps the codes back gets the usual output.
+//!
+//! Keys are rewritten on each serialized line: the rewriter walks the JSON
+//! and replaces the strings in key position, leaving values alone. Every
+//! object in the output is a struct, so each key is a field name.
+
+use lazy_static::lazy_static;
+use once_cell::sync::OnceCell;
+use std::collections::HashMap;
+use std::io::{self, Write};
+
+static COMPACT_KEYS: OnceCell<bool> = OnceCell::new();
+
+/// Field names and their compact keys. A name maps to the same key in every
+/// struct it appears in (`term` in `calque_of` and in `nyms`).
+pub const KEYS: &[(&str, &str)] = &[
+    // Entry, and the word in the nested shape
+    ("id", "id"),
+    ("ascii", "as"),
+    ("sort_key", "sk"),
+    ("pos", "p"),
+    ("pos_source", "ps"),
+    ("wc", "wc"),
+    ("page_id", "pi"),
+    ("rev_timestamp", "rt"),
+    ("is_abbreviation", "ab"),
+    ("is_heteronym", "ht"),
+    ("is_inflected", "in"),
+    ("is_neologism", "ne"),
+    ("is_phrase", "ph"),
+    ("is_proper_noun", "pn"),
+    ("non_gloss", "ng"),
+    ("uncountable", "un"),
+    ("plural_only", "po"),
+    ("nsyll", "sy"),
+    ("ipa", "ip"),
+    ("phrase_type", "pt"),
+    ("tokens", "tk"),
+    ("grapheme_type", "gt"),
+    ("neologism_date", "nd"),
+    ("attested", "at"),
+    ("lemma", "l"),
+    ("inflection_tags", "it"),
+    ("forms", "f"),
+    ("headword", "hw"),
+    ("variant_type", "vt"),
+    ("variant_of", "vo"),
+    ("relation_type", "ry"),
+    ("relation_target", "ro"),
+    ("calque_of", "cq"),
+    ("doublets", "db"),
+    ("etymology_text", "ex"),
+    ("dialect_tags", "dt"),
+    ("domain_tags", "dm"),
+    ("region_tags", "rg"),
+    ("register_tags", "rs"),
+    ("temporal_tags", "tt"),
+    ("other_labels", "ol"),
+    ("valency", "va"),
+    ("definition", "d"),
+    ("glosses", "gl"),
+    ("qualifiers", "ql"),
+    ("quotations", "q"),
+    ("nyms", "ny"),
+    ("spelling_region", "sr"),
+    ("categories", "c"),
+    ("usage_notes", "us"),
+    ("wikidata_lexeme", "wl"),
+    ("morphology", "m"),
+    ("senses", "s"),
+    // morphology
+    ("type", "ty"),
+    ("base", "b"),
+    ("components", "co"),
+    ("prefixes", "pf"),
+    ("suffixes", "sf"),
+    ("interfixes", "if"),
+    ("foreign_components", "fc"),
+    ("is_compound", "ic"),
+    ("etymology_template", "et"),
+    ("analysis", "an"),
+    ("non_concatenative", "nc"),
+    // calque_of
+    ("lang", "lg"),
+    ("term", "t"),
+    // quotations
+    ("year", "y"),
+    ("author", "au"),
+    ("text", "tx"),
+    // nyms
+    ("relation", "r"),
+    ("sense", "se"),
+];
+
+lazy_static! {
+    static ref COMPACT: HashMap<&'static str, &'static str> = KEYS.iter().copied().collect();
+}
+
+pub fn init_compact_keys(on: bool) {
+    let _ = COMPACT_KEYS.set(on);
+}
+
+pub fn enabled() -> bool {
+    COMPACT_KEYS.get().is_some_and(|&on| on)
+}
+
+/// Write serialized lines with their keys compacted. Keys without a compact
+/// form are written as they are.
+pub fn write_compacted<W: Write>(writer: &mut W, json: &[u8]) -> io::Result<()> {
+    // Whether each open container is an object, and whether the next string is a key
+    let mut objects: Vec<bool> = Vec::new();
+    let mut expect_key = false;
+    // Bytes before `start` are written
+    let mut start = 0;
+    let mut i = 0;
+    while i < json.len() {
+        match json[i] {
+            b'{' => {
+                objects.push(true);
+                expect_key = true;
+            }
+            b'[' => {
+                objects.push(false);
+                expect_key = false;
+            }
+            b'}' | b']' => {
+                objects.pop();
+                expect_key = false;
+            }
+            b',' => expect_key = objects.last() == Some(&true),
+            b'"' => {
+                let end = string_end(json, i + 1);
+                if expect_key {
+                    let key = std::str::from_utf8(&json[i + 1..end]).ok().and_then(|key| COMPACT.get(key));
+                    if let Some(key) = key {
+                        writer.write_all(&json[start..i])?;
+                        write!(writer, "\"{}\"", key)?;
+                        start = end + 1;
+                    }
+                    expect_key = false;
+                }
+                i = end;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    writer.write_all(&json[start..])
+}
+
+/// The index of the quote closing the string whose contents start at `from`
+fn string_end(json: &[u8], mut from: usize) -> usize {
+    while let Some(offset) = memchr::memchr2(b'"', b'\\', &json[from..]) {
+        if json[from + offset] == b'"' {
+            return from + offset;
+        }
+        from += offset + 2;
+    }
+    json.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn compacted(json: &str) -> String {
+        let mut out = Vec::new();
+        write_compacted(&mut out, json.as_bytes()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn keys_are_short_and_distinct() {
+        let names: HashSet<&str> = KEYS.iter().map(|(name, _)| *name).collect();
+        let keys: HashSet<&str> = KEYS.iter().map(|(_, key)| *key).collect();
+        assert_eq!((names.len(), keys.len()), (KEYS.len(), KEYS.len()));
+        assert!(keys.iter().all(|key| (1..=2).contains(&key.len())));
+    }
+
+    #[test]
+    fn only_keys_are_rewritten() {
+        let line = r#"{"id":"text","pos":"NOU","wc":1,"glosses":["lemma","a \"pos\": {x}"],"nyms":[{"relation":"synonym","term":"type"}],"morphology":{"type":"compound","components":["a","b"],"new_field":[]}}"#;
+        assert_eq!(
+            compacted(line),
+            r#"{"id":"text","p":"NOU","wc":1,"gl":["lemma","a \"pos\": {x}"],"ny":[{"r":"synonym","t":"type"}],"m":{"ty":"compound","co":["a","b"],"new_field":[]}}"#
+        );
+        assert_eq!(compacted("{\"senses\":[{\"pos\":\"VRB\"}]}\n{\"id\":\"\\\\\"}\n"), "{\"s\":[{\"p\":\"VRB\"}]}\n{\"id\":\"\\\\\"}\n");
+    }
+}
@@ -0,0 +1,271 @@
+//! TOML run configuration.
+//!
+//! `--config run.toml` can set any top-level CLI option, keyed by the flag's
+//! long name (`batch-size = 500`, `strategy = "sequential"`). Options given on
+//! the command line take precedence over the file, so a checked-in config can
+//! still be tweaked per run. Relative paths are resolved against the config
+//! file's directory.
+
+use crate::normalize::Normalization;
+use crate::shape::OutputShape;
+use crate::sidecar::Sidecar;
+use crate::split::SplitBy;
+use crate::syllables::SyllableRule;
+use crate::{Args, PhraseMode, Strategy};
+
+use clap::parser::ValueSource;
+use clap::ArgMatches;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields, rename_all = "kebab-case")]
+pub struct RunConfig {
+    pub inputs: Option<Vec<PathBuf>>,
+    pub output: Option<PathBuf>,
+    pub strategy: Option<Strategy>,
+    pub threads: Option<usize>,
+    pub batch_size: Option<usize>,
+    pub channel_buffer: Option<usize>,
+    pub autoscale: Option<bool>,
+    pub reorder_limit: Option<usize>,
+    pub chunk_pages: Option<usize>,
+    pub memory_budget: Option<usize>,
+    pub metrics_every: Option<u64>,
+    pub metrics_textfile: Option<PathBuf>,
+    pub limit: Option<usize>,
+    pub word_limit: Option<usize>,
+    pub page_limit: Option<usize>,
+    pub benchmark: Option<bool>,
+    pub quiet: Option<bool>,
+    pub force: Option<bool>,
+    pub verify: Option<bool>,
+    pub syllable_validation: Option<bool>,
+    pub syllable_rules: Option<Vec<SyllableRule>>,
+    pub schema: Option<PathBuf>,
+    pub labels_schema: Option<PathBuf>,
+    pub ipa_schema: Option<PathBuf>,
+    pub form_of_schema: Option<PathBuf>,
+    pub profiles_schema: Option<PathBuf>,
+    pub include_pos: Option<Vec<String>>,
+    pub exclude_pos: Option<Vec<String>>,
+    pub only_register: Option<Vec<String>>,
+    pub exclude_register: Option<Vec<String>>,
+    pub profile: Option<String>,
+    pub min_syllables: Option<usize>,
+    pub max_syllables: Option<usize>,
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    pub phrases: Option<PhraseMode>,
+    pub graphemes: Option<bool>,
+    pub normalize: Option<Normalization>,
+    pub ascii: Option<bool>,
+    pub sort_key: Option<bool>,
+    pub definitions: Option<bool>,
+    pub quotations: Option<bool>,
+    pub usage_notes: Option<bool>,
+    pub etymology_text: Option<bool>,
+    pub headwords: Option<bool>,
+    pub shape: Option<OutputShape>,
+    pub compact_keys: Option<bool>,
+    pub json_array: Option<bool>,
+    pub split_by: Option<SplitBy>,
+    pub wikidata_lexemes: Option<PathBuf>,
+    pub lua: Option<bool>,
+    pub appendix_out: Option<PathBuf>,
+    pub sidecars: Option<Vec<Sidecar>>,
+    pub sidecar_dir: Option<PathBuf>,
+    pub resolve_lemmas: Option<bool>,
+    pub errors_out: Option<PathBuf>,
+    pub titles: Option<PathBuf>,
+    pub title_regex: Option<String>,
+    pub multistream_index: Option<PathBuf>,
+    pub sample: Option<f64>,
+    pub sample_n: Option<usize>,
+    pub sample_seed: Option<u64>,
+    pub verify_checksum: Option<String>,
+}
+
+pub fn load_config(path: &Path) -> Result<RunConfig, String> {
+    let contents = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config file {:?}: {}", path, e))?;
+
+    let mut config: RunConfig = toml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse config TOML: {}", e))?;
+
+    let base = path.parent().unwrap_or(Path::new(""));
+    let resolve = |p: &mut PathBuf| {
+        if p.is_relative() && p.as_os_str() != "-" {
+            *p = base.join(&*p);
+        }
+    };
+    config.inputs.iter_mut().flatten().for_each(resolve);
+    config.output.iter_mut().for_each(resolve);
+    config.schema.iter_mut().for_each(resolve);
+    config.labels_schema.iter_mut().for_each(resolve);
+    config.ipa_schema.iter_mut().for_each(resolve);
+    config.form_of_schema.iter_mut().for_each(resolve);
+    config.profiles_schema.iter_mut().for_each(resolve);
+    config.appendix_out.iter_mut().for_each(resolve);
+    config.sidecar_dir.iter_mut().for_each(resolve);
+    config.errors_out.iter_mut().for_each(resolve);
+    config.metrics_textfile.iter_mut().for_each(resolve);
+    config.titles.iter_mut().for_each(resolve);
+    config.multistream_index.iter_mut().for_each(resolve);
+    config.wikidata_lexemes.iter_mut().for_each(resolve);
+    if let Some(dumpstatus) = config.verify_checksum.as_mut().filter(|d| !d.contains("://")) {
+        let mut path = PathBuf::from(&*dumpstatus);
+        resolve(&mut path);
+        *dumpstatus = path.display().to_string();
+    }
+
+    Ok(config)
+}
+
+/// Fill in every option that was not given explicitly on the command line
+pub fn apply_config(args: &mut Args, matches: &ArgMatches, config: RunConfig) {
+    let from_cli = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+    // Options stored as plain values in Args
+    macro_rules! merge {
+        ($($field:ident),* $(,)?) => {
+            $(
+                if let Some(value) = config.$field {
+                    if !from_cli(stringify!($field)) {
+                        args.$field = value;
+                    }
+                }
+            )*
+        };
+    }
+
+    // Options stored as Option<T> in Args
+    macro_rules! merge_optional {
+        ($($field:ident),* $(,)?) => {
+            $(
+                if config.$field.is_some() && !from_cli(stringify!($field)) {
+                    args.$field = config.$field;
+                }
+            )*
+        };
+    }
+
+    // Positional paths on the command line replace both inputs and output
+    if !from_cli("paths") {
+        if let Some(inputs) = config.inputs {
+            args.inputs = inputs;
+        }
+        if config.output.is_some() {
+            args.output = config.output;
+        }
+    }
+
+    merge!(
+        strategy,
+        threads,
+        batch_size,
+        channel_buffer,
+        autoscale,
+        reorder_limit,
+        chunk_pages,
+        benchmark,
+        quiet,
+        force,
+        verify,
+        sample_seed,
+        syllable_validation,
+        syllable_rules,
+        include_pos,
+        exclude_pos,
+        only_register,
+        exclude_register,
+        phrases,
+        graphemes,
+        normalize,
+        ascii,
+        sort_key,
+        definitions,
+        quotations,
+        usage_notes,
+        etymology_text,
+        headwords,
+        shape,
+        compact_keys,
+        json_array,
+        lua,
+        resolve_lemmas,
+        sidecars,
+    );
+    merge_optional!(
+        memory_budget,
+        metrics_every,
+        metrics_textfile,
+        limit,
+        word_limit,
+        page_limit,
+        schema,
+        labels_schema,
+        ipa_schema,
+        form_of_schema,
+        profiles_schema,
+        profile,
+        min_syllables,
+        max_syllables,
+        min_len,
+        max_len,
+        appendix_out,
+        sidecar_dir,
+        errors_out,
+        titles,
+        title_regex,
+        multistream_index,
+        sample,
+        sample_n,
+        verify_checksum,
+        wikidata_lexemes,
+        split_by,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    fn parse_with_config(argv: &[&str], toml_text: &str) -> Args {
+        let matches = Args::command().get_matches_from(argv);
+        let mut args = Args::from_arg_matches(&matches).unwrap();
+        args.split_paths();
+        let config: RunConfig = toml::from_str(toml_text).unwrap();
+        apply_config(&mut args, &matches, config);
+        args
+    }
+
+    #[test]
+    fn config_fills_unset_options() {
+        let args = parse_with_config(
+            &["scanner", "in.xml", "out.jsonl"],
+            "strategy = \"sequential\"\nthreads = 8\nquiet = true\nlimit = 10\n",
+        );
+        assert_eq!(args.strategy, Strategy::Sequential);
+        assert_eq!(args.threads, 8);
+        assert!(args.quiet);
+        assert_eq!(args.limit, Some(10));
+    }
+
+    #[test]
+    fn command_line_overrides_config() {
+        let args = parse_with_config(
+            &["scanner", "--threads", "2", "in.xml", "out.jsonl"],
+            "threads = 8\ninputs = [\"other.xml\"]\n",
+        );
+        assert_eq!(args.threads, 2);
+        assert_eq!(args.inputs, vec![PathBuf::from("in.xml")]);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<RunConfig>("thread = 8\n").is_err());
+    }
+}
@@ -0,0 +1,250 @@
+//! StarDict and dictd export.
+//!
+//! `export stardict` and `export dictd` turn scanner output into files that
+//! offline dictionary readers (GoldenDict, KOReader, dictd) load directly.
+//! Each word gets one article listing its senses under their parts of
+//! speech, with the text from `definition` or else `glosses`; words without
+//! either are left out.
+//!
+//! StarDict gets `<name>.ifo`, `<name>.idx` and `<name>.dict` (plain text
+//! articles, `sametypesequence=m`); dictd gets `<name>.index` and
+//! `<name>.dict`, with the `00-database-*` headers marking it UTF-8.
+
+use crate::{create_output, open_input, OutputFile};
+
+use clap::Args as ClapArgs;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+const B64_DIGITS: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+#[derive(ClapArgs, Debug)]
+pub struct DictionaryArgs {
+    /// Scanner output JSONL made with --definitions (use - for stdin)
+    pub input: PathBuf,
+
+    /// Output path without extension; the format's files are written next to it
+    pub output: PathBuf,
+
+    /// Dictionary name shown by readers
+    #[arg(long, default_value = "OpenWord Lexicon")]
+    pub name: String,
+}
+
+/// Only the fields the dictionary exports need
+#[derive(Deserialize)]
+struct SenseRecord {
+    id: String,
+    pos: String,
+    definition: Option<String>,
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+/// A word's senses, grouped by part of speech in the order first seen
+#[derive(Default)]
+struct Article {
+    parts: Vec<(String, Vec<String>)>,
+}
+
+impl Article {
+    fn add(&mut self, pos: &str, text: String) {
+        match self.parts.iter_mut().find(|(part, _)| part == pos) {
+            Some((_, senses)) => senses.push(text),
+            None => self.parts.push((pos.to_string(), vec![text])),
+        }
+    }
+
+    /// Plain text: a part-of-speech line, then its numbered senses
+    fn text(&self) -> String {
+        let mut text = String::new();
+        for (pos, senses) in &self.parts {
+            let _ = writeln!(text, "{}", pos_name(pos));
+            for (n, sense) in senses.iter().enumerate() {
+                let _ = writeln!(text, "{}. {}", n + 1, sense);
+            }
+        }
+        text.truncate(text.trim_end().len());
+        text
+    }
+}
+
+fn pos_name(pos: &str) -> &str {
+    match pos {
+        "NOU" => "noun",
+        "NAM" => "proper noun",
+        "VRB" => "verb",
+        "ADJ" => "adjective",
+        "ADV" => "adverb",
+        "PRN" => "pronoun",
+        "DET" => "determiner",
+        "ADP" => "preposition",
+        "CNJ" => "conjunction",
+        "PRT" => "particle",
+        "ITJ" => "interjection",
+        "PHR" => "phrase",
+        "PRV" => "proverb",
+        "PPP" => "prepositional phrase",
+        "IDM" => "idiom",
+        "AFX" => "affix",
+        "NUM" => "numeral",
+        "SYM" => "symbol",
+        "CTN" => "contraction",
+        _ => pos,
+    }
+}
+
+/// Read the input into one article per word
+fn read_articles(input: &Path) -> io::Result<HashMap<String, Article>> {
+    let mut articles: HashMap<String, Article> = HashMap::new();
+    for line in open_input(input)?.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sense: SenseRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let text = match sense.definition {
+            Some(definition) => definition,
+            None if !sense.glosses.is_empty() => sense.glosses.join("; "),
+            None => continue,
+        };
+        articles.entry(sense.id).or_default().add(&sense.pos, text);
+    }
+    Ok(articles)
+}
+
+fn with_extension(output: &Path, extension: &str) -> PathBuf {
+    let mut name = output.as_os_str().to_os_string();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+/// StarDict's index order: ASCII case-insensitive, then bytewise
+pub fn stardict_order(a: &str, b: &str) -> Ordering {
+    let folded = |s: &str| s.bytes().map(|b| b.to_ascii_lowercase()).collect::<Vec<u8>>();
+    folded(a).cmp(&folded(b)).then_with(|| a.cmp(b))
+}
+
+/// Write `<output>.ifo`, `.idx` and `.dict`. Returns the number of words.
+pub fn run_export_stardict(args: &DictionaryArgs, force: bool) -> io::Result<usize> {
+    let articles = read_articles(&args.input)?;
+    let mut words: Vec<&String> = articles.keys().collect();
+    words.sort_by(|a, b| stardict_order(a, b));
+
+    let mut dict = Vec::new();
+    let mut idx = Vec::new();
+    for word in &words {
+        let text = articles[*word].text();
+        idx.extend_from_slice(word.as_bytes());
+        idx.push(0);
+        idx.extend_from_slice(&(dict.len() as u32).to_be_bytes());
+        idx.extend_from_slice(&(text.len() as u32).to_be_bytes());
+        dict.extend_from_slice(text.as_bytes());
+    }
+    let ifo = format!(
+        "StarDict's dict ifo file\nversion=2.4.2\nbookname={}\nwordcount={}\nidxfilesize={}\nsametypesequence=m\n\
+         description=English Wiktionary senses\n",
+        args.name,
+        words.len(),
+        idx.len()
+    );
+
+    let written = [write_file(&args.output, "dict", &dict, force)?, write_file(&args.output, "idx", &idx, force)?];
+    // The .ifo goes last, so readers never see one whose .idx is missing
+    let ifo = write_file(&args.output, "ifo", ifo.as_bytes(), force)?;
+    for file in written.into_iter().chain([ifo]) {
+        file.commit()?;
+    }
+    Ok(words.len())
+}
+
+/// dictd's number encoding: base-64 digits, most significant first
+pub fn dictd_b64(mut value: u64) -> String {
+    let mut digits = Vec::new();
+    loop {
+        digits.push(B64_DIGITS[(value % 64) as usize]);
+        value /= 64;
+        if value == 0 {
+            break;
+        }
+    }
+    digits.iter().rev().map(|&d| d as char).collect()
+}
+
+/// dictd's lookup order for a database without 00-database-allchars: only
+/// letters, digits and spaces count, case-insensitively
+pub fn dictd_key(word: &str) -> String {
+    word.chars()
+        .filter(|ch| ch.is_alphanumeric() || ch.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Write `<output>.index` and `.dict`. Returns the number of words.
+pub fn run_export_dictd(args: &DictionaryArgs, force: bool) -> io::Result<usize> {
+    let articles = read_articles(&args.input)?;
+    let mut headwords: Vec<(String, String)> = vec![
+        ("00-database-utf8".to_string(), String::new()),
+        ("00-database-short".to_string(), args.name.clone()),
+        ("00-database-info".to_string(), "English Wiktionary senses".to_string()),
+    ];
+    headwords.extend(articles.iter().map(|(word, article)| (word.clone(), article.text())));
+    headwords.sort_by(|(a, _), (b, _)| dictd_key(a).cmp(&dictd_key(b)).then_with(|| a.cmp(b)));
+
+    let mut dict = Vec::new();
+    let mut index = String::new();
+    for (word, text) in &headwords {
+        // Articles start with their headword, as dictfmt writes them
+        let article = format!("{}\n{}\n", word, text);
+        let _ = writeln!(index, "{}\t{}\t{}", word, dictd_b64(dict.len() as u64), dictd_b64(article.len() as u64));
+        dict.extend_from_slice(article.as_bytes());
+    }
+
+    let files = [
+        write_file(&args.output, "dict", &dict, force)?,
+        write_file(&args.output, "index", index.as_bytes(), force)?,
+    ];
+    for file in files {
+        file.commit()?;
+    }
+    Ok(articles.len())
+}
+
+fn write_file(output: &Path, extension: &str, contents: &[u8], force: bool) -> io::Result<OutputFile> {
+    let (mut writer, file) = create_output(&with_extension(output, extension), force)?;
+    writer.write_all(contents)?;
+    writer.flush()?;
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn articles_group_senses_by_part_of_speech() {
+        let mut article = Article::default();
+        article.add("NOU", "A feline.".to_string());
+        article.add("VRB", "To hoist the anchor.".to_string());
+        article.add("NOU", "A whip.".to_string());
+        assert_eq!(article.text(), "noun\n1. A feline.\n2. A whip.\nverb\n1. To hoist the anchor.");
+    }
+
+    #[test]
+    fn index_orders_and_offsets() {
+        let mut words = vec!["b", "B", "a", "Ab"];
+        words.sort_by(|a, b| stardict_order(a, b));
+        assert_eq!(words, vec!["a", "Ab", "B", "b"]);
+        assert_eq!(dictd_b64(0), "A");
+        assert_eq!(dictd_b64(63), "/");
+        assert_eq!(dictd_b64(64), "BA");
+        assert_eq!(dictd_b64(4096 + 65), "BBB");
+        assert_eq!(dictd_key("Co-op"), "coop");
+    }
+}
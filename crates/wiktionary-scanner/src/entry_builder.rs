@@ -0,0 +1,296 @@
+//! Validated construction of output entries.
+//!
+//! Extractors fill an `EntryBuilder` rather than writing an `Entry` literal,
+//! and `build` checks what every output line must hold:
+//!
+//! - the word (`id`) is not empty
+//! - each tag array is sorted, without repeats
+//! - `nsyll` is between 1 and `MAX_SYLLABLES`
+//! - a `lemma` is English-like, as the titles kept are
+//!
+//! An entry that breaks one is not written. With `--errors-out` it becomes an
+//! `invalid_entry` record naming the page and the broken invariant, with the
+//! entry's JSON as its snippet.
+
+use crate::form_of::FormOfRelation;
+use crate::{is_englishlike, nyms, quotations, Entry, EtymologyData, PosSection, PosSource, SenseLabels, WordData};
+
+use std::borrow::Cow;
+use std::fmt;
+
+/// More than the longest English words and proverbs have; a larger count is
+/// a misread template or category
+pub const MAX_SYLLABLES: usize = 30;
+
+/// An invariant an entry broke
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    EmptyWord,
+    /// The tag array, by field name
+    UnsortedTags(&'static str),
+    SyllablesOutOfRange(usize),
+    LemmaNotEnglishlike(String),
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::EmptyWord => write!(f, "empty word"),
+            Violation::UnsortedTags(field) => write!(f, "{} not sorted and unique", field),
+            Violation::SyllablesOutOfRange(count) => {
+                write!(f, "nsyll {} outside 1..={}", count, MAX_SYLLABLES)
+            }
+            Violation::LemmaNotEnglishlike(lemma) => write!(f, "lemma {:?} is not English-like", lemma),
+        }
+    }
+}
+
+/// An entry `build` refused, serialized for the error log
+#[derive(Debug, Clone)]
+pub struct InvalidEntry {
+    pub violation: Violation,
+    pub json: String,
+}
+
+/// One sense of a word, filled in by the extractors and checked by `build`
+pub struct EntryBuilder<'a> {
+    entry: Entry<'a>,
+}
+
+impl<'a> EntryBuilder<'a> {
+    /// A sense of `word_data`'s word under `pos`, with the word-level fields
+    /// borrowed from it
+    pub fn new(word_data: &'a WordData, pos: &'a str) -> Self {
+        EntryBuilder {
+            entry: Entry {
+                word: Cow::Borrowed(&word_data.word),
+                ascii: word_data.ascii.as_deref().map(Cow::Borrowed),
+                sort_key: word_data.sort_key.as_deref().map(Cow::Borrowed),
+                pos: Cow::Borrowed(pos),
+                word_count: word_data.word_count,
+                is_abbreviation: word_data.is_abbreviation,
+                is_inflected: word_data.is_inflected,
+                is_neologism: word_data.is_neologism,
+                is_phrase: word_data.is_phrase,
+                syllables: word_data.syllables,
+                phrase_type: word_data.phrase_type.as_deref().map(Cow::Borrowed),
+                tokens: Cow::Borrowed(&word_data.tokens),
+                neologism_date: word_data.neologism_date.as_deref().map(Cow::Borrowed),
+                lemma: word_data.lemma.as_deref().map(Cow::Borrowed),
+                inflection_tags: Cow::Borrowed(&word_data.inflection_tags),
+                spelling_region: word_data.spelling_region.as_deref().map(Cow::Borrowed),
+                categories: Cow::Borrowed(&word_data.categories),
+                usage_notes: word_data.usage_notes.as_deref().map(Cow::Borrowed),
+                ..Entry::default()
+            },
+        }
+    }
+
+    pub fn pos_source(mut self, source: PosSource) -> Self {
+        self.entry.pos_source = source;
+        self
+    }
+
+    pub fn proper_noun(mut self, is_proper_noun: bool) -> Self {
+        self.entry.is_proper_noun = is_proper_noun;
+        self
+    }
+
+    /// The flags the POS section's header and headword line give
+    pub fn section(mut self, section: &PosSection) -> Self {
+        self.entry.pos_source = section.source;
+        self.entry.is_proper_noun = section.is_proper_noun;
+        self.entry.uncountable = section.uncountable;
+        self.entry.plural_only = section.plural_only;
+        self.entry.grapheme_type = section.grapheme_type.map(Cow::Borrowed);
+        self
+    }
+
+    pub fn plural_only(mut self, plural_only: bool) -> Self {
+        self.entry.plural_only = plural_only;
+        self
+    }
+
+    /// A heteronym's senses carry their own transcriptions
+    pub fn heteronym(mut self, ipa: &[String]) -> Self {
+        self.entry.is_heteronym = true;
+        self.entry.ipa = ipa.to_vec();
+        self
+    }
+
+    /// The fields of the Etymology section the sense is under, if any
+    pub fn etymology(mut self, etymology: Option<&'a EtymologyData>) -> Self {
+        if let Some(etymology) = etymology {
+            self.entry.calque_of = etymology.calque_of.as_ref().map(Cow::Borrowed);
+            self.entry.doublets = Cow::Borrowed(&etymology.doublets);
+            self.entry.etymology_text = etymology.text.as_deref().map(Cow::Borrowed);
+            self.entry.morphology = etymology.morphology.as_ref().map(Cow::Borrowed);
+        }
+        self
+    }
+
+    pub fn labels(mut self, labels: SenseLabels) -> Self {
+        self.entry.dialect_tags = labels.dialect;
+        self.entry.domain_tags = labels.domain;
+        self.entry.region_tags = labels.region;
+        self.entry.register_tags = labels.register;
+        self.entry.temporal_tags = labels.temporal;
+        self.entry.valency = labels.valency;
+        self.entry.other_labels = labels.other;
+        self
+    }
+
+    pub fn non_gloss(mut self, non_gloss: bool) -> Self {
+        self.entry.non_gloss = non_gloss;
+        self
+    }
+
+    pub fn attested(mut self, attested: Option<u32>) -> Self {
+        self.entry.attested = attested;
+        self
+    }
+
+    pub fn forms(mut self, forms: Vec<String>) -> Self {
+        self.entry.forms = forms;
+        self
+    }
+
+    pub fn headword(mut self, line: Option<String>) -> Self {
+        self.entry.headword = line;
+        self
+    }
+
+    pub fn form_of(mut self, relation: Option<FormOfRelation>) -> Self {
+        if let Some(relation) = relation {
+            if relation.variant {
+                self.entry.variant_type = Some(relation.relation_type.clone());
+                self.entry.variant_of = Some(relation.target.clone());
+            }
+            self.entry.relation_type = Some(relation.relation_type);
+            self.entry.relation_target = Some(relation.target);
+        }
+        self
+    }
+
+    pub fn definition(mut self, definition: Option<String>, glosses: Vec<String>) -> Self {
+        self.entry.definition = definition;
+        self.entry.glosses = glosses;
+        self
+    }
+
+    pub fn qualifiers(mut self, qualifiers: Vec<String>) -> Self {
+        self.entry.qualifiers = qualifiers;
+        self
+    }
+
+    pub fn quotations(mut self, quotations: Vec<quotations::Quotation>) -> Self {
+        self.entry.quotations = quotations;
+        self
+    }
+
+    pub fn nyms(mut self, nyms: Vec<nyms::Nym>) -> Self {
+        self.entry.nyms = nyms;
+        self
+    }
+
+    pub fn wikidata_lexeme(mut self, lexemes: Vec<String>) -> Self {
+        self.entry.wikidata_lexeme = lexemes;
+        self
+    }
+
+    /// The entry, or the first invariant it breaks
+    pub fn build(self) -> Result<Entry<'a>, InvalidEntry> {
+        match violation(&self.entry) {
+            None => Ok(self.entry),
+            Some(violation) => Err(InvalidEntry {
+                violation,
+                json: serde_json::to_string(&self.entry).unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+fn violation(entry: &Entry<'_>) -> Option<Violation> {
+    if entry.word.trim().is_empty() {
+        return Some(Violation::EmptyWord);
+    }
+    let tags = [
+        ("dialect_tags", &entry.dialect_tags),
+        ("domain_tags", &entry.domain_tags),
+        ("region_tags", &entry.region_tags),
+        ("register_tags", &entry.register_tags),
+        ("temporal_tags", &entry.temporal_tags),
+    ];
+    if let Some((field, _)) = tags.iter().find(|(_, tags)| tags.windows(2).any(|pair| pair[0] >= pair[1])) {
+        return Some(Violation::UnsortedTags(field));
+    }
+    if let Some(count) = entry.syllables.filter(|count| !(1..=MAX_SYLLABLES).contains(count)) {
+        return Some(Violation::SyllablesOutOfRange(count));
+    }
+    if let Some(lemma) = entry.lemma.as_ref().filter(|lemma| !is_englishlike(lemma)) {
+        return Some(Violation::LemmaNotEnglishlike(lemma.to_string()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn word_data(word: &str) -> WordData {
+        WordData {
+            word: word.to_string(),
+            ascii: None,
+            sort_key: None,
+            word_count: 1,
+            is_phrase: false,
+            is_abbreviation: false,
+            is_inflected: false,
+            is_neologism: false,
+            neologism_date: None,
+            lemma: None,
+            inflection_tags: Vec::new(),
+            phrase_type: None,
+            tokens: Vec::new(),
+            syllables: Some(1),
+            spelling_region: None,
+            categories: Vec::new(),
+            usage_notes: None,
+        }
+    }
+
+    fn labels(register: &[&str]) -> SenseLabels {
+        SenseLabels {
+            register: register.iter().map(|tag| tag.to_string()).collect(),
+            ..SenseLabels::default()
+        }
+    }
+
+    #[test]
+    fn build_checks_invariants() {
+        let cat = word_data("cat");
+        let entry = EntryBuilder::new(&cat, "NOU").labels(labels(&["formal", "informal"])).build();
+        assert!(entry.is_ok());
+
+        let unsorted = EntryBuilder::new(&cat, "NOU").labels(labels(&["informal", "formal"])).build();
+        assert_eq!(unsorted.unwrap_err().violation, Violation::UnsortedTags("register_tags"));
+        let repeated = EntryBuilder::new(&cat, "NOU").labels(labels(&["slang", "slang"])).build();
+        assert_eq!(repeated.unwrap_err().violation, Violation::UnsortedTags("register_tags"));
+
+        let blank = word_data(" ");
+        let invalid = EntryBuilder::new(&blank, "NOU").build().unwrap_err();
+        assert_eq!(invalid.violation, Violation::EmptyWord);
+        assert!(invalid.json.starts_with(r#"{"id":" ","pos":"NOU""#));
+
+        let mut data = word_data("cats");
+        data.syllables = Some(0);
+        let zero = EntryBuilder::new(&data, "NOU").build().unwrap_err();
+        assert_eq!(zero.violation, Violation::SyllablesOutOfRange(0));
+        assert_eq!(zero.violation.to_string(), "nsyll 0 outside 1..=30");
+
+        data.syllables = Some(1);
+        data.lemma = Some("猫".to_string());
+        let lemma = EntryBuilder::new(&data, "NOU").build().unwrap_err();
+        assert_eq!(lemma.violation, Violation::LemmaNotEnglishlike("猫".to_string()));
+    }
+}
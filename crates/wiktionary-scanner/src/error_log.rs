@@ -0,0 +1,162 @@
+//! Log of pages that fail extraction, written with `--errors-out`.
+//!
+//! Pages without a title or text, English pages that parse to no entries, and
+//! pages whose extraction panics all end up in `stats.skipped`. With an error
+//! log, each one also gets a JSONL record naming the page, the failure and a
+//! snippet of its source. Entries that break an `EntryBuilder` invariant are
+//! left out of the output and get a record of their own, with the entry as
+//! the snippet. Records are written by the thread that writes the
+//! entries, so they come out in dump order for every strategy.
+
+use crate::entry_builder::InvalidEntry;
+use crate::{parse_page_validated, Entry, PageData, ENGLISH_SECTION};
+
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufWriter, Write};
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+
+/// Characters of page source kept in a record
+const SNIPPET_CHARS: usize = 300;
+
+static ERROR_LOG: OnceCell<Mutex<ErrorLog>> = OnceCell::new();
+
+struct ErrorLog {
+    /// None once `finish_error_log` has flushed and closed it
+    writer: Option<BufWriter<Box<dyn Write + Send>>>,
+    written: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    /// The page XML has no `<title>`
+    NoTitle,
+    /// The page XML has no `<text>`
+    NoText,
+    /// An English page that passed every check but yielded no entries
+    ParseAnomaly,
+    /// Extraction panicked; the panic was caught and the page skipped
+    Panic,
+    /// An entry broke an `EntryBuilder` invariant and was left out
+    InvalidEntry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PageFailure {
+    pub title: String,
+    pub category: FailureCategory,
+    pub snippet: String,
+    /// The panic message, for `panic`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl PageFailure {
+    pub fn new(title: &str, category: FailureCategory, source: &str) -> Self {
+        PageFailure {
+            title: title.to_string(),
+            category,
+            snippet: snippet(source),
+            message: None,
+        }
+    }
+
+    /// Record for an entry of the page `title` that failed validation
+    pub fn invalid_entry(title: &str, invalid: &InvalidEntry) -> Self {
+        let mut failure = PageFailure::new(title, FailureCategory::InvalidEntry, &invalid.json);
+        failure.message = Some(invalid.violation.to_string());
+        failure
+    }
+}
+
+/// Start of `source`, cut at a character boundary
+fn snippet(source: &str) -> String {
+    source.chars().take(SNIPPET_CHARS).collect()
+}
+
+/// Route failure records to `writer` for the rest of the run
+pub fn init_error_log(writer: Box<dyn Write + Send>) {
+    let log = ErrorLog {
+        writer: Some(BufWriter::new(writer)),
+        written: 0,
+    };
+    if ERROR_LOG.set(Mutex::new(log)).is_err() {
+        panic!("Error log already initialized");
+    }
+}
+
+/// Append a record to the error log, if one is open
+pub fn record_failure(failure: &PageFailure) {
+    let Some(log) = ERROR_LOG.get() else {
+        return;
+    };
+    let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+    let line = serde_json::to_string(failure).unwrap_or_default();
+    if let Some(writer) = log.writer.as_mut() {
+        if writeln!(writer, "{}", line).is_ok() {
+            log.written += 1;
+        }
+    }
+}
+
+/// Flush and close the error log. Returns the number of records written.
+pub fn finish_error_log() -> io::Result<usize> {
+    let Some(log) = ERROR_LOG.get() else {
+        return Ok(0);
+    };
+    let mut log = log.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(mut writer) = log.writer.take() {
+        writer.flush()?;
+    }
+    Ok(log.written)
+}
+
+/// Run `parse_page_borrowed`, with a failure for each entry that failed
+/// validation, and for a panic or an empty result when no entry failed
+pub fn parse_page_checked<'a>(title: &str, text: &str, data: &'a mut PageData) -> (Vec<Entry<'a>>, Vec<PageFailure>) {
+    // Moving `data` in lets the entries outlive the closure
+    let parse = || {
+        let data = data;
+        parse_page_validated(title, text, data)
+    };
+    match panic::catch_unwind(AssertUnwindSafe(parse)) {
+        Ok((entries, invalid)) if entries.is_empty() && invalid.is_empty() => {
+            let english = ENGLISH_SECTION.find(text).map_or(text, |m| &text[m.start()..]);
+            (entries, vec![PageFailure::new(title, FailureCategory::ParseAnomaly, english)])
+        }
+        Ok((entries, invalid)) => {
+            (entries, invalid.iter().map(|invalid| PageFailure::invalid_entry(title, invalid)).collect())
+        }
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            let mut failure = PageFailure::new(title, FailureCategory::Panic, text);
+            failure.message = Some(message);
+            (Vec::new(), vec![failure])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failure_record_shape() {
+        let mut failure = PageFailure::new("cat", FailureCategory::Panic, &"x".repeat(400));
+        failure.message = Some("index out of bounds".to_string());
+        let json: serde_json::Value = serde_json::to_value(&failure).unwrap();
+        assert_eq!(json["category"], "panic");
+        assert_eq!(json["snippet"].as_str().unwrap().len(), SNIPPET_CHARS);
+        assert_eq!(json["message"], "index out of bounds");
+
+        let json = serde_json::to_value(PageFailure::new("cat", FailureCategory::NoText, "")).unwrap();
+        assert_eq!(json["category"], "no_text");
+        assert!(json.get("message").is_none());
+    }
+}
@@ -0,0 +1,318 @@
+//! Etymology prose as plain text, with `--etymology-text`.
+//!
+//! The morphology and relation fields only cover what templates say; many
+//! Etymology sections are prose ("From Middle English ..., from Old English
+//! ...") with no morphology template at all. `etymology_text` keeps the first
+//! paragraph of the section. The templates prose is written with are rendered
+//! roughly as Wiktionary displays them: `{{inh|en|enm|hous}}` as "Middle
+//! English hous", `{{m|en|house||dwelling}}` as `house (“dwelling”)`,
+//! `{{af|en|un-|kind}}` as "un- + kind" and `{{unk|en}}` as "Unknown". Other
+//! templates are dropped.
+
+use crate::{appendix, unescape_xml, MarkupMode, ParsedTemplate, WikitextParser, LANG_CODE_PREFIX};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+
+lazy_static! {
+    // Gaps that dropped templates leave: "()", "from ." and "origin,."
+    static ref EMPTY_PARENS: Regex = Regex::new(r"\(\s*\)").unwrap();
+    static ref SPACE_BEFORE_PUNCTUATION: Regex = Regex::new(r"\s+([,.;:!?)])").unwrap();
+    static ref REPEATED_STOP: Regex = Regex::new(r"([^.,;:])[,;:]?\.\.?(\s|$)").unwrap();
+}
+
+/// First paragraph of an Etymology section body, rendered to plain text.
+/// List items count as lines of the paragraph.
+pub fn first_paragraph(body: &str) -> Option<String> {
+    let body = unescape_xml(body);
+    let mut lines: Vec<String> = Vec::new();
+    for line in logical_lines(&body) {
+        let line = line.trim();
+        if line.is_empty() {
+            if lines.is_empty() {
+                continue;
+            }
+            break;
+        }
+        // Images and tables don't survive as plain text
+        let lower = line.get(..8).unwrap_or(line).to_ascii_lowercase();
+        if lower.starts_with("[[file:") || lower.starts_with("[[image:") || line.starts_with("{|") {
+            continue;
+        }
+        let text = render_line(line.trim_start_matches(['*', '#', ':', ';']));
+        // Lines of templates that render to nothing, such as {{root}} or {{etymon}}
+        if text.chars().any(char::is_alphanumeric) {
+            lines.push(text);
+        }
+    }
+    (!lines.is_empty()).then(|| lines.join(" "))
+}
+
+/// Lines of the body, with a template that spans lines kept in one
+fn logical_lines(body: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut pending = String::new();
+    for line in body.lines() {
+        if !pending.is_empty() {
+            pending.push('\n');
+        }
+        pending.push_str(line);
+        if pending.matches("{{").count() <= pending.matches("}}").count() {
+            lines.push(std::mem::take(&mut pending));
+        }
+    }
+    if !pending.is_empty() {
+        lines.push(pending);
+    }
+    lines
+}
+
+/// A line with its templates rendered and its markup removed
+fn render_line(line: &str) -> String {
+    let mut rendered = String::new();
+    let mut rest = line;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let mut parser = WikitextParser::with_markup(&rest[start..], MarkupMode::Strip);
+        let template = parser.parse_template();
+        rendered.push_str(&render_template(&template));
+        rest = &rest[start + parser.pos..];
+    }
+    rendered.push_str(rest);
+    let text = appendix::clean_wikitext(&rendered);
+    let text = EMPTY_PARENS.replace_all(&text, "");
+    let text = SPACE_BEFORE_PUNCTUATION.replace_all(&text, "$1");
+    REPEATED_STOP.replace_all(&text, "$1.$2").trim().to_string()
+}
+
+fn render_template(template: &ParsedTemplate) -> String {
+    let params = &template.params;
+    let from = |index: usize| params.get(index..).unwrap_or_default();
+    let source = || term(params.get(1), from(2), template);
+    match template.name.to_lowercase().as_str() {
+        // {{m|lang|term|alt|gloss}}: the term alone
+        "l" | "m" | "ll" | "l-self" | "link" | "mention" => term(None, from(1), template),
+        // {{cog|lang|term|alt|gloss}}: language and term
+        "cog" | "cognate" | "ncog" | "noncog" | "nc" | "noncognate" => term(params.first(), from(1), template),
+        // {{inh|en|lang|term|alt|gloss}}: the source language and term
+        "inh" | "inherited" | "der" | "derived" | "uder" | "bor" | "borrowed" => source(),
+        "inh+" => lead("Inherited from", source(), template),
+        "bor+" => lead("Borrowed from", source(), template),
+        "lbor" | "learned borrowing" => lead("Learned borrowing from", source(), template),
+        "slbor" => lead("Semi-learned borrowing from", source(), template),
+        "ubor" => lead("Unadapted borrowing from", source(), template),
+        "calque" | "cal" | "clq" => lead("Calque of", source(), template),
+        "psm" => lead("Phono-semantic matching of", source(), template),
+        "sl" | "semantic loan" => lead("Semantic loan from", source(), template),
+        "clipping" | "clip" => lead("Clipping of", term(None, from(1), template), template),
+        "back-formation" | "back-form" | "bf" => lead("Back-formation from", term(None, from(1), template), template),
+        "doublet" | "dbt" => lead("Doublet of", parts(from(1)).join(", "), template),
+        "unk" | "unknown" => lead("Unknown", String::new(), template),
+        "unc" | "uncertain" => lead("Uncertain", String::new(), template),
+        "onom" | "onomatopoeic" => lead("Onomatopoeic", String::new(), template),
+        // {{af|en|part|part...}}: the parts joined with "+"
+        "af" | "affix" | "compound" | "com" | "confix" | "con" | "surf" | "blend" => parts(from(1)).join(" + "),
+        "prefix" | "pre" => {
+            let mut parts = parts(from(1));
+            if let Some(prefix) = parts.first_mut().filter(|prefix| !prefix.ends_with('-')) {
+                prefix.push('-');
+            }
+            parts.join(" + ")
+        }
+        "suffix" | "suf" => {
+            let mut parts = parts(from(1));
+            for suffix in parts.iter_mut().skip(1).filter(|suffix| !suffix.starts_with('-')) {
+                suffix.insert(0, '-');
+            }
+            parts.join(" + ")
+        }
+        "gloss" | "gl" | "q" | "qual" | "qualifier" | "i" => {
+            let text = parts(params).join(", ");
+            if text.is_empty() { text } else { format!("({})", text) }
+        }
+        // {{w|Article|display}}, {{lang|la|text}}
+        "w" | "lang" => params.get(1).filter(|p| !p.is_empty()).or(params.first()).cloned().unwrap_or_default(),
+        _ => String::new(),
+    }
+}
+
+/// The text a template puts before its terms ("Borrowed from"), unless
+/// `notext=1`; `nocap=1` lowercases it and `title=` replaces it
+fn lead(text: &str, rest: String, template: &ParsedTemplate) -> String {
+    let named = |key: &str| template.named.get(key).filter(|value| !value.is_empty());
+    if named("notext").is_some() {
+        return rest;
+    }
+    let mut lead = match named("title") {
+        Some(title) => title.clone(),
+        None if named("nocap").is_some() => text.to_lowercase(),
+        None => text.to_string(),
+    };
+    if !rest.is_empty() {
+        lead.push(' ');
+        lead.push_str(&rest);
+    }
+    lead
+}
+
+/// Positional parameters that name a part, without named ones or the
+/// language prefix of a part in another language ("la:pictus")
+fn parts(params: &[String]) -> Vec<String> {
+    params
+        .iter()
+        .filter(|p| !p.is_empty() && !p.contains('='))
+        .map(|p| LANG_CODE_PREFIX.find(p).map_or(p.as_str(), |prefix| &p[prefix.end()..]).to_string())
+        .collect()
+}
+
+/// `Language display (“gloss”)` from a language code and the parameters
+/// from the term on: term, alternative display, gloss
+fn term(lang: Option<&String>, params: &[String], template: &ParsedTemplate) -> String {
+    let param = |index: usize| params.get(index).filter(|p| !p.is_empty());
+    let named = |keys: &[&str]| keys.iter().find_map(|key| template.named.get(*key).filter(|v| !v.is_empty()));
+    // "-" stands for an unknown term
+    let term = param(0).filter(|term| *term != "-");
+    let display = named(&["alt"]).or(param(1)).or(term);
+    let gloss = named(&["t", "gloss"]).or(param(2));
+
+    let mut text = lang.and_then(|code| language_name(code)).unwrap_or_default().to_string();
+    for part in [display.cloned(), gloss.map(|gloss| format!("(“{}”)", gloss))].into_iter().flatten() {
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&part);
+    }
+    text
+}
+
+/// Display names of the languages English etymologies cite most
+fn language_name(code: &str) -> Option<&'static str> {
+    Some(match code {
+        "en" => "English",
+        "enm" => "Middle English",
+        "ang" => "Old English",
+        "sco" => "Scots",
+        "mul" => "Translingual",
+        "fr" => "French",
+        "frm" => "Middle French",
+        "fro" => "Old French",
+        "xno" => "Anglo-Norman",
+        "frk" => "Frankish",
+        "la" => "Latin",
+        "LL." | "la-lat" => "Late Latin",
+        "ML." | "la-med" => "Medieval Latin",
+        "NL." | "la-new" => "New Latin",
+        "VL." | "la-vul" => "Vulgar Latin",
+        "grc" => "Ancient Greek",
+        "grc-koi" => "Koine Greek",
+        "el" => "Greek",
+        "ine-pro" => "Proto-Indo-European",
+        "gem" => "Germanic",
+        "gem-pro" => "Proto-Germanic",
+        "gmq" => "North Germanic",
+        "gmw-pro" => "Proto-West Germanic",
+        "got" => "Gothic",
+        "lng" => "Lombardic",
+        "non" => "Old Norse",
+        "osx" => "Old Saxon",
+        "ofs" => "Old Frisian",
+        "fy" => "West Frisian",
+        "nl" => "Dutch",
+        "dum" => "Middle Dutch",
+        "odt" => "Old Dutch",
+        "af" => "Afrikaans",
+        "de" => "German",
+        "gmh" => "Middle High German",
+        "goh" => "Old High German",
+        "gml" => "Middle Low German",
+        "nds" => "Low German",
+        "yi" => "Yiddish",
+        "da" => "Danish",
+        "sv" => "Swedish",
+        "no" => "Norwegian",
+        "is" => "Icelandic",
+        "it" => "Italian",
+        "es" => "Spanish",
+        "pt" => "Portuguese",
+        "ca" => "Catalan",
+        "pro" | "oc-pro-old" => "Old Occitan",
+        "cel" => "Celtic",
+        "ga" => "Irish",
+        "gd" => "Scottish Gaelic",
+        "cy" => "Welsh",
+        "ru" => "Russian",
+        "pl" => "Polish",
+        "cs" => "Czech",
+        "hu" => "Hungarian",
+        "fi" => "Finnish",
+        "tr" => "Turkish",
+        "trk" => "Turkic",
+        "uz" => "Uzbek",
+        "ar" => "Arabic",
+        "fa" => "Persian",
+        "fa-cls" => "Classical Persian",
+        "tg" => "Tajik",
+        "sog" => "Sogdian",
+        "ps" => "Pashto",
+        "he" => "Hebrew",
+        "ka" => "Georgian",
+        "sa" => "Sanskrit",
+        "hi" => "Hindi",
+        "ur" => "Urdu",
+        "ta" => "Tamil",
+        "zh" => "Chinese",
+        "cmn" => "Mandarin",
+        "yue" => "Cantonese",
+        "ja" => "Japanese",
+        "ko" => "Korean",
+        "ms" => "Malay",
+        "id" => "Indonesian",
+        "tl" => "Tagalog",
+        "sw" => "Swahili",
+        "zu" => "Zulu",
+        "haw" => "Hawaiian",
+        "mi" => "Maori",
+        "nah" => "Nahuatl",
+        "qu" => "Quechua",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn etymology_templates_render_as_prose() {
+        let body = "\n{{root|en|ine-pro|*h₁ed-}}\n\
+                    From {{inh|en|enm|[[hous]]}}, from {{inh|en|ang|hūs||dwelling}}, \
+                    from {{inh|en|gem-pro|*hūsą}}. Cognate with {{cog|de|Haus}}.\n\
+                    * Or {{af|en|un-|kind|-ness}}; compare {{m|en|house|t=home}}{{rfe|en}}.\n\n\
+                    Second paragraph.";
+        assert_eq!(
+            first_paragraph(body).as_deref(),
+            Some(
+                "From Middle English hous, from Old English hūs (“dwelling”), from Proto-Germanic *hūsą. \
+                 Cognate with German Haus. Or un- + kind + -ness; compare house (“home”)."
+            )
+        );
+        let body = "{{bor+|en|LL.|color}}, of {{unk|en|nocap=1}} origin... {{doublet|en|couleur}}. \
+                    Of {{der|en|gem|-}} or {{unc|en|title=uncertain}} origin.";
+        assert_eq!(
+            first_paragraph(body).as_deref(),
+            Some("Borrowed from Late Latin color, of unknown origin... Doublet of couleur. Of Germanic or uncertain origin.")
+        );
+    }
+
+    #[test]
+    fn markup_and_unknown_terms_are_dropped() {
+        let body = "[[File:A.svg|thumb|A]]\n{{multiple images\n|image1=A.jpg\n|caption1=An {{m|en|a}}}}\n\
+                    ''Clipping'' of {{suffix|en|happy|ness}}, from {{der|en|fro|-}} \
+                    &quot;x&quot; {{m|la|}}.&lt;ref&gt;{{R:OED}}&lt;/ref&gt;. {{af|en|pt:Guiné|-ia}}";
+        assert_eq!(
+            first_paragraph(body).as_deref(),
+            Some("Clipping of happy + -ness, from Old French \"x\". Guiné + -ia")
+        );
+        assert_eq!(first_paragraph("\n{{etymon|en|:inh|enm:a}}\n{{rfe|en}}\n"), None);
+    }
+}
@@ -0,0 +1,652 @@
+//! Datasets derived from scanner output.
+//!
+//! `export clues` turns a scan made with `--definitions` into crossword
+//! records: the answer in capitals, its length and enumeration ("3,5" for
+//! "ice cream"), and a short clue cut from the definition. Only common,
+//! inoffensive, lowercase words make it through: proper nouns, vulgar,
+//! offensive and derogatory senses, archaic, obsolete, dated and rare senses,
+//! misspellings and abbreviations are left out.
+//!
+//! `export lemma-pairs` writes lemmatizer training data: one TSV row per
+//! distinct (inflected form, lemma, inflection tags) among the senses with a
+//! `lemma`, the tags joined with ";" ("ran", "run", "past").
+//!
+//! `export segmentation` writes a morpheme segmentation corpus for
+//! Morfessor-style training: each word with morphology and its morphemes,
+//! hyphens stripped, joined by "-" ("happiness\thappy-ness"). Blends,
+//! clippings and back-formations don't segment their word and are left out.
+//!
+//! `export wordlist` writes one word per line, for spell checkers. With
+//! `--region en-GB` words spelled for another region (`spelling_region`) are
+//! left out, and so are words whose senses are all tagged for other regions,
+//! giving a British list without "color" or "sidewalk". Misspellings are
+//! never listed.
+//!
+//! `export tiers` writes levelled vocabulary lists from output that has been
+//! given a `frequency_tier` (see `openword.enrich.frequency`): the lemmas
+//! among the top 1,000, 5,000 and 20,000 words, each level holding the words
+//! new to it. Inflections and proper nouns are left out.
+//!
+//! `export ontolex` writes OntoLex-Lemon Turtle (see `ontolex`), and
+//! `export stardict` and `export dictd` dictionaries for offline readers (see
+//! `dictionary`), and `export graph` a word-formation graph (see `graph`).
+
+use crate::normalize::fold_ascii;
+use crate::{create_output, open_input};
+
+use clap::{Args as ClapArgs, Subcommand};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+const OFFENSIVE_REGISTERS: &[&str] = &["vulgar", "offensive", "derogatory", "slur", "ethnic slur"];
+const UNCOMMON_TEMPORAL: &[&str] = &["archaic", "obsolete", "dated", "rare"];
+
+#[derive(ClapArgs, Debug)]
+pub struct ExportArgs {
+    #[command(subcommand)]
+    pub format: ExportFormat,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ExportFormat {
+    /// Crossword/word-game records: answer, length, enumeration and a short clue
+    Clues(CluesArgs),
+    /// Lemmatizer training pairs: inflected form, lemma and inflection tags as TSV
+    LemmaPairs(LemmaPairsArgs),
+    /// Morpheme segmentation corpus: each word and its morphemes from morphology
+    Segmentation(SegmentationArgs),
+    /// Plain word list, one word per line, optionally for one region's spelling
+    Wordlist(WordlistArgs),
+    /// Levelled vocabulary lists (top 1k/5k/20k lemmas) from a merged frequency_tier
+    Tiers(TiersArgs),
+    /// OntoLex-Lemon Turtle: lexical entries with their forms and senses
+    Ontolex(crate::ontolex::OntolexArgs),
+    /// StarDict dictionary (.ifo, .idx, .dict) for GoldenDict and similar readers
+    Stardict(crate::dictionary::DictionaryArgs),
+    /// dictd database (.index, .dict)
+    Dictd(crate::dictionary::DictionaryArgs),
+    /// Word-formation graph (DOT or GraphML): derivation and compound edges from morphology
+    Graph(crate::graph::GraphArgs),
+    /// Candidate Open English WordNet synsets per sense, by lemma, POS and gloss overlap
+    WordnetMap(crate::wordnet::WordnetMapArgs),
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct CluesArgs {
+    /// Scanner output JSONL made with --definitions (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the clue JSONL (- for stdout)
+    pub output: PathBuf,
+
+    /// Shortest answer, in letters
+    #[arg(long, default_value_t = 3)]
+    pub min_len: usize,
+
+    /// Longest answer, in letters
+    #[arg(long, default_value_t = 15)]
+    pub max_len: usize,
+
+    /// Longest clue, in characters; senses with longer clues are skipped
+    #[arg(long, default_value_t = 60)]
+    pub max_clue: usize,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct LemmaPairsArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the TSV (- for stdout)
+    pub output: PathBuf,
+
+    /// Start with a header row naming the columns
+    #[arg(long)]
+    pub header: bool,
+}
+
+/// Only the fields the lemma-pair export needs
+#[derive(Deserialize)]
+struct InflectionRecord {
+    id: String,
+    lemma: Option<String>,
+    #[serde(default)]
+    inflection_tags: Vec<String>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct SegmentationArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the corpus (- for stdout)
+    pub output: PathBuf,
+
+    /// String between morphemes (" " gives Morfessor's annotation format)
+    #[arg(long, default_value = "-")]
+    pub separator: String,
+
+    /// Only segmentations whose morphemes spell the word (not "happy" + "-ness" for "happiness")
+    #[arg(long)]
+    pub concatenative: bool,
+}
+
+/// Only the fields the segmentation export needs
+#[derive(Deserialize)]
+struct SegmentationRecord {
+    id: String,
+    morphology: Option<SegmentationFields>,
+}
+
+#[derive(Deserialize)]
+struct SegmentationFields {
+    #[serde(rename = "type")]
+    morph_type: String,
+    #[serde(default)]
+    components: Vec<String>,
+    #[serde(default)]
+    non_concatenative: bool,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct WordlistArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the list (- for stdout)
+    pub output: PathBuf,
+
+    /// Only words in use in this region (en-GB, en-US, ...); subregions count as their region
+    #[arg(long)]
+    pub region: Option<String>,
+}
+
+/// Only the fields the word list needs
+#[derive(Deserialize)]
+struct RegionRecord {
+    id: String,
+    spelling_region: Option<String>,
+    #[serde(default)]
+    region_tags: Vec<String>,
+    variant_type: Option<String>,
+}
+
+// Each level's rarest frequency tier: A-G are ranks up to 1,000, H-K up to
+// 5,000 and L-M up to 20,000
+const TIER_LEVELS: &[(&str, char)] = &[("1k", 'G'), ("5k", 'K'), ("20k", 'M')];
+
+#[derive(ClapArgs, Debug)]
+pub struct TiersArgs {
+    /// Scanner output JSONL with frequency_tier merged in (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the TSV of level and word (- for stdout)
+    pub output: PathBuf,
+
+    /// Start with a header row naming the columns
+    #[arg(long)]
+    pub header: bool,
+}
+
+/// Only the fields the levelled lists need
+#[derive(Deserialize)]
+struct TierRecord {
+    id: String,
+    pos: Option<String>,
+    frequency_tier: Option<String>,
+    #[serde(default)]
+    is_inflected: bool,
+    #[serde(default)]
+    is_proper_noun: bool,
+}
+
+impl TierRecord {
+    fn is_lemma(&self) -> bool {
+        !self.is_inflected && !self.is_proper_noun && self.pos.as_deref() != Some("NAM")
+    }
+}
+
+/// The level a frequency tier falls in, with the level's index
+fn tier_level(tier: &str) -> Option<(usize, &'static str)> {
+    let mut letters = tier.chars();
+    let letter = letters.next().filter(|_| letters.next().is_none())?;
+    TIER_LEVELS.iter().position(|&(_, rarest)| ('A'..=rarest).contains(&letter)).map(|index| (index, TIER_LEVELS[index].0))
+}
+
+/// Only the fields the clue export needs
+#[derive(Deserialize)]
+struct SenseRecord {
+    id: String,
+    pos: String,
+    #[serde(default)]
+    is_abbreviation: bool,
+    #[serde(default)]
+    non_gloss: bool,
+    variant_type: Option<String>,
+    #[serde(default)]
+    register_tags: Vec<String>,
+    #[serde(default)]
+    temporal_tags: Vec<String>,
+    definition: Option<String>,
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ClueRecord {
+    pub answer: String,
+    pub word: String,
+    pub length: usize,
+    pub pattern: String,
+    pub clue: String,
+    pub pos: String,
+}
+
+impl SenseRecord {
+    fn is_clueable(&self) -> bool {
+        let tagged = |tags: &[String], list: &[&str]| tags.iter().any(|tag| list.contains(&tag.as_str()));
+        self.pos != "NAM"
+            && self.id.starts_with(|ch: char| ch.is_lowercase())
+            && !self.is_abbreviation
+            && !self.non_gloss
+            && self.variant_type.is_none()
+            && !tagged(&self.register_tags, OFFENSIVE_REGISTERS)
+            && !tagged(&self.temporal_tags, UNCOMMON_TEMPORAL)
+    }
+}
+
+/// Answer in capitals and its enumeration ("co-op" → COOP, "2-2"), or None
+/// if the word has anything but letters between its spaces and hyphens
+pub fn answer_and_pattern(word: &str) -> Option<(String, String)> {
+    let folded = fold_ascii(word)?;
+    let mut answer = String::new();
+    let mut pattern = String::new();
+    let mut run = 0;
+    for ch in folded.chars() {
+        match ch {
+            'a'..='z' | 'A'..='Z' => {
+                answer.push(ch.to_ascii_uppercase());
+                run += 1;
+            }
+            '\'' => {}
+            ' ' | '-' if run > 0 => {
+                pattern.push_str(&format!("{}{}", run, if ch == ' ' { ',' } else { '-' }));
+                run = 0;
+            }
+            _ => return None,
+        }
+    }
+    if run == 0 {
+        return None;
+    }
+    pattern.push_str(&run.to_string());
+    Some((answer, pattern))
+}
+
+/// First clause of a definition, capitalized and without its final stop.
+/// None when it's too long or gives the answer away.
+pub fn short_clue(definition: &str, word: &str, max_len: usize) -> Option<String> {
+    let clause = definition.split([';', ':']).next()?.trim().trim_end_matches(['.', ',']).trim_end();
+    if clause.is_empty() || clause.chars().count() > max_len {
+        return None;
+    }
+    let word = word.to_lowercase();
+    let gives_away = clause
+        .to_lowercase()
+        .split(|ch: char| !ch.is_alphanumeric() && ch != '\'')
+        .any(|token| token == word || (word.len() >= 4 && token.starts_with(&word)));
+    if gives_away {
+        return None;
+    }
+    let mut chars = clause.chars();
+    let first = chars.next()?;
+    Some(first.to_uppercase().chain(chars).collect())
+}
+
+fn sense_clue(sense: &SenseRecord, args: &CluesArgs) -> Option<ClueRecord> {
+    if !sense.is_clueable() {
+        return None;
+    }
+    let (answer, pattern) = answer_and_pattern(&sense.id)?;
+    let length = answer.len();
+    if length < args.min_len || length > args.max_len {
+        return None;
+    }
+    let clue = sense
+        .definition
+        .iter()
+        .chain(&sense.glosses)
+        .find_map(|text| short_clue(text, &sense.id, args.max_clue))?;
+    Some(ClueRecord { answer, word: sense.id.clone(), length, pattern, clue, pos: sense.pos.clone() })
+}
+
+/// Write clue records for the input's senses. Returns the numbers of clues
+/// and distinct words written.
+pub fn run_export_clues(args: &CluesArgs, force: bool) -> io::Result<(usize, usize)> {
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut seen: HashSet<(String, String)> = HashSet::new();
+    let mut words: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sense: SenseRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let Some(record) = sense_clue(&sense, args) else {
+            continue;
+        };
+        if seen.insert((record.word.clone(), record.clue.clone())) {
+            writeln!(writer, "{}", serde_json::to_string(&record)?)?;
+            words.insert(record.word);
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((seen.len(), words.len()))
+}
+
+/// Whether a region tag covers `region`: the same region, one of its
+/// subregions (en-GB-north for en-GB) or its parent
+fn region_matches(tag: &str, region: &str) -> bool {
+    let within = |inner: &str, outer: &str| inner.strip_prefix(outer).is_some_and(|rest| rest.starts_with('-'));
+    tag == region || within(tag, region) || within(region, tag)
+}
+
+impl RegionRecord {
+    /// A sense that belongs in the list: not a misspelling, and with `region`
+    /// not spelled or tagged for another region only
+    fn in_region(&self, region: Option<&str>) -> bool {
+        if self.variant_type.as_deref() == Some("misspelling") {
+            return false;
+        }
+        let Some(region) = region else {
+            return true;
+        };
+        self.spelling_region.as_deref().is_none_or(|spelling| region_matches(spelling, region))
+            && (self.region_tags.is_empty() || self.region_tags.iter().any(|tag| region_matches(tag, region)))
+    }
+}
+
+/// Write each word with a sense in the region once, in input order. Returns
+/// the number of words.
+pub fn run_export_wordlist(args: &WordlistArgs, force: bool) -> io::Result<usize> {
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: RegionRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        if seen.contains(&record.id) || !record.in_region(args.region.as_deref()) {
+            continue;
+        }
+        writeln!(writer, "{}", record.id)?;
+        seen.insert(record.id);
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(seen.len())
+}
+
+/// Write each level's lemmas, most frequent tier first and alphabetical
+/// within a tier. Returns the number of words in each level.
+pub fn run_export_tiers(args: &TiersArgs, force: bool) -> io::Result<Vec<(&'static str, usize)>> {
+    let reader = open_input(&args.input)?;
+    // Word -> (level index, tier letter), for words with a lemma sense in a level
+    let mut words: HashMap<String, (usize, String)> = HashMap::new();
+    let mut tiered = false;
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: TierRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let Some(tier) = &record.frequency_tier else {
+            continue;
+        };
+        tiered = true;
+        if let Some((index, _)) = tier_level(tier).filter(|_| record.is_lemma()) {
+            words.entry(record.id).or_insert_with(|| (index, tier.clone()));
+        }
+    }
+    if !tiered {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "No frequency_tier in the input; merge frequencies into it first",
+        ));
+    }
+
+    let mut rows: Vec<((usize, String), String)> = words.into_iter().map(|(word, level)| (level, word)).collect();
+    rows.sort();
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    if args.header {
+        writeln!(writer, "level\tword")?;
+    }
+    let mut counts: Vec<(&'static str, usize)> = TIER_LEVELS.iter().map(|&(level, _)| (level, 0)).collect();
+    for ((index, _), word) in &rows {
+        writeln!(writer, "{}\t{}", TIER_LEVELS[*index].0, tsv_field(word))?;
+        counts[*index].1 += 1;
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(counts)
+}
+
+/// Tab-separated fields can't hold tabs or newlines
+fn tsv_field(text: &str) -> String {
+    text.replace(['\t', '\n', '\r'], " ")
+}
+
+/// Write the input's (form, lemma, tags) rows in input order, each once.
+/// Returns the number of rows.
+pub fn run_export_lemma_pairs(args: &LemmaPairsArgs, force: bool) -> io::Result<usize> {
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    if args.header {
+        writeln!(writer, "inflected_form\tlemma\tinflection_tags")?;
+    }
+    let mut seen: HashSet<(String, String, String)> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: InflectionRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let Some(lemma) = record.lemma.filter(|lemma| *lemma != record.id) else {
+            continue;
+        };
+        let row = (tsv_field(&record.id), tsv_field(&lemma), tsv_field(&record.inflection_tags.join(";")));
+        if !seen.contains(&row) {
+            writeln!(writer, "{}\t{}\t{}", row.0, row.1, row.2)?;
+            seen.insert(row);
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(seen.len())
+}
+
+/// The word's morphemes, without their hyphens, if its morphology segments
+/// it into two or more
+fn morphemes(morphology: &SegmentationFields, concatenative: bool) -> Option<Vec<&str>> {
+    if matches!(morphology.morph_type.as_str(), "blend" | "clipping" | "back-formation")
+        || (concatenative && morphology.non_concatenative)
+    {
+        return None;
+    }
+    let morphemes: Vec<&str> = morphology.components.iter().map(|c| c.trim_matches('-')).collect();
+    // Template parameters that leaked into the components (`t1=...`) spoil the whole segmentation
+    if morphemes.len() < 2 || morphemes.iter().any(|m| m.is_empty() || m.contains('=')) {
+        return None;
+    }
+    Some(morphemes)
+}
+
+/// Write each word's segmentation once, in input order. Returns the number of words.
+pub fn run_export_segmentation(args: &SegmentationArgs, force: bool) -> io::Result<usize> {
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: SegmentationRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let Some(morphology) = &record.morphology else {
+            continue;
+        };
+        if seen.contains(&record.id) {
+            continue;
+        }
+        if let Some(morphemes) = morphemes(morphology, args.concatenative) {
+            writeln!(writer, "{}\t{}", tsv_field(&record.id), tsv_field(&morphemes.join(&args.separator)))?;
+            seen.insert(record.id);
+        }
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(seen.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn enumerations() {
+        assert_eq!(answer_and_pattern("ice cream"), Some(("ICECREAM".to_string(), "3,5".to_string())));
+        assert_eq!(answer_and_pattern("co-op"), Some(("COOP".to_string(), "2-2".to_string())));
+        assert_eq!(answer_and_pattern("can't"), Some(("CANT".to_string(), "4".to_string())));
+        assert_eq!(answer_and_pattern("café"), Some(("CAFE".to_string(), "4".to_string())));
+        assert_eq!(answer_and_pattern("B.O.A.T."), None);
+        assert_eq!(answer_and_pattern("4x4"), None);
+    }
+
+    #[test]
+    fn clues_are_short_and_fair() {
+        assert_eq!(short_clue("a small domesticated feline; a house cat.", "moggy", 60).as_deref(), Some("A small domesticated feline"));
+        assert_eq!(short_clue("A domestic cat.", "cat", 60), None);
+        assert_eq!(short_clue("The activity of running as a sport.", "running", 60), None);
+        assert_eq!(short_clue("A very long definition that keeps going on.", "word", 10), None);
+    }
+
+    #[test]
+    fn offensive_and_proper_senses_are_skipped() {
+        let args = CluesArgs { input: PathBuf::new(), output: PathBuf::new(), min_len: 3, max_len: 15, max_clue: 60 };
+        let sense = |json: &str| serde_json::from_str::<SenseRecord>(json).unwrap();
+        let clue = sense_clue(&sense(r#"{"id":"bank","pos":"NOU","definition":"A place to keep money."}"#), &args);
+        assert_eq!(clue.map(|c| c.clue), Some("A place to keep money".to_string()));
+        assert!(sense_clue(&sense(r#"{"id":"bank","pos":"NOU","register_tags":["vulgar"],"definition":"Money."}"#), &args).is_none());
+        assert!(sense_clue(&sense(r#"{"id":"Paris","pos":"NAM","definition":"A city."}"#), &args).is_none());
+        assert!(sense_clue(&sense(r#"{"id":"thee","pos":"PRN","temporal_tags":["archaic"],"definition":"You."}"#), &args).is_none());
+    }
+
+    #[test]
+    fn segmentations_from_components() {
+        let fields = |json: &str| serde_json::from_str::<SegmentationFields>(json).unwrap();
+        let happiness = fields(r#"{"type":"suffixed","components":["happy","-ness"],"non_concatenative":true}"#);
+        assert_eq!(morphemes(&happiness, false), Some(vec!["happy", "ness"]));
+        assert_eq!(morphemes(&happiness, true), None);
+        let beeswax = fields(r#"{"type":"compound","components":["bee","-s-","wax"]}"#);
+        assert_eq!(morphemes(&beeswax, true), Some(vec!["bee", "s", "wax"]));
+        assert_eq!(morphemes(&fields(r#"{"type":"clipping","components":["turkeycock","turkey-cock"]}"#), false), None);
+        assert_eq!(morphemes(&fields(r#"{"type":"suffixed","components":["Abdera","-t1=a town"]}"#), false), None);
+        assert_eq!(morphemes(&fields(r#"{"type":"simple","components":["cat"]}"#), false), None);
+    }
+
+    #[test]
+    fn regional_spellings_and_senses() {
+        let record = |json: &str| serde_json::from_str::<RegionRecord>(json).unwrap();
+        let color = record(r#"{"id":"color","spelling_region":"en-US"}"#);
+        assert!(color.in_region(Some("en-US")) && !color.in_region(Some("en-GB")) && color.in_region(None));
+        let sidewalk = record(r#"{"id":"sidewalk","region_tags":["en-US","en-CA"]}"#);
+        assert!(!sidewalk.in_region(Some("en-GB")) && sidewalk.in_region(Some("en-CA")));
+        let bairn = record(r#"{"id":"bairn","region_tags":["en-GB-north","en-GB"]}"#);
+        assert!(bairn.in_region(Some("en-GB")) && bairn.in_region(Some("en-GB-scotland")));
+        assert!(!record(r#"{"id":"recieve","variant_type":"misspelling"}"#).in_region(None));
+        assert!(!region_matches("en-GBX", "en-GB"));
+    }
+
+    #[test]
+    fn tiers_hold_lemmas_by_level() {
+        assert_eq!(tier_level("A"), Some((0, "1k")));
+        assert_eq!(tier_level("H"), Some((1, "5k")));
+        assert_eq!(tier_level("M"), Some((2, "20k")));
+        assert_eq!(tier_level("N"), None);
+        assert_eq!(tier_level("Z"), None);
+
+        let path = |name: &str| std::env::temp_dir().join(format!("tiers-{}-{}", std::process::id(), name));
+        let jsonl = concat!(
+            r#"{"id":"time","pos":"NOU","frequency_tier":"B"}"#, "\n",
+            r#"{"id":"time","pos":"VRB","frequency_tier":"B"}"#, "\n",
+            r#"{"id":"cats","pos":"NOU","is_inflected":true,"frequency_tier":"H"}"#, "\n",
+            r#"{"id":"London","pos":"NAM","is_proper_noun":true,"frequency_tier":"G"}"#, "\n",
+            r#"{"id":"apple","pos":"NOU","frequency_tier":"H"}"#, "\n",
+            r#"{"id":"able","pos":"ADJ","frequency_tier":"H"}"#, "\n",
+            r#"{"id":"zymurgy","pos":"NOU","frequency_tier":"Z"}"#, "\n",
+        );
+        std::fs::write(path("in.jsonl"), jsonl).unwrap();
+        let args = TiersArgs { input: path("in.jsonl"), output: path("out.tsv"), header: false };
+        assert_eq!(run_export_tiers(&args, true).unwrap(), vec![("1k", 1), ("5k", 2), ("20k", 0)]);
+        assert_eq!(std::fs::read_to_string(path("out.tsv")).unwrap(), "1k\ttime\n5k\table\n5k\tapple\n");
+
+        std::fs::write(path("in.jsonl"), r#"{"id":"time","pos":"NOU"}"#).unwrap();
+        assert!(run_export_tiers(&args, true).is_err());
+        for name in ["in.jsonl", "out.tsv"] {
+            std::fs::remove_file(path(name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn lemma_pairs_written_once() {
+        let path = |name: &str| std::env::temp_dir().join(format!("lemma-pairs-{}-{}", std::process::id(), name));
+        let jsonl = concat!(
+            r#"{"id":"cats","pos":"NOU","is_inflected":true,"lemma":"cat","inflection_tags":["plural"]}"#, "\n",
+            r#"{"id":"cats","pos":"VRB","is_inflected":true,"lemma":"cat","inflection_tags":["plural"]}"#, "\n",
+            r#"{"id":"cat","pos":"NOU"}"#, "\n",
+            r#"{"id":"is","pos":"VRB","is_inflected":true,"lemma":"be","inflection_tags":["third-person","singular"]}"#, "\n",
+            r#"{"id":"geese","pos":"NOU","is_inflected":true}"#, "\n",
+        );
+        std::fs::write(path("in.jsonl"), jsonl).unwrap();
+        let args = LemmaPairsArgs { input: path("in.jsonl"), output: path("out.tsv"), header: true };
+        assert_eq!(run_export_lemma_pairs(&args, true).unwrap(), 2);
+        assert_eq!(
+            std::fs::read_to_string(path("out.tsv")).unwrap(),
+            "inflected_form\tlemma\tinflection_tags\ncats\tcat\tplural\nis\tbe\tthird-person;singular\n"
+        );
+        for name in ["in.jsonl", "out.tsv"] {
+            std::fs::remove_file(path(name)).unwrap();
+        }
+    }
+}
@@ -0,0 +1,662 @@
+//! Serializing output lines, with a hand-rolled JSON writer under
+//! `--features fast-json`.
+//!
+//! Once parsing runs in parallel, serde_json's writer is a measurable part of
+//! a full-dump scan: every sense is a dozen short strings, each escaped a
+//! byte at a time. The `fast-json` writer is a serde `Serializer` that emits
+//! exactly what `serde_json::to_writer` does (compact, fields in declaration
+//! order, the same escapes and number formatting), but checks strings eight
+//! bytes at a time for characters that need escaping and copies clean runs
+//! whole. Most of the output is ASCII without quotes or control characters,
+//! so most strings go out in one copy.
+//!
+//! Without the feature, `to_writer` is serde_json's.
+
+use serde::Serialize;
+use std::io::Write;
+
+#[cfg(feature = "fast-json")]
+pub use writer::to_writer;
+
+#[cfg(not(feature = "fast-json"))]
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: &mut W, value: &T) -> serde_json::Result<()> {
+    serde_json::to_writer(writer, value)
+}
+
+#[cfg(feature = "fast-json")]
+mod writer {
+    use super::*;
+    use serde::ser::{self, Error as _, SerializeMap, SerializeSeq, SerializeStruct};
+    use serde_json::{Error, Result};
+
+    /// Write `value` as compact JSON, byte for byte as serde_json would
+    pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: &mut W, value: &T) -> Result<()> {
+        value.serialize(&mut Serializer { writer })
+    }
+
+    /// What follows the backslash for each byte that needs escaping, `u` for
+    /// `\u00XX`, or 0
+    const ESCAPES: [u8; 256] = {
+        let mut escapes = [0; 256];
+        let mut byte = 0;
+        while byte < 0x20 {
+            escapes[byte] = b'u';
+            byte += 1;
+        }
+        escapes[0x08] = b'b';
+        escapes[0x09] = b't';
+        escapes[0x0a] = b'n';
+        escapes[0x0c] = b'f';
+        escapes[0x0d] = b'r';
+        escapes[b'"' as usize] = b'"';
+        escapes[b'\\' as usize] = b'\\';
+        escapes
+    };
+
+    const HEX: &[u8; 16] = b"0123456789abcdef";
+
+    const ONES: u64 = 0x0101_0101_0101_0101;
+    const HIGHS: u64 = 0x8080_8080_8080_8080;
+
+    /// Whether any of the eight bytes in `word` is a control character, a quote
+    /// or a backslash. It may say yes for a clean word next to one of those,
+    /// never no for a word that has one.
+    fn needs_escape(word: u64) -> bool {
+        let below = |x: u64, n: u64| x.wrapping_sub(ONES * n) & !x & HIGHS;
+        let quote = word ^ (ONES * b'"' as u64);
+        let backslash = word ^ (ONES * b'\\' as u64);
+        below(word, 0x20) | below(quote, 1) | below(backslash, 1) != 0
+    }
+
+    fn write_str<W: Write>(writer: &mut W, value: &str) -> std::io::Result<()> {
+        let bytes = value.as_bytes();
+        writer.write_all(b"\"")?;
+        // Bytes before `start` are written; `i` is the next to check
+        let mut start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if let Some(word) = bytes.get(i..i + 8) {
+                if !needs_escape(u64::from_le_bytes(word.try_into().unwrap())) {
+                    i += 8;
+                    continue;
+                }
+            }
+            let escape = ESCAPES[bytes[i] as usize];
+            if escape != 0 {
+                writer.write_all(&bytes[start..i])?;
+                match escape {
+                    b'u' => writer.write_all(&[
+                        b'\\',
+                        b'u',
+                        b'0',
+                        b'0',
+                        HEX[(bytes[i] >> 4) as usize],
+                        HEX[(bytes[i] & 0xf) as usize],
+                    ])?,
+                    _ => writer.write_all(&[b'\\', escape])?,
+                }
+                start = i + 1;
+            }
+            i += 1;
+        }
+        writer.write_all(&bytes[start..])?;
+        writer.write_all(b"\"")
+    }
+
+    struct Serializer<'w, W> {
+        writer: &'w mut W,
+    }
+
+    impl<W: Write> Serializer<'_, W> {
+        fn put(&mut self, bytes: &[u8]) -> Result<()> {
+            self.writer.write_all(bytes).map_err(Error::io)
+        }
+
+        fn integer(&mut self, value: impl itoa::Integer) -> Result<()> {
+            self.put(itoa::Buffer::new().format(value).as_bytes())
+        }
+
+        fn float(&mut self, value: impl ryu::Float, finite: bool) -> Result<()> {
+            if !finite {
+                return self.put(b"null");
+            }
+            self.put(ryu::Buffer::new().format_finite(value).as_bytes())
+        }
+
+        /// Open `{"variant":` around an enum variant's content
+        fn open_variant(&mut self, variant: &str) -> Result<()> {
+            self.put(b"{")?;
+            write_str(self.writer, variant).map_err(Error::io)?;
+            self.put(b":")
+        }
+    }
+
+    /// A sequence or map being written; `close` ends it, with `}` after it for
+    /// an enum variant
+    struct Compound<'a, 'w, W> {
+        ser: &'a mut Serializer<'w, W>,
+        first: bool,
+        close: &'static [u8],
+    }
+
+    impl<'a, 'w, W: Write> Compound<'a, 'w, W> {
+        fn open(ser: &'a mut Serializer<'w, W>, open: &[u8], close: &'static [u8]) -> Result<Self> {
+            ser.put(open)?;
+            Ok(Compound { ser, first: true, close })
+        }
+
+        fn separate(&mut self) -> Result<()> {
+            if !std::mem::take(&mut self.first) {
+                self.ser.put(b",")?;
+            }
+            Ok(())
+        }
+
+        fn element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            self.separate()?;
+            value.serialize(&mut *self.ser)
+        }
+
+        fn field<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> Result<()> {
+            self.separate()?;
+            write_str(self.ser.writer, key).map_err(Error::io)?;
+            self.ser.put(b":")?;
+            value.serialize(&mut *self.ser)
+        }
+
+        fn finish(self) -> Result<()> {
+            self.ser.put(self.close)
+        }
+    }
+
+    impl<'a, 'w, W: Write> ser::Serializer for &'a mut Serializer<'w, W> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = Compound<'a, 'w, W>;
+        type SerializeTuple = Compound<'a, 'w, W>;
+        type SerializeTupleStruct = Compound<'a, 'w, W>;
+        type SerializeTupleVariant = Compound<'a, 'w, W>;
+        type SerializeMap = Compound<'a, 'w, W>;
+        type SerializeStruct = Compound<'a, 'w, W>;
+        type SerializeStructVariant = Compound<'a, 'w, W>;
+
+        fn serialize_bool(self, value: bool) -> Result<()> {
+            self.put(if value { b"true" } else { b"false" })
+        }
+
+        fn serialize_i8(self, value: i8) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_i16(self, value: i16) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_i32(self, value: i32) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_i64(self, value: i64) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_i128(self, value: i128) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_u8(self, value: u8) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_u16(self, value: u16) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_u32(self, value: u32) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_u64(self, value: u64) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_u128(self, value: u128) -> Result<()> {
+            self.integer(value)
+        }
+
+        fn serialize_f32(self, value: f32) -> Result<()> {
+            self.float(value, value.is_finite())
+        }
+
+        fn serialize_f64(self, value: f64) -> Result<()> {
+            self.float(value, value.is_finite())
+        }
+
+        fn serialize_char(self, value: char) -> Result<()> {
+            self.serialize_str(value.encode_utf8(&mut [0; 4]))
+        }
+
+        fn serialize_str(self, value: &str) -> Result<()> {
+            write_str(self.writer, value).map_err(Error::io)
+        }
+
+        fn serialize_bytes(self, value: &[u8]) -> Result<()> {
+            let mut seq = Compound::open(self, b"[", b"]")?;
+            for byte in value {
+                seq.element(byte)?;
+            }
+            seq.finish()
+        }
+
+        fn serialize_none(self) -> Result<()> {
+            self.put(b"null")
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+            value.serialize(self)
+        }
+
+        fn serialize_unit(self) -> Result<()> {
+            self.put(b"null")
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+            self.put(b"null")
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<()> {
+            self.serialize_str(variant)
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            value: &T,
+        ) -> Result<()> {
+            self.open_variant(variant)?;
+            value.serialize(&mut *self)?;
+            self.put(b"}")
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+            Compound::open(self, b"[", b"]")
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+            Compound::open(self, b"[", b"]")
+        }
+
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+            Compound::open(self, b"[", b"]")
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant> {
+            self.open_variant(variant)?;
+            Compound::open(self, b"[", b"]}")
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+            Compound::open(self, b"{", b"}")
+        }
+
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+            Compound::open(self, b"{", b"}")
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant> {
+            self.open_variant(variant)?;
+            Compound::open(self, b"{", b"}}")
+        }
+    }
+
+    impl<W: Write> SerializeSeq for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> ser::SerializeTuple for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> ser::SerializeTupleStruct for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> ser::SerializeTupleVariant for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            self.element(value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> SerializeMap for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+            self.separate()?;
+            key.serialize(MapKey { ser: &mut *self.ser })?;
+            self.ser.put(b":")
+        }
+
+        fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+            value.serialize(&mut *self.ser)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> SerializeStruct for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+            self.field(key, value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    impl<W: Write> ser::SerializeStructVariant for Compound<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+
+        fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+            self.field(key, value)
+        }
+
+        fn end(self) -> Result<()> {
+            self.finish()
+        }
+    }
+
+    /// A map key: strings as they are, and numbers and booleans quoted, as
+    /// serde_json writes them
+    struct MapKey<'a, 'w, W> {
+        ser: &'a mut Serializer<'w, W>,
+    }
+
+    impl<W: Write> MapKey<'_, '_, W> {
+        fn quoted(self, value: impl std::fmt::Display) -> Result<()> {
+            write_str(self.ser.writer, &value.to_string()).map_err(Error::io)
+        }
+    }
+
+    fn key_must_be_a_string() -> Error {
+        Error::custom("key must be a string")
+    }
+
+    impl<W: Write> ser::Serializer for MapKey<'_, '_, W> {
+        type Ok = ();
+        type Error = Error;
+        type SerializeSeq = ser::Impossible<(), Error>;
+        type SerializeTuple = ser::Impossible<(), Error>;
+        type SerializeTupleStruct = ser::Impossible<(), Error>;
+        type SerializeTupleVariant = ser::Impossible<(), Error>;
+        type SerializeMap = ser::Impossible<(), Error>;
+        type SerializeStruct = ser::Impossible<(), Error>;
+        type SerializeStructVariant = ser::Impossible<(), Error>;
+
+        fn serialize_bool(self, value: bool) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_i8(self, value: i8) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_i16(self, value: i16) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_i32(self, value: i32) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_i64(self, value: i64) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_i128(self, value: i128) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_u8(self, value: u8) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_u16(self, value: u16) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_u32(self, value: u32) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_u64(self, value: u64) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_u128(self, value: u128) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_f32(self, _value: f32) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_f64(self, _value: f64) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_char(self, value: char) -> Result<()> {
+            self.quoted(value)
+        }
+
+        fn serialize_str(self, value: &str) -> Result<()> {
+            write_str(self.ser.writer, value).map_err(Error::io)
+        }
+
+        fn serialize_bytes(self, _value: &[u8]) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_none(self) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_some<T: Serialize + ?Sized>(self, _value: &T) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_unit(self) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_unit_struct(self, _name: &'static str) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<()> {
+            self.serialize_str(variant)
+        }
+
+        fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<()> {
+            value.serialize(self)
+        }
+
+        fn serialize_newtype_variant<T: Serialize + ?Sized>(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _value: &T,
+        ) -> Result<()> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_tuple_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeTupleStruct> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_tuple_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeTupleVariant> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_struct(self, _name: &'static str, _len: usize) -> Result<Self::SerializeStruct> {
+            Err(key_must_be_a_string())
+        }
+
+        fn serialize_struct_variant(
+            self,
+            _name: &'static str,
+            _index: u32,
+            _variant: &'static str,
+            _len: usize,
+        ) -> Result<Self::SerializeStructVariant> {
+            Err(key_must_be_a_string())
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use serde::Serialize;
+        use std::collections::BTreeMap;
+
+        fn same_as_serde_json<T: Serialize + ?Sized>(value: &T) {
+            let mut fast = Vec::new();
+            to_writer(&mut fast, value).unwrap();
+            assert_eq!(String::from_utf8(fast).unwrap(), serde_json::to_string(value).unwrap());
+        }
+
+        #[derive(Serialize)]
+        enum Variant {
+            Unit,
+            Newtype(u8),
+            Tuple(i32, String),
+            Struct { a: Option<bool>, b: Vec<f64> },
+        }
+
+        #[derive(Serialize)]
+        struct Word {
+            #[serde(rename = "id")]
+            word: String,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            nsyll: Option<usize>,
+            tags: Vec<String>,
+            #[serde(flatten)]
+            extra: BTreeMap<String, i64>,
+            variants: Vec<Variant>,
+            unit: (),
+        }
+
+        #[test]
+        fn strings_escape_like_serde_json() {
+            // Every ASCII byte, alone and at each offset of an eight-byte word
+            for byte in 0..0x80u8 {
+                let c = char::from(byte);
+                for offset in 0..10 {
+                    same_as_serde_json(&format!("{}{}tail", "x".repeat(offset), c));
+                }
+            }
+            for s in ["", "cat", "café", "\u{7f}\u{85}\u{2028}", "ʃ\"\\/😀\u{1}", "a\"b\"c\"d\"e\"f\"g\"h\"i", "🐈‍⬛ \t\r\n end"] {
+                same_as_serde_json(s);
+                same_as_serde_json(&s.repeat(5));
+            }
+        }
+
+        #[test]
+        fn values_write_like_serde_json() {
+            same_as_serde_json(&Word {
+                word: "ice \"cream\"".to_string(),
+                nsyll: Some(2),
+                tags: vec!["informal".to_string(), "US\n".to_string()],
+                extra: BTreeMap::from([("b".to_string(), -7), ("a".to_string(), i64::MIN)]),
+                variants: vec![
+                    Variant::Unit,
+                    Variant::Newtype(255),
+                    Variant::Tuple(-1, "x".to_string()),
+                    Variant::Struct { a: None, b: vec![0.1, -2.5e-9, 1e21, f64::NAN, 3.0] },
+                ],
+                unit: (),
+            });
+            same_as_serde_json(&BTreeMap::from([(1u32, 'é'), (20, '"')]));
+            same_as_serde_json(&(u128::MAX, i8::MIN, 1.5f32, [true, false], b"by".as_slice()));
+            let mut fast = Vec::new();
+            assert!(to_writer(&mut fast, &BTreeMap::from([(vec![1], 2)])).is_err());
+        }
+    }
+}
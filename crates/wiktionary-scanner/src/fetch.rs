@@ -0,0 +1,389 @@
+//! Download support for Wikimedia dumps and live pages.
+//!
+//! Resolves the newest completed `pages-articles` dump for a wiki from
+//! dumps.wikimedia.org, downloads it with HTTP range-request resume, and
+//! verifies the published SHA-1 before handing the file to the scanner.
+//! Individual pages can also be pulled from the MediaWiki API so extractor
+//! changes can be checked against current wikitext without a full dump.
+
+use clap::Args as ClapArgs;
+use indicatif::{ProgressBar, ProgressStyle};
+use lazy_static::lazy_static;
+use regex::Regex;
+use sha1::{Digest, Sha1};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::create_output;
+
+/// Identify ourselves to Wikimedia, as their API etiquette requires
+pub const USER_AGENT: &str = "openword-lexicon-wiktionary-scanner/0.1 (https://github.com/macshonle/openword-lexicon)";
+
+const DEFAULT_MIRROR: &str = "https://dumps.wikimedia.org";
+const DEFAULT_API: &str = "https://en.wiktionary.org/w/api.php";
+
+/// The MediaWiki API accepts at most 50 titles per query for anonymous clients
+const API_TITLES_PER_QUERY: usize = 50;
+
+lazy_static! {
+    // Dated run directories in the wiki's dump index: <a href="20251101/">
+    static ref DUMP_DATE_LINK: Regex = Regex::new(r#"href="(\d{8})/""#).unwrap();
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct FetchDumpArgs {
+    /// Wiki database name
+    #[arg(long, default_value = "enwiktionary")]
+    pub wiki: String,
+
+    /// Dump date (YYYYMMDD); defaults to the newest completed run
+    #[arg(long)]
+    pub date: Option<String>,
+
+    /// Base URL of the dump mirror
+    #[arg(long, default_value = DEFAULT_MIRROR)]
+    pub mirror: String,
+
+    /// Directory to download into
+    #[arg(long, default_value = "data/raw/en")]
+    pub dir: PathBuf,
+
+    /// Skip SHA-1 verification of the downloaded file
+    #[arg(long)]
+    pub no_verify: bool,
+
+    /// Run the scanner on the downloaded dump, writing JSONL to this path
+    #[arg(long)]
+    pub scan: Option<PathBuf>,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct FetchPageArgs {
+    /// Page titles to fetch
+    #[arg(required = true)]
+    pub titles: Vec<String>,
+
+    /// MediaWiki API endpoint
+    #[arg(long, default_value = DEFAULT_API)]
+    pub api: String,
+
+    /// Write entries to this file instead of stdout
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+
+    /// Print the fetched wikitext instead of parsed entries
+    #[arg(long)]
+    pub raw: bool,
+}
+
+/// A dump file as described by the run's dumpstatus.json
+#[derive(Debug)]
+pub struct DumpFile {
+    pub name: String,
+    pub url: String,
+    pub size: Option<u64>,
+    pub sha1: Option<String>,
+}
+
+fn http_error(e: ureq::Error) -> io::Error {
+    io::Error::other(format!("HTTP request failed: {}", e))
+}
+
+pub fn get_string(url: &str) -> io::Result<String> {
+    ureq::get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .map_err(http_error)?
+        .body_mut()
+        .read_to_string()
+        .map_err(http_error)
+}
+
+/// Look up the pages-articles file of one dump run.
+///
+/// Returns None when the run exists but its articles job has not finished.
+fn resolve_dump_run(mirror: &str, wiki: &str, date: &str) -> io::Result<Option<DumpFile>> {
+    let status_url = format!("{}/{}/{}/dumpstatus.json", mirror, wiki, date);
+    let status: serde_json::Value = serde_json::from_str(&get_string(&status_url)?)
+        .map_err(|e| io::Error::other(format!("Invalid dumpstatus.json at {}: {}", status_url, e)))?;
+
+    let job = &status["jobs"]["articlesdump"];
+    if job["status"].as_str() != Some("done") {
+        return Ok(None);
+    }
+
+    let name = format!("{}-{}-pages-articles.xml.bz2", wiki, date);
+    let info = &job["files"][&name];
+    if info.is_null() {
+        return Ok(None);
+    }
+
+    let url = match info["url"].as_str() {
+        Some(path) if path.starts_with('/') => format!("{}{}", mirror, path),
+        Some(path) => path.to_string(),
+        None => format!("{}/{}/{}/{}", mirror, wiki, date, name),
+    };
+
+    Ok(Some(DumpFile {
+        name,
+        url,
+        size: info["size"].as_u64(),
+        sha1: info["sha1"].as_str().map(|s| s.to_string()),
+    }))
+}
+
+/// Find the newest completed pages-articles dump (or the one for a given date)
+pub fn resolve_dump(mirror: &str, wiki: &str, date: Option<&str>) -> io::Result<DumpFile> {
+    let mirror = mirror.trim_end_matches('/');
+
+    if let Some(date) = date {
+        return resolve_dump_run(mirror, wiki, date)?.ok_or_else(|| {
+            io::Error::other(format!("Dump {} for {} is not complete", date, wiki))
+        });
+    }
+
+    let index = get_string(&format!("{}/{}/", mirror, wiki))?;
+    let mut dates: Vec<String> = DUMP_DATE_LINK
+        .captures_iter(&index)
+        .map(|cap| cap[1].to_string())
+        .collect();
+    dates.sort();
+    dates.dedup();
+
+    // Newest first; an in-progress run is skipped in favour of the previous one
+    for date in dates.iter().rev() {
+        if let Some(file) = resolve_dump_run(mirror, wiki, date)? {
+            return Ok(file);
+        }
+    }
+
+    Err(io::Error::other(format!("No completed pages-articles dump found for {}", wiki)))
+}
+
+/// Download a URL to `dest`, resuming from `dest.part` if a previous attempt was interrupted
+pub fn download_with_resume(url: &str, dest: &Path, expected_size: Option<u64>, quiet: bool) -> io::Result<()> {
+    let part_path = dest.with_extension(format!(
+        "{}.part",
+        dest.extension().and_then(|e| e.to_str()).unwrap_or("")
+    ));
+    let existing = fs::metadata(&part_path).map(|m| m.len()).unwrap_or(0);
+
+    let mut request = ureq::get(url).header("User-Agent", USER_AGENT);
+    if existing > 0 {
+        request = request.header("Range", &format!("bytes={}-", existing));
+    }
+    let mut response = request.call().map_err(http_error)?;
+
+    // 206 means the server honoured the range; anything else restarts from scratch
+    let resumed = existing > 0 && response.status().as_u16() == 206;
+    let mut file = if resumed {
+        OpenOptions::new().append(true).open(&part_path)?
+    } else {
+        File::create(&part_path)?
+    };
+    let start = if resumed { existing } else { 0 };
+
+    let pb = if quiet {
+        ProgressBar::hidden()
+    } else {
+        let pb = match expected_size {
+            Some(size) => ProgressBar::new(size),
+            None => ProgressBar::new_spinner(),
+        };
+        pb.set_style(
+            ProgressStyle::default_bar()
+                .template("{bar:40} {bytes}/{total_bytes} ({bytes_per_sec}, {eta})")
+                .unwrap()
+        );
+        pb.set_position(start);
+        pb
+    };
+
+    let mut reader = response.body_mut().as_reader();
+    let mut chunk = vec![0u8; 1024 * 1024];
+    loop {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        file.write_all(&chunk[..n])?;
+        pb.inc(n as u64);
+    }
+    file.flush()?;
+    pb.finish_and_clear();
+
+    if let Some(size) = expected_size {
+        let actual = fs::metadata(&part_path)?.len();
+        if actual != size {
+            return Err(io::Error::other(format!(
+                "Download incomplete: got {} of {} bytes (re-run to resume)", actual, size
+            )));
+        }
+    }
+
+    fs::rename(&part_path, dest)
+}
+
+/// Compute the hex SHA-1 digest of a file
+pub fn sha1_file(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut chunk = vec![0u8; 1024 * 1024];
+    loop {
+        let n = file.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&chunk[..n]);
+    }
+    Ok(hasher.finalize().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+fn verify_sha1(path: &Path, expected: &str) -> io::Result<bool> {
+    Ok(sha1_file(path)?.eq_ignore_ascii_case(expected))
+}
+
+/// Resolve, download and verify a dump; returns the local path
+pub fn run_fetch_dump(args: &FetchDumpArgs, quiet: bool) -> io::Result<PathBuf> {
+    let dump = resolve_dump(&args.mirror, &args.wiki, args.date.as_deref())?;
+    let dest = args.dir.join(&dump.name);
+
+    if !quiet {
+        println!("Dump: {}", dump.name);
+        println!("URL: {}", dump.url);
+        println!("Destination: {}", dest.display());
+    }
+
+    fs::create_dir_all(&args.dir)?;
+
+    let verify = |path: &Path| -> io::Result<bool> {
+        match (&dump.sha1, args.no_verify) {
+            (Some(expected), false) => verify_sha1(path, expected),
+            _ => Ok(true),
+        }
+    };
+
+    if dest.exists() && verify(&dest)? {
+        if !quiet {
+            println!("Already downloaded and verified");
+        }
+        return Ok(dest);
+    }
+
+    download_with_resume(&dump.url, &dest, dump.size, quiet)?;
+
+    if !verify(&dest)? {
+        fs::remove_file(&dest)?;
+        return Err(io::Error::other(format!(
+            "SHA-1 mismatch for {}; the corrupt file was removed", dump.name
+        )));
+    }
+
+    if !quiet {
+        println!("Downloaded and verified {}", dest.display());
+    }
+    Ok(dest)
+}
+
+/// Escape wikitext the way the XML dump does, so parse_page sees identical input
+pub fn escape_like_dump(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Fetch current wikitext for the given titles. Missing pages are reported
+/// on stderr and left out of the result.
+pub fn fetch_wikitext(api: &str, titles: &[String]) -> io::Result<Vec<(String, String)>> {
+    let mut pages = Vec::new();
+
+    for batch in titles.chunks(API_TITLES_PER_QUERY) {
+        let body = ureq::get(api)
+            .header("User-Agent", USER_AGENT)
+            .query("action", "query")
+            .query("prop", "revisions")
+            .query("rvprop", "content")
+            .query("rvslots", "main")
+            .query("format", "json")
+            .query("formatversion", "2")
+            .query("titles", batch.join("|"))
+            .call()
+            .map_err(http_error)?
+            .body_mut()
+            .read_to_string()
+            .map_err(http_error)?;
+
+        let response: serde_json::Value = serde_json::from_str(&body)
+            .map_err(|e| io::Error::other(format!("Invalid API response: {}", e)))?;
+
+        for page in response["query"]["pages"].as_array().into_iter().flatten() {
+            let title = page["title"].as_str().unwrap_or_default().to_string();
+            match page["revisions"][0]["slots"]["main"]["content"].as_str() {
+                Some(content) => pages.push((title, content.to_string())),
+                None => eprintln!("Page not found: {}", title),
+            }
+        }
+    }
+
+    Ok(pages)
+}
+
+/// Fetch live pages and run them through parse_page
+pub fn run_fetch_page(args: &FetchPageArgs, force: bool) -> io::Result<()> {
+    let pages = fetch_wikitext(&args.api, &args.titles)?;
+
+    let (out, output_file) = create_output(args.output.as_deref().unwrap_or(Path::new("-")), force)?;
+    let mut out = io::BufWriter::new(out);
+
+    for (title, wikitext) in pages {
+        if args.raw {
+            writeln!(out, "{}", wikitext)?;
+            continue;
+        }
+        let entries = crate::parse_page(&title, &escape_like_dump(&wikitext));
+        if entries.is_empty() {
+            eprintln!("No English entries extracted from: {}", title);
+        }
+        for entry in entries {
+            writeln!(out, "{}", serde_json::to_string(&entry)?)?;
+        }
+    }
+
+    out.flush()?;
+    drop(out);
+    output_file.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dump_date_links_from_index() {
+        let html = r#"<a href="../">../</a><a href="20251001/">20251001/</a><a href="20251101/">20251101/</a><a href="latest/">latest/</a>"#;
+        let dates: Vec<&str> = DUMP_DATE_LINK
+            .captures_iter(html)
+            .map(|cap| cap.get(1).unwrap().as_str())
+            .collect();
+        assert_eq!(dates, vec!["20251001", "20251101"]);
+    }
+
+    #[test]
+    fn escape_matches_dump_encoding() {
+        assert_eq!(
+            escape_like_dump(r#"{{also|Appendix:Variations of "cat"}} <ref>a & b</ref>"#),
+            "{{also|Appendix:Variations of &quot;cat&quot;}} &lt;ref&gt;a &amp; b&lt;/ref&gt;"
+        );
+    }
+
+    #[test]
+    fn sha1_of_known_content() {
+        let path = std::env::temp_dir().join(format!("wikt-sha1-{}.txt", std::process::id()));
+        fs::write(&path, b"abc").unwrap();
+        let digest = sha1_file(&path).unwrap();
+        fs::remove_file(&path).ok();
+        assert_eq!(digest, "a9993e364706816aba3e25717850c26c9cd0d89d");
+    }
+}
@@ -0,0 +1,313 @@
+//! Output filters applied to parsed entries before they are written.
+//!
+//! Filtering happens right after parse_page (on the worker threads for the
+//! parallel strategies), so dropped senses never reach the writer. The active
+//! filter is set once at startup, like the schema maps.
+//!
+//! `--profile` adds the exclusions of a named policy in `schema/profiles.yaml`
+//! (`kids`, `standard`, `unfiltered`) to those given on the command line, so
+//! a common filtering policy gives the same lexicon for every consumer.
+
+use crate::sidecar::retain_entries;
+use crate::{find_schema_file, get_pos_map, get_register_labels, Args, Entry, PhraseMode};
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+static ENTRY_FILTER: OnceCell<EntryFilter> = OnceCell::new();
+
+#[derive(Debug, Default)]
+pub struct EntryFilter {
+    /// Keep only these POS codes (empty = keep all)
+    pub include_pos: HashSet<String>,
+    /// Drop these POS codes
+    pub exclude_pos: HashSet<String>,
+    /// Keep only senses carrying at least one of these register labels (empty = keep all)
+    pub only_register: HashSet<String>,
+    /// Drop senses carrying any of these register labels
+    pub exclude_register: HashSet<String>,
+    /// Drop senses carrying any of these domain or temporal labels (from --profile)
+    pub exclude_domain: HashSet<String>,
+    pub exclude_temporal: HashSet<String>,
+    /// Syllable count bounds (inclusive); senses without a count fail either bound
+    pub min_syllables: Option<usize>,
+    pub max_syllables: Option<usize>,
+    /// Word length bounds in characters (inclusive)
+    pub min_len: Option<usize>,
+    pub max_len: Option<usize>,
+    /// Keep, drop, or keep only multiword expressions
+    pub phrases: PhraseMode,
+    /// Admit titles that aren't English-like for their letter, numeral and symbol senses
+    pub graphemes: bool,
+}
+
+/// Resolve a user-supplied POS name ("noun", "proper noun", "NOU") to its output code
+fn resolve_pos(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    let lower = name.to_lowercase();
+    if let Some(code) = get_pos_map().get(&lower) {
+        return Ok(code.clone());
+    }
+    let upper = name.to_uppercase();
+    if lower == "unknown" || get_pos_map().values().any(|code| *code == upper) {
+        return Ok(if lower == "unknown" { lower } else { upper });
+    }
+    Err(format!("Unknown part of speech: {}", name))
+}
+
+fn resolve_pos_list(names: &[String]) -> Result<HashSet<String>, String> {
+    names.iter().map(|n| resolve_pos(n)).collect()
+}
+
+/// Register labels are matched as they appear in register_tags, so only
+/// labels known to the labels schema can ever match
+fn resolve_register_list(names: &[String]) -> Result<HashSet<String>, String> {
+    names
+        .iter()
+        .map(|name| {
+            let label = name.trim().to_lowercase();
+            if get_register_labels().contains(&label) {
+                Ok(label)
+            } else {
+                Err(format!("Unknown register label: {}", name.trim()))
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct ProfilesSchema {
+    profiles: BTreeMap<String, Profile>,
+}
+
+/// A named filtering policy: the labels whose senses it drops
+#[derive(Debug, Default, Deserialize)]
+pub struct Profile {
+    #[allow(dead_code)]
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    exclude_register: Vec<String>,
+    #[serde(default)]
+    exclude_domain: Vec<String>,
+    #[serde(default)]
+    exclude_temporal: Vec<String>,
+}
+
+/// Profile labels are matched like tags; ones the labels schema lacks never match
+fn label_set(labels: &[String]) -> HashSet<String> {
+    labels.iter().map(|label| label.trim().to_lowercase()).collect()
+}
+
+pub fn load_profile(schema_path: Option<&PathBuf>, name: &str) -> Result<Profile, String> {
+    let path = match schema_path {
+        Some(path) => path.clone(),
+        None => find_schema_file("profiles.yaml")?,
+    };
+    let contents = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to open profiles schema file {:?}: {}", path, e))?;
+    let mut schema: ProfilesSchema = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse profiles schema YAML: {}", e))?;
+    schema.profiles.remove(name).ok_or_else(|| {
+        let names: Vec<&str> = schema.profiles.keys().map(String::as_str).collect();
+        format!("Unknown profile: {} (profiles: {})", name, names.join(", "))
+    })
+}
+
+impl EntryFilter {
+    pub fn from_args(args: &Args) -> Result<Self, String> {
+        let profile = match &args.profile {
+            Some(name) => load_profile(args.profiles_schema.as_ref(), name)?,
+            None => Profile::default(),
+        };
+        let mut exclude_register = resolve_register_list(&args.exclude_register)?;
+        exclude_register.extend(label_set(&profile.exclude_register));
+        Ok(EntryFilter {
+            include_pos: resolve_pos_list(&args.include_pos)?,
+            exclude_pos: resolve_pos_list(&args.exclude_pos)?,
+            only_register: resolve_register_list(&args.only_register)?,
+            exclude_register,
+            exclude_domain: label_set(&profile.exclude_domain),
+            exclude_temporal: label_set(&profile.exclude_temporal),
+            min_syllables: args.min_syllables,
+            max_syllables: args.max_syllables,
+            min_len: args.min_len,
+            max_len: args.max_len,
+            phrases: args.phrases,
+            graphemes: args.graphemes,
+        })
+    }
+
+    pub fn accepts(&self, entry: &Entry<'_>) -> bool {
+        if !self.include_pos.is_empty() && !self.include_pos.contains(entry.pos.as_ref()) {
+            return false;
+        }
+        if self.exclude_pos.contains(entry.pos.as_ref()) {
+            return false;
+        }
+        if !self.only_register.is_empty()
+            && !entry.register_tags.iter().any(|tag| self.only_register.contains(tag))
+        {
+            return false;
+        }
+        if entry.register_tags.iter().any(|tag| self.exclude_register.contains(tag))
+            || entry.domain_tags.iter().any(|tag| self.exclude_domain.contains(tag))
+            || entry.temporal_tags.iter().any(|tag| self.exclude_temporal.contains(tag))
+        {
+            return false;
+        }
+        match self.phrases {
+            PhraseMode::Include => {}
+            PhraseMode::Only if !entry.is_phrase => return false,
+            PhraseMode::Exclude if entry.is_phrase => return false,
+            _ => {}
+        }
+        if self.min_syllables.is_some() || self.max_syllables.is_some() {
+            match entry.syllables {
+                Some(n) if within(n, self.min_syllables, self.max_syllables) => {}
+                _ => return false,
+            }
+        }
+        if self.min_len.is_some() || self.max_len.is_some() {
+            let len = entry.word.chars().count();
+            if !within(len, self.min_len, self.max_len) {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn within(value: usize, min: Option<usize>, max: Option<usize>) -> bool {
+    min.is_none_or(|m| value >= m) && max.is_none_or(|m| value <= m)
+}
+
+pub fn init_entry_filter(filter: EntryFilter) -> Result<(), String> {
+    ENTRY_FILTER.set(filter).map_err(|_| "ENTRY_FILTER already initialized".to_string())
+}
+
+/// Whether --graphemes is on
+pub fn graphemes_enabled() -> bool {
+    ENTRY_FILTER.get().is_some_and(|filter| filter.graphemes)
+}
+
+/// Drop entries rejected by the active filter; returns the kept entries and
+/// the number dropped. Without an initialized filter everything is kept.
+pub fn filter_entries(mut entries: Vec<Entry<'_>>) -> (Vec<Entry<'_>>, usize) {
+    match ENTRY_FILTER.get() {
+        Some(filter) => {
+            let before = entries.len();
+            retain_entries(&mut entries, |e| filter.accepts(e));
+            let dropped = before - entries.len();
+            (entries, dropped)
+        }
+        None => (entries, 0),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(pos: &str) -> Entry<'static> {
+        serde_json::from_str(&format!(r#"{{"id":"word","pos":"{}","wc":1}}"#, pos)).unwrap()
+    }
+
+    fn entry_with_registers(registers: &[&str]) -> Entry<'static> {
+        let mut entry = entry("NOU");
+        entry.register_tags = registers.iter().map(|s| s.to_string()).collect();
+        entry
+    }
+
+    fn codes(list: &[&str]) -> HashSet<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn include_and_exclude_pos() {
+        let filter = EntryFilter {
+            include_pos: codes(&["NOU", "VRB"]),
+            exclude_pos: codes(&["VRB"]),
+            ..Default::default()
+        };
+        assert!(filter.accepts(&entry("NOU")));
+        assert!(!filter.accepts(&entry("VRB")));
+        assert!(!filter.accepts(&entry("ADJ")));
+        assert!(EntryFilter::default().accepts(&entry("SYM")));
+    }
+
+    #[test]
+    fn only_and_exclude_register() {
+        let slang = EntryFilter {
+            only_register: codes(&["slang"]),
+            ..Default::default()
+        };
+        assert!(slang.accepts(&entry_with_registers(&["informal", "slang"])));
+        assert!(!slang.accepts(&entry_with_registers(&[])));
+
+        let clean = EntryFilter {
+            exclude_register: codes(&["vulgar", "offensive"]),
+            ..Default::default()
+        };
+        assert!(clean.accepts(&entry_with_registers(&["slang"])));
+        assert!(!clean.accepts(&entry_with_registers(&["slang", "vulgar"])));
+    }
+
+    #[test]
+    fn profiles_from_schema() {
+        let schema = PathBuf::from("../../schema/profiles.yaml");
+        let kids = load_profile(Some(&schema), "kids").unwrap();
+        assert!(kids.exclude_register.contains(&"vulgar".to_string()));
+        assert!(load_profile(Some(&schema), "unfiltered").unwrap().exclude_register.is_empty());
+        let unknown = load_profile(Some(&schema), "adult").unwrap_err();
+        assert!(unknown.contains("kids, standard, unfiltered"), "{}", unknown);
+
+        let filter = EntryFilter {
+            exclude_domain: label_set(&kids.exclude_domain),
+            exclude_temporal: label_set(&kids.exclude_temporal),
+            ..Default::default()
+        };
+        let mut sense = entry("NOU");
+        assert!(filter.accepts(&sense));
+        sense.temporal_tags = vec!["obsolete".to_string()];
+        assert!(!filter.accepts(&sense));
+        sense.temporal_tags.clear();
+        sense.domain_tags = vec!["sexuality".to_string()];
+        assert!(!filter.accepts(&sense));
+    }
+
+    #[test]
+    fn syllable_and_length_ranges() {
+        let filter = EntryFilter {
+            min_syllables: Some(2),
+            max_len: Some(5),
+            ..Default::default()
+        };
+        let mut word = entry("NOU");
+        word.word = "apple".into();
+        word.syllables = Some(2);
+        assert!(filter.accepts(&word));
+
+        word.syllables = None;
+        assert!(!filter.accepts(&word), "unknown syllable count fails a syllable bound");
+
+        word.syllables = Some(3);
+        word.word = "banana".into();
+        assert!(!filter.accepts(&word));
+    }
+
+    #[test]
+    fn phrase_modes() {
+        let single = entry("NOU");
+        let mut phrase = entry("PHR");
+        phrase.is_phrase = true;
+
+        let only = EntryFilter { phrases: PhraseMode::Only, ..Default::default() };
+        assert!(only.accepts(&phrase) && !only.accepts(&single));
+
+        let exclude = EntryFilter { phrases: PhraseMode::Exclude, ..Default::default() };
+        assert!(exclude.accepts(&single) && !exclude.accepts(&phrase));
+    }
+}
@@ -0,0 +1,208 @@
+//! Small, representative dumps for tests and benchmarks, with `make-fixture`.
+//!
+//! A real dump is far too large to check in, and a handful of hand-picked
+//! pages covers only what its picker thought of. `make-fixture` reads a dump
+//! and keeps `--pages` English pages spread across strata: the size of the
+//! English section (under 1 KB, under 8 KB, larger) crossed with the features
+//! extraction branches on, namely inflection templates, Etymology sections,
+//! multiword titles and IPA. The strata give up pages in turn, each its pages
+//! with the smallest sample points first (see sample.rs), so a dump and seed
+//! always make the same fixture.
+//!
+//! Pages are written in dump order after the dump's `<siteinfo>`, so the
+//! fixture scans like the dump it came from. An output ending in `.bz2` is
+//! compressed.
+
+use crate::sample::sample_point;
+use crate::{
+    create_output, extract_english_section, open_input, scan_pages, siteinfo, INFLECTION_TEMPLATE_EXISTS, NS_PATTERN,
+    REDIRECT_PATTERN, TEXT_PATTERN, TITLE_PATTERN,
+};
+
+use bzip2::write::BzEncoder;
+use bzip2::Compression;
+use clap::Args as ClapArgs;
+use std::collections::{BTreeMap, BinaryHeap};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// English sections shorter than these are small, then medium; others large
+const SIZE_LIMITS: [usize; 2] = [1024, 8 * 1024];
+
+const INFLECTION: u8 = 1;
+const ETYMOLOGY: u8 = 2;
+const PHRASE: u8 = 4;
+const IPA: u8 = 8;
+
+#[derive(ClapArgs, Debug)]
+pub struct MakeFixtureArgs {
+    /// Wiktionary dump (.xml or .xml.bz2, - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the fixture (.xml, or .xml.bz2 to compress)
+    pub output: PathBuf,
+
+    /// Number of English pages to keep
+    #[arg(long, default_value_t = 200)]
+    pub pages: usize,
+
+    /// Seed for choosing pages within a stratum
+    #[arg(long, default_value_t = 0)]
+    pub seed: u64,
+}
+
+/// A page's size class and feature bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct Stratum {
+    size: usize,
+    features: u8,
+}
+
+impl Stratum {
+    fn of(title: &str, english: &str) -> Self {
+        let flags = [
+            (INFLECTION, INFLECTION_TEMPLATE_EXISTS.is_match(english)),
+            (ETYMOLOGY, english.contains("===Etymology")),
+            (PHRASE, title.contains(' ')),
+            (IPA, english.contains("{{IPA|en|")),
+        ];
+        Stratum {
+            size: SIZE_LIMITS.iter().filter(|&&limit| english.len() >= limit).count(),
+            features: flags.iter().filter(|(_, set)| *set).fold(0, |bits, (bit, _)| bits | bit),
+        }
+    }
+}
+
+/// A candidate page: its sample point, its position in the dump, its XML
+type Candidate = (u64, usize, String);
+
+/// The pages of `reader` the fixture keeps, in dump order, with the number of
+/// strata they come from and of English pages seen
+fn pick_pages(reader: impl io::BufRead, pages: usize, seed: u64) -> io::Result<(Vec<String>, usize, usize)> {
+    // Per stratum, a max-heap on the sample point, so the worst kept page is on top
+    let mut strata: BTreeMap<Stratum, BinaryHeap<Candidate>> = BTreeMap::new();
+    let mut seen = 0;
+
+    scan_pages(reader, |page_xml| {
+        let in_main = NS_PATTERN.captures(&page_xml).is_none_or(|cap| &cap[1] == "0");
+        if !in_main || REDIRECT_PATTERN.is_match(&page_xml) {
+            return true;
+        }
+        let Some(title) = TITLE_PATTERN.captures(&page_xml).map(|cap| cap[1].to_string()) else {
+            return true;
+        };
+        let Some(english) = TEXT_PATTERN.captures(&page_xml).and_then(|cap| cap.get(1)).and_then(|text| extract_english_section(text.as_str())) else {
+            return true;
+        };
+        let stratum = Stratum::of(&title, english);
+
+        let point = sample_point(seed, &title).to_bits();
+        seen += 1;
+        let kept = strata.entry(stratum).or_default();
+        if kept.len() < pages {
+            kept.push((point, seen, page_xml));
+        } else if kept.peek().is_some_and(|top| point < top.0) {
+            kept.pop();
+            kept.push((point, seen, page_xml));
+        }
+        true
+    })?;
+
+    // Round robin over the strata, best sample point first within each
+    let strata: Vec<Vec<Candidate>> = strata.into_values().map(BinaryHeap::into_sorted_vec).collect();
+    let mut picked: Vec<Candidate> = Vec::new();
+    let mut used = 0;
+    for round in 0.. {
+        let mut any = false;
+        for stratum in &strata {
+            if let Some(page) = stratum.get(round).filter(|_| picked.len() < pages) {
+                picked.push(page.clone());
+                used += usize::from(round == 0);
+                any = true;
+            }
+        }
+        if !any {
+            break;
+        }
+    }
+    picked.sort_by_key(|(_, position, _)| *position);
+    Ok((picked.into_iter().map(|(_, _, xml)| xml).collect(), used, seen))
+}
+
+/// Write the fixture. Returns the number of pages written, of strata they
+/// come from and of English pages in the input.
+pub fn run_make_fixture(args: &MakeFixtureArgs, force: bool) -> io::Result<(usize, usize, usize)> {
+    let (reader, siteinfo) = siteinfo::read_siteinfo(open_input(&args.input)?)?;
+    let (pages, strata, seen) = pick_pages(reader, args.pages, args.seed)?;
+
+    let mut xml = String::from("<mediawiki>\n");
+    if let Some(siteinfo) = siteinfo {
+        xml.push_str("  ");
+        xml.push_str(&siteinfo);
+        xml.push('\n');
+    }
+    for page in &pages {
+        xml.push_str("  ");
+        xml.push_str(page);
+        xml.push('\n');
+    }
+    xml.push_str("</mediawiki>\n");
+
+    let (mut output, output_file) = create_output(&args.output, force)?;
+    if args.output.to_string_lossy().ends_with(".bz2") {
+        let mut encoder = BzEncoder::new(output, Compression::best());
+        encoder.write_all(xml.as_bytes())?;
+        encoder.finish()?.flush()?;
+    } else {
+        output.write_all(xml.as_bytes())?;
+        output.flush()?;
+        drop(output);
+    }
+    output_file.commit()?;
+    Ok((pages.len(), strata, seen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::open_inputs;
+
+    fn page(title: &str, body: &str) -> String {
+        format!("<page><title>{}</title><ns>0</ns><text>==English==\n{}\n==French==\n{{{{IPA|en|/x/}}}}</text></page>\n", title, body)
+    }
+
+    #[test]
+    fn strata_share_the_pages() {
+        let plain: String = (0..6).map(|i| page(&format!("word{}", i), "===Noun===\n# A thing.")).collect();
+        let xml = format!(
+            "{}{}{}{}<page><title>Talk:cat</title><ns>1</ns><text>==English==</text></page>\n",
+            plain,
+            page("cats", "===Noun===\n# {{plural of|en|cat}}"),
+            page("ice cream", "===Etymology===\nice + cream\n===Noun===\n# A dessert."),
+            page("long", &format!("===Pronunciation===\n* {{{{IPA|en|/lɒŋ/}}}}\n===Adjective===\n# {}", "x".repeat(9000))),
+        );
+        let (pages, strata, seen) = pick_pages(xml.as_bytes(), 4, 0).unwrap();
+        assert_eq!((strata, seen), (4, 9));
+        // One plain page, then every other stratum's only page, in dump order
+        let titles: Vec<String> = pages.iter().map(|p| TITLE_PATTERN.captures(p).unwrap()[1].to_string()).collect();
+        assert_eq!(titles.len(), 4);
+        assert!(titles[0].starts_with("word"));
+        assert_eq!(titles[1..], ["cats", "ice cream", "long"]);
+
+        // The French section's IPA doesn't put the plain pages in "long"'s stratum
+        assert_eq!(Stratum::of("long", "{{IPA|en|/x/}}").features, IPA);
+        assert_eq!(Stratum::of("ice cream", "===Etymology===").features, ETYMOLOGY | PHRASE);
+
+        let input = std::env::temp_dir().join(format!("fixture-{}-dump.xml", std::process::id()));
+        let output = std::env::temp_dir().join(format!("fixture-{}-out.xml.bz2", std::process::id()));
+        std::fs::write(&input, format!("<mediawiki>\n<siteinfo><sitename>W</sitename></siteinfo>\n{}</mediawiki>\n", xml)).unwrap();
+        let args = MakeFixtureArgs { input: input.clone(), output: output.clone(), pages: 2, seed: 0 };
+        assert_eq!(run_make_fixture(&args, true).unwrap(), (2, 2, 9));
+        let mut written = String::new();
+        open_inputs(std::slice::from_ref(&output)).unwrap().read_to_string(&mut written).unwrap();
+        assert!(written.starts_with("<mediawiki>\n  <siteinfo>"));
+        assert_eq!(written.matches("<page>").count(), 2);
+        std::fs::remove_file(input).unwrap();
+        std::fs::remove_file(output).unwrap();
+    }
+}
@@ -0,0 +1,180 @@
+//! Form-of relations on definition lines, from `schema/form_of.yaml`.
+//!
+//! A sense written as `{{synonym of|en|feline}}` or `{{short for|en|advert}}`
+//! only points at another entry. The schema maps each such template to a
+//! relation, and the sense gets `relation_type` and `relation_target`. The
+//! relations marked `variant` are non-standard spellings and also fill
+//! `variant_type` and `variant_of`; the templates a relation lists under
+//! `abbreviation` make a word `is_abbreviation`.
+
+use crate::page_ast::PageAst;
+use crate::{clean_lemma, find_schema_file, WikitextParser};
+
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+static FORM_OF: OnceCell<FormOf> = OnceCell::new();
+
+#[derive(Debug, Deserialize)]
+struct FormOfSchema {
+    relations: Vec<Relation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Relation {
+    #[serde(rename = "type")]
+    relation_type: String,
+    templates: Vec<String>,
+    #[serde(default)]
+    variant: bool,
+    #[serde(default)]
+    abbreviation: Vec<String>,
+}
+
+/// The schema with its template names indexed and matched
+pub struct FormOf {
+    relations: Vec<Relation>,
+    /// Lowercase template name → index into `relations`
+    by_name: HashMap<String, usize>,
+    /// The start of any listed template with an English term
+    template: Regex,
+    abbreviation_names: Vec<String>,
+    abbreviation: Regex,
+}
+
+/// A sense's relation to the entry its form-of template names
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormOfRelation {
+    pub relation_type: String,
+    pub target: String,
+    pub variant: bool,
+}
+
+/// `{{(?:name|alias|...)\|en\|`, matching the names case-insensitively
+fn template_start<'n>(names: impl Iterator<Item = &'n String>) -> Result<Regex, String> {
+    let alternatives: Vec<String> = names.map(|name| regex::escape(name)).collect();
+    Regex::new(&format!(r"(?i)\{{\{{(?:{})\|en\|", alternatives.join("|")))
+        .map_err(|e| format!("Failed to build form-of template pattern: {}", e))
+}
+
+impl FormOf {
+    fn new(schema: FormOfSchema) -> Result<Self, String> {
+        let mut by_name = HashMap::new();
+        for (index, relation) in schema.relations.iter().enumerate() {
+            for name in &relation.templates {
+                if by_name.insert(name.to_lowercase(), index).is_some() {
+                    return Err(format!("Form-of template {:?} is listed under two relations", name));
+                }
+            }
+            if let Some(name) = relation.abbreviation.iter().find(|name| !relation.templates.contains(name)) {
+                return Err(format!("Abbreviation template {:?} is not one of {:?}'s templates", name, relation.relation_type));
+            }
+        }
+        let abbreviation_names: Vec<String> = schema
+            .relations
+            .iter()
+            .flat_map(|relation| relation.abbreviation.iter().cloned())
+            .collect();
+        Ok(FormOf {
+            template: template_start(schema.relations.iter().flat_map(|relation| &relation.templates))?,
+            abbreviation: template_start(abbreviation_names.iter())?,
+            abbreviation_names,
+            relations: schema.relations,
+            by_name,
+        })
+    }
+
+    /// The relation of the first form-of template on a definition line
+    pub fn relation(&self, def_line: &str) -> Option<FormOfRelation> {
+        self.template.find_iter(def_line).find_map(|m| {
+            let template = WikitextParser::new(&def_line[m.start()..]).parse_template();
+            let relation = &self.relations[*self.by_name.get(&template.name.to_lowercase())?];
+            // params[0] is the language code
+            let target = clean_lemma(template.params.get(1)?);
+            (!target.is_empty()).then(|| FormOfRelation {
+                relation_type: relation.relation_type.clone(),
+                target,
+                variant: relation.variant,
+            })
+        })
+    }
+
+    /// Whether the page uses one of the abbreviation templates
+    pub fn is_abbreviation(&self, ast: &PageAst) -> bool {
+        let names: Vec<&str> = self.abbreviation_names.iter().map(String::as_str).collect();
+        ast.captures(&names, &self.abbreviation).is_some()
+    }
+}
+
+fn load_form_of_schema(schema_path: &PathBuf) -> Result<FormOf, String> {
+    let contents = std::fs::read_to_string(schema_path)
+        .map_err(|e| format!("Failed to open form-of schema file {:?}: {}", schema_path, e))?;
+    let schema: FormOfSchema = serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse form-of schema YAML: {}", e))?;
+    FormOf::new(schema)
+}
+
+pub fn init_form_of(schema_path: Option<&PathBuf>) -> Result<(), String> {
+    let path = if let Some(p) = schema_path {
+        p.clone()
+    } else {
+        find_schema_file("form_of.yaml")?
+    };
+    FORM_OF.set(load_form_of_schema(&path)?)
+        .map_err(|_| "FORM_OF already initialized".to_string())
+}
+
+pub fn get_form_of() -> &'static FormOf {
+    FORM_OF.get().expect("Form-of schema not initialized - call init_form_of() first")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schema() -> FormOf {
+        load_form_of_schema(&PathBuf::from("../../schema/form_of.yaml")).unwrap()
+    }
+
+    #[test]
+    fn relations_come_from_the_first_form_of_template() {
+        let form_of = schema();
+        let relation = |line: &str| form_of.relation(line).map(|r| (r.relation_type, r.target, r.variant));
+        assert_eq!(relation("{{Synonym of|en|[[feline]]}}"), Some(("synonym".into(), "feline".into(), false)));
+        assert_eq!(
+            relation("{{lb|en|informal}} {{short for|en|advertisement}}; {{syn of|en|ad}}"),
+            Some(("short_for".into(), "advertisement".into(), false))
+        );
+        assert_eq!(relation("{{missp|en|receive}}"), Some(("misspelling".into(), "receive".into(), true)));
+        // Inflections give a lemma instead, and other languages' terms are skipped
+        assert_eq!(relation("{{plural of|en|cat}}"), None);
+        assert_eq!(relation("{{synonym of|fr|chat}} {{female equivalent of|en|actor}}").unwrap().0, "female_equivalent");
+        assert_eq!(relation("{{synonym of|en|}}"), None);
+    }
+
+    #[test]
+    fn abbreviation_templates() {
+        let form_of = schema();
+        let is_abbreviation = |text: &str| form_of.is_abbreviation(&PageAst::parse(text));
+        for name in ["abbreviation of", "abbrev of", "abbr of", "initialism of"] {
+            assert!(is_abbreviation(&format!("# {{{{{}|en|for example}}}}", name)), "{}", name);
+        }
+        assert!(!is_abbreviation("# {{acronym of|en|light amplification by stimulated emission of radiation}}"));
+        assert!(!is_abbreviation("# {{init of|en|as soon as possible}}"));
+        assert_eq!(form_of.relation("{{init of|en|as soon as possible}}").unwrap().relation_type, "initialism");
+
+        let yaml = "relations:\n  - {type: a, templates: [x of], abbreviation: [y of]}\n";
+        let error = FormOf::new(serde_yaml::from_str(yaml).unwrap()).err().unwrap();
+        assert!(error.contains("not one of"), "{}", error);
+    }
+
+    #[test]
+    fn template_names_belong_to_one_relation() {
+        let yaml = "relations:\n  - {type: a, templates: [x of]}\n  - {type: b, templates: [X of]}\n";
+        let error = FormOf::new(serde_yaml::from_str(yaml).unwrap()).err().unwrap();
+        assert!(error.contains("two relations"), "{}", error);
+    }
+}
@@ -0,0 +1,549 @@
+//! Predicted inflections of lemmas, with `generate-forms`.
+//!
+//! Wiktionary has pages for the inflections of most common words, but many
+//! nouns' plurals, verbs' past tenses and adjectives' comparatives have none,
+//! so a lexicon built from the dump lacks them. This pass reads scanner output
+//! and works out the inflections of each single-word noun, verb and adjective:
+//! from the parameters of its `{{en-noun}}`, `{{en-verb}}` or `{{en-adj}}`
+//! headword when the scan kept it (`--headwords`), else from the regular
+//! spelling rules. Forms that are already words in the output are skipped,
+//! as are inflections the output already has another word for (no "goed"
+//! next to "went"); the rest are written as entries of their own, flagged
+//! `generated`:
+//!
+//! ```json
+//! {"id":"abseilers","pos":"NOU","wc":1,"is_inflected":true,"lemma":"abseiler","inflection_tags":["plural"],"generated":true}
+//! ```
+
+use crate::{create_output, open_input, ParsedTemplate, WikitextParser};
+
+use clap::Args as ClapArgs;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+const PLURAL: &[&str] = &["plural"];
+const THIRD_PERSON: &[&str] = &["third-person", "singular", "present"];
+const PRESENT_PARTICIPLE: &[&str] = &["present", "participle"];
+const PAST: &[&str] = &["past"];
+const PAST_PARTICIPLE: &[&str] = &["past", "participle"];
+const COMPARATIVE: &[&str] = &["comparative"];
+const SUPERLATIVE: &[&str] = &["superlative"];
+
+// Tags that {{inflection of}} adds without changing which inflection it is
+const NEUTRAL_TAGS: &[&str] = &["simple", "indicative"];
+
+// Endings from the old stem-and-ending {{en-verb|tr|ies|ying|ied}} syntax
+const VERB_ENDINGS: &[&str] = &["s", "es", "ies", "d", "ed", "ied", "ing", "ying"];
+
+lazy_static! {
+    // Inline modifiers on a form: {{en-verb|+|+|~|~,~ten<l:dialectal>}}
+    static ref MODIFIER: Regex = Regex::new(r"<[a-z]+:[^>]*>").unwrap();
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct GenerateFormsArgs {
+    /// Scanner output JSONL, best scanned with --headwords (- for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the generated entries JSONL (- for stdout)
+    pub output: PathBuf,
+}
+
+/// The fields of a sense the pass needs
+#[derive(Deserialize)]
+struct SenseRecord {
+    pos: String,
+    #[serde(default)]
+    is_proper_noun: bool,
+    #[serde(default)]
+    uncountable: bool,
+    #[serde(default)]
+    plural_only: bool,
+    headword: Option<String>,
+}
+
+/// A line of output in either shape: a sense, or a word with its `senses`
+#[derive(Deserialize)]
+struct WordRecord {
+    id: String,
+    #[serde(default)]
+    wc: usize,
+    #[serde(default)]
+    is_abbreviation: bool,
+    #[serde(default)]
+    is_inflected: bool,
+    pos: Option<String>,
+    #[serde(default)]
+    is_proper_noun: bool,
+    #[serde(default)]
+    uncountable: bool,
+    #[serde(default)]
+    plural_only: bool,
+    headword: Option<String>,
+    lemma: Option<String>,
+    #[serde(default)]
+    inflection_tags: Vec<String>,
+    #[serde(default)]
+    senses: Vec<SenseRecord>,
+}
+
+impl WordRecord {
+    fn into_senses(self) -> Vec<SenseRecord> {
+        match self.pos {
+            Some(pos) => vec![SenseRecord {
+                pos,
+                is_proper_noun: self.is_proper_noun,
+                uncountable: self.uncountable,
+                plural_only: self.plural_only,
+                headword: self.headword,
+            }],
+            None => self.senses,
+        }
+    }
+}
+
+/// One line of the output
+#[derive(Serialize)]
+struct GeneratedEntry<'a> {
+    id: &'a str,
+    pos: &'a str,
+    wc: usize,
+    is_inflected: bool,
+    lemma: &'a str,
+    inflection_tags: &'a [&'a str],
+    generated: bool,
+}
+
+/// A predicted form and the inflection it is
+type Form = (String, &'static [&'static str]);
+
+/// The forms of a word under its headword template, if the scan kept one
+type Inflect = fn(&str, Option<&ParsedTemplate>) -> Vec<Form>;
+
+fn is_vowel(ch: char) -> bool {
+    matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u')
+}
+
+/// Words the rules can spell: letters, hyphens and apostrophes
+fn is_plain(word: &str) -> bool {
+    !word.is_empty() && word.chars().all(|ch| ch.is_alphabetic() || ch == '-' || ch == '\'')
+}
+
+/// "try", but not "day"
+fn ends_in_consonant_y(word: &str) -> bool {
+    let mut chars = word.chars().rev();
+    chars.next() == Some('y') && chars.next().is_some_and(|ch| !is_vowel(ch))
+}
+
+fn ends_in_sibilant(word: &str) -> bool {
+    ["s", "x", "z", "ch", "sh"].iter().any(|ending| word.ends_with(ending))
+}
+
+/// One syllable ending in a single vowel and consonant ("stop", "big"), whose
+/// consonant is doubled before a vowel
+fn doubles_final_consonant(word: &str) -> bool {
+    let chars: Vec<char> = word.chars().collect();
+    let vowel_groups = chars.iter().enumerate().filter(|(i, ch)| is_vowel(**ch) && (*i == 0 || !is_vowel(chars[i - 1]))).count();
+    match chars[..] {
+        [.., before, vowel, last] => {
+            vowel_groups == 1 && !is_vowel(before) && is_vowel(vowel) && !is_vowel(last) && !matches!(last, 'w' | 'x' | 'y')
+        }
+        _ => false,
+    }
+}
+
+/// `word` with an ending that starts with a vowel, doubling the final consonant if asked
+fn with_ending(word: &str, ending: &str, double: bool) -> String {
+    match word.chars().last() {
+        Some(last) if double => format!("{}{}{}", word, last, ending),
+        _ => format!("{}{}", word, ending),
+    }
+}
+
+/// cats, boxes, flies
+fn regular_plural(word: &str) -> String {
+    if ends_in_consonant_y(word) {
+        format!("{}ies", &word[..word.len() - 1])
+    } else if ends_in_sibilant(word) {
+        format!("{}es", word)
+    } else {
+        format!("{}s", word)
+    }
+}
+
+fn present_participle(word: &str, double: bool) -> String {
+    if let Some(stem) = word.strip_suffix("ie") {
+        format!("{}ying", stem)
+    } else if word.len() > 2 && word.ends_with('e') && !["ee", "oe", "ye"].iter().any(|ending| word.ends_with(ending)) {
+        format!("{}ing", &word[..word.len() - 1])
+    } else {
+        with_ending(word, "ing", double)
+    }
+}
+
+fn past(word: &str, double: bool) -> String {
+    if word.ends_with('e') {
+        format!("{}d", word)
+    } else if ends_in_consonant_y(word) {
+        format!("{}ied", &word[..word.len() - 1])
+    } else {
+        with_ending(word, "ed", double)
+    }
+}
+
+/// bigger, nicer, happier
+fn regular_comparative(word: &str) -> String {
+    if word.ends_with('e') {
+        format!("{}r", word)
+    } else if ends_in_consonant_y(word) {
+        format!("{}ier", &word[..word.len() - 1])
+    } else {
+        with_ending(word, "er", doubles_final_consonant(word))
+    }
+}
+
+/// The values of a headword parameter, which can list several ("er,more")
+fn values(param: &str) -> Vec<String> {
+    MODIFIER
+        .replace_all(param, "")
+        .split(',')
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+        .collect()
+}
+
+/// Plurals from `{{en-noun}}`: `s`, `es` and `'s` add that ending, `ies`
+/// replaces a final "y" or "ey" ("turkies"), and `+` or no plural at all
+/// gives the regular one. `-` marks the noun uncountable, `~` countable as
+/// well, and `!`/`?` an unattested or unknown plural. Anything else is a
+/// plural as written.
+fn noun_forms(word: &str, template: Option<&ParsedTemplate>) -> Vec<Form> {
+    let Some(template) = template else {
+        return vec![(regular_plural(word), PLURAL)];
+    };
+    let mut plurals = Vec::new();
+    let mut countable = true;
+    for value in template.params.iter().flat_map(|param| values(param)) {
+        match value.as_str() {
+            "~" => {}
+            "-" => countable = false,
+            "!" | "?" => return Vec::new(),
+            "s" => plurals.push(format!("{}s", word)),
+            "es" => plurals.push(format!("{}es", word)),
+            "'s" => plurals.push(format!("{}'s", word)),
+            "ies" => {
+                let stem = word.strip_suffix("ey").or_else(|| word.strip_suffix('y')).unwrap_or(word);
+                plurals.push(format!("{}ies", stem));
+            }
+            "+" => plurals.push(regular_plural(word)),
+            plural => plurals.push(plural.to_string()),
+        }
+    }
+    if plurals.is_empty() && countable {
+        plurals.push(regular_plural(word));
+    }
+    plurals.into_iter().map(|plural| (plural, PLURAL)).collect()
+}
+
+/// Forms from `{{en-verb}}`: the third-person singular, present participle,
+/// past and past participle in that order. Each is the default when left out
+/// or `+` and missing when `-`; `~` stands for the lemma (`~ed`). One-syllable
+/// verbs double their final consonant ("stop" → "stopped"), and `++` doubles
+/// it in longer ones ("refer" → "referred"); a first parameter `+,++` gives
+/// both. The past participle defaults to the past.
+fn verb_forms(word: &str, template: Option<&ParsedTemplate>) -> Vec<Form> {
+    let slots: Vec<Vec<String>> = template.map(|t| t.params.iter().map(|p| values(p)).collect()).unwrap_or_default();
+    if slots.iter().flatten().any(|value| VERB_ENDINGS.contains(&value.as_str())) {
+        return Vec::new();
+    }
+    let default_double = doubles_final_consonant(word);
+    let mut doubles: Vec<bool> = slots
+        .first()
+        .into_iter()
+        .flatten()
+        .filter_map(|value| match value.as_str() {
+            "+" => Some(default_double),
+            "++" => Some(true),
+            _ => None,
+        })
+        .collect();
+    doubles.dedup();
+    if doubles.is_empty() {
+        doubles.push(default_double);
+    }
+
+    // A slot's forms, given its default forms and its regular form with a doubled consonant
+    let slot = |index: usize, defaults: Vec<String>, doubled: String| -> Vec<String> {
+        let Some(values) = slots.get(index).filter(|values| !values.is_empty()) else {
+            return defaults;
+        };
+        let mut forms = Vec::new();
+        for value in values {
+            match value.as_str() {
+                "+" => forms.extend(defaults.iter().cloned()),
+                "++" => forms.push(doubled.clone()),
+                "-" => {}
+                value => match value.strip_prefix('~') {
+                    Some(ending) => forms.push(format!("{}{}", word, ending)),
+                    // One-letter shorthands the rules don't know
+                    None if value.chars().count() == 1 => {}
+                    None => forms.push(value.to_string()),
+                },
+            }
+        }
+        forms.dedup();
+        forms
+    };
+    let regular = |inflect: fn(&str, bool) -> String| doubles.iter().map(|&double| inflect(word, double)).collect();
+    let pasts = slot(2, regular(past), past(word, true));
+
+    let tagged = |forms: Vec<String>, tags: &'static [&'static str]| forms.into_iter().map(move |form| (form, tags));
+    let third_person = regular_plural(word);
+    tagged(slot(0, vec![third_person.clone()], third_person), THIRD_PERSON)
+        .chain(tagged(slot(1, regular(present_participle), present_participle(word, true)), PRESENT_PARTICIPLE))
+        .chain(tagged(pasts.clone(), PAST))
+        .chain(tagged(slot(3, pasts, past(word, true)), PAST_PARTICIPLE))
+        .collect()
+}
+
+/// Forms from `{{en-adj}}`: `er` gives the regular comparative and
+/// superlative, and a comparative written out ("redder") its superlative
+/// unless `sup=` gives them. `more` (the default), `-` and other values give
+/// no forms of their own.
+fn adjective_forms(word: &str, template: Option<&ParsedTemplate>) -> Vec<Form> {
+    let Some(template) = template else {
+        return Vec::new();
+    };
+    let superlatives = template.named.get("sup").map(|sup| values(sup)).unwrap_or_default();
+    let mut forms = Vec::new();
+    for value in template.params.iter().flat_map(|param| values(param)) {
+        let comparative = match value.as_str() {
+            "er" => regular_comparative(word),
+            comparative if comparative.len() > 2 && comparative.ends_with("er") => value.clone(),
+            _ => continue,
+        };
+        let derived = format!("{}est", &comparative[..comparative.len() - 2]);
+        forms.push((comparative, COMPARATIVE));
+        if superlatives.is_empty() {
+            forms.push((derived, SUPERLATIVE));
+        }
+    }
+    if !forms.is_empty() {
+        forms.extend(superlatives.into_iter().map(|superlative| (superlative, SUPERLATIVE)));
+    }
+    forms
+}
+
+/// The predicted forms of one sense of a lemma. A headword line without the
+/// template its part of speech takes gives none.
+fn sense_forms(word: &str, sense: &SenseRecord) -> Vec<Form> {
+    let (template_name, inflect): (&str, Inflect) = match sense.pos.as_str() {
+        "NOU" if sense.plural_only || sense.headword.is_none() && sense.uncountable => return Vec::new(),
+        "NOU" => ("en-noun", noun_forms),
+        "VRB" => ("en-verb", verb_forms),
+        "ADJ" => ("en-adj", adjective_forms),
+        _ => return Vec::new(),
+    };
+    let template = match &sense.headword {
+        Some(line) => {
+            let template = WikitextParser::new(line).parse_template();
+            // Inline specs for the words of a phrase ({{en-verb|run<,,ran>}}) are left alone
+            let inline_spec = || template.params.iter().any(|param| MODIFIER.replace_all(param, "").contains('<'));
+            if !template.name.trim().eq_ignore_ascii_case(template_name) || inline_spec() {
+                return Vec::new();
+            }
+            Some(template)
+        }
+        None => None,
+    };
+    inflect(word, template.as_ref())
+}
+
+/// An inflection of a lemma: the lemma and its sorted tags
+type Inflection = (String, Vec<String>);
+
+fn inflection<'a>(lemma: &str, tags: impl IntoIterator<Item = &'a str>) -> Inflection {
+    let mut tags: Vec<String> = tags
+        .into_iter()
+        .filter(|tag| !NEUTRAL_TAGS.contains(tag))
+        .map(str::to_string)
+        .collect();
+    tags.sort();
+    (lemma.to_string(), tags)
+}
+
+/// The lemmas that can be inflected, every word of the output, and the
+/// inflections its form-of entries already give
+struct Lexicon {
+    lemmas: Vec<(String, SenseRecord)>,
+    words: HashSet<String>,
+    inflected: HashSet<Inflection>,
+}
+
+fn read_lexicon<R: BufRead>(reader: R) -> io::Result<Lexicon> {
+    let mut lexicon = Lexicon { lemmas: Vec::new(), words: HashSet::new(), inflected: HashSet::new() };
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: WordRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Malformed line in input: {}", e)))?;
+        let id = record.id.clone();
+        if let Some(lemma) = &record.lemma {
+            lexicon.inflected.insert(inflection(lemma, record.inflection_tags.iter().map(String::as_str)));
+        }
+        let lemma = record.wc == 1
+            && !record.is_inflected
+            && !record.is_abbreviation
+            && is_plain(&id)
+            && id.ends_with(|ch: char| ch.is_ascii_lowercase());
+        if lemma {
+            let senses = record.into_senses().into_iter().filter(|sense| !sense.is_proper_noun);
+            lexicon.lemmas.extend(senses.map(|sense| (id.clone(), sense)));
+        }
+        lexicon.words.insert(id);
+    }
+    Ok(lexicon)
+}
+
+/// Write the generated entries for the lemmas of `input`. Returns the number
+/// of entries and of lemmas they inflect.
+pub fn generate_forms<R: BufRead, W: Write>(input: R, writer: &mut W) -> io::Result<(usize, usize)> {
+    let lexicon = read_lexicon(input)?;
+    let mut written = HashSet::new();
+    let mut lemmas = HashSet::new();
+    for (lemma, sense) in &lexicon.lemmas {
+        for (form, tags) in sense_forms(lemma, sense) {
+            if form == *lemma || !is_plain(&form) || lexicon.words.contains(&form) {
+                continue;
+            }
+            // An irregular form has the inflection's entry: went, not goed
+            if lexicon.inflected.contains(&inflection(lemma, tags.iter().copied())) {
+                continue;
+            }
+            if !written.insert((form.clone(), sense.pos.clone(), lemma.clone(), tags)) {
+                continue;
+            }
+            lemmas.insert(lemma.as_str());
+            let entry = GeneratedEntry {
+                id: &form,
+                pos: &sense.pos,
+                wc: 1,
+                is_inflected: true,
+                lemma,
+                inflection_tags: tags,
+                generated: true,
+            };
+            serde_json::to_writer(&mut *writer, &entry)?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    Ok((written.len(), lemmas.len()))
+}
+
+/// Write the generated entries for `args.input` to `args.output`
+pub fn run_generate_forms(args: &GenerateFormsArgs, force: bool) -> io::Result<(usize, usize)> {
+    let input = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let counts = generate_forms(input, &mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn forms(pos: &str, word: &str, headword: Option<&str>) -> Vec<String> {
+        let sense = SenseRecord {
+            pos: pos.to_string(),
+            is_proper_noun: false,
+            uncountable: false,
+            plural_only: false,
+            headword: headword.map(str::to_string),
+        };
+        sense_forms(word, &sense).into_iter().map(|(form, _)| form).collect()
+    }
+
+    #[test]
+    fn regular_rules_and_headword_parameters() {
+        assert_eq!(forms("NOU", "fly", None), ["flies"]);
+        assert_eq!(forms("NOU", "box", Some("{{en-noun}}")), ["boxes"]);
+        assert_eq!(forms("NOU", "bus", Some("{{en-noun|es,busses}}")), ["buses", "busses"]);
+        assert_eq!(forms("NOU", "mouse", Some("{{en-noun|mice}}")), ["mice"]);
+        assert_eq!(forms("NOU", "turkey", Some("{{en-noun|s|ies<l:obsolete>}}")), ["turkeys", "turkies"]);
+        assert_eq!(forms("NOU", "a", Some("{{en-noun|'s|es<l:rare>}}")), ["a's", "aes"]);
+        assert_eq!(forms("NOU", "rice", Some("{{en-noun|-}}")), Vec::<String>::new());
+        assert_eq!(forms("NOU", "beer", Some("{{en-noun|~}}")), ["beers"]);
+        assert_eq!(forms("NOU", "scissors", Some("{{en-plural noun}}")), Vec::<String>::new());
+
+        assert_eq!(forms("VRB", "stop", None), ["stops", "stopping", "stopped", "stopped"]);
+        assert_eq!(forms("VRB", "visit", Some("{{en-verb}}")), ["visits", "visiting", "visited", "visited"]);
+        assert_eq!(forms("VRB", "refer", Some("{{en-verb|++}}")), ["refers", "referring", "referred", "referred"]);
+        assert_eq!(forms("VRB", "tie", None), ["ties", "tying", "tied", "tied"]);
+        assert_eq!(forms("VRB", "bat", Some("{{en-verb}}")), ["bats", "batting", "batted", "batted"]);
+        assert_eq!(forms("VRB", "may", Some("{{en-verb|~|-|might|-,mought<l:obsolete>}}")), ["may", "might", "mought"]);
+        assert_eq!(forms("VRB", "set", Some("{{en-verb|~s|~ting|~|+,~ten<l:dialectal>}}")), ["sets", "setting", "set", "set", "setten"]);
+        assert_eq!(forms("VRB", "acronym", Some("{{en-verb|+,++<l:rare>}}")).len(), 7);
+        assert_eq!(forms("VRB", "run", Some("{{en-verb|run<,,ran,run:ran>}}")), Vec::<String>::new());
+        assert_eq!(forms("VRB", "go", Some("{{en-verb|goes|going|went|gone}}")), ["goes", "going", "went", "gone"]);
+        assert_eq!(forms("VRB", "cry", Some("{{en-verb|+|+|cried}}")), ["cries", "crying", "cried", "cried"]);
+        assert_eq!(forms("VRB", "try", Some("{{en-verb|tr|ies|ying|ied}}")), Vec::<String>::new());
+
+        assert_eq!(forms("ADJ", "big", Some("{{en-adj|er}}")), ["bigger", "biggest"]);
+        assert_eq!(forms("ADJ", "happy", Some("{{en-adj|er,more}}")), ["happier", "happiest"]);
+        assert_eq!(forms("ADJ", "good", Some("{{en-adj|better|sup=best}}")), ["better", "best"]);
+        assert_eq!(forms("ADJ", "careful", Some("{{en-adj}}")), Vec::<String>::new());
+        assert_eq!(forms("ADJ", "nice", None), Vec::<String>::new());
+    }
+
+    #[test]
+    fn forms_with_pages_are_skipped() {
+        let jsonl = concat!(
+            r#"{"id":"cat","pos":"NOU","wc":1,"headword":"{{en-noun}}"}"#, "\n",
+            r#"{"id":"cats","pos":"NOU","wc":1,"is_inflected":true,"lemma":"cat","inflection_tags":["plural"]}"#, "\n",
+            r#"{"id":"abseil","wc":1,"senses":[{"pos":"VRB","headword":"{{en-verb}}"},{"pos":"NOU","headword":"{{en-noun}}"}]}"#, "\n",
+            r#"{"id":"abseiled","pos":"VRB","wc":1,"is_inflected":true,"lemma":"abseil","inflection_tags":["past"]}"#, "\n",
+            r#"{"id":"Paris","pos":"NAM","wc":1}"#, "\n",
+            r#"{"id":"ice cream","pos":"NOU","wc":2}"#, "\n",
+        );
+        let mut out = Vec::new();
+        assert_eq!(generate_forms(jsonl.as_bytes(), &mut out).unwrap(), (3, 1));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"id":"abseils","pos":"VRB","wc":1,"is_inflected":true,"lemma":"abseil","inflection_tags":["third-person","singular","present"],"generated":true}"#, "\n",
+                r#"{"id":"abseiling","pos":"VRB","wc":1,"is_inflected":true,"lemma":"abseil","inflection_tags":["present","participle"],"generated":true}"#, "\n",
+                r#"{"id":"abseils","pos":"NOU","wc":1,"is_inflected":true,"lemma":"abseil","inflection_tags":["plural"],"generated":true}"#, "\n",
+            )
+        );
+    }
+
+    #[test]
+    fn irregular_inflections_are_not_regularised() {
+        let jsonl = concat!(
+            r#"{"id":"go","pos":"VRB","wc":1}"#, "\n",
+            r#"{"id":"goes","pos":"VRB","wc":1,"is_inflected":true,"lemma":"go","inflection_tags":["third-person","singular","simple","present","indicative"]}"#, "\n",
+            r#"{"id":"went","pos":"VRB","wc":1,"is_inflected":true,"lemma":"go","inflection_tags":["past"]}"#, "\n",
+            r#"{"id":"gone","pos":"VRB","wc":1,"is_inflected":true,"lemma":"go","inflection_tags":["past","participle"]}"#, "\n",
+            r#"{"id":"child","wc":1,"senses":[{"pos":"NOU"}]}"#, "\n",
+            r#"{"id":"children","wc":1,"is_inflected":true,"lemma":"child","inflection_tags":["plural"],"senses":[{"pos":"NOU"}]}"#, "\n",
+        );
+        let mut out = Vec::new();
+        assert_eq!(generate_forms(jsonl.as_bytes(), &mut out).unwrap(), (1, 1));
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            concat!(
+                r#"{"id":"going","pos":"VRB","wc":1,"is_inflected":true,"lemma":"go","inflection_tags":["present","participle"],"generated":true}"#, "\n",
+            )
+        );
+    }
+}
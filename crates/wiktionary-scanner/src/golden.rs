@@ -0,0 +1,171 @@
+//! Golden-entry regression checks.
+//!
+//! A golden directory holds `cases.jsonl`, one `{"title": ..., "entries": [...]}`
+//! line per title, and `pages/<title>.wikitext` with each page's source as
+//! `fetch-page --raw` prints it ("/" in a title is written as "%2F"). The
+//! `golden` subcommand runs parse_page over every page and diffs the entries
+//! against the expected ones field by field; `--bless` rewrites the
+//! expectations from the current output after an intended change.
+
+use crate::fetch::escape_like_dump;
+use crate::parse_page;
+
+use clap::Args as ClapArgs;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(ClapArgs, Debug)]
+pub struct GoldenArgs {
+    /// Directory with cases.jsonl and pages/
+    #[arg(default_value = "tests/golden")]
+    pub dir: PathBuf,
+
+    /// Replace the expected entries with the current output
+    #[arg(long)]
+    pub bless: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoldenCase {
+    pub title: String,
+    /// Expected entries; a title may be expected to yield none
+    #[serde(default)]
+    pub entries: Vec<Value>,
+}
+
+/// Where the source of `title` is stored
+pub fn page_path(dir: &Path, title: &str) -> PathBuf {
+    dir.join("pages").join(format!("{}.wikitext", title.replace('/', "%2F")))
+}
+
+pub fn read_cases(path: &Path) -> io::Result<Vec<GoldenCase>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut cases = Vec::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let case = serde_json::from_str(&line).map_err(|e| {
+            io::Error::other(format!("{}:{}: malformed golden case: {}", path.display(), number + 1, e))
+        })?;
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+/// Entries parse_page currently extracts from the stored page
+pub fn current_entries(dir: &Path, title: &str) -> io::Result<Vec<Value>> {
+    let path = page_path(dir, title);
+    let wikitext = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    parse_page(title, &escape_like_dump(&wikitext))
+        .iter()
+        .map(|entry| serde_json::to_value(entry).map_err(io::Error::from))
+        .collect()
+}
+
+/// One line per difference: changed fields of matching entries, then missing
+/// or unexpected entries. Empty when the entries are equal.
+pub fn diff_entries(expected: &[Value], actual: &[Value]) -> Vec<String> {
+    let mut diffs = Vec::new();
+    for index in 0..expected.len().max(actual.len()) {
+        match (expected.get(index), actual.get(index)) {
+            (Some(Value::Object(want)), Some(Value::Object(got))) => {
+                let keys: BTreeSet<&String> = want.keys().chain(got.keys()).collect();
+                for key in keys {
+                    let (want, got) = (want.get(key), got.get(key));
+                    if want != got {
+                        diffs.push(format!(
+                            "entry {}: {}: expected {}, got {}",
+                            index,
+                            key,
+                            want.map_or("(absent)".to_string(), Value::to_string),
+                            got.map_or("(absent)".to_string(), Value::to_string),
+                        ));
+                    }
+                }
+            }
+            (Some(want), Some(got)) if want != got => {
+                diffs.push(format!("entry {}: expected {}, got {}", index, want, got));
+            }
+            (Some(want), None) => diffs.push(format!("entry {}: missing, expected {}", index, want)),
+            (None, Some(got)) => diffs.push(format!("entry {}: unexpected {}", index, got)),
+            _ => {}
+        }
+    }
+    diffs
+}
+
+/// Check (or with --bless, rewrite) every case. Returns false if any differ.
+pub fn run_golden(args: &GoldenArgs) -> io::Result<bool> {
+    let cases_path = args.dir.join("cases.jsonl");
+    let mut cases = read_cases(&cases_path)?;
+
+    if args.bless {
+        let mut out = Vec::new();
+        for case in &mut cases {
+            case.entries = current_entries(&args.dir, &case.title)?;
+            writeln!(out, "{}", serde_json::to_string(case)?)?;
+        }
+        fs::write(&cases_path, out)?;
+        println!("Blessed {} golden titles in {}", cases.len(), cases_path.display());
+        return Ok(true);
+    }
+
+    let mut passed = 0;
+    for case in &cases {
+        let diffs = diff_entries(&case.entries, &current_entries(&args.dir, &case.title)?);
+        if diffs.is_empty() {
+            passed += 1;
+            println!("ok    {}", case.title);
+        } else {
+            println!("FAIL  {}", case.title);
+            for diff in diffs {
+                println!("      {}", diff);
+            }
+        }
+    }
+    println!("{} of {} golden titles match", passed, cases.len());
+    Ok(passed == cases.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn diff_reports_fields_and_extra_entries() {
+        let expected = vec![
+            json!({"id": "cat", "pos": "NOU", "wc": 1}),
+            json!({"id": "cat", "pos": "VRB", "wc": 1}),
+        ];
+        let actual = vec![
+            json!({"id": "cat", "pos": "NOU", "wc": 1, "is_phrase": true}),
+            json!({"id": "cat", "pos": "ADJ", "wc": 1}),
+            json!({"id": "cat", "pos": "ADV", "wc": 1}),
+        ];
+        assert_eq!(
+            diff_entries(&expected, &actual),
+            vec![
+                "entry 0: is_phrase: expected (absent), got true",
+                r#"entry 1: pos: expected "VRB", got "ADJ""#,
+                r#"entry 2: unexpected {"id":"cat","pos":"ADV","wc":1}"#,
+            ]
+        );
+        assert!(diff_entries(&expected, &expected).is_empty());
+    }
+
+    #[test]
+    fn titles_with_slashes_map_to_one_file() {
+        assert_eq!(
+            page_path(Path::new("golden"), "AC/DC"),
+            Path::new("golden/pages/AC%2FDC.wikitext")
+        );
+    }
+}
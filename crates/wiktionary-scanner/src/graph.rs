@@ -0,0 +1,275 @@
+//! Word-formation graph export.
+//!
+//! `export graph` turns the morphology fields of scanner output into a
+//! directed graph for Graphviz, Gephi or a graph database. Nodes are words
+//! and edges point from a source to the word formed from it: from the base
+//! of a derivation (labelled with the morphology type, e.g. "suffixed" or
+//! "clipping") and from each constituent of a compound ("compound"). With
+//! `--affixes`, prefixes, suffixes and interfixes become nodes too, with
+//! "affix" edges to the words they form.
+//!
+//! Senses of the same word share morphology, so each edge is written once.
+
+use crate::fetch::escape_like_dump;
+use crate::{create_output, open_input};
+
+use clap::{Args as ClapArgs, ValueEnum};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// GraphML, with `kind` on nodes and `relation` on edges
+    Graphml,
+}
+
+#[derive(ClapArgs, Debug)]
+pub struct GraphArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the graph (- for stdout)
+    pub output: PathBuf,
+
+    /// Graph file format
+    #[arg(long, value_enum, default_value_t)]
+    pub format: GraphFormat,
+
+    /// Add affixes as nodes, with edges to the words formed with them
+    #[arg(long)]
+    pub affixes: bool,
+}
+
+/// Only the fields the graph needs
+#[derive(Deserialize)]
+struct MorphologyRecord {
+    id: String,
+    morphology: Option<MorphologyFields>,
+}
+
+#[derive(Deserialize)]
+struct MorphologyFields {
+    #[serde(rename = "type")]
+    morph_type: String,
+    base: Option<String>,
+    #[serde(default)]
+    components: Vec<String>,
+    #[serde(default)]
+    prefixes: Vec<String>,
+    #[serde(default)]
+    suffixes: Vec<String>,
+    #[serde(default)]
+    interfixes: Vec<String>,
+    #[serde(default)]
+    is_compound: bool,
+}
+
+/// What a node stands for: a word in the input, an affix, or a source word
+/// (a base or constituent) with no entry of its own
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum NodeKind {
+    Word,
+    Affix,
+    Source,
+}
+
+impl NodeKind {
+    fn name(self) -> &'static str {
+        match self {
+            NodeKind::Word => "word",
+            NodeKind::Affix => "affix",
+            NodeKind::Source => "source",
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub nodes: BTreeMap<String, NodeKind>,
+    /// (from, to, relation)
+    pub edges: BTreeSet<(String, String, String)>,
+}
+
+fn is_affix(term: &str) -> bool {
+    term.starts_with('-') || term.ends_with('-')
+}
+
+/// Template parameters that leaked into a component (`gloss1=two`) aren't words
+fn is_term(term: &str, word: &str) -> bool {
+    !term.is_empty() && !term.contains('=') && term != word
+}
+
+impl Graph {
+    fn add_node(&mut self, name: &str, kind: NodeKind) {
+        // A word in the input stays a word when it is also some other word's source
+        let current = self.nodes.entry(name.to_string()).or_insert(kind);
+        *current = (*current).min(kind);
+    }
+
+    fn add_edge(&mut self, from: &str, to: &str, relation: &str, kind: NodeKind) {
+        self.add_node(from, kind);
+        self.edges.insert((from.to_string(), to.to_string(), relation.to_string()));
+    }
+
+    fn add_word(&mut self, word: &str, morphology: &MorphologyFields, affixes: bool) {
+        self.add_node(word, NodeKind::Word);
+        if morphology.is_compound {
+            for part in &morphology.components {
+                if is_term(part, word) && !is_affix(part) {
+                    self.add_edge(part, word, "compound", NodeKind::Source);
+                }
+            }
+        } else if let Some(base) = morphology.base.as_deref().filter(|base| is_term(base, word)) {
+            self.add_edge(base, word, &morphology.morph_type, NodeKind::Source);
+        }
+        if affixes {
+            let all = morphology.prefixes.iter().chain(&morphology.suffixes).chain(&morphology.interfixes);
+            for affix in all.filter(|affix| is_term(affix, word)) {
+                self.add_edge(affix, word, "affix", NodeKind::Affix);
+            }
+        }
+    }
+}
+
+pub fn collect_graph<R: BufRead>(reader: R, affixes: bool) -> io::Result<Graph> {
+    let mut graph = Graph::default();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: MorphologyRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        if let Some(morphology) = record.morphology {
+            graph.add_word(&record.id, &morphology, affixes);
+        }
+    }
+    Ok(graph)
+}
+
+/// A DOT quoted ID
+fn dot_id(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn write_dot<W: Write>(out: &mut W, graph: &Graph) -> io::Result<()> {
+    writeln!(out, "digraph morphology {{")?;
+    for (name, kind) in &graph.nodes {
+        match kind {
+            NodeKind::Word => writeln!(out, "  {};", dot_id(name))?,
+            NodeKind::Affix => writeln!(out, "  {} [shape=box];", dot_id(name))?,
+            NodeKind::Source => writeln!(out, "  {} [style=dashed];", dot_id(name))?,
+        }
+    }
+    for (from, to, relation) in &graph.edges {
+        writeln!(out, "  {} -> {} [label={}];", dot_id(from), dot_id(to), dot_id(relation))?;
+    }
+    writeln!(out, "}}")
+}
+
+pub fn write_graphml<W: Write>(out: &mut W, graph: &Graph) -> io::Result<()> {
+    writeln!(out, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(out, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    writeln!(out, r#"  <key id="kind" for="node" attr.name="kind" attr.type="string"/>"#)?;
+    writeln!(out, r#"  <key id="relation" for="edge" attr.name="relation" attr.type="string"/>"#)?;
+    writeln!(out, r#"  <graph id="morphology" edgedefault="directed">"#)?;
+    for (name, kind) in &graph.nodes {
+        writeln!(
+            out,
+            r#"    <node id="{}"><data key="kind">{}</data></node>"#,
+            escape_like_dump(name),
+            kind.name()
+        )?;
+    }
+    for (from, to, relation) in &graph.edges {
+        writeln!(
+            out,
+            r#"    <edge source="{}" target="{}"><data key="relation">{}</data></edge>"#,
+            escape_like_dump(from),
+            escape_like_dump(to),
+            escape_like_dump(relation)
+        )?;
+    }
+    writeln!(out, "  </graph>")?;
+    writeln!(out, "</graphml>")
+}
+
+/// Write the graph. Returns the number of nodes and edges.
+pub fn run_export_graph(args: &GraphArgs, force: bool) -> io::Result<(usize, usize)> {
+    let graph = collect_graph(open_input(&args.input)?, args.affixes)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    match args.format {
+        GraphFormat::Dot => write_dot(&mut writer, &graph)?,
+        GraphFormat::Graphml => write_graphml(&mut writer, &graph)?,
+    }
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((graph.nodes.len(), graph.edges.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSONL: &str = concat!(
+        r#"{"id":"happy","pos":"ADJ","morphology":{"type":"suffixed","base":"hap","components":["hap","-y"],"suffixes":["-y"]}}"#,
+        "\n",
+        r#"{"id":"happiness","pos":"NOU","morphology":{"type":"suffixed","base":"happy","components":["happy","-ness"],"suffixes":["-ness"]}}"#,
+        "\n",
+        r#"{"id":"happiness","pos":"NOU","morphology":{"type":"suffixed","base":"happy","components":["happy","-ness"],"suffixes":["-ness"]}}"#,
+        "\n",
+        r#"{"id":"beeswax","pos":"NOU","morphology":{"type":"compound","components":["bee","-s-","wax"],"interfixes":["-s-"],"is_compound":true}}"#,
+        "\n",
+        r#"{"id":"billion","pos":"NUM","morphology":{"type":"circumfixed","base":"gloss1=two","components":["bi-","-illion"]}}"#,
+        "\n",
+        r#"{"id":"cat","pos":"NOU"}"#,
+        "\n",
+    );
+
+    #[test]
+    fn derivation_and_compound_edges() {
+        let graph = collect_graph(JSONL.as_bytes(), false).unwrap();
+        let edges: Vec<(&str, &str, &str)> =
+            graph.edges.iter().map(|(f, t, r)| (f.as_str(), t.as_str(), r.as_str())).collect();
+        assert_eq!(
+            edges,
+            vec![
+                ("bee", "beeswax", "compound"),
+                ("hap", "happy", "suffixed"),
+                ("happy", "happiness", "suffixed"),
+                ("wax", "beeswax", "compound"),
+            ]
+        );
+        assert_eq!(graph.nodes["happy"], NodeKind::Word);
+        assert_eq!(graph.nodes["hap"], NodeKind::Source);
+        assert!(!graph.nodes.contains_key("cat"));
+
+        let with_affixes = collect_graph(JSONL.as_bytes(), true).unwrap();
+        assert_eq!(with_affixes.nodes["-s-"], NodeKind::Affix);
+        assert!(with_affixes.edges.contains(&("-ness".into(), "happiness".into(), "affix".into())));
+    }
+
+    #[test]
+    fn writers_escape_names() {
+        let mut graph = Graph::default();
+        graph.add_edge("say \"hi\"", "a<b", "compound", NodeKind::Source);
+        graph.add_node("a<b", NodeKind::Word);
+
+        let mut dot = Vec::new();
+        write_dot(&mut dot, &graph).unwrap();
+        assert!(String::from_utf8(dot).unwrap().contains(r#"  "say \"hi\"" -> "a<b" [label="compound"];"#));
+
+        let mut graphml = Vec::new();
+        write_graphml(&mut graphml, &graph).unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert!(graphml.contains(r#"<edge source="say &quot;hi&quot;" target="a&lt;b">"#));
+        assert!(graphml.contains(r#"<node id="a&lt;b"><data key="kind">word</data></node>"#));
+    }
+}
@@ -0,0 +1,182 @@
+//! Dump integrity checks.
+//!
+//! A dump that was cut short (an interrupted download, a full disk) still
+//! decompresses and scans up to the damage, which would quietly yield a
+//! partial lexicon. Each input is read through an `EndCheck`, which notes how
+//! the XML starts and ends and any read error; after the scan, a dump that
+//! opened with `<mediawiki` must have closed with `</mediawiki>`. Inputs
+//! can also be checked against the SHA-1 published in the run's
+//! dumpstatus.json before the scan starts.
+
+use crate::fetch::{get_string, sha1_file};
+use crate::{is_stdio, open_input};
+
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Bytes kept from each end of the input
+const EDGE_BYTES: usize = 64;
+
+/// What was seen of one input. Shared, since the channel pipeline reads on
+/// its own thread and drops the reader there.
+#[derive(Debug, Default)]
+pub struct InputState {
+    head: Vec<u8>,
+    tail: Vec<u8>,
+    reached_eof: bool,
+    error: Option<String>,
+}
+
+/// An input and what its reader saw
+#[derive(Debug)]
+pub struct InputCheck {
+    pub path: PathBuf,
+    state: Arc<Mutex<InputState>>,
+}
+
+struct EndCheck<R> {
+    inner: R,
+    path: PathBuf,
+    state: Arc<Mutex<InputState>>,
+}
+
+impl<R: Read> Read for EndCheck<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let result = self.inner.read(buf);
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        match &result {
+            Ok(0) if !buf.is_empty() => state.reached_eof = true,
+            Ok(n) => {
+                let data = &buf[..*n];
+                if state.head.len() < EDGE_BYTES {
+                    let take = (EDGE_BYTES - state.head.len()).min(data.len());
+                    state.head.extend_from_slice(&data[..take]);
+                }
+                state.tail.extend_from_slice(data);
+                let excess = state.tail.len().saturating_sub(EDGE_BYTES);
+                state.tail.drain(..excess);
+            }
+            Err(e) => {
+                let message = format!("{}: reading failed, so the dump is corrupt or truncated: {}", self.path.display(), e);
+                state.error = Some(message.clone());
+                return Err(io::Error::new(e.kind(), message));
+            }
+        }
+        result
+    }
+}
+
+/// Open the inputs as one stream, like `open_inputs`, with an end check on each
+pub fn open_checked_inputs(inputs: &[PathBuf]) -> io::Result<(Box<dyn BufRead + Send>, Vec<InputCheck>)> {
+    let mut combined: Box<dyn BufRead + Send> = Box::new(io::empty());
+    let mut checks = Vec::new();
+    for path in inputs {
+        let state = Arc::new(Mutex::new(InputState::default()));
+        let reader = EndCheck {
+            inner: open_input(path)?,
+            path: path.clone(),
+            state: Arc::clone(&state),
+        };
+        combined = Box::new(combined.chain(BufReader::with_capacity(256 * 1024, reader)));
+        checks.push(InputCheck { path: path.clone(), state });
+    }
+    Ok((combined, checks))
+}
+
+/// Fail if any input hit a read error, or was read to the end and opened a
+/// `<mediawiki>` root it never closed. Inputs the scan stopped reading early
+/// (--limit) and page fragments without a root element pass.
+pub fn verify_ends(checks: &[InputCheck]) -> Result<(), String> {
+    for check in checks {
+        let state = check.state.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(error) = &state.error {
+            return Err(error.clone());
+        }
+        let is_dump = state.head.trim_ascii_start().starts_with(b"<mediawiki");
+        if state.reached_eof && is_dump && !state.tail.trim_ascii_end().ends_with(b"</mediawiki>") {
+            return Err(format!(
+                "{}: the XML ends before </mediawiki>, so the dump is truncated",
+                check.path.display()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// SHA-1 that dumpstatus.json publishes for the file called `name`, from any job
+pub fn published_sha1<'a>(status: &'a serde_json::Value, name: &str) -> Option<&'a str> {
+    status["jobs"]
+        .as_object()?
+        .values()
+        .find_map(|job| job["files"][name]["sha1"].as_str())
+}
+
+/// Check each input file against the SHA-1 in a dumpstatus.json, given as a
+/// local path or a URL
+pub fn verify_checksums(inputs: &[PathBuf], dumpstatus: &str, quiet: bool) -> io::Result<()> {
+    let contents = if dumpstatus.starts_with("http://") || dumpstatus.starts_with("https://") {
+        get_string(dumpstatus)?
+    } else {
+        std::fs::read_to_string(dumpstatus)?
+    };
+    let status: serde_json::Value = serde_json::from_str(&contents)
+        .map_err(|e| io::Error::other(format!("Invalid dumpstatus.json {}: {}", dumpstatus, e)))?;
+
+    for path in inputs.iter().filter(|p| !is_stdio(p)) {
+        let name = file_name(path);
+        let expected = published_sha1(&status, &name)
+            .ok_or_else(|| io::Error::other(format!("{} is not listed in {}", name, dumpstatus)))?;
+        if !sha1_file(path)?.eq_ignore_ascii_case(expected) {
+            return Err(io::Error::other(format!(
+                "{}: SHA-1 does not match {}; the file is corrupt or incomplete",
+                path.display(),
+                dumpstatus
+            )));
+        }
+        if !quiet {
+            println!("Verified SHA-1 of {}", name);
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_of(text: &'static str) -> Result<(), String> {
+        let state = Arc::new(Mutex::new(InputState::default()));
+        let mut reader = EndCheck {
+            inner: text.as_bytes(),
+            path: PathBuf::from("dump.xml"),
+            state: Arc::clone(&state),
+        };
+        io::copy(&mut reader, &mut io::sink()).unwrap();
+        verify_ends(&[InputCheck { path: PathBuf::from("dump.xml"), state }])
+    }
+
+    #[test]
+    fn truncated_dump_is_reported() {
+        assert!(check_of("<mediawiki>\n<page><title>a</title></page>\n</mediawiki>\n").is_ok());
+        assert!(check_of("<mediawiki>\n<page><title>a</title></page>\n<page><tit").is_err());
+        // Fragments without the root element can't be checked
+        assert!(check_of("<page><title>a</title></page>\n").is_ok());
+    }
+
+    #[test]
+    fn sha1_from_any_job() {
+        let status = serde_json::json!({
+            "jobs": {
+                "articlesdump": {"files": {"en-pages-articles.xml.bz2": {"sha1": "abc"}}},
+                "articlesmultistreamdump": {"files": {"en-multistream.xml.bz2": {"sha1": "def"}}}
+            }
+        });
+        assert_eq!(published_sha1(&status, "en-multistream.xml.bz2"), Some("def"));
+        assert_eq!(published_sha1(&status, "missing.xml.bz2"), None);
+    }
+}
@@ -0,0 +1,140 @@
+//! Lemma chains, with `--resolve-lemmas`.
+//!
+//! An inflection's lemma can itself be inflected: "mice's" has the lemma
+//! "mice", whose lemma is "mouse". A page only names the next lemma, so once
+//! the scan is done a pass over the output follows each chain to its end and
+//! adds `root_lemma` and `lemma_depth` (the number of hops) after `lemma`:
+//!
+//! ```json
+//! {"id":"mice's",...,"lemma":"mice","root_lemma":"mouse","lemma_depth":2,...}
+//! ```
+//!
+//! Entries whose lemma is already a base form are copied unchanged. A chain
+//! that loops back on itself ends before the repeat.
+
+use crate::create_output;
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+// Lemma is never the first field, and inside a JSON string the quotes would be escaped
+const LEMMA_KEY: &str = ",\"lemma\":";
+
+/// Only the fields the chains need; in the nested shape both are on the word
+#[derive(Deserialize)]
+struct LemmaRecord {
+    id: String,
+    lemma: Option<String>,
+}
+
+fn read_record(line: &str) -> io::Result<Option<LemmaRecord>> {
+    if !line.contains(LEMMA_KEY) {
+        return Ok(None);
+    }
+    let record: LemmaRecord = serde_json::from_str(line)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Malformed line in output: {}", e)))?;
+    Ok(Some(record).filter(|record| record.lemma.is_some()))
+}
+
+/// End of the chain from `word` through `lemma`, and its length in hops.
+/// None when the lemma is not itself inflected.
+fn root_lemma<'a>(lemmas: &'a HashMap<String, String>, word: &'a str, lemma: &'a str) -> Option<(&'a str, usize)> {
+    let mut chain = vec![word, lemma];
+    while let Some(next) = lemmas.get(chain[chain.len() - 1]) {
+        if chain.contains(&next.as_str()) {
+            break;
+        }
+        chain.push(next);
+    }
+    (chain.len() > 2).then(|| (chain[chain.len() - 1], chain.len() - 1))
+}
+
+/// `line` with the chain's fields after its lemma
+fn insert_root(line: &str, root: &str, depth: usize) -> io::Result<String> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "Malformed lemma in output");
+    let value_start = line.find(LEMMA_KEY).ok_or_else(malformed)? + LEMMA_KEY.len();
+    let mut values = serde_json::Deserializer::from_str(&line[value_start..]).into_iter::<String>();
+    values.next().ok_or_else(malformed)?.map_err(|_| malformed())?;
+    let value_end = value_start + values.byte_offset();
+    let root = serde_json::to_string(root)?;
+    Ok(format!("{},\"root_lemma\":{},\"lemma_depth\":{}{}", &line[..value_end], root, depth, &line[value_end..]))
+}
+
+/// Rewrite the scan's output with the root of every lemma chain. Returns the
+/// number of lines given a `root_lemma`.
+pub fn resolve_lemma_chains(output: &Path) -> io::Result<usize> {
+    let mut lemmas: HashMap<String, String> = HashMap::new();
+    for line in BufReader::new(File::open(output)?).lines() {
+        if let Some(LemmaRecord { id, lemma: Some(lemma) }) = read_record(&line?)? {
+            lemmas.entry(id).or_insert(lemma);
+        }
+    }
+
+    // The output is rewritten in place: create_output writes beside it and renames
+    let (writer, output_file) = create_output(output, true)?;
+    let mut writer = BufWriter::new(writer);
+    let mut resolved = 0;
+    for line in BufReader::new(File::open(output)?).lines() {
+        let line = line?;
+        let root = match read_record(&line)? {
+            Some(LemmaRecord { id, lemma: Some(lemma) }) => {
+                root_lemma(&lemmas, &id, &lemma).map(|(root, depth)| (root.to_string(), depth))
+            }
+            _ => None,
+        };
+        match root {
+            Some((root, depth)) => {
+                writeln!(writer, "{}", insert_root(&line, &root, depth)?)?;
+                resolved += 1;
+            }
+            None => writeln!(writer, "{}", line)?,
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok(resolved)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chains_resolve_to_their_root() {
+        let lemmas: HashMap<String, String> = [("mice's", "mice"), ("mice", "mouse"), ("a", "b"), ("b", "a")]
+            .into_iter()
+            .map(|(word, lemma)| (word.to_string(), lemma.to_string()))
+            .collect();
+        assert_eq!(root_lemma(&lemmas, "mice's", "mice"), Some(("mouse", 2)));
+        assert_eq!(root_lemma(&lemmas, "mice", "mouse"), None);
+        // A loop ends before the repeat
+        assert_eq!(root_lemma(&lemmas, "c", "a"), Some(("b", 2)));
+        assert_eq!(root_lemma(&lemmas, "a", "b"), None);
+    }
+
+    #[test]
+    fn output_is_rewritten_with_root_lemmas() {
+        let path = std::env::temp_dir().join(format!("lemma-chains-{}.jsonl", std::process::id()));
+        std::fs::write(
+            &path,
+            "{\"id\":\"mice's\",\"pos\":\"NOU\",\"wc\":1,\"is_inflected\":true,\"lemma\":\"mice\",\"inflection_tags\":[\"possessive\"]}\n\
+             {\"id\":\"mice\",\"pos\":\"NOU\",\"wc\":1,\"is_inflected\":true,\"lemma\":\"mouse\"}\n\
+             {\"id\":\"mouse\",\"pos\":\"NOU\",\"wc\":1,\"definition\":\"A \\\"lemma\\\":\\\"x\\\" rodent.\"}\n",
+        )
+        .unwrap();
+        assert_eq!(resolve_lemma_chains(&path).unwrap(), 1);
+        let lines = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let lines: Vec<&str> = lines.lines().collect();
+        assert_eq!(
+            lines[0],
+            r#"{"id":"mice's","pos":"NOU","wc":1,"is_inflected":true,"lemma":"mice","root_lemma":"mouse","lemma_depth":2,"inflection_tags":["possessive"]}"#
+        );
+        assert_eq!(lines[1], r#"{"id":"mice","pos":"NOU","wc":1,"is_inflected":true,"lemma":"mouse"}"#);
+        assert_eq!(lines.len(), 3);
+    }
+}
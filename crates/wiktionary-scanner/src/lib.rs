@@ -0,0 +1,5920 @@
+use bzip2::read::MultiBzDecoder;
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use lazy_static::lazy_static;
+use memchr::memmem;
+use once_cell::sync::OnceCell;
+use regex::{Regex, RegexSet, SetMatches};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::ops::{Range, RangeInclusive};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+mod affix_stats;
+mod appendix;
+mod collation;
+mod compact;
+mod config;
+mod dictionary;
+mod entry_builder;
+mod error_log;
+mod etymology_text;
+mod export;
+mod fast_json;
+mod fetch;
+mod filters;
+mod fixture;
+mod form_of;
+mod generate_forms;
+mod golden;
+mod graph;
+mod integrity;
+mod lemmas;
+mod lua;
+mod manifest;
+mod merge_case;
+mod metrics;
+mod multistream;
+mod normalize;
+mod nyms;
+mod ontolex;
+mod page_ast;
+mod parallel;
+mod quotations;
+mod reorder;
+mod report;
+mod rhymes;
+mod sample;
+mod select;
+mod shape;
+mod sidecar;
+mod siteinfo;
+mod split;
+mod syllables;
+mod update;
+mod wikidata;
+mod wordnet;
+use entry_builder::{EntryBuilder, InvalidEntry};
+use error_log::{parse_page_checked, record_failure, FailureCategory, PageFailure};
+use normalize::Normalization;
+pub use form_of::init_form_of;
+use shape::OutputShape;
+use split::SplitBy;
+use page_ast::{HeaderNode, PageAst};
+use parallel::{ParallelConfig, process_batch_parallel, process_channel_pipeline, process_two_phase};
+
+/// Processing strategy for parsing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// Sequential processing (original baseline)
+    Sequential,
+    /// Batch-parallel processing with thread pool
+    BatchParallel,
+    /// Channel-based pipeline processing
+    ChannelPipeline,
+    /// Two-phase: load all pages, then process in parallel
+    TwoPhase,
+}
+
+/// Which senses to keep by phrase status (word count > 1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PhraseMode {
+    /// Keep single words and multiword expressions
+    #[default]
+    Include,
+    /// Keep only multiword expressions
+    Only,
+    /// Keep only single words
+    Exclude,
+}
+
+#[derive(Parser)]
+#[command(name = "wiktionary-scanner-rust")]
+#[command(about = "Fast Rust-based Wiktionary XML parser - outputs one entry per sense")]
+struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Input XML files (.xml or .xml.bz2) or glob patterns, read as one stream,
+    /// followed by the output JSONL file; - stands for stdin/stdout
+    #[arg(value_name = "INPUTS... OUTPUT")]
+    paths: Vec<PathBuf>,
+
+    /// Inputs taken from `paths` (or the config file)
+    #[arg(skip)]
+    inputs: Vec<PathBuf>,
+
+    /// Output taken from `paths` (or the config file)
+    #[arg(skip)]
+    output: Option<PathBuf>,
+
+    /// TOML file setting any of these options (command-line flags take precedence)
+    #[arg(long)]
+    config: Option<PathBuf>,
+
+    /// Processing strategy
+    #[arg(short, long, value_enum, default_value_t = Strategy::ChannelPipeline)]
+    strategy: Strategy,
+
+    /// Number of threads (4 = default, 0 = auto-detect)
+    #[arg(short, long, default_value_t = 4)]
+    threads: usize,
+
+    /// Batch size for batch-parallel strategy
+    #[arg(long, default_value_t = 1000)]
+    batch_size: usize,
+
+    /// Channel buffer size for channel-pipeline strategy
+    #[arg(long, default_value_t = 10000)]
+    channel_buffer: usize,
+
+    /// Channel-pipeline strategy: start from --threads and --channel-buffer, then
+    /// activate or park workers and grow the queue based on observed backpressure
+    #[arg(long)]
+    autoscale: bool,
+
+    /// Two-phase strategy: hold at most this many MB of page XML at a time,
+    /// processing the dump in chunks (default: load the whole dump)
+    #[arg(long, value_name = "MB")]
+    memory_budget: Option<usize>,
+
+    /// Channel-pipeline strategy: out-of-order results to hold in memory; beyond
+    /// this the furthest from their turn are spilled to a temp file
+    #[arg(long, default_value_t = 10000)]
+    reorder_limit: usize,
+
+    /// Channel-pipeline strategy: consecutive pages a worker takes at a time
+    #[arg(long, default_value_t = 100)]
+    chunk_pages: usize,
+
+    /// Print pages/sec, queue depths and per-stage timings to stderr every SECS seconds
+    #[arg(long, value_name = "SECS")]
+    metrics_every: Option<u64>,
+
+    /// Rewrite FILE with the metrics in Prometheus text format instead of printing
+    /// them (every --metrics-every seconds, default 10), for node_exporter
+    #[arg(long, value_name = "FILE")]
+    metrics_textfile: Option<PathBuf>,
+
+    /// Limit number of entries to extract (for testing)
+    #[arg(long)]
+    limit: Option<usize>,
+
+    /// Stop after this many words, writing all of each word's senses (for testing)
+    #[arg(long)]
+    word_limit: Option<usize>,
+
+    /// Limit number of pages to scan (for testing with raw dumps)
+    #[arg(long)]
+    page_limit: Option<usize>,
+
+    /// Run all strategies and compare (benchmark mode)
+    #[arg(long)]
+    benchmark: bool,
+
+    /// Quiet mode - minimal output
+    #[arg(short, long)]
+    quiet: bool,
+
+    /// Write a JSONL record (title, failure category, snippet) for every page that
+    /// fails extraction: no title or text, an English page with no entries, or a panic
+    #[arg(long, value_name = "FILE")]
+    errors_out: Option<PathBuf>,
+
+    /// Only process the titles listed in this file, one per line
+    #[arg(long, value_name = "FILE")]
+    titles: Option<PathBuf>,
+
+    /// Only process titles matching this regex, e.g. '^un'
+    #[arg(long, value_name = "REGEX")]
+    title_regex: Option<String>,
+
+    /// Multistream index (…-multistream-index.txt.bz2) for the single multistream
+    /// input; with --titles or --title-regex, only the bzip2 streams holding a
+    /// matching title are read
+    #[arg(long, value_name = "FILE")]
+    multistream_index: Option<PathBuf>,
+
+    /// Process a deterministic random subset of this fraction of mainspace pages,
+    /// e.g. 0.001 for a quick QA run
+    #[arg(long, value_name = "RATE", conflicts_with = "sample_n")]
+    sample: Option<f64>,
+
+    /// Process a deterministic random subset of this many mainspace pages
+    /// (held in memory until the input has been read)
+    #[arg(long, value_name = "N")]
+    sample_n: Option<usize>,
+
+    /// Seed for --sample and --sample-n; another seed gives another subset
+    #[arg(long, default_value_t = 0)]
+    sample_seed: u64,
+
+    /// dumpstatus.json of the dump run (path or URL); each input file must
+    /// match the SHA-1 published there before the scan starts
+    #[arg(long, value_name = "DUMPSTATUS")]
+    verify_checksum: Option<String>,
+
+    /// After the scan, scan again with another strategy and check the output is identical
+    /// (Sequential, or ChannelPipeline when --strategy is sequential)
+    #[arg(long)]
+    verify: bool,
+
+    /// Overwrite the output file (and --appendix-out, --errors-out) if it already exists
+    #[arg(long, global = true)]
+    force: bool,
+
+    /// Syllable validation mode - outputs all syllable sources for cross-validation
+    #[arg(long)]
+    syllable_validation: bool,
+
+    /// Rules for settling disagreeing syllable counts, tried in order before the
+    /// IPA > hyphenation > category > rhymes priority (e.g. majority,rhymes-near-ipa)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    syllable_rules: Vec<syllables::SyllableRule>,
+
+    /// Path to POS schema YAML file (default: schema/pos.yaml relative to project root)
+    #[arg(long)]
+    schema: Option<PathBuf>,
+
+    /// Path to labels schema YAML file (default: schema/labels.yaml relative to project root)
+    #[arg(long)]
+    labels_schema: Option<PathBuf>,
+
+    /// Path to IPA schema YAML file with the vowel inventory for syllable counts
+    /// (default: schema/ipa.yaml relative to project root)
+    #[arg(long)]
+    ipa_schema: Option<PathBuf>,
+
+    /// Path to form-of schema YAML file mapping {{synonym of}}, {{short for}}, ... to
+    /// relation types (default: schema/form_of.yaml relative to project root)
+    #[arg(long)]
+    form_of_schema: Option<PathBuf>,
+
+    /// Path to filter profiles YAML file (default: schema/profiles.yaml relative to project root)
+    #[arg(long)]
+    profiles_schema: Option<PathBuf>,
+
+    /// Only write senses with these parts of speech (names like noun or codes like NOU)
+    #[arg(long, value_delimiter = ',')]
+    include_pos: Vec<String>,
+
+    /// Drop senses with these parts of speech
+    #[arg(long, value_delimiter = ',')]
+    exclude_pos: Vec<String>,
+
+    /// Only write senses carrying one of these register labels (e.g. slang)
+    #[arg(long, value_delimiter = ',')]
+    only_register: Vec<String>,
+
+    /// Drop senses carrying any of these register labels (e.g. vulgar,offensive,derogatory)
+    #[arg(long, value_delimiter = ',')]
+    exclude_register: Vec<String>,
+
+    /// Also drop the senses a named profile in schema/profiles.yaml excludes (kids, standard, unfiltered)
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Only write senses with at least this many syllables (senses without a count are dropped)
+    #[arg(long)]
+    min_syllables: Option<usize>,
+
+    /// Only write senses with at most this many syllables (senses without a count are dropped)
+    #[arg(long)]
+    max_syllables: Option<usize>,
+
+    /// Only write words with at least this many characters
+    #[arg(long)]
+    min_len: Option<usize>,
+
+    /// Only write words with at most this many characters
+    #[arg(long)]
+    max_len: Option<usize>,
+
+    /// Whether to include, exclude, or only write multiword expressions
+    #[arg(long, value_enum, default_value_t = PhraseMode::Include)]
+    phrases: PhraseMode,
+
+    /// Also write letters, numerals and symbols whose titles aren't words ("&", "½", "Ω"),
+    /// keeping only those senses for such titles
+    #[arg(long)]
+    graphemes: bool,
+
+    /// Unicode normalization for the emitted word
+    #[arg(long, value_enum, default_value_t = Normalization::None)]
+    normalize: Normalization,
+
+    /// Add an `ascii` field with the word folded to plain ASCII (café → cafe)
+    #[arg(long)]
+    ascii: bool,
+
+    /// Add a `sort_key` field that orders entries dictionary-style (case, diacritics and
+    /// punctuation only break ties)
+    #[arg(long)]
+    sort_key: bool,
+
+    /// Add a `definition` field with each sense's definition as plain text
+    #[arg(long)]
+    definitions: bool,
+
+    /// Add a `quotations` field with each sense's quotations (year, author, text)
+    #[arg(long)]
+    quotations: bool,
+
+    /// Add a `usage_notes` field with the word's Usage notes sections as plain text
+    #[arg(long)]
+    usage_notes: bool,
+
+    /// Add an `etymology_text` field with the first paragraph of each sense's Etymology
+    /// section as plain text
+    #[arg(long)]
+    etymology_text: bool,
+
+    /// Add a `headword` field with each part of speech's headword line as written
+    /// ({{en-noun|es}}), which generate-forms reads
+    #[arg(long)]
+    headwords: bool,
+
+    /// One line per sense (flat), or one per word with its senses in a `senses` array (nested)
+    #[arg(long, value_enum, default_value_t = OutputShape::Flat)]
+    shape: OutputShape,
+
+    /// Write one- or two-letter keys in place of the field names (see the README for the table)
+    #[arg(long)]
+    compact_keys: bool,
+
+    /// Write one JSON array (one element per line) instead of JSON lines
+    #[arg(long)]
+    json_array: bool,
+
+    /// Write OUTPUT as a directory of files, one per part of speech (nouns.jsonl, verbs.jsonl, ...)
+    #[arg(long, value_enum, value_name = "FIELD")]
+    split_by: Option<SplitBy>,
+
+    /// Wikidata lexeme dump (latest-lexemes.json[.bz2]) to join on lemma and part of speech,
+    /// adding to the IDs from {{wikidata lexeme}} links in `wikidata_lexeme`
+    #[arg(long, value_name = "FILE")]
+    wikidata_lexemes: Option<PathBuf>,
+
+    /// Expand headword lines with the dump's own Module: and Template: pages, adding the
+    /// forms they link to in `forms` (a first pass over the input; needs --features lua)
+    #[arg(long)]
+    lua: bool,
+
+    /// Also extract Appendix:Glossary* and Appendix:English pages into this JSONL sidecar
+    /// (a second pass over the input)
+    #[arg(long)]
+    appendix_out: Option<PathBuf>,
+
+    /// Also write these JSONL sidecars in the same run: pronunciations (pronunciations.jsonl),
+    /// relations (relations.jsonl)
+    #[arg(long, value_enum, value_delimiter = ',')]
+    sidecars: Vec<sidecar::Sidecar>,
+
+    /// Directory for the --sidecars files (default: the output file's directory)
+    #[arg(long)]
+    sidecar_dir: Option<PathBuf>,
+
+    /// After the scan, follow lemma chains (mice's → mice → mouse) and add `root_lemma`
+    /// and `lemma_depth` to inflections of inflected words (rewrites the output file)
+    #[arg(long)]
+    resolve_lemmas: bool,
+}
+
+impl Args {
+    /// The last positional path is the output; everything before it is input
+    fn split_paths(&mut self) {
+        if let Some((output, inputs)) = self.paths.split_last() {
+            if !inputs.is_empty() {
+                self.output = Some(output.clone());
+                self.inputs = inputs.to_vec();
+            }
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Download the latest pages-articles dump (with resume and checksum verification)
+    FetchDump(fetch::FetchDumpArgs),
+    /// Fetch live pages from the MediaWiki API and print their parsed entries
+    FetchPage(fetch::FetchPageArgs),
+    /// Re-extract a list of changed titles and splice them into an existing output
+    Update(update::UpdateArgs),
+    /// Rank prefixes, suffixes and interfixes in an output file by productivity
+    AffixStats(affix_stats::AffixStatsArgs),
+    /// Link words that differ only in case ("God" to "god") in an output file
+    MergeCase(merge_case::MergeCaseArgs),
+    /// Predict regular inflections of the lemmas in an output file that have no page of their own
+    GenerateForms(generate_forms::GenerateFormsArgs),
+    /// Summarize an output file: POS, syllable, length, tag and morphology distributions
+    Report(report::ReportArgs),
+    /// Diff parse_page output for stored pages against checked-in expected entries
+    Golden(golden::GoldenArgs),
+    /// Index English words by rhyme ({{rhymes}} keys, else IPA finals) from a dump
+    BuildRhymes(rhymes::BuildRhymesArgs),
+    /// Extract a small fixture of English pages, stratified by size and features, from a dump
+    MakeFixture(fixture::MakeFixtureArgs),
+    /// Derive datasets (crossword clues, ...) from an output file
+    Export(export::ExportArgs),
+}
+
+// === POS Schema YAML structures ===
+
+#[derive(Debug, Deserialize)]
+struct PosSchema {
+    pos_classes: Vec<PosClass>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PosClass {
+    code: String,
+    name: String,
+    #[allow(dead_code)]
+    description: String,
+    #[allow(dead_code)]
+    short_description: Option<String>,
+    variants: Vec<String>,
+}
+
+// IPA schema: the characters syllable counting treats as vowels and off-glides
+#[derive(Debug, Deserialize)]
+pub struct IpaInventory {
+    vowels: Vec<char>,
+    offglides: Vec<char>,
+}
+
+// Labels schema for label classifications
+#[derive(Debug, Deserialize)]
+struct LabelsSchema {
+    register_labels: Vec<String>,
+    temporal_labels: Vec<String>,
+    domain_labels: Vec<String>,
+    region_labels: HashMap<String, String>,
+    // Subregion code (e.g. "en-GB-north") -> the broader region it is part of; optional
+    #[serde(default)]
+    region_parents: HashMap<String, String>,
+    spelling_labels: HashMap<String, String>,
+    // Dialect labels (e.g. "aave", "geordie") -> dialect name; optional in older schemas
+    #[serde(default)]
+    dialect_labels: HashMap<String, String>,
+    special_page_prefixes: Vec<String>,
+}
+
+// Global POS map loaded from YAML at runtime
+static POS_MAP: OnceCell<HashMap<String, String>> = OnceCell::new();
+// POS code -> the class's name ("NOU" -> "Noun")
+static POS_NAMES: OnceCell<HashMap<String, String>> = OnceCell::new();
+static IPA_INVENTORY: OnceCell<IpaInventory> = OnceCell::new();
+
+// Global label sets loaded from YAML at runtime
+static REGISTER_LABELS_SET: OnceCell<HashSet<String>> = OnceCell::new();
+static TEMPORAL_LABELS_SET: OnceCell<HashSet<String>> = OnceCell::new();
+static DOMAIN_LABELS_SET: OnceCell<HashSet<String>> = OnceCell::new();
+static REGION_LABELS_MAP: OnceCell<HashMap<String, String>> = OnceCell::new();
+static REGION_PARENTS_MAP: OnceCell<HashMap<String, String>> = OnceCell::new();
+static SPELLING_LABELS_MAP: OnceCell<HashMap<String, String>> = OnceCell::new();
+static DIALECT_LABELS_MAP: OnceCell<HashMap<String, String>> = OnceCell::new();
+static SPECIAL_PREFIXES_VEC: OnceCell<Vec<String>> = OnceCell::new();
+// Namespace prefixes from the dump's <siteinfo>; preferred over the labels schema list
+static SITE_PREFIXES_VEC: OnceCell<Vec<String>> = OnceCell::new();
+// Whether entries carry their definition text (--definitions)
+static DEFINITIONS: OnceCell<bool> = OnceCell::new();
+// Whether senses carry their quotations (--quotations)
+static QUOTATIONS: OnceCell<bool> = OnceCell::new();
+// Whether entries carry their word's usage notes (--usage-notes)
+static USAGE_NOTES: OnceCell<bool> = OnceCell::new();
+// Whether entries carry their Etymology section's prose (--etymology-text)
+static ETYMOLOGY_TEXT: OnceCell<bool> = OnceCell::new();
+// Whether senses carry their section's headword line (--headwords)
+static HEADWORDS: OnceCell<bool> = OnceCell::new();
+
+fn read_pos_schema(schema_path: &PathBuf) -> Result<PosSchema, String> {
+    let mut file = File::open(schema_path)
+        .map_err(|e| format!("Failed to open schema file {:?}: {}", schema_path, e))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|e| format!("Failed to read schema file: {}", e))?;
+
+    serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse schema YAML: {}", e))
+}
+
+/// Header and template values -> POS code
+fn pos_variant_map(schema: &PosSchema) -> HashMap<String, String> {
+    let mut map = HashMap::new();
+    for pos_class in &schema.pos_classes {
+        for variant in &pos_class.variants {
+            map.insert(variant.clone(), pos_class.code.clone());
+        }
+    }
+    map
+}
+
+pub fn init_pos_map(schema_path: Option<&PathBuf>) -> Result<(), String> {
+    // Try to find schema file
+    let path = if let Some(p) = schema_path {
+        p.clone()
+    } else {
+        // Default: look for schema/pos.yaml relative to current dir or parent dirs
+        let candidates = [
+            PathBuf::from("schema/pos.yaml"),
+            PathBuf::from("../../schema/pos.yaml"),  // When running from tools/wiktionary-scanner-rust
+        ];
+        candidates.into_iter()
+            .find(|p| p.exists())
+            .ok_or_else(|| "Could not find schema/pos.yaml. Use --schema to specify path.".to_string())?
+    };
+
+    let schema = read_pos_schema(&path)?;
+    POS_MAP.set(pos_variant_map(&schema)).map_err(|_| "POS_MAP already initialized".to_string())?;
+    let _ = POS_NAMES.set(schema.pos_classes.into_iter().map(|class| (class.code, class.name)).collect());
+    Ok(())
+}
+
+fn get_pos_map() -> &'static HashMap<String, String> {
+    POS_MAP.get().expect("POS_MAP not initialized - call init_pos_map() first")
+}
+
+/// The schema's name for a POS code
+fn pos_name(code: &str) -> Option<&'static str> {
+    POS_NAMES.get()?.get(code).map(String::as_str)
+}
+
+/// Every POS code in the schema
+fn pos_codes() -> impl Iterator<Item = &'static str> {
+    POS_NAMES.get().into_iter().flat_map(|names| names.keys().map(String::as_str))
+}
+
+fn find_schema_file(filename: &str) -> Result<PathBuf, String> {
+    let candidates = [
+        PathBuf::from(format!("schema/{}", filename)),
+        PathBuf::from(format!("../../schema/{}", filename)),  // When running from tools/wiktionary-scanner-rust
+    ];
+    candidates.into_iter()
+        .find(|p| p.exists())
+        .ok_or_else(|| format!("Could not find schema/{}. Use --schema to specify path.", filename))
+}
+
+fn load_ipa_schema(schema_path: &PathBuf) -> Result<IpaInventory, String> {
+    let contents = std::fs::read_to_string(schema_path)
+        .map_err(|e| format!("Failed to open IPA schema file {:?}: {}", schema_path, e))?;
+    serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse IPA schema YAML: {}", e))
+}
+
+pub fn init_ipa_inventory(schema_path: Option<&PathBuf>) -> Result<(), String> {
+    let path = if let Some(p) = schema_path {
+        p.clone()
+    } else {
+        find_schema_file("ipa.yaml")?
+    };
+    IPA_INVENTORY.set(load_ipa_schema(&path)?)
+        .map_err(|_| "IPA_INVENTORY already initialized".to_string())
+}
+
+fn get_ipa_inventory() -> &'static IpaInventory {
+    IPA_INVENTORY.get().expect("IPA inventory not initialized - call init_ipa_inventory() first")
+}
+
+fn load_labels_schema(schema_path: &PathBuf) -> Result<LabelsSchema, String> {
+    let mut file = File::open(schema_path)
+        .map_err(|e| format!("Failed to open labels schema file {:?}: {}", schema_path, e))?;
+
+    let mut contents = String::new();
+    file.read_to_string(&mut contents)
+        .map_err(|e| format!("Failed to read labels schema file: {}", e))?;
+
+    serde_yaml::from_str(&contents)
+        .map_err(|e| format!("Failed to parse labels schema YAML: {}", e))
+}
+
+pub fn init_labels(schema_path: Option<&PathBuf>) -> Result<(), String> {
+    let path = if let Some(p) = schema_path {
+        p.clone()
+    } else {
+        find_schema_file("labels.yaml")?
+    };
+
+    let schema = load_labels_schema(&path)?;
+
+    REGISTER_LABELS_SET.set(schema.register_labels.into_iter().collect())
+        .map_err(|_| "REGISTER_LABELS_SET already initialized".to_string())?;
+    TEMPORAL_LABELS_SET.set(schema.temporal_labels.into_iter().collect())
+        .map_err(|_| "TEMPORAL_LABELS_SET already initialized".to_string())?;
+    DOMAIN_LABELS_SET.set(schema.domain_labels.into_iter().collect())
+        .map_err(|_| "DOMAIN_LABELS_SET already initialized".to_string())?;
+    REGION_LABELS_MAP.set(schema.region_labels)
+        .map_err(|_| "REGION_LABELS_MAP already initialized".to_string())?;
+    REGION_PARENTS_MAP.set(schema.region_parents)
+        .map_err(|_| "REGION_PARENTS_MAP already initialized".to_string())?;
+    SPELLING_LABELS_MAP.set(schema.spelling_labels)
+        .map_err(|_| "SPELLING_LABELS_MAP already initialized".to_string())?;
+    DIALECT_LABELS_MAP.set(schema.dialect_labels)
+        .map_err(|_| "DIALECT_LABELS_MAP already initialized".to_string())?;
+    SPECIAL_PREFIXES_VEC.set(schema.special_page_prefixes)
+        .map_err(|_| "SPECIAL_PREFIXES_VEC already initialized".to_string())?;
+
+    Ok(())
+}
+
+fn get_register_labels() -> &'static HashSet<String> {
+    REGISTER_LABELS_SET.get().expect("Labels not initialized - call init_labels() first")
+}
+
+fn get_temporal_labels() -> &'static HashSet<String> {
+    TEMPORAL_LABELS_SET.get().expect("Labels not initialized - call init_labels() first")
+}
+
+fn get_domain_labels() -> &'static HashSet<String> {
+    DOMAIN_LABELS_SET.get().expect("Labels not initialized - call init_labels() first")
+}
+
+fn get_region_labels() -> &'static HashMap<String, String> {
+    REGION_LABELS_MAP.get().expect("Labels not initialized - call init_labels() first")
+}
+
+fn get_region_parents() -> &'static HashMap<String, String> {
+    REGION_PARENTS_MAP.get().expect("Labels not initialized - call init_labels() first")
+}
+
+/// A region code followed by every broader region containing it, from the
+/// schema's `region_parents` ("en-GB-north" -> en-GB-north, en-GB)
+fn region_with_parents(code: &str) -> Vec<String> {
+    let parents = get_region_parents();
+    let mut codes = vec![code.to_string()];
+    while let Some(parent) = parents.get(codes.last().unwrap()) {
+        if codes.contains(parent) {
+            break;
+        }
+        codes.push(parent.clone());
+    }
+    codes
+}
+
+fn get_spelling_labels() -> &'static HashMap<String, String> {
+    SPELLING_LABELS_MAP.get().expect("Labels not initialized - call init_labels() first")
+}
+
+fn get_dialect_labels() -> &'static HashMap<String, String> {
+    DIALECT_LABELS_MAP.get().expect("Labels not initialized - call init_labels() first")
+}
+
+pub fn get_special_prefixes() -> &'static Vec<String> {
+    SITE_PREFIXES_VEC
+        .get()
+        .or_else(|| SPECIAL_PREFIXES_VEC.get())
+        .expect("Labels not initialized - call init_labels() first")
+}
+
+/// Source of a calque (loan translation): {{calque|en|fr|gratte-ciel}}
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct CalqueSource {
+    lang: String,
+    term: String,
+}
+
+/// A morphology component in another language, written with a language code
+/// prefix: "la:pictus" in {{affix|en|la:pictus|-o-|graph}}
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+struct ForeignComponent {
+    lang: String,
+    term: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Morphology {
+    #[serde(rename = "type")]
+    morph_type: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    base: Option<String>,
+    components: Vec<String>,
+    prefixes: Vec<String>,
+    suffixes: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    interfixes: Vec<String>,
+    // Components in other languages, which are left out of the lists above
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    foreign_components: Vec<ForeignComponent>,
+    is_compound: bool,
+    etymology_template: String,
+    // "surface" for {{surf}} segmentations of the modern form, "etymological" for
+    // derivations recorded by affix/compound/word-formation templates
+    #[serde(default)]
+    analysis: String,
+    // Components (without hyphens) do not spell the headword, e.g. "happy" + "-ness"
+    // for "happiness"; only set for segmentations, not blends or clippings
+    #[serde(default, skip_serializing_if = "is_false")]
+    non_concatenative: bool,
+}
+
+// Helper functions for serde skip_serializing_if
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// How a sense's POS was found
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PosSource {
+    /// A section header the POS schema maps
+    #[default]
+    Header,
+    /// A headword template (`{{en-noun}}`, `{{en-verb}}`, ...) under an unmapped header
+    Template,
+    /// Nothing: a page with English content but no POS section
+    Unknown,
+}
+
+impl PosSource {
+    fn is_header(&self) -> bool {
+        *self == PosSource::Header
+    }
+}
+
+/// Flat entry structure - one per sense (definition line)
+/// Field order is normalized for consistent JSON output across Python/Rust scanners.
+/// The fields a page's senses share borrow from its `PageData` (see
+/// `parse_page_borrowed`); `parse_page` returns owned `Entry<'static>` values.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct Entry<'a> {
+    // Core identifiers; senses nested under their word (--shape nested) leave out
+    // the emptied id and zeroed word count
+    #[serde(rename = "id", default, skip_serializing_if = "str::is_empty")]
+    word: Cow<'a, str>,
+    // ASCII-folded word with --ascii, when it differs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ascii: Option<Cow<'a, str>>,
+    // Dictionary-order collation key with --sort-key
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_key: Option<Cow<'a, str>>,
+    pos: Cow<'a, str>,  // Single POS, not Vec
+    // Where the POS came from; left out for the usual mapped section header
+    #[serde(default, skip_serializing_if = "PosSource::is_header")]
+    pos_source: PosSource,
+    #[serde(rename = "wc", default, skip_serializing_if = "is_zero")]
+    word_count: usize,
+    // Dump provenance: the page's <id> and its revision's <timestamp>
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rev_timestamp: Option<Cow<'a, str>>,
+
+    // Boolean predicates (alphabetical order) - omit when false
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_abbreviation: bool,
+    // Spelling with pronunciations that differ by etymology or part of speech ("lead")
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_heteronym: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_inflected: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_neologism: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_phrase: bool,
+    // Sense is under a proper noun header or headword ({{en-proper noun}}, {{en-prop}})
+    #[serde(default, skip_serializing_if = "is_false")]
+    is_proper_noun: bool,
+    // Sense is a {{non-gloss definition}} (usage description rather than a definition)
+    #[serde(default, skip_serializing_if = "is_false")]
+    non_gloss: bool,
+    // Noun senses without a plural ({{en-noun|-}}), or with only a plural
+    // ({{en-plural noun}}, pluralia tantum)
+    #[serde(default, skip_serializing_if = "is_false")]
+    uncountable: bool,
+    #[serde(default, skip_serializing_if = "is_false")]
+    plural_only: bool,
+
+    // Syllables and phrase type (before lemma)
+    #[serde(rename = "nsyll", skip_serializing_if = "Option::is_none")]
+    syllables: Option<usize>,
+    // A heteronym's transcriptions for this sense's etymology or part of speech
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    ipa: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phrase_type: Option<Cow<'a, str>>,
+    // A phrase's words (phrase_tokens)
+    #[serde(default, skip_serializing_if = "<[String]>::is_empty")]
+    tokens: Cow<'a, [String]>,
+    // "letter", "numeral" or "symbol" for senses under those headers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    grapheme_type: Option<Cow<'a, str>>,
+    // Attestation date from {{hot word|en|date=...}}, as written
+    #[serde(skip_serializing_if = "Option::is_none")]
+    neologism_date: Option<Cow<'a, str>>,
+    // Earliest year in the sense's {{defdate}}, e.g. 1400 for "from 15th c."
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attested: Option<u32>,
+
+    // Lemma (base form) for inflected words
+    // Extracted from templates like {{plural of|en|cat}} → "cat"
+    // (--resolve-lemmas adds root_lemma and lemma_depth after it in a later pass)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lemma: Option<Cow<'a, str>>,
+    // What inflection of the lemma this is: ["plural"], ["past", "participle"], ...
+    #[serde(default, skip_serializing_if = "<[String]>::is_empty")]
+    inflection_tags: Cow<'a, [String]>,
+    // Forms linked from the headword line as its modules render it, with --lua
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    forms: Vec<String>,
+    // The POS section's headword line as written ({{en-verb|++}}), with --headwords
+    #[serde(skip_serializing_if = "Option::is_none")]
+    headword: Option<String>,
+
+    // Non-standard spelling of another word (misspelling, obsolete spelling, ...)
+    // Extracted from templates like {{misspelling of|en|receive}} → "misspelling", "receive"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    variant_of: Option<String>,
+    // What other entry a form-of sense points at, typed from schema/form_of.yaml
+    // {{synonym of|en|feline}} → "synonym", "feline"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relation_type: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    relation_target: Option<String>,
+
+    // Etymological relations: loan translation source and doublets (same ultimate origin)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    calque_of: Option<Cow<'a, CalqueSource>>,
+    #[serde(default, skip_serializing_if = "<[String]>::is_empty")]
+    doublets: Cow<'a, [String]>,
+    // First paragraph of the Etymology section as plain text, with --etymology-text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etymology_text: Option<Cow<'a, str>>,
+
+    // Tag arrays (alphabetical order)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    dialect_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    domain_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    region_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    register_tags: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    temporal_tags: Vec<String>,
+    // Context labels the label schema doesn't classify, as written
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    other_labels: Vec<String>,
+    // Verb valency from transitivity labels: transitive, intransitive,
+    // ditransitive or ambitransitive
+    #[serde(skip_serializing_if = "Option::is_none")]
+    valency: Option<String>,
+
+    // Definition line as plain text, with --definitions
+    #[serde(skip_serializing_if = "Option::is_none")]
+    definition: Option<String>,
+    // Sense qualifiers from {{gloss|...}} on the definition line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    glosses: Vec<String>,
+    // Usage qualifiers from {{qualifier|...}} / {{q|...}} on the definition line
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    qualifiers: Vec<String>,
+    // Quotations from the #* lines under the definition, with --quotations
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    quotations: Vec<quotations::Quotation>,
+    // Synonyms, antonyms, hypernyms and hyponyms, with the {{sense|...}} key
+    // that tied a nym section's line to this sense
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    nyms: Vec<nyms::Nym>,
+
+    // Regional spelling variant (e.g., "en-US" for American spelling, "en-GB" for British)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spelling_region: Option<Cow<'a, str>>,
+
+    // Page-level [[Category:English ...]] memberships, without the "English " prefix
+    #[serde(default, skip_serializing_if = "<[String]>::is_empty")]
+    categories: Cow<'a, [String]>,
+    // The page's Usage notes sections as plain text, with --usage-notes
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_notes: Option<Cow<'a, str>>,
+
+    // Wikidata lexeme IDs (L1347), from {{wikidata lexeme}} and --wikidata-lexemes
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    wikidata_lexeme: Vec<String>,
+
+    // Morphology (last)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    morphology: Option<Cow<'a, Morphology>>,
+
+    // The page's --sidecars records, on its first entry; written to their own streams
+    #[serde(skip)]
+    sidecar: Vec<sidecar::SidecarRecord>,
+}
+
+impl Entry<'_> {
+    /// The entry with its borrowed fields copied, for a caller that keeps it
+    /// past the page it came from
+    pub fn into_owned(self) -> Entry<'static> {
+        fn owned<B: ToOwned + ?Sized + 'static>(cow: Cow<'_, B>) -> Cow<'static, B> {
+            Cow::Owned(cow.into_owned())
+        }
+        let Entry {
+            word, ascii, sort_key, pos, pos_source, word_count, page_id, rev_timestamp, is_abbreviation, is_heteronym,
+            is_inflected, is_neologism, is_phrase, is_proper_noun, non_gloss, uncountable, plural_only, syllables, ipa,
+            phrase_type, tokens, grapheme_type, neologism_date, attested, lemma, inflection_tags, forms, headword,
+            variant_type, variant_of, relation_type, relation_target, calque_of, doublets, etymology_text,
+            dialect_tags, domain_tags, region_tags, register_tags, temporal_tags, other_labels, valency, definition,
+            glosses, qualifiers, quotations, nyms, spelling_region, categories, usage_notes, wikidata_lexeme,
+            morphology, sidecar,
+        } = self;
+        Entry {
+            word: owned(word), ascii: ascii.map(owned), sort_key: sort_key.map(owned), pos: owned(pos), pos_source,
+            word_count, page_id, rev_timestamp: rev_timestamp.map(owned), is_abbreviation, is_heteronym, is_inflected,
+            is_neologism, is_phrase, is_proper_noun, non_gloss, uncountable, plural_only, syllables, ipa,
+            phrase_type: phrase_type.map(owned), tokens: owned(tokens), grapheme_type: grapheme_type.map(owned),
+            neologism_date: neologism_date.map(owned), attested, lemma: lemma.map(owned),
+            inflection_tags: owned(inflection_tags), forms, headword, variant_type, variant_of, relation_type,
+            relation_target, calque_of: calque_of.map(owned), doublets: owned(doublets),
+            etymology_text: etymology_text.map(owned), dialect_tags, domain_tags, region_tags, register_tags,
+            temporal_tags, other_labels, valency, definition, glosses, qualifiers, quotations, nyms,
+            spelling_region: spelling_region.map(owned), categories: owned(categories),
+            usage_notes: usage_notes.map(owned), wikidata_lexeme, morphology: morphology.map(owned), sidecar,
+        }
+    }
+}
+
+/// Where a page came from in the dump, stamped onto each of its entries
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PageMeta {
+    pub page_id: Option<u64>,
+    pub rev_timestamp: Option<String>,
+}
+
+impl PageMeta {
+    pub fn from_xml(page_xml: &str) -> Self {
+        // The page's own <id> comes before its revision, which has ids of its own
+        let head = page_xml.find("<revision>").map_or(page_xml, |end| &page_xml[..end]);
+        PageMeta {
+            page_id: PAGE_ID_PATTERN.captures(head).and_then(|cap| cap[1].parse().ok()),
+            rev_timestamp: TIMESTAMP_PATTERN.captures(page_xml).map(|cap| cap[1].to_string()),
+        }
+    }
+
+    pub fn apply<'a>(&'a self, entries: &mut [Entry<'a>]) {
+        for entry in entries {
+            entry.page_id = self.page_id;
+            entry.rev_timestamp = self.rev_timestamp.as_deref().map(Cow::Borrowed);
+        }
+    }
+}
+
+/// Represents a POS section with its definitions
+struct PosSection<'a> {
+    pos: &'static str,
+    source: PosSource,
+    is_proper_noun: bool,
+    uncountable: bool,
+    plural_only: bool,
+    grapheme_type: Option<&'static str>,
+    ipa: Vec<String>,          // Transcriptions from the Pronunciation section in effect
+    headword: Option<&'a str>,  // Headword line, kept with --lua or --headwords
+    start: usize,              // Byte offset of the header in the English text
+    definitions: Vec<&'a str>,  // Raw definition lines, borrowed from the page text
+    quotations: Vec<Vec<quotations::Quotation>>,  // Per definition, with --quotations
+    nyms: Vec<Vec<nyms::Nym>>,  // Per definition
+}
+
+/// Etymology-derived data for one Etymology section and the senses under it
+#[derive(Debug, Default, Clone)]
+struct EtymologyData {
+    morphology: Option<Morphology>,
+    calque_of: Option<CalqueSource>,
+    doublets: Vec<String>,
+    text: Option<String>,
+}
+
+/// Syllable validation record - shows all sources for cross-validation
+#[derive(Debug, Serialize, Deserialize)]
+struct SyllableValidation {
+    #[serde(rename = "id")]
+    word: String,
+    rhymes: Option<usize>,
+    ipa: Option<usize>,
+    // Number of IPA transcriptions, and whether their syllable counts differ
+    // (the ipa count is the first one's)
+    ipa_transcriptions: usize,
+    ipa_counts_differ: bool,
+    category: Option<usize>,
+    hyphenation: Option<usize>,
+    final_value: Option<usize>,
+    has_disagreement: bool,
+    // The --syllable-rules rule that settled a disagreement
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved_by: Option<syllables::SyllableRule>,
+}
+
+/// Word-level data extracted once and shared across senses
+#[derive(Default)]
+struct WordData {
+    word: String,
+    ascii: Option<String>,
+    sort_key: Option<String>,
+    word_count: usize,
+    is_phrase: bool,
+    is_abbreviation: bool,
+    is_inflected: bool,
+    is_neologism: bool,
+    neologism_date: Option<String>,
+    lemma: Option<String>,
+    inflection_tags: Vec<String>,
+    phrase_type: Option<String>,
+    tokens: Vec<String>,
+    syllables: Option<usize>,
+    spelling_region: Option<String>,
+    categories: Vec<String>,
+    usage_notes: Option<String>,
+}
+
+/// What a page's senses share, kept by the caller of `parse_page_borrowed`
+/// while the entries borrow from it
+#[derive(Default)]
+pub struct PageData {
+    word: WordData,
+    // Per Etymology section, by the byte offset of its header
+    etymologies: Vec<(usize, EtymologyData)>,
+}
+
+lazy_static! {
+    // Basic XML patterns
+    pub static ref TITLE_PATTERN: Regex = Regex::new(r"<title>([^<]+)</title>").unwrap();
+    pub static ref NS_PATTERN: Regex = Regex::new(r"<ns>(\d+)</ns>").unwrap();
+    pub static ref PAGE_ID_PATTERN: Regex = Regex::new(r"<id>(\d+)</id>").unwrap();
+    pub static ref TIMESTAMP_PATTERN: Regex = Regex::new(r"<timestamp>([^<]+)</timestamp>").unwrap();
+    pub static ref TEXT_PATTERN: Regex = Regex::new(r"(?s)<text[^>]*>(.+?)</text>").unwrap();
+    pub static ref REDIRECT_PATTERN: Regex = Regex::new(r#"<redirect\s+title="[^"]+""#).unwrap();
+
+    // English section
+    pub static ref ENGLISH_SECTION: Regex = Regex::new(r"(?i)==\s*English\s*==").unwrap();
+    static ref LANGUAGE_SECTION: Regex = Regex::new(r"(?m)^==\s*([^=]+?)\s*==$").unwrap();
+
+    // POS patterns - match level 3 and 4 headers
+    static ref HEAD_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:head|en-head|head-lite)\|en\|([^}|]+)").unwrap();
+    static ref EN_POS_TEMPLATE: Regex = Regex::new(r"(?i)\{\{en-(noun|plural noun|verb|adj|adv|prop|proper noun|pron)\b").unwrap();
+    // Headword line of a POS section, and the headwords of proper nouns
+    static ref HEADWORD_LINE: Regex = Regex::new(r"(?i)^\{\{(?:en-|head\|en\|)").unwrap();
+    // Noun headwords marked uncountable ({{en-noun|-}}) or plural only
+    static ref UNCOUNTABLE_HEADWORD: Regex = Regex::new(r"(?i)^\{\{en-noun\|\s*-\s*[|}]").unwrap();
+    static ref PLURAL_ONLY_HEADWORD: Regex = Regex::new(r"(?i)^\{\{en-plural noun\s*[|}]").unwrap();
+    static ref PROPER_HEADWORD: Regex = Regex::new(r"(?i)^\{\{(?:en-prop(?:er noun)?|head\|en\|proper nouns?)\s*[|}]").unwrap();
+
+    // Definition line pattern - lines starting with # (but not ## which are sub-definitions)
+    static ref DEFINITION_LINE: Regex = Regex::new(r"(?m)^#\s+(.+)$").unwrap();
+
+    // Label patterns - for extracting from definition lines
+    static ref CONTEXT_LABEL: Regex = Regex::new(r"(?i)\{\{(?:lb|label|context)\|en\|([^}]+)\}\}").unwrap();
+    static ref CATEGORY: Regex = Regex::new(r"(?i)\[\[Category:English[\s_]+([^\]]+)\]\]").unwrap();
+    // {{cln|en|nouns with irregular plurals|heteronyms}} adds [[Category:English ...]] for each name
+    static ref CATLANGNAME_TEMPLATE: Regex = Regex::new(r"\{\{(?:cln|catlangname)\|en\|([^}]+)\}\}").unwrap();
+
+    // Other patterns
+    // Template-existence check for inflection detection (handles cases where lemma extraction fails)
+    // This matches Python's detect_inflected_form() which just checks if templates exist
+    static ref INFLECTION_TEMPLATE_EXISTS: Regex = Regex::new(r"(?i)\{\{(?:plural of|past tense of|past participle of|present participle of|comparative of|superlative of|inflection of)\|en\|").unwrap();
+    pub static ref DICT_ONLY: Regex = Regex::new(r"(?i)\{\{no entry\|en").unwrap();
+
+    // Definition-generating templates that indicate English content (even without POS headers)
+    // These are tertiary validation signals for entries that have definitions but no POS headers
+    static ref DEFINITION_TEMPLATES: Regex = Regex::new(r"(?i)\{\{(?:abbr of|abbreviation of|abbrev of|initialism of|acronym of|alternative form of|alt form|alt sp|plural of|past tense of|past participle of|present participle of|en-(?:noun|verb|adj|adv|past of))\|en\|").unwrap();
+
+    // Syllable extraction patterns
+    static ref HYPHENATION_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:hyphenation|hyph)\|en\|([^}]+)\}\}").unwrap();
+    static ref RHYMES_SYLLABLE: Regex = Regex::new(r"(?i)\{\{rhymes\|en\|[^}]*\|s=(\d+)").unwrap();
+    static ref SYLLABLE_CATEGORY: Regex = Regex::new(r"(?i)\[\[Category:English\s+(\d+)-syllable\s+words?\]\]").unwrap();
+
+    // IPA extraction pattern - matches {{IPA|en|/transcription/}} or {{IPA|en|[transcription]}}
+    static ref IPA_TEMPLATE: Regex = Regex::new(r"(?i)\{\{IPA\|en\|([^}]+)\}\}").unwrap();
+    // Extract transcription from slashes or brackets
+    static ref IPA_TRANSCRIPTION: Regex = Regex::new(r"[/\[]([^/\[\]]+)[/\]]").unwrap();
+    // Parenthesized italic qualifier on a pronunciation line: (''noun'')
+    static ref ITALIC_QUALIFIER: Regex = Regex::new(r"\(''([^'()]+)''\)").unwrap();
+
+    // Phrase type patterns
+    static ref PREP_PHRASE_TEMPLATE: Regex = Regex::new(r"(?i)\{\{en-prepphr\b").unwrap();
+
+    // Morphology/etymology patterns
+    static ref ETYMOLOGY_TITLE: Regex = Regex::new(r"(?i)^Etymology\s*\d*$").unwrap();
+    static ref NEXT_SECTION: Regex = Regex::new(r"\n===").unwrap();
+    static ref SUFFIX_TEMPLATE: Regex = Regex::new(r"(?i)\{\{suffix\|en\|([^}|]+)\|([^}|]+)(?:\|([^}|]+))?\}\}").unwrap();
+    static ref PREFIX_TEMPLATE: Regex = Regex::new(r"(?i)\{\{prefix\|en\|([^}|]+)\|([^}|]+)(?:\|([^}|]+))?\}\}").unwrap();
+    // Matches both {{affix|en|...}} and {{af|en|...}} (common shorthand)
+    static ref AFFIX_TEMPLATE: Regex = Regex::new(r"(?i)\{\{af(?:fix)?\|en\|([^}]+)\}\}").unwrap();
+    static ref COMPOUND_TEMPLATE: Regex = Regex::new(r"(?i)\{\{compound\|en\|([^}]+)\}\}").unwrap();
+    static ref SURF_TEMPLATE: Regex = Regex::new(r"(?i)\{\{surf\|en\|([^}]+)\}\}").unwrap();
+    static ref CONFIX_TEMPLATE: Regex = Regex::new(r"(?i)\{\{confix\|en\|([^}|]+)\|([^}|]+)\|([^}|]+)(?:\|([^}|]+))?\}\}").unwrap();
+    // Word-formation processes that derive from whole source words rather than affixes
+    static ref BLEND_TEMPLATE: Regex = Regex::new(r"(?i)\{\{blend\|en\|([^}]+)\}\}").unwrap();
+    static ref CLIPPING_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:clipping of|clipping|clip)\|en\|([^}]+)\}\}").unwrap();
+    // Etymological relations to other words
+    static ref CALQUE_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:calque|cal|clq)\|en\|").unwrap();
+    static ref DOUBLET_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:doublet|dbt)\|en\|").unwrap();
+    static ref BACK_FORMATION_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:back-formation from|back-formation|back-form|bf)\|en\|([^}]+)\}\}").unwrap();
+    // Language code prefix pattern (e.g., "pt:", "grc:", "ang:") - matches Python's LANG_CODE_PREFIX
+    static ref LANG_CODE_PREFIX: Regex = Regex::new(r"(?i)^[a-z]{2,4}:").unwrap();
+    // Wikilink pattern - matches [[word]] or [[word|display]] and extracts the target
+    // Used to strip wikilink markup from morphology components
+    static ref WIKILINK_PATTERN: Regex = Regex::new(r"\[\[([^\]|]+)(?:\|[^\]]+)?\]\]").unwrap();
+
+    // POS_MAP and label sets are now loaded from schema/*.yaml at runtime
+    // via init_pos_map() and init_labels()
+
+    // Pattern to extract {{tlb|en|...}} or {{lb|en|...}} from text
+    // Used for head line labels (spelling variants)
+    static ref TLB_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:tlb|lb)\|en\|([^}]+)\}\}").unwrap();
+
+    // Sense-level templates on definition lines
+    static ref NON_GLOSS_TEMPLATE: Regex = Regex::new(r"\{\{(?:non-gloss definition|non-gloss|n-g|ngd)\|").unwrap();
+    static ref HOT_WORD_TEMPLATE: Regex = Regex::new(r"(?i)\{\{(?:hot word|hotword)\s*[|}]").unwrap();
+    static ref GLOSS_TEMPLATE: Regex = Regex::new(r"\{\{(?:gloss|gl)\|").unwrap();
+    static ref QUALIFIER_TEMPLATE: Regex = Regex::new(r"\{\{(?:qualifier|qual|q|i)\|").unwrap();
+    static ref DEFDATE_TEMPLATE: Regex = Regex::new(r"\{\{(?:defdate|defdt|defdates)\|").unwrap();
+    // Dates inside {{defdate}}: "1837", "1950s", "15th c.", "late 19th century".
+    // An ordinal there is always a century, also in ranges like "8th–17th c."
+    static ref ATTESTED_YEAR: Regex = Regex::new(r"\b(\d{3,4})s?\b").unwrap();
+    static ref ATTESTED_CENTURY: Regex =
+        Regex::new(r"(?i)\b(?:(early|mid|late)[\s-]*)?(\d{1,2})(?:st|nd|rd|th)\b").unwrap();
+
+    // Pre-filter run once over the English section: which template families
+    // occur at all. Each pattern must match wherever its extractors could, so
+    // a family that is absent lets parse_page skip them. Order follows TemplateFamily.
+    static ref TEMPLATE_FAMILIES: RegexSet = RegexSet::new([
+        // Labels: CONTEXT_LABEL
+        r"(?i)\{\{(?:lb|label|context)\|en\|",
+        // Glosses: GLOSS_TEMPLATE
+        r"\{\{(?:gloss|gl)\|",
+        // NonGloss: NON_GLOSS_TEMPLATE
+        r"\{\{(?:non-gloss definition|non-gloss|n-g|ngd)\|",
+        // Qualifiers: QUALIFIER_TEMPLATE
+        r"\{\{(?:qualifier|qual|q|i)\|",
+        // Defdate: DEFDATE_TEMPLATE
+        r"\{\{(?:defdate|defdt|defdates)\|",
+        // Etymology: morphology, calque and doublet templates
+        r"(?i)\{\{(?:suffix|prefix|confix|compound|af|affix|surf|blend|clipping of|clipping|clip|back-formation from|back-formation|back-form|bf|calque|cal|clq|doublet|dbt)\|en\|",
+    ]).unwrap();
+
+    // Inflection templates for lemma extraction
+    // These templates indicate the word is a grammatical inflection of a base word (lemma)
+    // Only includes true morphological inflections, not alternative spellings or forms
+    // Format: {{template name|en|lemma|optional params...}}
+    // Each with the inflection tags it implies; {{inflection of}} lists its own
+    static ref INFLECTION_TEMPLATES: Vec<(&'static [&'static str], &'static [&'static str], Regex)> = vec![
+        // Noun inflections
+        (&["plural of"], &["plural"], Regex::new(r"(?i)\{\{plural of\|en\|([^|}]+)").unwrap()),
+
+        // Verb inflections
+        (&["past tense of"], &["past"], Regex::new(r"(?i)\{\{past tense of\|en\|([^|}]+)").unwrap()),
+        (&["past participle of"], &["past", "participle"], Regex::new(r"(?i)\{\{past participle of\|en\|([^|}]+)").unwrap()),
+        (&["present participle of"], &["present", "participle"], Regex::new(r"(?i)\{\{present participle of\|en\|([^|}]+)").unwrap()),
+        (&["en-third-person singular of", "third-person singular of"], &["third-person", "singular", "present"], Regex::new(r"(?i)\{\{(?:en-third-person singular of|third-person singular of)\|en\|([^|}]+)").unwrap()),
+
+        // Adjective/adverb inflections
+        (&["comparative of"], &["comparative"], Regex::new(r"(?i)\{\{comparative of\|en\|([^|}]+)").unwrap()),
+        (&["superlative of"], &["superlative"], Regex::new(r"(?i)\{\{superlative of\|en\|([^|}]+)").unwrap()),
+
+        // Generic inflection template (handles various forms)
+        (&["inflection of"], &[], Regex::new(r"(?i)\{\{inflection of\|en\|([^|}]+)").unwrap()),
+    ];
+}
+
+/// Template families detected by TEMPLATE_FAMILIES, in pattern order
+#[derive(Debug, Clone, Copy)]
+enum TemplateFamily {
+    Labels,
+    Glosses,
+    NonGloss,
+    Qualifiers,
+    Defdate,
+    Etymology,
+}
+
+/// Families present in one page's English section
+struct PageFamilies(SetMatches);
+
+impl PageFamilies {
+    fn scan(english_text: &str) -> Self {
+        PageFamilies(TEMPLATE_FAMILIES.matches(english_text))
+    }
+
+    fn has(&self, family: TemplateFamily) -> bool {
+        self.0.matched(family as usize)
+    }
+}
+
+// Template names for the page-level patterns above. PageAst finds the templates
+// by name, and each pattern then only runs over a candidate template's source.
+const HEAD_NAMES: &[&str] = &["head", "en-head", "head-lite"];
+const INFLECTION_NAMES: &[&str] = &[
+    "plural of", "past tense of", "past participle of", "present participle of",
+    "comparative of", "superlative of", "inflection of",
+];
+const DEFINITION_NAMES: &[&str] = &[
+    "abbr of", "abbreviation of", "abbrev of", "initialism of", "acronym of",
+    "alternative form of", "alt form", "alt sp", "plural of", "past tense of",
+    "past participle of", "present participle of", "en-noun", "en-verb", "en-adj",
+    "en-adv", "en-past of",
+];
+const HYPHENATION_NAMES: &[&str] = &["hyphenation", "hyph"];
+const CATLANGNAME_NAMES: &[&str] = &["cln", "catlangname"];
+const HOT_WORD_NAMES: &[&str] = &["hot word", "hotword"];
+const TLB_NAMES: &[&str] = &["tlb", "lb"];
+const CALQUE_NAMES: &[&str] = &["calque", "cal", "clq"];
+const DOUBLET_NAMES: &[&str] = &["doublet", "dbt"];
+const AFFIX_NAMES: &[&str] = &["affix", "af"];
+const CLIPPING_NAMES: &[&str] = &["clipping of", "clipping", "clip"];
+const BACK_FORMATION_NAMES: &[&str] = &["back-formation from", "back-formation", "back-form", "bf"];
+
+// Apostrophes count as part of a word (o'clock, rock 'n' roll)
+const APOSTROPHES: [char; 3] = ['\u{2019}', '\'', '\u{2018}'];
+
+// The file, group, record and unit separators, which Python's str.isspace()
+// counts as whitespace and char::is_whitespace() does not
+const INFORMATION_SEPARATORS: RangeInclusive<char> = '\u{1C}'..='\u{1F}';
+
+// Symbols that char::is_alphabetic() takes in (they are Other_Alphabetic) but
+// str.isalpha() does not, and that are neither numbers nor combining marks:
+// Ⓐ-ⓩ, and the squared and negative circled/squared capitals 🄰-🆉
+const CIRCLED_LATIN_LETTERS: RangeInclusive<char> = '\u{24B6}'..='\u{24E9}';
+const ENCLOSED_LATIN_CAPITALS: RangeInclusive<char> = '\u{1F130}'..='\u{1F189}';
+
+pub fn is_englishlike(token: &str) -> bool {
+    let normalized: String = token.nfc().collect();
+
+    // Reject non-ASCII whitespace except ordinary space
+    if normalized.chars().any(|ch| ch != ' ' && (ch.is_whitespace() || INFORMATION_SEPARATORS.contains(&ch))) {
+        return false;
+    }
+
+    // Reject empty or only spaces
+    if normalized.trim().is_empty() {
+        return false;
+    }
+
+    let allowed_punct = ['-', '\u{2013}', '.', '/'];
+    let forbidden = ['&', ';', '<', '>'];
+
+    let mut saw_latin_letter = false;
+
+    for ch in normalized.chars() {
+        if ch == ' ' {
+            continue;
+        }
+
+        if forbidden.contains(&ch) {
+            return false;
+        }
+
+        if ch.is_ascii() {
+            if ch.is_alphabetic() {
+                saw_latin_letter = true;
+            }
+        } else {
+            // Non-ASCII character - check if it's Latin-based
+            let cp = ch as u32;
+            if is_letter(ch) {
+                // Accept common Latin diacritics (À-ɏ range)
+                if (0x00C0..=0x024F).contains(&cp) {
+                    saw_latin_letter = true;
+                } else {
+                    return false;
+                }
+            } else if APOSTROPHES.contains(&ch) || allowed_punct.contains(&ch) {
+                // Allow punctuation
+            } else {
+                // Reject combining diacritical marks (U+0300-U+036F) and emojis
+                // to match Python scanner behavior
+                if (0x0300..=0x036F).contains(&cp) {
+                    return false;
+                }
+                if cp > 0xFFFF || (0x1F000..=0x1FFFF).contains(&cp) {
+                    return false;
+                }
+                // Other non-alphabetic non-punctuation chars pass through
+            }
+        }
+    }
+
+    saw_latin_letter
+}
+
+/// The words of a multiword title, split at whitespace, hyphens and en
+/// dashes. Apostrophes stay in their token as in `is_englishlike`, and so do
+/// periods (St. Louis); other punctuation at either end is trimmed
+/// ("easy come, easy go").
+pub fn phrase_tokens(word: &str) -> Vec<String> {
+    word.split(|ch: char| ch.is_whitespace() || ch == '-' || ch == '\u{2013}')
+        .map(|token| token.trim_matches(|ch: char| !ch.is_alphanumeric() && ch != '.' && !APOSTROPHES.contains(&ch)))
+        .filter(|token| !token.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Python's str.isalpha(): the letter categories. Rust's is_alphabetic() also
+/// takes in letter numbers (Ⅻ), vowel signs (ा) and enclosed letters (Ⓐ).
+fn is_letter(ch: char) -> bool {
+    ch.is_alphabetic()
+        && !ch.is_numeric()
+        && !is_combining_mark(ch)
+        && !CIRCLED_LATIN_LETTERS.contains(&ch)
+        && !ENCLOSED_LATIN_CAPITALS.contains(&ch)
+}
+
+/// How a page's title passes the English-like check
+pub enum TitleKind {
+    Word,
+    /// Not a word, but --graphemes admits it for its letter, numeral and
+    /// symbol senses; holds the unescaped title ("&amp;" -> "&")
+    Grapheme(String),
+    NonLatin,
+}
+
+pub fn classify_title(title: &str) -> TitleKind {
+    if is_englishlike(title) {
+        TitleKind::Word
+    } else if filters::graphemes_enabled() {
+        TitleKind::Grapheme(unescape_xml(title))
+    } else {
+        TitleKind::NonLatin
+    }
+}
+
+/// Drop the senses of a grapheme title other than its letters, numerals and symbols
+pub fn keep_grapheme_senses(entries: &mut Vec<Entry<'_>>) {
+    sidecar::retain_entries(entries, |entry| entry.grapheme_type.is_some());
+}
+
+/// A page's text, as `TEXT_PATTERN` captures it, found with byte searches:
+/// from the first `<text ...>` tag to the first `</text>` after at least one
+/// byte of text
+fn page_text(page_xml: &str) -> Option<&str> {
+    let bytes = page_xml.as_bytes();
+    let tag = memmem::find(bytes, b"<text")?;
+    let start = tag + memchr::memchr(b'>', &bytes[tag..])? + 1;
+    let end = start + 1 + memmem::find(bytes.get(start + 1..)?, b"</text>")?;
+    Some(&page_xml[start..end])
+}
+
+/// Whether wikitext has an English section. Nearly every English page spells
+/// the header `==English==`, found with a byte search; the regex, which allows
+/// spacing and case variants, only runs on pages without it.
+fn has_english_section(text: &str) -> bool {
+    memmem::find(text.as_bytes(), b"==English==").is_some() || ENGLISH_SECTION.is_match(text)
+}
+
+/// Where the English section is in a page's text: the start and end of its
+/// header, and the start of the next language's header (or the end)
+fn english_section_bounds(text: &str) -> Option<(usize, usize, usize)> {
+    let english_match = ENGLISH_SECTION.find(text)?;
+    let english_start = english_match.end();
+
+    // Find next language section
+    let next_section = LANGUAGE_SECTION
+        .find_iter(&text[english_start..])
+        .find(|m| {
+            let lang = m.as_str().trim_matches('=').trim();
+            !lang.eq_ignore_ascii_case("english")
+        })
+        .map(|m| english_start + m.start());
+
+    Some((english_match.start(), english_start, next_section.unwrap_or(text.len())))
+}
+
+fn extract_english_section(text: &str) -> Option<&str> {
+    let (_, start, end) = english_section_bounds(text)?;
+    Some(&text[start..end])
+}
+
+/// The English section with its header: all of a page's text that
+/// `parse_page` reads, and that `extract_english_section` finds the same
+/// section in
+fn english_span(text: &str) -> Option<&str> {
+    let (header, _, end) = english_section_bounds(text)?;
+    Some(&text[header..end])
+}
+
+/// Classified context labels of one definition line, each list sorted
+#[derive(Debug, Default)]
+struct SenseLabels {
+    register: Vec<String>,
+    region: Vec<String>,
+    domain: Vec<String>,
+    temporal: Vec<String>,
+    dialect: Vec<String>,
+    valency: Option<String>,
+    /// Labels none of the above classify, as written, in line order and without repeats
+    other: Vec<String>,
+}
+
+/// Transitivity labels, which sit outside the schema's label buckets.
+/// "also intransitive" on a transitive sense makes it ambitransitive.
+const VALENCY_LABELS: &[(&str, &str)] = &[
+    ("transitive", "transitive"),
+    ("also transitive", "transitive"),
+    ("intransitive", "intransitive"),
+    ("also intransitive", "intransitive"),
+    ("ditransitive", "ditransitive"),
+    ("ambitransitive", "ambitransitive"),
+];
+
+/// Combine the transitivity labels of one sense into a single valency
+fn valency(labels: &HashSet<&str>) -> Option<String> {
+    let valency = if labels.contains("ambitransitive")
+        || (labels.contains("intransitive") && (labels.contains("transitive") || labels.contains("ditransitive")))
+    {
+        "ambitransitive"
+    } else if labels.contains("ditransitive") {
+        "ditransitive"
+    } else if labels.contains("transitive") {
+        "transitive"
+    } else if labels.contains("intransitive") {
+        "intransitive"
+    } else {
+        return None;
+    };
+    Some(valency.to_string())
+}
+
+/// Hedges in front of a label that don't change what it classifies as
+/// ("chiefly US", "especially British")
+const LABEL_MODIFIERS: &[&str] = &["chiefly", "especially", "mainly", "mostly", "primarily", "usually"];
+
+/// {{lb}} parameters that join the labels around them ("chiefly|_|US",
+/// "transitive|or|intransitive") rather than label the sense
+const LABEL_CONNECTORS: &[&str] = &["and", "or", ",", ";"];
+
+/// Bring a context label to the form the label schema keys use: lowercase,
+/// underscores as spaces, single spaces and no leading hedges. "US English"
+/// style labels fall back to the bare region when only that is in the schema.
+fn normalize_label(label: &str) -> String {
+    let label = label.replace('_', " ").to_lowercase();
+    let mut words: Vec<&str> = label.split_whitespace().collect();
+    while words.len() > 1 && LABEL_MODIFIERS.contains(&words[0]) {
+        words.remove(0);
+    }
+    let label = words.join(" ");
+    match label.strip_suffix(" english") {
+        Some(region) if !is_known_label(&label) && is_known_label(region) => region.to_string(),
+        _ => label,
+    }
+}
+
+fn is_known_label(label: &str) -> bool {
+    get_register_labels().contains(label)
+        || get_temporal_labels().contains(label)
+        || get_domain_labels().contains(label)
+        || get_region_labels().contains_key(label)
+        || get_spelling_labels().contains_key(label)
+        || get_dialect_labels().contains_key(label)
+}
+
+/// Extract labels from a single definition line
+fn extract_labels_from_line(line: &str) -> SenseLabels {
+    let mut register_tags = HashSet::new();
+    let mut region_tags = HashSet::new();
+    let mut domain_tags = HashSet::new();
+    let mut temporal_tags = HashSet::new();
+    let mut dialect_tags = HashSet::new();
+    let mut valency_labels = HashSet::new();
+    let mut other: Vec<String> = Vec::new();
+
+    // Extract from context labels in this line
+    let register_labels = get_register_labels();
+    let temporal_labels = get_temporal_labels();
+    let domain_labels = get_domain_labels();
+    let region_labels = get_region_labels();
+    let dialect_labels = get_dialect_labels();
+
+    for cap in CONTEXT_LABEL.captures_iter(line) {
+        for raw in cap[1].split('|') {
+            let label = normalize_label(raw);
+
+            if let Some(&(_, valency)) = VALENCY_LABELS.iter().find(|(name, _)| *name == label) {
+                valency_labels.insert(valency);
+                continue;
+            }
+
+            // Dialects are finer-grained than regions, so a label like "scotland"
+            // can yield both a dialect and a region code
+            let dialect = dialect_labels.get(&label);
+            if let Some(dialect) = dialect {
+                dialect_tags.insert(dialect.clone());
+            }
+
+            if register_labels.contains(&label) {
+                register_tags.insert(label);
+            } else if temporal_labels.contains(&label) {
+                temporal_tags.insert(label);
+            } else if domain_labels.contains(&label) {
+                domain_tags.insert(label);
+            } else if let Some(region_code) = region_labels.get(&label) {
+                region_tags.extend(region_with_parents(region_code));
+            } else if dialect.is_none()
+                && !label.is_empty()
+                && !raw.contains('=')
+                && !LABEL_CONNECTORS.contains(&label.as_str())
+                && !LABEL_MODIFIERS.contains(&label.as_str())
+                && !other.iter().any(|seen| normalize_label(seen) == label)
+            {
+                other.push(raw.trim().to_string());
+            }
+        }
+    }
+
+    // Convert to sorted vectors
+    let mut register: Vec<String> = register_tags.into_iter().collect();
+    let mut region: Vec<String> = region_tags.into_iter().collect();
+    let mut domain: Vec<String> = domain_tags.into_iter().collect();
+    let mut temporal: Vec<String> = temporal_tags.into_iter().collect();
+    let mut dialect: Vec<String> = dialect_tags.into_iter().collect();
+
+    register.sort();
+    region.sort();
+    domain.sort();
+    temporal.sort();
+    dialect.sort();
+
+    SenseLabels { register, region, domain, temporal, dialect, valency: valency(&valency_labels), other }
+}
+
+/// Detect a recently coined term from {{hot word}} or a neologisms category.
+/// Returns (is_neologism, date) where the date comes from the template's date= parameter.
+fn extract_neologism(ast: &PageAst, categories: &[String]) -> (bool, Option<String>) {
+    let range = 0..ast.text().len();
+    if let Some(source) = ast.templates_matching_in(range, HOT_WORD_NAMES, &HOT_WORD_TEMPLATE).next() {
+        let template = WikitextParser::new(source).parse_template();
+        let date = template.named.get("date").filter(|date| !date.is_empty()).cloned();
+        return (true, date);
+    }
+    let in_category = categories.iter().any(|c| c == "neologisms" || c.ends_with(" neologisms"));
+    (in_category, None)
+}
+
+/// Collect {{gloss|...}} qualifiers from a definition line, with links and
+/// nested templates resolved by WikitextParser
+fn extract_glosses(line: &str) -> Vec<String> {
+    GLOSS_TEMPLATE
+        .find_iter(line)
+        .filter_map(|m| {
+            let template = WikitextParser::new(&line[m.start()..]).parse_template();
+            template.params.into_iter().find(|p| !p.is_empty())
+        })
+        .collect()
+}
+
+/// Qualifiers from {{qualifier}} and its aliases on a definition line, one
+/// per parameter as plain text: {{q|chiefly|of horses}} gives "chiefly" and
+/// "of horses"
+fn extract_qualifiers(line: &str) -> Vec<String> {
+    QUALIFIER_TEMPLATE
+        .find_iter(line)
+        .flat_map(|m| WikitextParser::with_markup(&line[m.start()..], MarkupMode::Strip).parse_template().params)
+        .map(|param| appendix::clean_wikitext(&param))
+        .filter(|param| !param.is_empty())
+        .collect()
+}
+
+/// Plain text of a definition line for the `definition` field, with --definitions
+fn definition_text(def_line: &str) -> Option<String> {
+    if !DEFINITIONS.get().is_some_and(|&on| on) {
+        return None;
+    }
+    // Form-of templates are removed entirely, leaving only their punctuation
+    Some(appendix::clean_wikitext(&unescape_xml(def_line))).filter(|text| text.chars().any(char::is_alphanumeric))
+}
+
+/// Kind of grapheme a NUM or SYM header introduces ("Letter" maps to SYM)
+fn grapheme_type(pos: &str, header: &str) -> Option<&'static str> {
+    match pos {
+        "NUM" => Some("numeral"),
+        "SYM" if header.starts_with("letter") => Some("letter"),
+        "SYM" => Some("symbol"),
+        _ => None,
+    }
+}
+
+/// POS code for the part of speech an `{{en-...}}` headword template names
+fn template_pos(name: &str) -> Option<&'static str> {
+    let header = match name.to_lowercase().as_str() {
+        "noun" | "plural noun" => "noun",
+        "verb" => "verb",
+        "adj" => "adjective",
+        "adv" => "adverb",
+        "prop" | "proper noun" => "proper noun",
+        "pron" => "pronoun",
+        _ => return None,
+    };
+    get_pos_map().get(header).map(String::as_str)
+}
+
+/// Plain text of the Usage notes sections, one line per paragraph or list item
+fn extract_usage_notes(ast: &PageAst) -> Option<String> {
+    let text = ast.text();
+    let headers: Vec<&HeaderNode> = ast.headers_from(3).collect();
+    let mut lines: Vec<String> = Vec::new();
+    for (i, header) in headers.iter().enumerate() {
+        if !ast.slice(&header.title).eq_ignore_ascii_case("usage notes") {
+            continue;
+        }
+        let body_end = headers.get(i + 1).map_or(text.len(), |next| next.span.start);
+        for line in text[header.span.end..body_end].lines() {
+            let line = line.trim();
+            // Tables don't survive as plain text
+            if line.starts_with("{|") || line.starts_with('|') || line.starts_with('!') {
+                continue;
+            }
+            let line = appendix::clean_wikitext(&unescape_xml(line.trim_start_matches(['*', '#', ':', ';'])));
+            if line.chars().any(char::is_alphanumeric) {
+                lines.push(line);
+            }
+        }
+    }
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Headword line of a section body: the first line before its definitions
+/// that opens with an English headword template
+fn headword_line(body: &str) -> Option<&str> {
+    body.lines()
+        .map(str::trim)
+        .take_while(|line| !line.starts_with('#'))
+        .find(|line| HEADWORD_LINE.is_match(line))
+}
+
+/// POS named by a headword line's `{{en-...}}` template
+fn headword_template_pos(line: &str) -> Option<&'static str> {
+    let cap = EN_POS_TEMPLATE.captures(line).filter(|cap| cap.get(0).is_some_and(|m| m.start() == 0))?;
+    template_pos(&cap[1])
+}
+
+/// POS codes a pronunciation qualifier names ("noun", "noun and verb", "adjective, adverb")
+fn qualifier_pos(qualifier: &str) -> Vec<String> {
+    qualifier
+        .split([',', ';', '/'])
+        .flat_map(|part| part.split(" and "))
+        .filter_map(|part| get_pos_map().get(part.trim().to_lowercase().as_str()).cloned())
+        .collect()
+}
+
+/// The POS codes a pronunciation line is qualified with ({{q|noun}},
+/// {{sense|verb}}, `q=`, `(''noun'')`; none if unqualified) and its transcriptions
+type PronunciationLine = (Vec<String>, Vec<String>);
+
+/// The lines of a Pronunciation section that have transcriptions
+fn pronunciation_lines(body: &str) -> Vec<PronunciationLine> {
+    let mut lines = Vec::new();
+    for line in body.lines() {
+        let line = unescape_xml(line);
+        let ast = PageAst::parse(&line);
+        let mut transcriptions: Vec<String> = Vec::new();
+        let mut qualifiers: Vec<String> = ITALIC_QUALIFIER.captures_iter(&line).map(|cap| cap[1].to_string()).collect();
+        for source in ast.templates(&["IPA", "q", "qual", "qualifier", "i", "sense", "s"]) {
+            let template = WikitextParser::new(source).parse_template();
+            if template.name.eq_ignore_ascii_case("IPA") {
+                if template.params.first().is_some_and(|lang| lang == "en") {
+                    transcriptions.extend(template.params.iter().skip(1).filter(|p| IPA_TRANSCRIPTION.is_match(p)).cloned());
+                    qualifiers.extend(template.named.iter().filter(|(k, _)| k.starts_with('q')).map(|(_, v)| v.clone()));
+                }
+            } else {
+                qualifiers.extend(template.params);
+            }
+        }
+        if !transcriptions.is_empty() {
+            lines.push((qualifiers.iter().flat_map(|q| qualifier_pos(q)).collect(), transcriptions));
+        }
+    }
+    lines
+}
+
+/// A POS section's transcriptions: the lines qualified with its POS, or else
+/// the unqualified ones
+fn section_ipa(lines: &[PronunciationLine], pos: &str) -> Vec<String> {
+    let qualified = lines.iter().any(|(parts, _)| parts.iter().any(|part| part == pos));
+    let mut ipa: Vec<String> = Vec::new();
+    for (parts, transcriptions) in lines {
+        if (qualified && parts.iter().any(|part| part == pos)) || (!qualified && parts.is_empty()) {
+            for transcription in transcriptions {
+                if !ipa.contains(transcription) {
+                    ipa.push(transcription.clone());
+                }
+            }
+        }
+    }
+    ipa
+}
+
+/// A transcription without its delimiters and syllable breaks, for comparison
+fn ipa_key(transcription: &str) -> String {
+    transcription.chars().filter(|c| !matches!(c, '/' | '[' | ']' | '.' | '‿') && !c.is_whitespace()).collect()
+}
+
+/// One spelling, several pronunciations: two POS sections (by etymology or
+/// part of speech) whose transcriptions have none in common
+fn is_heteronym(sections: &[PosSection]) -> bool {
+    let keys: Vec<HashSet<String>> = sections
+        .iter()
+        .filter(|section| !section.ipa.is_empty())
+        .map(|section| section.ipa.iter().map(|t| ipa_key(t)).collect())
+        .collect();
+    keys.iter().enumerate().any(|(i, a)| keys[i + 1..].iter().any(|b| a.is_disjoint(b)))
+}
+
+/// Parse POS sections and their definitions from English text
+fn parse_pos_sections<'a>(ast: &PageAst<'a>) -> Vec<PosSection<'a>> {
+    let english_text = ast.text();
+    let proper_noun = get_pos_map().get("proper noun").map(String::as_str);
+
+    // Find all POS headers (level 3 and 4) and their positions. A header the
+    // schema doesn't map still counts when its headword template names a POS.
+    let all_headers: Vec<&HeaderNode> = ast.headers_from(3).collect();
+
+    // The Pronunciation section in effect at each header: the latest one, until
+    // a shallower header (the next Etymology) closes it
+    let mut pronunciations: Vec<Vec<PronunciationLine>> = Vec::new();
+    // The level and index in `pronunciations` of the section in effect
+    let mut pronunciation: Option<(usize, usize)> = None;
+    let mut pronunciation_at: Vec<Option<usize>> = Vec::with_capacity(all_headers.len());
+    for (i, header) in all_headers.iter().enumerate() {
+        if pronunciation.is_some_and(|(level, _)| header.level < level) {
+            pronunciation = None;
+        }
+        if ast.slice(&header.title).to_lowercase().starts_with("pronunciation") {
+            let body_end = all_headers.get(i + 1).map_or(english_text.len(), |next| next.span.start);
+            pronunciations.push(pronunciation_lines(&english_text[header.span.end..body_end]));
+            pronunciation = Some((header.level, pronunciations.len() - 1));
+        }
+        pronunciation_at.push(pronunciation.map(|(_, index)| index));
+    }
+
+    let mut sections: Vec<PosSection> = all_headers
+        .iter()
+        .enumerate()
+        .filter_map(|(i, header)| {
+            let header_text = ast.slice(&header.title).to_lowercase();
+            let header_normalized = header_text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let body_end = all_headers.get(i + 1).map_or(english_text.len(), |next| next.span.start);
+            let headword = headword_line(&english_text[header.span.end..body_end]);
+
+            // Map to normalized POS (proper noun -> proper, etc.)
+            let (pos, grapheme_type, source) = match get_pos_map().get(header_normalized.as_str()) {
+                Some(mapped_pos) => {
+                    (mapped_pos.as_str(), grapheme_type(mapped_pos, &header_normalized), PosSource::Header)
+                }
+                None => (headword.and_then(headword_template_pos)?, None, PosSource::Template),
+            };
+            Some(PosSection {
+                pos,
+                source,
+                is_proper_noun: Some(pos) == proper_noun || headword.is_some_and(|line| PROPER_HEADWORD.is_match(line)),
+                uncountable: headword.is_some_and(|line| UNCOUNTABLE_HEADWORD.is_match(line)),
+                plural_only: headword.is_some_and(|line| PLURAL_ONLY_HEADWORD.is_match(line)),
+                grapheme_type,
+                ipa: pronunciation_at[i].map(|index| section_ipa(&pronunciations[index], pos)).unwrap_or_default(),
+                headword: headword.filter(|_| lua::enabled() || HEADWORDS.get().is_some_and(|&on| on)),
+                start: header.span.start,
+                definitions: Vec::new(),
+                quotations: Vec::new(),
+                nyms: Vec::new(),
+            })
+        })
+        .collect();
+
+    // For each POS header, extract definitions until next header
+    let starts: Vec<usize> = sections.iter().map(|section| section.start).collect();
+    for (i, section) in sections.iter_mut().enumerate() {
+        let section_end = starts.get(i + 1).copied().unwrap_or(english_text.len());
+        let section_text = &english_text[section.start..section_end];
+
+        // Extract definition lines (lines starting with single #)
+        let lines: Vec<(Range<usize>, &str)> = DEFINITION_LINE
+            .captures_iter(section_text)
+            .map(|cap| (cap.get(0).map_or(0..0, |m| m.range()), cap.get(1).map_or("", |m| m.as_str())))
+            .collect();
+        // A definition's quotations are on the lines up to the next definition
+        if QUOTATIONS.get().is_some_and(|&on| on) {
+            section.quotations = lines
+                .iter()
+                .enumerate()
+                .map(|(i, (line, _))| {
+                    let block_end = lines.get(i + 1).map_or(section_text.len(), |(next, _)| next.start);
+                    quotations::parse_quotations(&section_text[line.end..block_end])
+                })
+                .collect();
+        }
+        section.nyms = lines
+            .iter()
+            .enumerate()
+            .map(|(i, (line, _))| {
+                let block_end = lines.get(i + 1).map_or(section_text.len(), |(next, _)| next.start);
+                nyms::inline_nyms(&section_text[line.end..block_end])
+            })
+            .collect();
+        section.definitions = lines.into_iter().map(|(_, definition)| definition).collect();
+    }
+
+    // Lines of a nym section go to the sense their {{sense|...}} key names, in
+    // the POS section the nym section is under
+    for (i, header) in all_headers.iter().enumerate() {
+        let Some(relation) = nyms::relation(ast.slice(&header.title)) else {
+            continue;
+        };
+        let Some(section) = sections.iter_mut().rev().find(|section| section.start < header.span.start) else {
+            continue;
+        };
+        let body_end = all_headers.get(i + 1).map_or(english_text.len(), |next| next.span.start);
+        for (key, nyms) in nyms::section_nyms(relation, &english_text[header.span.end..body_end]) {
+            let index = match key {
+                Some(key) => nyms::sense_for_key(&key, &section.definitions),
+                None => (section.definitions.len() == 1).then_some(0),
+            };
+            if let Some(sense_nyms) = index.and_then(|index| section.nyms.get_mut(index)) {
+                for nym in nyms {
+                    if !sense_nyms.iter().any(|known| known.relation == nym.relation && known.term == nym.term) {
+                        sense_nyms.push(nym);
+                    }
+                }
+            }
+        }
+    }
+    sections.retain(|section| !section.definitions.is_empty());
+
+    sections
+}
+
+fn extract_syllable_count_from_hyphenation(ast: &PageAst) -> Option<usize> {
+    let cap = ast.captures(HYPHENATION_NAMES, &HYPHENATION_TEMPLATE)?;
+    let content = cap[1].to_string();
+
+    // Handle alternatives (||) - use first alternative
+    let first_alt = content.split("||").next()?;
+
+    // Parse pipe-separated segments
+    let parts: Vec<&str> = first_alt.split('|').collect();
+
+    // Filter syllables (exclude parameters and empty parts)
+    let syllables: Vec<String> = parts
+        .iter()
+        .filter_map(|&part| {
+            let part = part.trim();
+            if part.is_empty() || part.contains('=') {
+                None
+            } else {
+                Some(part.to_string())
+            }
+        })
+        .collect();
+
+    // Single-part templates with long unseparated text are likely incomplete
+    if syllables.len() == 1 && syllables[0].len() > 3 {
+        return None;
+    }
+
+    if syllables.is_empty() {
+        None
+    } else {
+        Some(syllables.len())
+    }
+}
+
+fn extract_syllable_count_from_rhymes(ast: &PageAst) -> Option<usize> {
+    ast.captures(&["rhymes"], &RHYMES_SYLLABLE)
+        .and_then(|cap| cap[1].parse::<usize>().ok())
+}
+
+/// Collect English category memberships, from explicit links and {{cln}} templates,
+/// normalized: "English" prefix and sort keys dropped, underscores and runs of spaces
+/// collapsed. Links come first, then templates, each in page order.
+fn extract_categories(ast: &PageAst) -> Vec<String> {
+    let linked = ast
+        .links_with_prefix("Category:English")
+        .filter_map(|link| CATEGORY.captures(link))
+        .filter_map(|cap| cap[1].split('|').next().map(str::to_string));
+    let templated = ast.captures_iter(CATLANGNAME_NAMES, &CATLANGNAME_TEMPLATE).flat_map(|cap| {
+        cap[1]
+            .split('|')
+            .filter(|param| !param.contains('='))
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    });
+
+    let mut categories: Vec<String> = Vec::new();
+    for name in linked.chain(templated) {
+        let name = name.replace('_', " ").split_whitespace().collect::<Vec<_>>().join(" ");
+        if !name.is_empty() && !categories.contains(&name) {
+            categories.push(name);
+        }
+    }
+    categories
+}
+
+fn extract_syllable_count_from_categories(ast: &PageAst) -> Option<usize> {
+    ast.links_with_prefix("Category:English")
+        .find_map(|link| SYLLABLE_CATEGORY.captures(link))
+        .and_then(|cap| cap[1].parse::<usize>().ok())
+}
+
+/// Count syllables from IPA transcription
+/// Counts vowel nuclei (monophthongs and diphthongs) plus syllabic consonants
+pub fn count_syllables_from_ipa(ipa: &str) -> usize {
+    let mut count = 0;
+    let chars: Vec<char> = ipa.chars().collect();
+    let mut i = 0;
+    let inventory = get_ipa_inventory();
+    let vowels = &inventory.vowels;
+
+    // Syllabic consonant marker (combining character U+0329)
+    let syllabic_marker = '\u{0329}';
+
+    while i < chars.len() {
+        let ch = chars[i];
+
+        // Check for syllabic consonant (consonant followed by syllabic marker)
+        if i + 1 < chars.len() && chars[i + 1] == syllabic_marker {
+            count += 1;
+            i += 2; // Skip consonant and marker
+            continue;
+        }
+
+        // Check for vowel
+        if vowels.contains(&ch) {
+            count += 1;
+            i += 1;
+
+            // Skip diphthong off-glides (from schema/ipa.yaml) and modifiers
+            let offglides = &inventory.offglides;
+            let mut vowel_skipped = false;
+            while i < chars.len() {
+                let next = chars[i];
+                if next == 'ː'  // length marker
+                    || next == 'ˑ'  // half-long
+                    || next == '\u{0303}'  // combining tilde (nasalization)
+                    || next == '\u{032F}'  // combining inverted breve (non-syllabic)
+                    || next == '\u{0361}'  // combining double inverted breve (tie bar)
+                {
+                    i += 1;
+                } else if !vowel_skipped && offglides.contains(&next) {
+                    // Skip off-glide vowels (second element of diphthongs)
+                    vowel_skipped = true;
+                    i += 1;
+                } else {
+                    break;
+                }
+            }
+            continue;
+        }
+
+        i += 1;
+    }
+
+    count
+}
+
+/// Syllable count of one transcription, or None for implausible counts (0 or very high)
+fn plausible_syllable_count(ipa: &str) -> Option<usize> {
+    let count = count_syllables_from_ipa(ipa);
+    if count == 0 || count > 15 {
+        None
+    } else {
+        Some(count)
+    }
+}
+
+/// Extract syllable count from IPA transcription
+fn extract_syllable_count_from_ipa(ast: &PageAst) -> Option<usize> {
+    // Find IPA template
+    let cap = ast.captures(&["IPA"], &IPA_TEMPLATE)?;
+    let template_content = &cap[1];
+
+    // Extract the first transcription (between / / or [ ])
+    let transcription = IPA_TRANSCRIPTION.captures(template_content)?;
+    plausible_syllable_count(&transcription[1])
+}
+
+/// Syllable counts of every transcription in the page's {{IPA|en|...}}
+/// templates, in order (None where the count is implausible)
+fn ipa_syllable_counts(ast: &PageAst) -> Vec<Option<usize>> {
+    ast.captures_iter(&["IPA"], &IPA_TEMPLATE)
+        .flat_map(|cap| {
+            IPA_TRANSCRIPTION
+                .captures_iter(cap.get(1).map_or("", |m| m.as_str()))
+                .map(|transcription| plausible_syllable_count(&transcription[1]))
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Extract syllable validation data from a page (for cross-validation analysis)
+fn extract_syllable_validation(title: &str, text: &str) -> Option<SyllableValidation> {
+    // Extract English section
+    let english_text = extract_english_section(text)?;
+    let ast = PageAst::parse(english_text);
+
+    // Get all syllable counts from different sources
+    let sources = syllables::SyllableSources::from_ast(&ast);
+
+    // If no syllable data at all, skip
+    if sources.is_empty() {
+        return None;
+    }
+
+    // The IPA source only reads the first transcription; count them all to see what that misses
+    let ipa_counts = ipa_syllable_counts(&ast);
+    let mut distinct_ipa_counts: Vec<usize> = ipa_counts.iter().flatten().copied().collect();
+    distinct_ipa_counts.sort_unstable();
+    distinct_ipa_counts.dedup();
+
+    // Settle on a value with --syllable-rules, then the priority order (IPA > hyphenation > category > rhymes)
+    let (final_value, resolved_by) = sources.resolve(syllables::syllable_rules()).unzip();
+
+    Some(SyllableValidation {
+        word: title.to_string(),
+        rhymes: sources.rhymes,
+        ipa: sources.ipa,
+        ipa_transcriptions: ipa_counts.len(),
+        ipa_counts_differ: distinct_ipa_counts.len() > 1,
+        category: sources.category,
+        hyphenation: sources.hyphenation,
+        final_value,
+        has_disagreement: sources.has_disagreement(),
+        resolved_by: resolved_by.flatten(),
+    })
+}
+
+/// Extract regional spelling variant from head lines
+/// Looks for {{tlb|en|American spelling}} or similar patterns
+fn extract_spelling_region(ast: &PageAst) -> Option<String> {
+    let spelling_labels = get_spelling_labels();
+    for cap in ast.captures_iter(TLB_NAMES, &TLB_TEMPLATE) {
+        // Get all labels in this template
+        for label in cap[1].split('|') {
+            let label = normalize_label(label);
+            // Check if this is a spelling variant label
+            if let Some(region) = spelling_labels.get(&label) {
+                return Some(region.clone());
+            }
+        }
+    }
+    None
+}
+
+/// Clean wiki markup from extracted lemma
+/// Removes section anchors (#...), wiki links ([[...]]), and templates ({{...}})
+pub fn clean_lemma(raw: &str) -> String {
+    let mut result = raw.to_string();
+
+    // Remove section anchors (e.g., "after#noun" -> "after")
+    if let Some(hash_pos) = result.find('#') {
+        result = result[..hash_pos].to_string();
+    }
+
+    // Remove wiki link syntax: [[target]] or [[target|display]] or [[:en:target]]
+    // Extract just the target word
+    while result.contains("[[") {
+        if let Some(start) = result.find("[[") {
+            if let Some(end) = result[start..].find("]]") {
+                let link_content = &result[start + 2..start + end];
+                // Handle [[target|display]] - take target
+                // Handle [[:en:target]] - take target after last colon
+                let cleaned = if link_content.contains('|') {
+                    link_content.split('|').next().unwrap_or("")
+                } else {
+                    link_content
+                };
+                // Remove language prefix like ":en:"
+                let cleaned = cleaned.trim_start_matches(':');
+                let cleaned = if cleaned.contains(':') {
+                    cleaned.rsplit(':').next().unwrap_or(cleaned)
+                } else {
+                    cleaned
+                };
+                result = format!("{}{}{}", &result[..start], cleaned, &result[start + end + 2..]);
+            } else {
+                // Malformed (no closing ]]) - remove from [[ to end of string
+                result = result[..start].to_string();
+            }
+        }
+    }
+
+    // Remove any remaining ]]
+    result = result.replace("]]", "");
+
+    // Remove template syntax: {{...}} -> empty (nested templates shouldn't be in lemmas)
+    while result.contains("{{") {
+        if let Some(start) = result.find("{{") {
+            if let Some(end) = result[start..].find("}}") {
+                result = format!("{}{}", &result[..start], &result[start + end + 2..]);
+            } else {
+                // Malformed (no closing }}) - remove from {{ to end of string
+                result = result[..start].to_string();
+            }
+        }
+    }
+
+    // Remove any remaining }}
+    result = result.replace("}}", "");
+
+    // Clean up any double slashes (from malformed templates)
+    result = result.replace("//", "");
+
+    result.trim().to_string()
+}
+
+/// Earliest year a {{defdate}} on the definition line gives. Centuries count
+/// from their start, or from the middle or last third when qualified ("late
+/// 19th c." → 1867).
+fn extract_attested(def_line: &str) -> Option<u32> {
+    let start = DEFDATE_TEMPLATE.find(def_line)?.start();
+    // Strip markup such as "19<sup>th</sup> century"
+    let template = WikitextParser::with_markup(&unescape_xml(&def_line[start..]), MarkupMode::Strip).parse_template();
+    let text = template.params.join(" ");
+
+    let years = ATTESTED_YEAR.captures_iter(&text).filter_map(|cap| cap[1].parse::<u32>().ok());
+    let centuries = ATTESTED_CENTURY.captures_iter(&text).filter_map(|cap| {
+        let century: u32 = cap[2].parse().ok().filter(|&c| c > 0)?;
+        let offset = match cap.get(1).map(|m| m.as_str().to_lowercase()).as_deref() {
+            Some("mid") => 33,
+            Some("late") => 67,
+            _ => 0,
+        };
+        Some((century - 1) * 100 + offset)
+    });
+    years.chain(centuries).min()
+}
+
+/// Extract lemma (base form) from inflection templates
+/// Returns the first matching lemma found in the text
+fn extract_lemma(ast: &PageAst) -> Option<(String, Vec<String>)> {
+    let range = 0..ast.text().len();
+    for (names, tags, regex) in INFLECTION_TEMPLATES.iter() {
+        if let Some(source) = ast.templates_matching_in(range.clone(), names, regex).next() {
+            let cap = regex.captures(source)?;
+            let raw_lemma = cap[1].trim();
+            let lemma = clean_lemma(raw_lemma).to_lowercase();
+            // Validate the lemma is reasonable
+            if !lemma.is_empty() && is_englishlike(&lemma) {
+                let tags = if tags.is_empty() {
+                    inflection_of_tags(source)
+                } else {
+                    tags.iter().map(|tag| tag.to_string()).collect()
+                };
+                return Some((lemma, tags));
+            }
+        }
+    }
+    None
+}
+
+/// Short tags of {{inflection of|en|lemma|alt|tag...}} and their full names
+const INFLECTION_TAG_NAMES: &[(&str, &str)] = &[
+    ("1", "first-person"), ("2", "second-person"), ("3", "third-person"),
+    ("s", "singular"), ("sg", "singular"), ("p", "plural"), ("pl", "plural"),
+    ("pres", "present"), ("ptcp", "participle"), ("part", "participle"),
+    ("ind", "indicative"), ("indc", "indicative"), ("sub", "subjunctive"), ("subj", "subjunctive"),
+    ("imp", "imperative"), ("inf", "infinitive"), ("comd", "comparative"), ("supd", "superlative"),
+    ("gen", "genitive"),
+];
+
+/// Tags from the parameters after the lemma and display form, with short
+/// tags spelled out; ";" between tag sets and empty parameters are dropped
+fn inflection_of_tags(source: &str) -> Vec<String> {
+    let template = WikitextParser::new(source).parse_template();
+    let mut tags: Vec<String> = Vec::new();
+    for param in template.params.iter().skip(3) {
+        let param = param.trim();
+        if param.is_empty() || param == ";" {
+            continue;
+        }
+        let name = INFLECTION_TAG_NAMES
+            .iter()
+            .find(|(short, _)| short.eq_ignore_ascii_case(param))
+            .map_or_else(|| param.to_lowercase(), |(_, name)| name.to_string());
+        if !tags.contains(&name) {
+            tags.push(name);
+        }
+    }
+    tags
+}
+
+fn extract_phrase_type(ast: &PageAst) -> Option<String> {
+    // Check section headers for specific phrase types
+    for header in ast.headers_from(3) {
+        let header = ast.slice(&header.title).to_lowercase();
+        let header = header.split_whitespace().collect::<Vec<_>>().join(" ");
+
+        match header.as_str() {
+            "idiom" | "proverb" | "prepositional phrase" | "adverbial phrase" |
+            "verb phrase" | "verb phrase form" | "noun phrase" => {
+                return Some(header);
+            }
+            "saying" | "adage" => {
+                return Some("proverb".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    // Check {{head}} templates
+    for cap in ast.captures_iter(HEAD_NAMES, &HEAD_TEMPLATE) {
+        let pos = cap[1].to_lowercase().trim().to_string();
+        match pos.as_str() {
+            "idiom" | "proverb" | "prepositional phrase" | "adverbial phrase" |
+            "verb phrase" | "noun phrase" => {
+                return Some(pos);
+            }
+            "saying" | "adage" => {
+                return Some("proverb".to_string());
+            }
+            _ => {}
+        }
+    }
+
+    // Check for phrase-specific templates
+    if ast.captures(&["en-prepphr"], &PREP_PHRASE_TEMPLATE).is_some() {
+        return Some("prepositional phrase".to_string());
+    }
+
+    // Check categories
+    let category_patterns = [
+        ("Category:English idioms", "idiom"),
+        ("Category:English proverbs", "proverb"),
+        ("Category:English prepositional phrases", "prepositional phrase"),
+        ("Category:English adverbial phrases", "adverbial phrase"),
+        ("Category:English verb phrases", "verb phrase"),
+        ("Category:English noun phrases", "noun phrase"),
+        ("Category:English sayings", "proverb"),
+    ];
+
+    for (pattern, phrase_type) in &category_patterns {
+        if has_category_link(ast, pattern) {
+            return Some(phrase_type.to_string());
+        }
+    }
+
+    None
+}
+
+/// Whether any link, including a `[[:Category:...]]` page link, targets a name
+/// starting with `prefix`
+fn has_category_link(ast: &PageAst, prefix: &str) -> bool {
+    ast.link_targets().any(|target| target.trim_start_matches(':').starts_with(prefix))
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Wikitext Recursive Descent Parser
+// ─────────────────────────────────────────────────────────────────────────────
+
+/// Parsed wikilink: [[target#anchor|display]]
+/// Note: anchor is parsed for completeness but not currently used
+#[derive(Debug)]
+#[allow(dead_code)]
+struct Wikilink {
+    target: String,
+    anchor: Option<String>,
+    display: Option<String>,
+}
+
+impl Wikilink {
+    /// Return display text if present, otherwise target
+    fn text(&self) -> &str {
+        self.display.as_deref().unwrap_or(&self.target)
+    }
+}
+
+/// Parsed template: {{name|param1|param2|key=value|...}}
+/// Note: Nested templates are parsed but discarded (treated as metadata)
+#[derive(Debug)]
+#[allow(dead_code)]
+struct ParsedTemplate {
+    name: String,
+    params: Vec<String>,              // Positional params, in order
+    named: HashMap<String, String>,   // key=value params
+}
+
+/// Template parameters split into positional and named (key=value)
+#[derive(Debug, Default, PartialEq)]
+struct TemplateParams {
+    positional: Vec<String>,
+    named: HashMap<String, String>,
+}
+
+impl TemplateParams {
+    /// Add a raw param; `eq_at` is the offset of its top-level "=", if any
+    fn push(&mut self, text: String, eq_at: Option<usize>) {
+        match eq_at {
+            Some(i) if !text[..i].trim().is_empty() => {
+                self.named.insert(text[..i].trim().to_string(), text[i + 1..].trim().to_string());
+            }
+            _ => self.positional.push(text.trim().to_string()),
+        }
+    }
+}
+
+/// How the parser treats HTML tags and character entities in param text
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MarkupMode {
+    /// Keep tags and entities verbatim
+    Preserve,
+    /// Drop tags, `<ref>` bodies and inline modifiers (`<t:...>`), decode entities
+    Strip,
+}
+
+/// Recursive descent parser for Wiktionary template parameters.
+/// Uses the call stack for nesting - no explicit depth counters.
+pub struct WikitextParser<'a> {
+    text: &'a str,
+    pos: usize,
+    markup: MarkupMode,
+}
+
+impl<'a> WikitextParser<'a> {
+    pub fn new(text: &'a str) -> Self {
+        Self::with_markup(text, MarkupMode::Preserve)
+    }
+
+    fn with_markup(text: &'a str, markup: MarkupMode) -> Self {
+        WikitextParser { text, pos: 0, markup }
+    }
+
+    fn peek(&self, n: usize) -> &str {
+        // n is character count, not byte count
+        let remaining = &self.text[self.pos..];
+        let end_offset: usize = remaining.chars().take(n).map(|c| c.len_utf8()).sum();
+        &remaining[..end_offset]
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn consume(&mut self, n: usize) -> &str {
+        // n is character count, not byte count
+        let remaining = &self.text[self.pos..];
+        let byte_len: usize = remaining.chars().take(n).map(|c| c.len_utf8()).sum();
+        let result = &self.text[self.pos..self.pos + byte_len];
+        self.pos += byte_len;
+        result
+    }
+
+    fn consume_char(&mut self) -> Option<char> {
+        let c = self.peek_char()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn at_end(&self) -> bool {
+        self.pos >= self.text.len()
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Top-level entry point: params ::= param ("|" param)*
+    // ─────────────────────────────────────────────────────────────
+    pub fn parse_params(&mut self) -> Vec<String> {
+        let mut params = Vec::new();
+        while !self.at_end() {
+            let param = self.parse_param();
+            params.push(param);
+            if self.peek(1) == "|" {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        params
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Named variant: params ::= param ("|" param)*, split into
+    // positional and key=value parameters
+    // ─────────────────────────────────────────────────────────────
+    fn parse_params_named(&mut self) -> TemplateParams {
+        let mut params = TemplateParams::default();
+        while !self.at_end() {
+            let (text, eq_at) = self.parse_param_with_eq();
+            params.push(text, eq_at);
+            if self.peek(1) == "|" {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+        params
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // param ::= element*  (terminated by | or end)
+    // ─────────────────────────────────────────────────────────────
+    fn parse_param(&mut self) -> String {
+        let (text, _) = self.parse_param_with_eq();
+        text.trim().to_string()
+    }
+
+    /// Parse one param, also returning the offset of the first "=" outside
+    /// links and templates (the key/value separator of a named param)
+    fn parse_param_with_eq(&mut self) -> (String, Option<usize>) {
+        let mut result = String::new();
+        let mut eq_at = None;
+        while !self.at_end() && self.peek(1) != "|" {
+            if self.peek(2) == "[[" {
+                let wikilink = self.parse_wikilink();
+                result.push_str(wikilink.text());
+            } else if self.peek(2) == "{{" {
+                let template = self.parse_template();
+                // For morphology params, nested templates are metadata - discard
+                let _ = template;
+            } else if self.parse_markup(&mut result) {
+                // Tag or entity, handled per markup mode
+            } else if let Some(c) = self.consume_char() {
+                if c == '=' && eq_at.is_none() {
+                    eq_at = Some(result.len());
+                }
+                result.push(c);
+            }
+        }
+        (result, eq_at)
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // wikilink ::= "[[" target ("#" anchor)? ("|" display)? "]]"
+    // ─────────────────────────────────────────────────────────────
+    fn parse_wikilink(&mut self) -> Wikilink {
+        self.consume(2); // consume "[["
+
+        let target = self.parse_target();
+        let mut anchor = None;
+        let mut display = None;
+
+        // Optional: "#" anchor
+        if self.peek(1) == "#" {
+            self.consume(1);
+            anchor = Some(self.parse_anchor());
+        }
+
+        // Optional: "|" display
+        if self.peek(1) == "|" {
+            self.consume(1);
+            display = Some(self.parse_display());
+        }
+
+        // Consume "]]"
+        if self.peek(2) == "]]" {
+            self.consume(2);
+        }
+
+        Wikilink { target, anchor, display }
+    }
+
+    fn parse_target(&mut self) -> String {
+        let mut result = String::new();
+        while !self.at_end() {
+            let c = self.peek_char();
+            match c {
+                Some('#') | Some('|') | Some(']') => break,
+                Some(ch) => {
+                    self.consume_char();
+                    result.push(ch);
+                }
+                None => break,
+            }
+        }
+        result
+    }
+
+    fn parse_anchor(&mut self) -> String {
+        let mut result = String::new();
+        while !self.at_end() {
+            let c = self.peek_char();
+            match c {
+                Some('|') | Some(']') => break,
+                Some(ch) => {
+                    self.consume_char();
+                    result.push(ch);
+                }
+                None => break,
+            }
+        }
+        result
+    }
+
+    fn parse_display(&mut self) -> String {
+        let mut result = String::new();
+        while !self.at_end() && self.peek(1) != "]" {
+            if let Some(c) = self.consume_char() {
+                result.push(c);
+            }
+        }
+        result
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // template ::= "{{" params "}}"
+    // ─────────────────────────────────────────────────────────────
+    fn parse_template(&mut self) -> ParsedTemplate {
+        self.consume(2); // consume "{{"
+
+        let mut name = None;
+        let mut params = TemplateParams::default();
+        while !self.at_end() && self.peek(2) != "}}" {
+            let (text, eq_at) = self.parse_template_param_inner();
+            // The first field is the template name, never a named param
+            if name.is_none() {
+                name = Some(text.trim().to_string());
+            } else {
+                params.push(text, eq_at);
+            }
+            if self.peek(1) == "|" {
+                self.consume(1);
+            } else {
+                break;
+            }
+        }
+
+        if self.peek(2) == "}}" {
+            self.consume(2);
+        }
+
+        ParsedTemplate {
+            name: name.unwrap_or_default(),
+            params: params.positional,
+            named: params.named,
+        }
+    }
+
+    fn parse_template_param_inner(&mut self) -> (String, Option<usize>) {
+        let mut result = String::new();
+        let mut eq_at = None;
+        while !self.at_end() && self.peek(1) != "|" && self.peek(2) != "}}" {
+            if self.peek(2) == "[[" {
+                let wikilink = self.parse_wikilink();
+                result.push_str(wikilink.text());
+            } else if self.peek(2) == "{{" {
+                let template = self.parse_template(); // RECURSIVE!
+                // Nested templates produce no text for our purposes
+                let _ = template;
+            } else if self.parse_markup(&mut result) {
+                // Tag or entity, handled per markup mode
+            } else if let Some(c) = self.consume_char() {
+                if c == '=' && eq_at.is_none() {
+                    eq_at = Some(result.len());
+                }
+                result.push(c);
+            }
+        }
+        (result, eq_at)
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // markup ::= tag | entity
+    // tag    ::= "<" "/"? name (attrs | ":" modifier)? "/"? ">"
+    // entity ::= "&" (name | "#" digits | "#x" hexdigits) ";"
+    // Dump text escapes "<", ">" and "&", so "&lt;" opens a tag too.
+    // Returns false (consuming nothing) if no markup starts here.
+    // ─────────────────────────────────────────────────────────────
+    fn parse_markup(&mut self, out: &mut String) -> bool {
+        let start = self.pos;
+        let rest = &self.text[start..];
+        if !rest.starts_with('<') && !rest.starts_with('&') {
+            return false;
+        }
+
+        if let Some(tag) = scan_tag(rest) {
+            let mut end = start + tag.len;
+            if tag.name.eq_ignore_ascii_case("ref") && !tag.closing && !tag.self_closing {
+                // A <ref> body is a citation, not part of the param value
+                end += find_ref_end(&self.text[end..]).unwrap_or(self.text.len() - end);
+            }
+            if self.markup == MarkupMode::Preserve {
+                out.push_str(&self.text[start..end]);
+            }
+            self.pos = end;
+            return true;
+        }
+
+        if let Some((len, decoded)) = scan_entity(rest) {
+            match self.markup {
+                MarkupMode::Preserve => out.push_str(&rest[..len]),
+                MarkupMode::Strip => out.push(decoded),
+            }
+            self.pos += len;
+            return true;
+        }
+
+        false
+    }
+}
+
+/// An HTML tag (or inline modifier like `<t:gloss>`) at the start of some text
+struct ScannedTag<'a> {
+    name: &'a str,
+    len: usize,
+    closing: bool,
+    self_closing: bool,
+}
+
+fn scan_tag(text: &str) -> Option<ScannedTag<'_>> {
+    let (open_len, close) = if text.starts_with('<') {
+        (1, ">")
+    } else if text.starts_with("&lt;") {
+        (4, "&gt;")
+    } else {
+        return None;
+    };
+
+    let after_open = &text[open_len..];
+    let closing = after_open.starts_with('/');
+    let name_start = open_len + usize::from(closing);
+    let name_len = text[name_start..]
+        .find(|c: char| !c.is_ascii_alphanumeric())
+        .unwrap_or(text.len() - name_start);
+    let name = &text[name_start..name_start + name_len];
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic()) {
+        return None;
+    }
+
+    // The name must be followed by a modifier, attributes, "/" or the tag end
+    let after_name = &text[name_start + name_len..];
+    if !(after_name.starts_with([':', ' ', '/']) || after_name.starts_with(close)) {
+        return None;
+    }
+    let close_at = after_name.find(close)?;
+    let body = &after_name[..close_at];
+    if body.contains(['<', '\n']) {
+        return None;
+    }
+
+    Some(ScannedTag {
+        name,
+        len: name_start + name_len + close_at + close.len(),
+        closing,
+        self_closing: body.ends_with('/'),
+    })
+}
+
+/// Length of a `<ref>` body up to and including its closing tag
+fn find_ref_end(text: &str) -> Option<usize> {
+    let mut offset = 0;
+    while let Some(i) = text[offset..].find(['<', '&']) {
+        let at = offset + i;
+        match scan_tag(&text[at..]) {
+            Some(tag) if tag.closing && tag.name.eq_ignore_ascii_case("ref") => return Some(at + tag.len),
+            _ => offset = at + 1,
+        }
+    }
+    None
+}
+
+/// Decode a character entity at the start of some text, returning its length.
+/// Dump text double-escapes entities (`&amp;nbsp;`), so that form is accepted too;
+/// when what follows `&amp;` isn't an entity (`R&amp;D;`), only the `&amp;` is decoded.
+fn scan_entity(text: &str) -> Option<(usize, char)> {
+    let body = text.strip_prefix('&')?;
+    if let Some((len, decoded)) = body.strip_prefix("amp;").and_then(entity_body) {
+        return Some((5 + len, decoded));
+    }
+    entity_body(body).map(|(len, decoded)| (1 + len, decoded))
+}
+
+/// Decode an entity's name and `;` at the start of some text, returning their length
+fn entity_body(body: &str) -> Option<(usize, char)> {
+    let end = body.find(';').filter(|&i| i > 0 && i <= 8)?;
+    let name = &body[..end];
+    let decoded = match name {
+        "lt" => '<',
+        "gt" => '>',
+        "amp" => '&',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => ' ',
+        "ndash" => '–',
+        "mdash" => '—',
+        _ => {
+            let code = match name.strip_prefix("#x").or_else(|| name.strip_prefix("#X")) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => name.strip_prefix('#')?.parse().ok()?,
+            };
+            char::from_u32(code)?
+        }
+    };
+    Some((end + 1, decoded))
+}
+
+/// Parse the positional template parameters with proper bracket handling;
+/// named ones (t1=..., pos2=...) are left out.
+fn parse_template_params(content: &str) -> Vec<String> {
+    parse_template_params_named(content).positional
+}
+
+/// Parse template parameters, separating named (key=value) from positional ones.
+/// Markup is stripped: tags and ref bodies are dropped, entities decoded.
+fn parse_template_params_named(content: &str) -> TemplateParams {
+    let mut parser = WikitextParser::with_markup(content, MarkupMode::Strip);
+    parser.parse_params_named()
+}
+
+/// English components of a template, and the foreign ones set apart
+fn clean_template_components(parts: &[String]) -> (Vec<String>, Vec<ForeignComponent>) {
+    // Note: Wikilink, tag and entity handling is done by WikitextParser during
+    // parsing, so this function only handles post-parsing cleanup.
+    let mut components = Vec::new();
+    let mut foreign = Vec::new();
+    for part in parts {
+        let part = part.trim();
+        if part.is_empty() {
+            continue;
+        }
+        // Language code prefixes (grc:, la:, ang:, pt:, etc.) at start of part
+        // indicate non-English etymological roots
+        if let Some(prefix) = LANG_CODE_PREFIX.find(part) {
+            let term = part[prefix.end()..].trim();
+            if !term.is_empty() {
+                let lang = part[..prefix.end() - 1].to_lowercase();
+                foreign.push(ForeignComponent { lang, term: term.to_string() });
+            }
+            continue;
+        }
+        components.push(part.to_string());
+    }
+    (components, foreign)
+}
+
+/// Strip wikilink markup from a string: [[word]] -> word, [[word|display]] -> word
+fn strip_wikilinks(s: &str) -> String {
+    if s.contains("[[") || s.contains("]]") {
+        let result = WIKILINK_PATTERN.replace_all(s, "$1").to_string();
+        result.replace("]]", "")
+    } else {
+        s.to_string()
+    }
+}
+
+fn analysis_for_template(etymology_template: &str) -> String {
+    if etymology_template.to_lowercase().starts_with("{{surf|") {
+        "surface".to_string()
+    } else {
+        "etymological".to_string()
+    }
+}
+
+/// Whether the components, with affix hyphens removed, spell out the word.
+/// Case, spaces and hyphens in the word are ignored.
+fn components_spell_word(components: &[String], word: &str) -> bool {
+    let normalize = |s: &str| {
+        s.chars()
+            .filter(|c| !c.is_whitespace() && *c != '-')
+            .flat_map(char::to_lowercase)
+            .collect::<String>()
+    };
+    normalize(&components.concat()) == normalize(word)
+}
+
+/// Classify morphology components and build a unified Morphology result.
+///
+/// Classification is purely based on hyphen patterns:
+/// - Ends with '-' (but doesn't start with '-'): prefix
+/// - Starts with '-' (but doesn't end with '-'): suffix
+/// - Starts and ends with '-': interfix
+/// - No hyphens: base word
+fn classify_morphology(components: Vec<String>, etymology_template: String) -> Morphology {
+    // Classify components by hyphen pattern in a single pass
+    let (prefixes, suffixes, interfixes, bases) = components.iter().fold(
+        (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
+        |(mut pre, mut suf, mut inter, mut base), c| {
+            match (c.starts_with('-'), c.ends_with('-')) {
+                (false, true) => pre.push(c.clone()),   // prefix: "un-"
+                (true, false) => suf.push(c.clone()),   // suffix: "-ness"
+                (true, true) => inter.push(c.clone()),  // interfix: "-s-"
+                (false, false) => base.push(c.clone()), // base: "happy"
+            }
+            (pre, suf, inter, base)
+        },
+    );
+
+    // Determine morphology type based on what we found
+    let has_prefix = !prefixes.is_empty();
+    let has_suffix = !suffixes.is_empty();
+
+    let (morph_type, is_compound) = match (has_prefix, has_suffix) {
+        (true, true) => ("affixed", false),
+        (true, false) => ("prefixed", false),
+        (false, true) => ("suffixed", false),
+        (false, false) if bases.len() >= 2 => ("compound", true),
+        _ => ("simple", false),
+    };
+
+    // Determine base word
+    // For derivations: first base word is the root
+    // For compounds: no single base (all parts are equal constituents)
+    let base = if !is_compound { bases.first().cloned() } else { None };
+
+    let analysis = analysis_for_template(&etymology_template);
+    Morphology {
+        morph_type: morph_type.to_string(),
+        base,
+        components,
+        prefixes,
+        suffixes,
+        interfixes,
+        foreign_components: Vec::new(),
+        is_compound,
+        etymology_template,
+        analysis,
+        non_concatenative: false,
+    }
+}
+
+/// Extract normalized morphology components from any etymology template.
+///
+/// Tries each template type in priority order and normalizes to a common
+/// component format where affixes are marked with hyphens.
+///
+/// Returns (components, foreign components, raw_template) or None if no template found.
+fn extract_morphology_components(
+    ast: &PageAst,
+    etymology: Range<usize>,
+) -> Option<(Vec<String>, Vec<ForeignComponent>, String)> {
+    // 1. Try suffix template: {{suffix|en|base|suffix}}
+    if let Some(cap) = ast.captures_in(etymology.clone(), &["suffix"], &SUFFIX_TEMPLATE) {
+        let base = strip_wikilinks(cap[1].trim());
+        let mut suffix = strip_wikilinks(cap[2].trim());
+        // Normalize: add leading hyphen if missing
+        if !suffix.starts_with('-') {
+            suffix = format!("-{}", suffix);
+        }
+        return Some((vec![base, suffix], Vec::new(), cap[0].to_string()));
+    }
+
+    // 2. Try prefix template: {{prefix|en|prefix|base}}
+    if let Some(cap) = ast.captures_in(etymology.clone(), &["prefix"], &PREFIX_TEMPLATE) {
+        let mut prefix = strip_wikilinks(cap[1].trim());
+        let base = strip_wikilinks(cap[2].trim());
+        // Normalize: add trailing hyphen if missing
+        if !prefix.ends_with('-') {
+            prefix = format!("{}-", prefix);
+        }
+        return Some((vec![prefix, base], Vec::new(), cap[0].to_string()));
+    }
+
+    // 3. Try confix template: {{confix|en|prefix|base|suffix}}
+    if let Some(cap) = ast.captures_in(etymology.clone(), &["confix"], &CONFIX_TEMPLATE) {
+        let mut prefix = strip_wikilinks(cap[1].trim());
+        let base = strip_wikilinks(cap[2].trim());
+        let mut suffix = strip_wikilinks(cap[3].trim());
+        // Normalize affix hyphens
+        if !prefix.ends_with('-') {
+            prefix = format!("{}-", prefix);
+        }
+        if !suffix.starts_with('-') {
+            suffix = format!("-{}", suffix);
+        }
+        return Some((vec![prefix, base, suffix], Vec::new(), cap[0].to_string()));
+    }
+
+    // 4-6. Try variable-arg templates: compound, affix, surf
+    // These use parse_template_params for bracket-aware parsing
+    let variable = [
+        (&["compound"][..], &*COMPOUND_TEMPLATE),
+        (AFFIX_NAMES, &*AFFIX_TEMPLATE),
+        (&["surf"][..], &*SURF_TEMPLATE),
+    ];
+    for (names, template_re) in variable {
+        if let Some(cap) = ast.captures_in(etymology.clone(), names, template_re) {
+            let parts = parse_template_params(&cap[1]);
+            let (components, foreign) = clean_template_components(&parts);
+            if components.len() >= 2 {
+                return Some((components, foreign, cap[0].to_string()));
+            }
+        }
+    }
+
+    None
+}
+
+/// Etymology data for every Etymology section, keyed by header offset.
+///
+/// Each section's text runs from its header to the next header of any kind,
+/// while the senses it governs run until the next Etymology header. Pages
+/// with "Etymology 1", "Etymology 2", ... get one entry per section.
+fn extract_etymologies(ast: &PageAst, with_text: bool) -> Vec<(usize, EtymologyData)> {
+    let english_text = ast.text();
+    ast.headers_from(3)
+        .filter(|header| ETYMOLOGY_TITLE.is_match(ast.slice(&header.title)))
+        .map(|header| {
+            let body_start = header.span.end;
+            let body_end = NEXT_SECTION
+                .find(&english_text[body_start..])
+                .map_or(english_text.len(), |next| body_start + next.start());
+            let etymology = body_start..body_end;
+            let (calque_of, doublets) = extract_etymology_relations(ast, etymology.clone());
+            let text = if with_text {
+                etymology_text::first_paragraph(&english_text[etymology.clone()])
+            } else {
+                None
+            };
+            let data = EtymologyData {
+                morphology: morphology_from_etymology(ast, etymology),
+                calque_of,
+                doublets,
+                text,
+            };
+            (header.span.start, data)
+        })
+        .collect()
+}
+
+/// Etymology sections of a page. Morphology and etymology relations need the
+/// Etymology template family, but the --etymology-text paragraph is often plain
+/// {{inh}}/{{der}}/{{bor}} prose, so the sections are always read for it.
+fn page_etymologies(ast: &PageAst, families: &PageFamilies, with_text: bool) -> Vec<(usize, EtymologyData)> {
+    if with_text || families.has(TemplateFamily::Etymology) {
+        extract_etymologies(ast, with_text)
+    } else {
+        Vec::new()
+    }
+}
+
+/// Etymology governing a POS section: the last Etymology header before it,
+/// or the first one when the POS header precedes all of them
+fn etymology_for(etymologies: &[(usize, EtymologyData)], section_start: usize) -> Option<&EtymologyData> {
+    etymologies
+        .iter()
+        .rev()
+        .find(|(start, _)| *start <= section_start)
+        .or_else(|| etymologies.first())
+        .map(|(_, data)| data)
+}
+
+/// Extract calque source and doublets from an etymology section.
+///
+/// {{calque|en|fr|gratte-ciel}} names the source language and term;
+/// every {{doublet|en|...}} contributes its terms to the doublets list.
+fn extract_etymology_relations(ast: &PageAst, etymology: Range<usize>) -> (Option<CalqueSource>, Vec<String>) {
+    let positional = |source: &str| -> Vec<String> {
+        let template = WikitextParser::new(source).parse_template();
+        // params[0] is the "en" language code
+        template.params.into_iter().skip(1).filter(|p| !p.is_empty()).collect()
+    };
+
+    let calque = ast.templates_matching_in(etymology.clone(), CALQUE_NAMES, &CALQUE_TEMPLATE).next();
+    let calque_of = calque.and_then(|source| {
+        let mut params = positional(source).into_iter();
+        let lang = params.next()?;
+        let term = params.next()?;
+        Some(CalqueSource { lang, term })
+    });
+
+    let mut doublets: Vec<String> = Vec::new();
+    for source in ast.templates_matching_in(etymology, DOUBLET_NAMES, &DOUBLET_TEMPLATE) {
+        for term in positional(source) {
+            if !doublets.contains(&term) {
+                doublets.push(term);
+            }
+        }
+    }
+
+    (calque_of, doublets)
+}
+
+/// Extract blend, clipping, and back-formation morphology.
+///
+/// These are not segmentations of the headword: the components are the
+/// source words. A blend has no single base; clippings and back-formations
+/// use their source word as the base.
+fn extract_source_morphology(ast: &PageAst, etymology: Range<usize>) -> Option<Morphology> {
+    let sources = [
+        ("blend", &["blend"][..], &*BLEND_TEMPLATE),
+        ("clipping", CLIPPING_NAMES, &*CLIPPING_TEMPLATE),
+        ("back-formation", BACK_FORMATION_NAMES, &*BACK_FORMATION_TEMPLATE),
+    ];
+
+    for (morph_type, names, template_re) in sources {
+        if let Some(cap) = ast.captures_in(etymology.clone(), names, template_re) {
+            let parts = parse_template_params(&cap[1]);
+            let (components, foreign_components) = clean_template_components(&parts);
+            if components.is_empty() {
+                continue;
+            }
+            let base = if morph_type == "blend" { None } else { components.first().cloned() };
+            return Some(Morphology {
+                morph_type: morph_type.to_string(),
+                base,
+                components,
+                prefixes: vec![],
+                suffixes: vec![],
+                interfixes: vec![],
+                foreign_components,
+                is_compound: false,
+                etymology_template: cap[0].to_string(),
+                analysis: "etymological".to_string(),
+                non_concatenative: false,
+            });
+        }
+    }
+
+    None
+}
+
+/// Morphology of the first Etymology section in a page's text.
+/// parse_page goes through extract_etymologies instead, to handle every section.
+pub fn extract_morphology(text: &str) -> Option<Morphology> {
+    extract_etymologies(&PageAst::parse(text), false).into_iter().next()?.1.morphology
+}
+
+/// Extract morphological structure from one etymology section's text.
+///
+/// This is the main entry point for morphology extraction. It uses a unified
+/// approach that:
+/// 1. Extracts and normalizes components from any morphology template
+/// 2. Classifies the morphology type based on hyphen patterns
+fn morphology_from_etymology(ast: &PageAst, etymology: Range<usize>) -> Option<Morphology> {
+
+    // Extract and normalize components from any template type; word-formation
+    // templates without affixes are only consulted when none match
+    let Some((components, foreign_components, template_str)) = extract_morphology_components(ast, etymology.clone()) else {
+        return extract_source_morphology(ast, etymology);
+    };
+
+    // Special case: confix template should be classified as 'circumfixed'
+    // We detect this by checking if the template is confix
+    if template_str.to_lowercase().contains("confix") {
+        // Build circumfixed result directly
+        let prefix = components.first().cloned().unwrap_or_default();
+        let base = components.get(1).cloned();
+        let suffix = components.get(2).cloned();
+
+        return Some(Morphology {
+            morph_type: "circumfixed".to_string(),
+            base,
+            components,
+            prefixes: vec![prefix],
+            suffixes: suffix.map(|s| vec![s]).unwrap_or_default(),
+            interfixes: vec![],
+            foreign_components,
+            is_compound: false,
+            analysis: analysis_for_template(&template_str),
+            etymology_template: template_str,
+            non_concatenative: false,
+        });
+    }
+
+    // Classify morphology based on component hyphen patterns
+    let mut morphology = classify_morphology(components, template_str);
+    morphology.foreign_components = foreign_components;
+    Some(morphology)
+}
+
+/// Parse a page and return multiple entries (one per sense)
+pub fn parse_page(title: &str, text: &str) -> Vec<Entry<'static>> {
+    let mut data = PageData::default();
+    parse_page_borrowed(title, text, &mut data).into_iter().map(Entry::into_owned).collect()
+}
+
+/// `parse_page`, with the fields the page's senses share borrowed from
+/// `data` rather than copied into each entry. The batch pipeline serializes
+/// these entries before dropping the page.
+pub fn parse_page_borrowed<'a>(title: &str, text: &str, data: &'a mut PageData) -> Vec<Entry<'a>> {
+    parse_page_validated(title, text, data).0
+}
+
+/// `parse_page_borrowed`, also returning the entries that broke an `EntryBuilder` invariant
+fn parse_page_validated<'a>(title: &str, text: &str, data: &'a mut PageData) -> (Vec<Entry<'a>>, Vec<InvalidEntry>) {
+    // Preserve original case - downstream consumers can filter by case pattern as needed
+    let word = normalize::word_form(title.trim());
+
+    // Extract English section
+    let english_text = match extract_english_section(text) {
+        Some(t) => t,
+        None => return (vec![], vec![]),
+    };
+    // One pass over the section; extractors below query its templates, links and headers
+    let ast = PageAst::parse(english_text);
+    let families = PageFamilies::scan(english_text);
+
+    // Extract word-level data (shared across all senses)
+    let word_count = word.split_whitespace().count();
+    let phrase_type = if word_count > 1 {
+        extract_phrase_type(&ast)
+    } else {
+        None
+    };
+
+    // Priority order: IPA (most reliable) > hyphenation > categories > rhymes (has data quality issues),
+    // after any --syllable-rules
+    let syllables = syllables::page_syllables(&ast);
+
+    // Morphology and etymology relations are per Etymology section
+    let with_text = ETYMOLOGY_TEXT.get().is_some_and(|&on| on);
+    let mut etymologies = page_etymologies(&ast, &families, with_text);
+    for (_, etymology) in etymologies.iter_mut() {
+        if let Some(morphology) = etymology.morphology.as_mut() {
+            let segments_word = !matches!(morphology.morph_type.as_str(), "blend" | "clipping" | "back-formation");
+            morphology.non_concatenative =
+                segments_word && !components_spell_word(&morphology.components, &word);
+        }
+    }
+    // Detect abbreviations via templates only
+    // Note: Category checks like 'Category:English acronyms' have false positives
+    // because [[:Category:...]] links (to the category page) look similar to
+    // [[Category:...]] membership. Template-based detection is more reliable.
+    let is_abbreviation = form_of::get_form_of().is_abbreviation(&ast);
+    // Extract lemma from inflection templates (e.g., {{plural of|en|cat}} → "cat")
+    // Search in english_text only to avoid matching templates from other language sections
+    let (lemma, inflection_tags) = extract_lemma(&ast).unzip();
+    let inflection_tags = inflection_tags.unwrap_or_default();
+
+    // Mark as inflected if we found a lemma OR if inflection template exists OR if category indicates inflection
+    // The template-existence check handles cases like {{inflection of|en|[[link|word]]}} where
+    // the lemma extraction fails due to complex wiki syntax but the template is present
+    let is_inflected = lemma.is_some()
+        || ast.captures(INFLECTION_NAMES, &INFLECTION_TEMPLATE_EXISTS).is_some()
+        || has_category_link(&ast, "Category:English verb forms")
+        || has_category_link(&ast, "Category:English noun forms")
+        || has_category_link(&ast, "Category:English adjective forms")
+        || has_category_link(&ast, "Category:English adverb forms")
+        || has_category_link(&ast, "Category:English plurals");
+
+    // Extract regional spelling variant (e.g., "American spelling", "British spelling")
+    let spelling_region = extract_spelling_region(&ast);
+
+    let categories = extract_categories(&ast);
+    let usage_notes = if USAGE_NOTES.get().is_some_and(|&on| on) { extract_usage_notes(&ast) } else { None };
+    let (is_neologism, neologism_date) = extract_neologism(&ast, &categories);
+    let page_lexemes = wikidata::template_lexemes(&ast);
+
+    data.word = WordData {
+        ascii: normalize::ascii_form(&word),
+        sort_key: normalize::sort_key_form(&word),
+        word: word.clone(),
+        word_count,
+        is_phrase: word_count > 1,
+        is_abbreviation,
+        is_inflected,
+        is_neologism,
+        neologism_date,
+        lemma,
+        inflection_tags,
+        phrase_type,
+        tokens: if word_count > 1 { phrase_tokens(&word) } else { Vec::new() },
+        syllables,
+        spelling_region,
+        categories,
+        usage_notes,
+    };
+    data.etymologies = etymologies;
+    let PageData { word: word_data, etymologies } = &*data;
+
+    // Parse POS sections and their definitions
+    let pos_sections = parse_pos_sections(&ast);
+
+    // If no POS sections found, try to create a single entry with unknown POS
+    if pos_sections.is_empty() {
+        // Check for English categories or templates as validation
+        let has_categories = ast.links_with_prefix("Category:English").next().is_some();
+        let has_en_templates = ast.template_names().any(|name| {
+            ["en-noun", "en-verb", "en-adj", "en-adv"].iter().any(|prefix| name.starts_with(prefix))
+        });
+        let has_definition_templates = ast.captures(DEFINITION_NAMES, &DEFINITION_TEMPLATES).is_some();
+
+        if has_categories || has_en_templates || has_definition_templates {
+            // Create a single entry, with the POS of a headword template if there is one
+            let (pos, pos_source) = EN_POS_TEMPLATE
+                .captures_iter(ast.text())
+                .find_map(|cap| template_pos(&cap[1]))
+                .map_or(("unknown", PosSource::Unknown), |pos| (pos, PosSource::Template));
+            let builder = EntryBuilder::new(word_data, pos)
+                .pos_source(pos_source)
+                .proper_noun(Some(pos) == get_pos_map().get("proper noun").map(String::as_str))
+                .etymology(etymologies.first().map(|(_, etymology)| etymology))
+                .wikidata_lexeme(wikidata::sense_lexemes(&page_lexemes, &word, pos));
+            return page_entries(vec![builder], sidecar::page_records(&word, &ast));
+        }
+        return (vec![], vec![]);
+    }
+
+    // Create one entry per definition
+    let mut builders = Vec::new();
+    // A pluralia tantum category covers the page's noun senses
+    let noun = get_pos_map().get("noun").map(String::as_str);
+    let pluralia_tantum = word_data.categories.iter().any(|category| category == "pluralia tantum");
+    let is_heteronym = is_heteronym(&pos_sections);
+
+    for section in pos_sections {
+        let etymology = etymology_for(etymologies, section.start);
+        let forms = section.headword.map(|line| lua::headword_forms(title.trim(), line)).unwrap_or_default();
+
+        for (index, def_line) in section.definitions.iter().enumerate() {
+            let labels = if families.has(TemplateFamily::Labels) {
+                extract_labels_from_line(def_line)
+            } else {
+                SenseLabels::default()
+            };
+            let non_gloss = families.has(TemplateFamily::NonGloss) && NON_GLOSS_TEMPLATE.is_match(def_line);
+            let glosses = if families.has(TemplateFamily::Glosses) {
+                extract_glosses(def_line)
+            } else {
+                Vec::new()
+            };
+            let qualifiers = if families.has(TemplateFamily::Qualifiers) {
+                extract_qualifiers(def_line)
+            } else {
+                Vec::new()
+            };
+            let attested = if families.has(TemplateFamily::Defdate) { extract_attested(def_line) } else { None };
+            let relation = form_of::get_form_of().relation(def_line);
+
+            let mut builder = EntryBuilder::new(word_data, section.pos)
+                .section(&section)
+                .plural_only(section.plural_only || (pluralia_tantum && Some(section.pos) == noun))
+                .etymology(etymology)
+                .labels(labels)
+                .non_gloss(non_gloss)
+                .attested(attested)
+                .forms(forms.clone())
+                .headword(section.headword.filter(|_| HEADWORDS.get().is_some_and(|&on| on)).map(unescape_xml))
+                .form_of(relation)
+                .definition(definition_text(def_line), glosses)
+                .qualifiers(qualifiers)
+                .quotations(section.quotations.get(index).cloned().unwrap_or_default())
+                .nyms(section.nyms.get(index).cloned().unwrap_or_default())
+                .wikidata_lexeme(wikidata::sense_lexemes(&page_lexemes, &word_data.word, section.pos));
+            if is_heteronym {
+                builder = builder.heteronym(&section.ipa);
+            }
+            builders.push(builder);
+        }
+    }
+    page_entries(builders, sidecar::page_records(&word, &ast))
+}
+
+/// Build a page's entries, with its sidecar records on the first one kept
+fn page_entries<'a>(
+    builders: Vec<EntryBuilder<'a>>,
+    sidecar: Vec<sidecar::SidecarRecord>,
+) -> (Vec<Entry<'a>>, Vec<InvalidEntry>) {
+    let (mut entries, mut invalid) = (Vec::new(), Vec::new());
+    for builder in builders {
+        match builder.build() {
+            Ok(entry) => entries.push(entry),
+            Err(e) => invalid.push(e),
+        }
+    }
+    if let Some(first) = entries.first_mut() {
+        first.sidecar = sidecar;
+    }
+    (entries, invalid)
+}
+
+fn scan_pages(mut reader: impl BufRead, mut callback: impl FnMut(String) -> bool) -> std::io::Result<()> {
+    const OPEN: &[u8] = b"<page>";
+    const CLOSE: &[u8] = b"</page>";
+    let open_finder = memmem::Finder::new(OPEN);
+    let close_finder = memmem::Finder::new(CLOSE);
+
+    let mut buffer: Vec<u8> = Vec::new();
+    let mut chunk = vec![0u8; 1024 * 1024]; // 1MB chunks
+    // Offset of the current page's "<page>", once found
+    let mut page_start: Option<usize> = None;
+    // Searches resume here, so each byte is scanned once per tag
+    let mut scanned = 0;
+    // Start of the read of the next page, with --metrics-every or --metrics-textfile
+    let mut read_start = metrics::enabled().then(Instant::now);
+
+    loop {
+        let bytes_read = reader.read(&mut chunk)?;
+        if bytes_read == 0 {
+            break;
+        }
+        buffer.extend_from_slice(&chunk[..bytes_read]);
+
+        // Extract complete pages
+        loop {
+            let start = match page_start {
+                Some(start) => start,
+                None => match open_finder.find(&buffer[scanned..]) {
+                    Some(i) => {
+                        let start = scanned + i;
+                        scanned = start + OPEN.len();
+                        start
+                    }
+                    None => {
+                        // Nothing to keep but a possibly split "<page>"
+                        buffer.drain(..buffer.len().saturating_sub(OPEN.len() - 1));
+                        scanned = 0;
+                        break;
+                    }
+                },
+            };
+
+            match close_finder.find(&buffer[scanned..]) {
+                Some(i) => {
+                    let end = scanned + i + CLOSE.len();
+                    // Decoding whole pages keeps characters split across reads intact
+                    let page_xml = String::from_utf8_lossy(&buffer[start..end]).into_owned();
+                    page_start = None;
+                    scanned = end;
+
+                    if let Some(start) = read_start {
+                        metrics::add_time(metrics::Stage::Read, start);
+                        metrics::page_read();
+                    }
+                    if !callback(page_xml) {
+                        return Ok(());
+                    }
+                    read_start = read_start.map(|_| Instant::now());
+                }
+                None => {
+                    // Drop everything before the open page and resume the search
+                    // just before the end, in case "</page>" was split
+                    buffer.drain(..start);
+                    page_start = Some(0);
+                    scanned = (buffer.len() + 1).saturating_sub(CLOSE.len()).max(OPEN.len());
+                    break;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run sequential processing (original baseline)
+fn run_sequential<W: Write>(
+    reader: impl BufRead,
+    writer: &mut BufWriter<W>,
+    limit: OutputLimit,
+    quiet: bool,
+) -> std::io::Result<Stats> {
+    let start_time = Instant::now();
+    let mut stats = Stats::default();
+
+    let pb = if quiet {
+        ProgressBar::hidden()
+    } else {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner} {msg}")
+                .unwrap()
+        );
+        pb
+    };
+
+    let limit_reached = std::cell::Cell::new(false);
+
+    scan_pages(reader, |page_xml| {
+        if limit_reached.get() {
+            return false;
+        }
+
+        stats.pages_processed += 1;
+        metrics::page_written();
+        let process_timer = metrics::time(metrics::Stage::Process);
+
+        if !quiet && stats.pages_processed % 1000 == 0 {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let rate = stats.pages_processed as f64 / elapsed;
+            pb.set_message(format!(
+                "Pages: {} | Senses: {} | Words: {} | Rate: {:.0} pg/s",
+                stats.pages_processed, stats.senses_written, stats.words_written, rate
+            ));
+        }
+
+        // Extract title
+        let title = match TITLE_PATTERN.captures(&page_xml) {
+            Some(cap) => cap[1].to_string(),
+            None => {
+                record_failure(&PageFailure::new("", FailureCategory::NoTitle, &page_xml));
+                stats.skipped += 1;
+                return true;
+            }
+        };
+
+        // Check namespace
+        if let Some(cap) = NS_PATTERN.captures(&page_xml) {
+            if &cap[1] != "0" {
+                stats.special += 1;
+                return true;
+            }
+        }
+
+        // Check for special prefixes
+        if get_special_prefixes().iter().any(|prefix| title.starts_with(prefix)) {
+            stats.special += 1;
+            return true;
+        }
+
+        if !select::selected(&title) {
+            return true;
+        }
+
+        // Check for redirects
+        if REDIRECT_PATTERN.is_match(&page_xml) {
+            stats.redirects += 1;
+            return true;
+        }
+
+        // Find the text, borrowed from the page XML
+        let Some(text) = page_text(&page_xml) else {
+            record_failure(&PageFailure::new(&title, FailureCategory::NoText, &page_xml));
+            stats.skipped += 1;
+            return true;
+        };
+
+        // Check for English section
+        if !has_english_section(text) {
+            stats.non_english += 1;
+            return true;
+        }
+
+        // Check for dict-only
+        if DICT_ONLY.is_match(text) {
+            stats.dict_only += 1;
+            return true;
+        }
+        // Only the English section is parsed, as in the parallel strategies
+        let text = english_span(text).unwrap_or(text);
+
+        // Check if English-like
+        let grapheme_title = match classify_title(&title) {
+            TitleKind::Word => None,
+            TitleKind::Grapheme(unescaped) => Some(unescaped),
+            TitleKind::NonLatin => {
+                stats.non_latin += 1;
+                return true;
+            }
+        };
+
+        // Parse page into multiple entries (one per sense), then apply output filters
+        let meta = PageMeta::from_xml(&page_xml);
+        let mut data = PageData::default();
+        let (mut entries, failures) = parse_page_checked(grapheme_title.as_deref().unwrap_or(&title), text, &mut data);
+        failures.iter().for_each(record_failure);
+        if entries.is_empty() {
+            stats.skipped += 1;
+            return true;
+        }
+        meta.apply(&mut entries);
+        if grapheme_title.is_some() {
+            keep_grapheme_senses(&mut entries);
+            if entries.is_empty() {
+                stats.non_latin += 1;
+                return true;
+            }
+        }
+        let (entries, filtered) = filters::filter_entries(entries);
+        stats.senses_filtered += filtered;
+
+        if entries.is_empty() {
+            stats.skipped += 1;
+            return true;
+        }
+
+        stats.count_word(&title);
+
+        drop(process_timer);
+        stats.senses_written += shape::write_entries(writer, entries, limit.room(&stats)).unwrap_or(0);
+        if limit.reached(&stats) {
+            limit_reached.set(true);
+            return false;
+        }
+
+        true
+    })?;
+
+    writer.flush()?;
+
+    if limit_reached.get() && !quiet {
+        pb.finish_with_message(limit.describe(&stats));
+    } else {
+        pb.finish_and_clear();
+    }
+
+    stats.elapsed = start_time.elapsed();
+    Ok(stats)
+}
+
+/// Run syllable validation mode - extract all syllable sources for cross-validation
+fn run_syllable_validation<W: Write>(
+    reader: impl BufRead,
+    writer: &mut BufWriter<W>,
+    page_limit: Option<usize>,
+    quiet: bool,
+) -> std::io::Result<SyllableValidationStats> {
+    let start_time = Instant::now();
+    let mut stats = SyllableValidationStats::default();
+
+    let pb = if quiet {
+        ProgressBar::hidden()
+    } else {
+        let pb = ProgressBar::new_spinner();
+        pb.set_style(
+            ProgressStyle::default_spinner()
+                .template("{spinner} {msg}")
+                .unwrap()
+        );
+        pb
+    };
+
+    let limit_reached = std::cell::Cell::new(false);
+
+    scan_pages(reader, |page_xml| {
+        if limit_reached.get() {
+            return false;
+        }
+
+        stats.pages_scanned += 1;
+
+        // Check page limit
+        if let Some(limit) = page_limit {
+            if stats.pages_scanned >= limit {
+                limit_reached.set(true);
+                return false;
+            }
+        }
+
+        if !quiet && stats.pages_scanned % 10000 == 0 {
+            let elapsed = start_time.elapsed().as_secs_f64();
+            let rate = stats.pages_scanned as f64 / elapsed;
+            pb.set_message(format!(
+                "Pages: {} | With syllables: {} | Disagreements: {} | Rate: {:.0} pg/s",
+                stats.pages_scanned, stats.words_with_syllables, stats.disagreements, rate
+            ));
+        }
+
+        // Extract title
+        let title = match TITLE_PATTERN.captures(&page_xml) {
+            Some(cap) => cap[1].to_string(),
+            None => return true,
+        };
+
+        // Check namespace
+        if let Some(cap) = NS_PATTERN.captures(&page_xml) {
+            if &cap[1] != "0" {
+                return true;
+            }
+        }
+
+        // Check for special prefixes
+        if get_special_prefixes().iter().any(|prefix| title.starts_with(prefix)) {
+            return true;
+        }
+
+        // Check for redirects
+        if REDIRECT_PATTERN.is_match(&page_xml) {
+            return true;
+        }
+
+        // Extract text
+        let text = match TEXT_PATTERN.captures(&page_xml) {
+            Some(cap) => cap[1].to_string(),
+            None => return true,
+        };
+
+        // Check for English section
+        if !ENGLISH_SECTION.is_match(&text) {
+            return true;
+        }
+
+        // Check if English-like
+        if !is_englishlike(&title) {
+            return true;
+        }
+
+        // Extract syllable validation data
+        if let Some(validation) = extract_syllable_validation(&title, &text) {
+            stats.words_with_syllables += 1;
+
+            // Track source coverage
+            if validation.rhymes.is_some() { stats.has_rhymes += 1; }
+            if validation.ipa.is_some() { stats.has_ipa += 1; }
+            if validation.ipa_transcriptions > 1 { stats.multiple_ipa += 1; }
+            if validation.ipa_counts_differ { stats.ipa_counts_differ += 1; }
+            if validation.category.is_some() { stats.has_category += 1; }
+            if validation.hyphenation.is_some() { stats.has_hyphenation += 1; }
+
+            if validation.has_disagreement {
+                stats.disagreements += 1;
+            }
+            if validation.resolved_by.is_some() {
+                stats.resolved += 1;
+            }
+
+            // Write the validation record
+            if let Ok(json) = serde_json::to_string(&validation) {
+                writeln!(writer, "{}", json).ok();
+            }
+        }
+
+        true
+    })?;
+
+    writer.flush()?;
+
+    if limit_reached.get() && !quiet {
+        pb.finish_with_message(format!("Reached page limit of {}", page_limit.unwrap()));
+    } else {
+        pb.finish_and_clear();
+    }
+
+    stats.elapsed = start_time.elapsed();
+    Ok(stats)
+}
+
+#[derive(Default)]
+struct SyllableValidationStats {
+    pages_scanned: usize,
+    words_with_syllables: usize,
+    has_rhymes: usize,
+    has_ipa: usize,
+    multiple_ipa: usize,       // Words with more than one IPA transcription
+    ipa_counts_differ: usize,  // ... whose transcriptions differ in syllable count
+    has_category: usize,
+    has_hyphenation: usize,
+    disagreements: usize,
+    resolved: usize,  // Disagreements settled by --syllable-rules
+    elapsed: Duration,
+}
+
+fn print_syllable_validation_stats(stats: &SyllableValidationStats) {
+    println!();
+    println!("============================================================");
+    println!("Syllable Validation Results");
+    println!("============================================================");
+    println!("Pages scanned: {}", stats.pages_scanned);
+    println!("Words with syllable data: {}", stats.words_with_syllables);
+    println!();
+    println!("Source coverage:");
+    println!("  Rhymes (s=): {} ({:.1}%)", stats.has_rhymes,
+        100.0 * stats.has_rhymes as f64 / stats.words_with_syllables.max(1) as f64);
+    println!("  IPA: {} ({:.1}%)", stats.has_ipa,
+        100.0 * stats.has_ipa as f64 / stats.words_with_syllables.max(1) as f64);
+    println!("    Several transcriptions: {} ({:.1}% of IPA)", stats.multiple_ipa,
+        100.0 * stats.multiple_ipa as f64 / stats.has_ipa.max(1) as f64);
+    println!("    Counts differ across them: {} ({:.1}% of IPA)", stats.ipa_counts_differ,
+        100.0 * stats.ipa_counts_differ as f64 / stats.has_ipa.max(1) as f64);
+    println!("  Category: {} ({:.1}%)", stats.has_category,
+        100.0 * stats.has_category as f64 / stats.words_with_syllables.max(1) as f64);
+    println!("  Hyphenation: {} ({:.1}%)", stats.has_hyphenation,
+        100.0 * stats.has_hyphenation as f64 / stats.words_with_syllables.max(1) as f64);
+    println!();
+    println!("Disagreements: {} ({:.2}%)", stats.disagreements,
+        100.0 * stats.disagreements as f64 / stats.words_with_syllables.max(1) as f64);
+    if stats.resolved > 0 {
+        println!("Settled by --syllable-rules: {} ({:.1}% of disagreements)", stats.resolved,
+            100.0 * stats.resolved as f64 / stats.disagreements.max(1) as f64);
+    }
+    println!();
+    println!("Time: {}m {}s", stats.elapsed.as_secs() / 60, stats.elapsed.as_secs() % 60);
+    println!("Rate: {:.0} pages/sec", stats.pages_scanned as f64 / stats.elapsed.as_secs_f64());
+    println!("============================================================");
+}
+
+fn print_stats(stats: &Stats, strategy_name: &str) {
+    println!();
+    println!("============================================================");
+    println!("Strategy: {}", strategy_name);
+    println!("Pages processed: {}", stats.pages_processed);
+    println!("Words written: {}", stats.words_written);
+    println!("Senses written: {}", stats.senses_written);
+    println!("Avg senses/word: {:.2}", stats.senses_written as f64 / stats.words_written.max(1) as f64);
+    println!("------------------------------------------------------------");
+    println!("Case distribution:");
+    println!("  lowercase: {} (e.g., sat)", stats.case_lower);
+    println!("  Titlecase: {} (e.g., Sat)", stats.case_title);
+    println!("  UPPERCASE: {} (e.g., SAT)", stats.case_upper);
+    println!("  miXedCase: {} (e.g., iPhone)", stats.case_mixed);
+    println!("------------------------------------------------------------");
+    println!("Special pages: {}", stats.special);
+    println!("Redirects: {}", stats.redirects);
+    println!("Dictionary-only terms: {}", stats.dict_only);
+    println!("Non-English pages: {}", stats.non_english);
+    println!("Non-Latin scripts: {}", stats.non_latin);
+    println!("Skipped: {}", stats.skipped);
+    if stats.senses_filtered > 0 {
+        println!("Senses filtered out: {}", stats.senses_filtered);
+    }
+    println!("Time: {}m {}s", stats.elapsed.as_secs() / 60, stats.elapsed.as_secs() % 60);
+    println!("Rate: {:.0} pages/sec", stats.pages_processed as f64 / stats.elapsed.as_secs_f64());
+    println!("============================================================");
+}
+
+/// Take the special-page prefixes from the dump's siteinfo header, when the
+/// input has one, and return a reader over the whole input
+fn use_site_namespaces(reader: Box<dyn BufRead + Send>, quiet: bool) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let (reader, prefixes) = siteinfo::read_namespaces(reader)?;
+    match prefixes {
+        Some(prefixes) => {
+            if !quiet {
+                println!("Skipping {} namespaces listed in the dump's siteinfo", prefixes.len());
+            }
+            let _ = SITE_PREFIXES_VEC.set(prefixes);
+        }
+        None if !quiet => println!("No siteinfo namespaces; using special_page_prefixes from the labels schema"),
+        None => {}
+    }
+    Ok(reader)
+}
+
+/// Undo the XML escaping of a dump's `<title>` or `<text>`
+fn unescape_xml(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+/// `-` stands for stdin (as input) or stdout (as output)
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+fn open_input(input: &Path) -> std::io::Result<Box<dyn BufRead + Send>> {
+    if is_stdio(input) {
+        // No file extension to go by, so sniff the bzip2 magic instead
+        let mut stdin = BufReader::with_capacity(256 * 1024, std::io::stdin());
+        let is_bz2 = stdin.fill_buf()?.starts_with(b"BZh");
+        return Ok(if is_bz2 {
+            Box::new(BufReader::with_capacity(256 * 1024, MultiBzDecoder::new(stdin)))
+        } else {
+            Box::new(stdin)
+        });
+    }
+
+    let file = File::open(input)?;
+    Ok(if input.to_string_lossy().ends_with(".bz2") {
+        Box::new(BufReader::with_capacity(256 * 1024, MultiBzDecoder::new(file)))
+    } else {
+        Box::new(BufReader::with_capacity(256 * 1024, file))
+    })
+}
+
+/// Open several inputs as one logical stream, in the order given.
+///
+/// Each file is decompressed independently, so split dump parts (each a
+/// complete bz2 stream with its own <mediawiki> wrapper) concatenate cleanly.
+fn open_inputs(inputs: &[PathBuf]) -> std::io::Result<Box<dyn BufRead + Send>> {
+    let mut readers = inputs.iter().map(|p| open_input(p));
+    let first = readers.next().unwrap_or_else(|| Ok(Box::new(std::io::empty())))?;
+    readers.try_fold(first, |combined, next| -> std::io::Result<Box<dyn BufRead + Send>> {
+        Ok(Box::new(combined.chain(next?)))
+    })
+}
+
+/// Expand glob patterns that the shell left unexpanded (e.g. when quoted)
+fn expand_inputs(inputs: &[PathBuf]) -> std::io::Result<Vec<PathBuf>> {
+    let mut expanded = Vec::new();
+    for input in inputs {
+        let pattern = input.to_string_lossy();
+        if input.exists() || is_stdio(input) || !pattern.contains(['*', '?', '[']) {
+            expanded.push(input.clone());
+            continue;
+        }
+        let mut matches: Vec<PathBuf> = glob::glob(&pattern)
+            .map_err(|e| std::io::Error::other(format!("Invalid glob {}: {}", pattern, e)))?
+            .filter_map(Result::ok)
+            .collect();
+        if matches.is_empty() {
+            return Err(std::io::Error::other(format!("No files match {}", pattern)));
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+    Ok(expanded)
+}
+
+/// An output file being written under a temporary name in the same directory.
+/// `commit` renames it into place; dropping it uncommitted (after an error)
+/// removes the partial file, so a file at the output path is always complete.
+pub struct OutputFile {
+    /// (temporary path, final path); None when writing to stdout or a
+    /// non-regular file such as /dev/null
+    rename: Option<(PathBuf, PathBuf)>,
+}
+
+impl OutputFile {
+    pub fn commit(mut self) -> std::io::Result<()> {
+        if let Some((tmp_path, path)) = self.rename.take() {
+            std::fs::rename(&tmp_path, &path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for OutputFile {
+    fn drop(&mut self) {
+        if let Some((tmp_path, _)) = &self.rename {
+            let _ = std::fs::remove_file(tmp_path);
+        }
+    }
+}
+
+/// Refuse to replace an existing output file unless `force` is set
+fn check_overwrite(output: &Path, force: bool) -> std::io::Result<()> {
+    if !force && !is_stdio(output) && output.is_file() {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::AlreadyExists,
+            format!("{} already exists (use --force to overwrite)", output.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// Open `output` for writing: stdout for `-`, otherwise `<output>.tmp`, which
+/// the returned `OutputFile` renames to `output` once the run has succeeded
+fn create_output(output: &Path, force: bool) -> std::io::Result<(Box<dyn Write + Send>, OutputFile)> {
+    if is_stdio(output) {
+        return Ok((Box::new(std::io::stdout()), OutputFile { rename: None }));
+    }
+    check_overwrite(output, force)?;
+
+    // Devices and pipes can't be renamed over; write to them directly
+    if output.exists() && !output.is_file() {
+        return Ok((Box::new(File::create(output)?), OutputFile { rename: None }));
+    }
+
+    let mut tmp_name = output.file_name().unwrap_or_default().to_os_string();
+    tmp_name.push(".tmp");
+    let tmp_path = output.with_file_name(tmp_name);
+    let file = File::create(&tmp_path)?;
+    Ok((Box::new(file), OutputFile { rename: Some((tmp_path, output.to_path_buf())) }))
+}
+
+fn init_schemas(args: &Args) {
+    // Initialize POS map from schema YAML
+    if let Err(e) = init_pos_map(args.schema.as_ref()) {
+        eprintln!("Error loading POS schema: {}", e);
+        std::process::exit(1);
+    }
+
+    // Initialize labels from schema YAML
+    if let Err(e) = init_labels(args.labels_schema.as_ref()) {
+        eprintln!("Error loading labels schema: {}", e);
+        std::process::exit(1);
+    }
+
+    init_ipa(args);
+
+    if let Err(e) = init_form_of(args.form_of_schema.as_ref()) {
+        eprintln!("Error loading form-of schema: {}", e);
+        std::process::exit(1);
+    }
+
+    if let Err(e) = wikidata::init_lexemes(args.wikidata_lexemes.as_deref()) {
+        eprintln!("Error loading Wikidata lexemes: {}", e);
+        std::process::exit(1);
+    }
+}
+
+/// Load the IPA inventory, for the scan's syllable counts or the rhyme index
+fn init_ipa(args: &Args) {
+    if let Err(e) = init_ipa_inventory(args.ipa_schema.as_ref()) {
+        eprintln!("Error loading IPA schema: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn init_filters(args: &Args) {
+    let filter = filters::EntryFilter::from_args(args).and_then(filters::init_entry_filter);
+    if let Err(e) = filter {
+        eprintln!("Error in output filters: {}", e);
+        std::process::exit(1);
+    }
+    let forms = normalize::WordForms {
+        normalize: args.normalize,
+        ascii: args.ascii,
+        sort_key: args.sort_key,
+    };
+    if let Err(e) = normalize::init_word_forms(forms) {
+        eprintln!("Error in output filters: {}", e);
+        std::process::exit(1);
+    }
+    let _ = DEFINITIONS.set(args.definitions);
+    let _ = QUOTATIONS.set(args.quotations);
+    let _ = USAGE_NOTES.set(args.usage_notes);
+    let _ = ETYMOLOGY_TEXT.set(args.etymology_text);
+    let _ = HEADWORDS.set(args.headwords);
+    let _ = shape::init_output_shape(args.shape);
+    compact::init_compact_keys(args.compact_keys);
+    shape::init_json_array(args.json_array);
+    let _ = syllables::init_syllable_rules(args.syllable_rules.clone());
+}
+
+/// Parse the command line and run the scan or subcommand it asks for
+pub fn run() -> std::io::Result<()> {
+    let matches = Args::command().get_matches();
+    let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    args.split_paths();
+
+    if let Some(path) = args.config.clone() {
+        match config::load_config(&path) {
+            Ok(run_config) => config::apply_config(&mut args, &matches, run_config),
+            Err(e) => {
+                eprintln!("Error loading config: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    match &args.command {
+        Some(Command::FetchDump(fetch_args)) => {
+            let dump = fetch::run_fetch_dump(fetch_args, args.quiet)?;
+            if let Some(output) = &fetch_args.scan {
+                init_schemas(&args);
+                init_filters(&args);
+                run_scan(&args, &[dump], output)?;
+            }
+            Ok(())
+        }
+        Some(Command::FetchPage(page_args)) => {
+            if !page_args.raw {
+                init_schemas(&args);
+            }
+            fetch::run_fetch_page(page_args, args.force)
+        }
+        Some(Command::Update(update_args)) => {
+            init_schemas(&args);
+            init_filters(&args);
+            update::run_update(update_args, args.force, args.quiet)
+        }
+        Some(Command::AffixStats(stats_args)) => affix_stats::run_affix_stats(stats_args),
+        Some(Command::MergeCase(merge_args)) => {
+            let (lines, spellings) = merge_case::run_merge_case(merge_args, args.force)?;
+            if !args.quiet && !is_stdio(&merge_args.output) {
+                println!("Linked {} case variants ({} lines)", spellings, lines);
+            }
+            Ok(())
+        }
+        Some(Command::GenerateForms(generate_args)) => {
+            let (forms, lemmas) = generate_forms::run_generate_forms(generate_args, args.force)?;
+            if !args.quiet && !is_stdio(&generate_args.output) {
+                println!("Generated {} forms of {} lemmas", forms, lemmas);
+            }
+            Ok(())
+        }
+        Some(Command::Report(report_args)) => report::run_report(report_args),
+        Some(Command::BuildRhymes(rhymes_args)) => {
+            init_ipa(&args);
+            let (rhymes, words) = rhymes::run_build_rhymes(rhymes_args, args.force)?;
+            if !args.quiet && !is_stdio(&rhymes_args.output) {
+                println!("Indexed {} words under {} rhymes", words, rhymes);
+            }
+            Ok(())
+        }
+        Some(Command::MakeFixture(fixture_args)) => {
+            let (pages, strata, seen) = fixture::run_make_fixture(fixture_args, args.force)?;
+            if !args.quiet && !is_stdio(&fixture_args.output) {
+                println!("Wrote {} pages from {} strata (of {} English pages)", pages, strata, seen);
+            }
+            Ok(())
+        }
+        Some(Command::Export(export_args)) => match &export_args.format {
+            export::ExportFormat::Clues(clues_args) => {
+                let (clues, words) = export::run_export_clues(clues_args, args.force)?;
+                if !args.quiet && !is_stdio(&clues_args.output) {
+                    println!("Wrote {} clues for {} words", clues, words);
+                }
+                Ok(())
+            }
+            export::ExportFormat::LemmaPairs(pairs_args) => {
+                let pairs = export::run_export_lemma_pairs(pairs_args, args.force)?;
+                if !args.quiet && !is_stdio(&pairs_args.output) {
+                    println!("Wrote {} lemma pairs", pairs);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Segmentation(segmentation_args) => {
+                let words = export::run_export_segmentation(segmentation_args, args.force)?;
+                if !args.quiet && !is_stdio(&segmentation_args.output) {
+                    println!("Wrote segmentations of {} words", words);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Wordlist(wordlist_args) => {
+                let words = export::run_export_wordlist(wordlist_args, args.force)?;
+                if !args.quiet && !is_stdio(&wordlist_args.output) {
+                    println!("Wrote {} words", words);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Tiers(tiers_args) => {
+                let counts = export::run_export_tiers(tiers_args, args.force)?;
+                if !args.quiet && !is_stdio(&tiers_args.output) {
+                    let counts: Vec<String> = counts.iter().map(|(level, words)| format!("{} {}", words, level)).collect();
+                    println!("Wrote levelled lists: {}", counts.join(", "));
+                }
+                Ok(())
+            }
+            export::ExportFormat::Ontolex(ontolex_args) => {
+                let (entries, senses) = ontolex::run_export_ontolex(ontolex_args, args.force)?;
+                if !args.quiet && !is_stdio(&ontolex_args.output) {
+                    println!("Wrote {} lexical entries with {} senses", entries, senses);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Stardict(dictionary_args) => {
+                let words = dictionary::run_export_stardict(dictionary_args, args.force)?;
+                if !args.quiet {
+                    println!("Wrote StarDict dictionary of {} words", words);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Dictd(dictionary_args) => {
+                let words = dictionary::run_export_dictd(dictionary_args, args.force)?;
+                if !args.quiet {
+                    println!("Wrote dictd database of {} words", words);
+                }
+                Ok(())
+            }
+            export::ExportFormat::Graph(graph_args) => {
+                let (nodes, edges) = graph::run_export_graph(graph_args, args.force)?;
+                if !args.quiet && !is_stdio(&graph_args.output) {
+                    println!("Wrote word-formation graph of {} nodes and {} edges", nodes, edges);
+                }
+                Ok(())
+            }
+            export::ExportFormat::WordnetMap(wordnet_args) => {
+                let (senses, candidates) = wordnet::run_export_wordnet_map(wordnet_args, args.force)?;
+                if !args.quiet && !is_stdio(&wordnet_args.output) {
+                    println!("Wrote {} WordNet candidates for {} senses", candidates, senses);
+                }
+                Ok(())
+            }
+        },
+        Some(Command::Golden(golden_args)) => {
+            init_schemas(&args);
+            if !golden::run_golden(golden_args)? {
+                std::process::exit(1);
+            }
+            Ok(())
+        }
+        None => {
+            // Checked here rather than by clap, since --config may supply them
+            let output = match (&args.output, args.inputs.is_empty()) {
+                (Some(output), false) => output.clone(),
+                _ => Args::command()
+                    .error(
+                        clap::error::ErrorKind::MissingRequiredArgument,
+                        "<INPUTS>... <OUTPUT> are required (on the command line or in --config)",
+                    )
+                    .exit(),
+            };
+            init_schemas(&args);
+            init_filters(&args);
+            let inputs = expand_inputs(&args.inputs)?;
+            run_scan(&args, &inputs, &output)
+        }
+    }
+}
+
+/// Scan a dump with the strategy and options given on the command line
+fn run_scan(args: &Args, inputs: &[PathBuf], output: &Path) -> std::io::Result<()> {
+    let input_list = inputs.iter().map(|p| p.display().to_string()).collect::<Vec<_>>().join(", ");
+
+    // Status output would corrupt the data when stdout is the sink
+    let quiet = args.quiet || is_stdio(output);
+
+    // Handle syllable validation mode
+    if args.syllable_validation {
+        if !quiet {
+            println!("Syllable Validation Mode");
+            println!("Input: {}", input_list);
+            println!("Output: {}", output.display());
+            if let Some(limit) = args.page_limit {
+                println!("Page limit: {}", limit);
+            }
+            println!();
+        }
+
+        if let Some(dumpstatus) = &args.verify_checksum {
+            integrity::verify_checksums(inputs, dumpstatus, quiet)?;
+        }
+        let (reader, checks) = integrity::open_checked_inputs(inputs)?;
+        let reader = use_site_namespaces(reader, quiet)?;
+        let (output, output_file) = create_output(output, args.force)?;
+        let mut writer = BufWriter::with_capacity(256 * 1024, output);
+
+        let stats = run_syllable_validation(reader, &mut writer, args.page_limit, quiet)?;
+        drop(writer);
+        if let Err(message) = integrity::verify_ends(&checks) {
+            drop(output_file);
+            eprintln!("Error: {}", message);
+            std::process::exit(1);
+        }
+        output_file.commit()?;
+
+        if !quiet {
+            print_syllable_validation_stats(&stats);
+        }
+
+        return Ok(());
+    }
+
+    // Validate: --limit requires sequential mode for efficient early termination
+    if (args.limit.is_some() || args.word_limit.is_some()) && args.strategy != Strategy::Sequential {
+        eprintln!(
+            "Error: --limit and --word-limit require --strategy sequential for efficient early termination.\n\
+             Parallel strategies must process pages out of order and reorder results,\n\
+             which means they cannot stop early when the limit is reached."
+        );
+        std::process::exit(1);
+    }
+
+    // The appendix sidecar re-reads the input, which stdin cannot do
+    if args.appendix_out.is_some() && inputs.iter().any(|p| is_stdio(p)) {
+        eprintln!("Error: --appendix-out needs file inputs; stdin cannot be read twice.");
+        std::process::exit(1);
+    }
+
+    // Module and template pages are read in a pass of their own
+    if args.lua && inputs.iter().any(|p| is_stdio(p)) {
+        eprintln!("Error: --lua needs file inputs; stdin cannot be read twice.");
+        std::process::exit(1);
+    }
+
+    // The lemma pass reads the output back
+    if args.resolve_lemmas && is_stdio(output) {
+        eprintln!("Error: --resolve-lemmas rewrites the output file, so it needs a file output.");
+        std::process::exit(1);
+    }
+    if args.resolve_lemmas && args.compact_keys {
+        eprintln!("Error: --resolve-lemmas reads full field names, so it cannot be combined with --compact-keys.");
+        std::process::exit(1);
+    }
+    if args.resolve_lemmas && args.json_array {
+        eprintln!("Error: --resolve-lemmas reads JSON lines, so it cannot be combined with --json-array.");
+        std::process::exit(1);
+    }
+
+    // The split files are written into the output directory
+    if args.split_by.is_some() && is_stdio(output) {
+        eprintln!("Error: --split-by writes a directory of files, so it needs an output directory.");
+        std::process::exit(1);
+    }
+    if args.split_by.is_some() && (args.verify || args.resolve_lemmas) {
+        eprintln!("Error: --verify and --resolve-lemmas read a single output file, so they cannot be combined with --split-by.");
+        std::process::exit(1);
+    }
+
+    // Verifying reads the input again and compares against the output file
+    if args.verify && (is_stdio(output) || inputs.iter().any(|p| is_stdio(p))) {
+        eprintln!("Error: --verify needs file inputs and a file output.");
+        std::process::exit(1);
+    }
+
+    // Check before scanning rather than failing at the end of a long run
+    if let Some(appendix_out) = &args.appendix_out {
+        check_overwrite(appendix_out, args.force)?;
+    }
+
+    let sidecar_files = if args.sidecars.is_empty() {
+        Vec::new()
+    } else {
+        let dir = match (&args.sidecar_dir, is_stdio(output)) {
+            (Some(dir), _) => dir.clone(),
+            (None, false) if args.split_by.is_some() => output.to_path_buf(),
+            (None, false) => output.parent().map(Path::to_path_buf).unwrap_or_default(),
+            (None, true) => {
+                eprintln!("Error: --sidecars with stdout output needs --sidecar-dir.");
+                std::process::exit(1);
+            }
+        };
+        sidecar::open_sidecars(&args.sidecars, &dir, args.force)?
+    };
+
+    let errors_file = match &args.errors_out {
+        Some(errors_out) => {
+            let (writer, errors_file) = create_output(errors_out, args.force)?;
+            error_log::init_error_log(writer);
+            Some(errors_file)
+        }
+        None => None,
+    };
+
+    if args.titles.is_some() || args.title_regex.is_some() {
+        let titles = match &args.titles {
+            Some(path) => Some(update::read_title_list(path)?.into_iter().collect()),
+            None => None,
+        };
+        let pattern = match &args.title_regex {
+            Some(pattern) => match Regex::new(pattern) {
+                Ok(regex) => Some(regex),
+                Err(e) => {
+                    eprintln!("Error: invalid --title-regex: {}", e);
+                    std::process::exit(1);
+                }
+            },
+            None => None,
+        };
+        select::init_title_filter(select::TitleFilter { titles, pattern });
+    }
+
+    if args.multistream_index.is_some() {
+        if inputs.len() != 1 || is_stdio(&inputs[0]) {
+            eprintln!("Error: --multistream-index needs a single multistream dump file as input.");
+            std::process::exit(1);
+        }
+        if select::get_title_filter().is_none() {
+            eprintln!("Error: --multistream-index needs --titles or --title-regex to choose streams.");
+            std::process::exit(1);
+        }
+    }
+
+    // clap rejects both flags together, but a config file may set one of them
+    if args.sample.is_some() && args.sample_n.is_some() {
+        eprintln!("Error: use either --sample or --sample-n, not both.");
+        std::process::exit(1);
+    }
+    if let Some(rate) = args.sample {
+        if !(rate > 0.0 && rate <= 1.0) {
+            eprintln!("Error: --sample takes a fraction of pages between 0 and 1, e.g. 0.001.");
+            std::process::exit(1);
+        }
+        sample::init_sample_rate(rate, args.sample_seed);
+    }
+    if args.sample_n == Some(0) {
+        eprintln!("Error: --sample-n must be at least 1.");
+        std::process::exit(1);
+    }
+
+    if let Some(dumpstatus) = &args.verify_checksum {
+        integrity::verify_checksums(inputs, dumpstatus, quiet)?;
+    }
+
+    if args.lua {
+        let pages = match lua::init_lua(inputs) {
+            Ok(pages) => pages,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        };
+        if !quiet {
+            println!("Lua: {} Module: and Template: pages loaded", pages);
+        }
+    }
+
+    // Build parallel config
+    let mut config = ParallelConfig::default();
+    if args.threads > 0 {
+        config.num_threads = args.threads;
+        config.num_workers = args.threads.saturating_sub(1).max(1);
+    }
+    config.batch_size = args.batch_size;
+    config.channel_buffer = args.channel_buffer;
+    config.autoscale = args.autoscale;
+    config.reorder_limit = args.reorder_limit;
+    config.chunk_pages = args.chunk_pages;
+    config.memory_budget = args.memory_budget.map(|mb| mb.max(1) * 1024 * 1024);
+    config.quiet = quiet;
+
+    if !quiet {
+        println!("Parsing: {}", input_list);
+        println!("Output: {}", output.display());
+        println!("Strategy: {:?}", args.strategy);
+        if args.strategy != Strategy::Sequential {
+            println!("Threads: {}", config.num_threads);
+        }
+        if let Some(limit) = args.limit {
+            println!("Limit: {} entries", limit);
+        }
+        if let Some(limit) = args.word_limit {
+            println!("Word limit: {} words", limit);
+        }
+        if let Some(limit) = args.page_limit {
+            println!("Page limit: {}", limit);
+        }
+        if let Some(rate) = args.sample {
+            println!("Sample: {} of mainspace pages (seed {})", rate, args.sample_seed);
+        }
+        if let Some(n) = args.sample_n {
+            println!("Sample: {} mainspace pages (seed {})", n, args.sample_seed);
+        }
+        println!();
+    }
+
+    // Run the selected strategy
+    let (reader, checks) = open_scan_input(args, inputs, quiet)?;
+    let (output_writer, output_file) = match args.split_by {
+        Some(SplitBy::Pos) => {
+            split::open_split(output, args.force)?;
+            (Box::new(std::io::sink()) as Box<dyn Write + Send>, OutputFile { rename: None })
+        }
+        None => create_output(output, args.force)?,
+    };
+    let reporter = match (&args.metrics_textfile, args.metrics_every) {
+        (None, None) => None,
+        (textfile, every) => {
+            let sink = textfile.clone().map_or(metrics::MetricsSink::Stderr, metrics::MetricsSink::Textfile);
+            Some(metrics::start_metrics(sink, Duration::from_secs(every.unwrap_or(10).max(1))))
+        }
+    };
+    let stats = run_strategy(args.strategy, reader, output_writer, &config, OutputLimit::from_args(args), quiet)?;
+    if let Some(reporter) = reporter {
+        reporter.finish()?;
+    }
+    // A partial lexicon is worse than none: leave no output behind
+    if let Err(message) = integrity::verify_ends(&checks) {
+        drop(output_file);
+        drop(errors_file);
+        split::discard_split();
+        eprintln!("Error: {}", message);
+        std::process::exit(1);
+    }
+    output_file.commit()?;
+    let split_files = split::finish_split()?;
+
+    if !quiet {
+        print_stats(&stats, &format!("{:?}", args.strategy));
+        if args.lua {
+            let (expanded, failed) = lua::lua_stats();
+            println!("Lua headwords: {} expanded, {} failed", expanded, failed);
+        }
+    }
+
+    if let (Some(errors_out), Some(errors_file)) = (&args.errors_out, errors_file) {
+        let failures = error_log::finish_error_log()?;
+        errors_file.commit()?;
+        if !quiet {
+            println!("Extraction failures: {} (written to {})", failures, errors_out.display());
+        }
+    }
+
+    // Closed before --verify scans again, so its pass adds nothing to them
+    let sidecars = sidecar::finish_sidecars()?;
+    for file in sidecar_files {
+        file.commit()?;
+    }
+    if !quiet {
+        for (path, records) in &sidecars {
+            println!("Sidecar records: {} (written to {})", records, path.display());
+        }
+    }
+
+    if let Some(appendix_out) = &args.appendix_out {
+        let (pages, records) = appendix::run_appendix_pass(inputs, appendix_out, args.force)?;
+        if !quiet {
+            println!("Appendix pages: {} ({} records written to {})", pages, records, appendix_out.display());
+        }
+    }
+
+    if args.verify {
+        let reference = verify_output(args, inputs, output, &config)?;
+        if !quiet {
+            println!("Verified: output matches --strategy {:?}", reference);
+        }
+    }
+
+    if args.resolve_lemmas {
+        let resolved = lemmas::resolve_lemma_chains(output)?;
+        if !quiet {
+            println!("Lemma chains: {} entries given a root_lemma", resolved);
+        }
+    }
+
+    // Written last, so it describes the output as --resolve-lemmas left it
+    if args.split_by.is_some() {
+        let manifest = manifest::write_manifest(output, &split_files, &sidecars, &stats)?;
+        if !quiet {
+            for (path, records) in &split_files {
+                println!("Split records: {} (written to {})", records, path.display());
+            }
+            println!("Manifest: {}", manifest.display());
+        }
+    } else if output.is_file() {
+        let shards = [(output.to_path_buf(), manifest::output_records(&stats))];
+        let manifest = manifest::write_manifest(output.parent().unwrap_or(Path::new("")), &shards, &sidecars, &stats)?;
+        if !quiet {
+            println!("Manifest: {}", manifest.display());
+        }
+    }
+
+    Ok(())
+}
+
+/// The scan's input: the dump files (or the multistream streams chosen by the
+/// title filter) past their siteinfo, sampled if asked. The checks are for
+/// `integrity::verify_ends` once the scan is done.
+fn open_scan_input(
+    args: &Args,
+    inputs: &[PathBuf],
+    quiet: bool,
+) -> std::io::Result<(Box<dyn BufRead + Send>, Vec<integrity::InputCheck>)> {
+    let (reader, checks): (Box<dyn BufRead + Send>, _) = match (&args.multistream_index, select::get_title_filter()) {
+        // Seeking skips the end of the dump, so there is nothing to check there
+        (Some(index), Some(filter)) => {
+            let (streams, selected, total) =
+                multistream::open_selected_streams(&inputs[0], index, |title| filter.matches(title))?;
+            if !quiet {
+                println!("Reading {} of {} streams from the multistream index", selected, total);
+            }
+            (Box::new(streams), Vec::new())
+        }
+        _ => integrity::open_checked_inputs(inputs)?,
+    };
+    let mut reader = use_site_namespaces(reader, quiet)?;
+    if let Some(n) = args.sample_n {
+        let (sample, seen) = sample::sample_n_pages(reader, n, args.sample_seed)?;
+        if !quiet {
+            println!("Sampled {} of {} mainspace pages", n.min(seen), seen);
+        }
+        reader = Box::new(sample);
+    }
+    Ok((reader, checks))
+}
+
+/// Run one strategy over the input, writing JSON lines or, with --json-array, one array
+fn run_strategy(
+    strategy: Strategy,
+    reader: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+    config: &ParallelConfig,
+    limit: OutputLimit,
+    quiet: bool,
+) -> std::io::Result<Stats> {
+    if shape::json_array() {
+        let (array, end) = shape::JsonArrayWriter::new(output);
+        let stats = run_strategy_lines(strategy, reader, Box::new(array), config, limit, quiet)?;
+        end.finish()?;
+        return Ok(stats);
+    }
+    run_strategy_lines(strategy, reader, output, config, limit, quiet)
+}
+
+fn run_strategy_lines(
+    strategy: Strategy,
+    reader: Box<dyn BufRead + Send>,
+    output: Box<dyn Write + Send>,
+    config: &ParallelConfig,
+    limit: OutputLimit,
+    quiet: bool,
+) -> std::io::Result<Stats> {
+    match strategy {
+        Strategy::Sequential => {
+            let mut writer = BufWriter::with_capacity(256 * 1024, output);
+            run_sequential(reader, &mut writer, limit, quiet)
+        }
+
+        Strategy::BatchParallel => {
+            let mut writer = BufWriter::with_capacity(256 * 1024, output);
+            process_batch_parallel(reader, &mut writer, config, limit)
+        }
+
+        Strategy::ChannelPipeline => process_channel_pipeline(reader, output, config, limit),
+
+        Strategy::TwoPhase => {
+            let mut writer = BufWriter::with_capacity(256 * 1024, output);
+            process_two_phase(reader, &mut writer, config, limit)
+        }
+    }
+}
+
+/// Scan the input again with a reference strategy (Sequential, or
+/// ChannelPipeline when Sequential made the output) and check that the
+/// output is byte-for-byte the same. Exits on a mismatch, naming the first
+/// differing line. Returns the reference strategy.
+fn verify_output(args: &Args, inputs: &[PathBuf], output: &Path, config: &ParallelConfig) -> std::io::Result<Strategy> {
+    let reference = match args.strategy {
+        Strategy::Sequential => Strategy::ChannelPipeline,
+        _ => Strategy::Sequential,
+    };
+    let mut verify_name = output.file_name().unwrap_or_default().to_os_string();
+    verify_name.push(".verify.tmp");
+    let verify_path = output.with_file_name(verify_name);
+
+    let (reader, _) = open_scan_input(args, inputs, true)?;
+    run_strategy(reference, reader, Box::new(File::create(&verify_path)?), config, OutputLimit::from_args(args), true)?;
+    let mismatch = first_difference(output, &verify_path);
+    std::fs::remove_file(&verify_path)?;
+
+    match mismatch? {
+        None => Ok(reference),
+        Some(line) => {
+            eprintln!(
+                "Error: --verify failed: output differs from --strategy {:?} at line {}",
+                reference, line
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Line number of the first difference between two files, if any
+fn first_difference(a: &Path, b: &Path) -> std::io::Result<Option<usize>> {
+    let mut a_lines = BufReader::new(File::open(a)?).split(b'\n');
+    let mut b_lines = BufReader::new(File::open(b)?).split(b'\n');
+    let mut number = 0;
+    loop {
+        number += 1;
+        match (a_lines.next().transpose()?, b_lines.next().transpose()?) {
+            (None, None) => return Ok(None),
+            (a_line, b_line) if a_line != b_line => return Ok(Some(number)),
+            _ => {}
+        }
+    }
+}
+
+/// Where writing stops: `--limit` counts senses and may cut a word's senses
+/// short, `--word-limit` counts words and always writes a word whole
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OutputLimit {
+    pub senses: Option<usize>,
+    pub words: Option<usize>,
+}
+
+impl OutputLimit {
+    fn from_args(args: &Args) -> Self {
+        OutputLimit { senses: args.limit, words: args.word_limit }
+    }
+
+    /// How many senses of the next word may still be written
+    pub fn room(&self, stats: &Stats) -> Option<usize> {
+        self.senses.map(|l| l.saturating_sub(stats.senses_written))
+    }
+
+    /// Whether every page is written whole, so its entries can be serialized
+    /// before the writer reaches it
+    pub fn whole_pages(&self) -> bool {
+        self.senses.is_none()
+    }
+
+    /// Whether writing should stop, checked after each word
+    pub fn reached(&self, stats: &Stats) -> bool {
+        self.senses.is_some_and(|l| stats.senses_written >= l)
+            || self.words.is_some_and(|l| stats.words_written >= l)
+    }
+
+    /// The progress message for a scan that stopped at the limit
+    fn describe(&self, stats: &Stats) -> String {
+        match self.words {
+            Some(l) if stats.words_written >= l => format!("Reached limit of {} words", l),
+            _ => format!("Reached limit of {} entries", self.senses.unwrap_or_default()),
+        }
+    }
+}
+
+/// Scan counters. The parallel strategies fill one per page on the worker
+/// threads (see `ProcessedPage::stats`) and the writer adds them up with
+/// `merge`, so counting a page costs the writer a few additions whatever the
+/// counters are.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Stats {
+    pub pages_processed: usize,
+    pub words_written: usize,
+    pub senses_written: usize,
+    pub special: usize,
+    pub redirects: usize,
+    pub dict_only: usize,
+    pub non_english: usize,
+    pub non_latin: usize,
+    pub skipped: usize,
+    pub senses_filtered: usize,
+    pub elapsed: Duration,
+    // Case distribution (for reporting)
+    pub case_lower: usize,      // all lowercase: "sat"
+    pub case_title: usize,      // Capitalized: "Sat"
+    pub case_upper: usize,      // ALL CAPS: "SAT"
+    pub case_mixed: usize,      // miXed case: "iPhone"
+}
+
+impl Stats {
+    /// Count a written word and its case pattern
+    pub fn count_word(&mut self, title: &str) {
+        self.words_written += 1;
+        match classify_case(title) {
+            CaseForm::Lower => self.case_lower += 1,
+            CaseForm::Title => self.case_title += 1,
+            CaseForm::Upper => self.case_upper += 1,
+            CaseForm::Mixed => self.case_mixed += 1,
+        }
+    }
+
+    /// Add a partial's counters to these; `elapsed` is left alone
+    pub fn merge(&mut self, partial: &Stats) {
+        self.pages_processed += partial.pages_processed;
+        self.words_written += partial.words_written;
+        self.senses_written += partial.senses_written;
+        self.special += partial.special;
+        self.redirects += partial.redirects;
+        self.dict_only += partial.dict_only;
+        self.non_english += partial.non_english;
+        self.non_latin += partial.non_latin;
+        self.skipped += partial.skipped;
+        self.senses_filtered += partial.senses_filtered;
+        self.case_lower += partial.case_lower;
+        self.case_title += partial.case_title;
+        self.case_upper += partial.case_upper;
+        self.case_mixed += partial.case_mixed;
+    }
+}
+
+/// Classify the case pattern of a word (for reporting purposes)
+pub fn classify_case(s: &str) -> CaseForm {
+    let has_alpha = s.chars().any(|c| c.is_alphabetic());
+    if !has_alpha {
+        return CaseForm::Lower; // Treat non-alphabetic as lowercase
+    }
+
+    let alpha_chars: Vec<char> = s.chars().filter(|c| c.is_alphabetic()).collect();
+    let all_lower = alpha_chars.iter().all(|c| c.is_lowercase());
+    let all_upper = alpha_chars.iter().all(|c| c.is_uppercase());
+    let first_upper = alpha_chars.first().map(|c| c.is_uppercase()).unwrap_or(false);
+    let rest_lower = alpha_chars.iter().skip(1).all(|c| c.is_lowercase());
+
+    if all_lower {
+        CaseForm::Lower
+    } else if all_upper {
+        CaseForm::Upper
+    } else if first_upper && rest_lower {
+        CaseForm::Title
+    } else {
+        CaseForm::Mixed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CaseForm {
+    Lower,
+    Title,
+    Upper,
+    Mixed,
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests for WikitextParser
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod wikitext_parser_tests {
+    use super::*;
+
+    // ─────────────────────────────────────────────────────────────
+    // Wikilink struct tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn wikilink_text_returns_display_when_present() {
+        let wl = Wikilink {
+            target: "isle".to_string(),
+            anchor: None,
+            display: Some("Isle".to_string()),
+        };
+        assert_eq!(wl.text(), "Isle");
+    }
+
+    #[test]
+    fn wikilink_text_returns_target_when_no_display() {
+        let wl = Wikilink {
+            target: "word".to_string(),
+            anchor: None,
+            display: None,
+        };
+        assert_eq!(wl.text(), "word");
+    }
+
+    #[test]
+    fn wikilink_anchor_preserved() {
+        let wl = Wikilink {
+            target: "Man".to_string(),
+            anchor: Some("Etymology 2".to_string()),
+            display: Some("Man".to_string()),
+        };
+        assert_eq!(wl.anchor, Some("Etymology 2".to_string()));
+        assert_eq!(wl.text(), "Man");
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Basic parameter parsing
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn simple_params() {
+        let result = parse_template_params("en|word|suffix");
+        assert_eq!(result, vec!["en", "word", "suffix"]);
+    }
+
+    #[test]
+    fn empty_string() {
+        let result = parse_template_params("");
+        assert!(result.is_empty() || result == vec![""]);
+    }
+
+    #[test]
+    fn single_param() {
+        let result = parse_template_params("word");
+        assert_eq!(result, vec!["word"]);
+    }
+
+    #[test]
+    fn whitespace_trimming() {
+        let result = parse_template_params("  en  |  word  |  suffix  ");
+        assert_eq!(result, vec!["en", "word", "suffix"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Wikilink parsing
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn simple_wikilink() {
+        let result = parse_template_params("[[cat]]");
+        assert_eq!(result, vec!["cat"]);
+    }
+
+    #[test]
+    fn wikilink_with_display() {
+        let result = parse_template_params("[[isle|Isle]]");
+        assert_eq!(result, vec!["Isle"]);
+    }
+
+    #[test]
+    fn wikilink_with_anchor() {
+        let result = parse_template_params("[[Man#Etymology 2]]");
+        assert_eq!(result, vec!["Man"]);
+    }
+
+    #[test]
+    fn wikilink_with_anchor_and_display() {
+        let result = parse_template_params("[[Man#Etymology 2|Man]]");
+        assert_eq!(result, vec!["Man"]);
+    }
+
+    #[test]
+    fn isle_of_man_example() {
+        // The motivating example: {{af|en|[[isle|Isle]]|of|[[Man#Etymology 2|Man]]}}
+        let result = parse_template_params("en|[[isle|Isle]]|of|[[Man#Etymology 2|Man]]");
+        assert_eq!(result, vec!["en", "Isle", "of", "Man"]);
+    }
+
+    #[test]
+    fn multiple_wikilinks() {
+        let result = parse_template_params("[[a|A]]|[[b|B]]|[[c|C]]");
+        assert_eq!(result, vec!["A", "B", "C"]);
+    }
+
+    #[test]
+    fn mixed_wikilinks_and_text() {
+        let result = parse_template_params("prefix|[[word|Word]]|suffix");
+        assert_eq!(result, vec!["prefix", "Word", "suffix"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Nested template handling
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn nested_template_discarded() {
+        let result = parse_template_params("foo|{{q|qualifier}}|bar");
+        assert_eq!(result, vec!["foo", "", "bar"]);
+    }
+
+    #[test]
+    fn deeply_nested_templates() {
+        let result = parse_template_params("foo|{{a|{{b|{{c|d}}}}}}|bar");
+        assert_eq!(result, vec!["foo", "", "bar"]);
+    }
+
+    #[test]
+    fn template_with_wikilink_inside() {
+        let result = parse_template_params("foo|{{m|en|[[word]]}}|bar");
+        assert_eq!(result, vec!["foo", "", "bar"]);
+    }
+
+    #[test]
+    fn named_params_left_out() {
+        let result = parse_template_params("en|un-|[[kind]]|t2=nice|-ness&lt;ref&gt;x&lt;/ref&gt;|pos3=suffix");
+        assert_eq!(result, vec!["en", "un-", "kind", "-ness"]);
+    }
+
+    #[test]
+    fn wikilink_after_template() {
+        let result = parse_template_params("{{info}}|[[word|Word]]");
+        assert_eq!(result, vec!["", "Word"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // UTF-8 handling (the bug we fixed!)
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn latin_extended_characters() {
+        let result = parse_template_params("nāsus|-o-");
+        assert_eq!(result, vec!["nāsus", "-o-"]);
+    }
+
+    #[test]
+    fn alphabeticus_example() {
+        // The case that caused the panic; lang1=la is a named param
+        let result = parse_template_params("lang1=la|alphabēticus|-al");
+        assert_eq!(result, vec!["alphabēticus", "-al"]);
+    }
+
+    #[test]
+    fn greek_characters() {
+        let result = parse_template_params("en|λόγος");
+        assert_eq!(result, vec!["en", "λόγος"]);
+    }
+
+    #[test]
+    fn cyrillic_characters() {
+        let result = parse_template_params("en|слово");
+        assert_eq!(result, vec!["en", "слово"]);
+    }
+
+    #[test]
+    fn mixed_scripts_in_wikilink() {
+        let result = parse_template_params("[[word|café]]");
+        assert_eq!(result, vec!["café"]);
+    }
+
+    #[test]
+    fn utf8_in_anchor() {
+        let result = parse_template_params("[[page#Étymologie|display]]");
+        assert_eq!(result, vec!["display"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Edge cases
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn unclosed_wikilink() {
+        let result = parse_template_params("[[word");
+        assert_eq!(result, vec!["word"]);
+    }
+
+    #[test]
+    fn unclosed_template() {
+        let result = parse_template_params("{{template");
+        assert_eq!(result, vec![""]);
+    }
+
+    #[test]
+    fn empty_wikilink() {
+        let result = parse_template_params("[[]]");
+        assert_eq!(result, vec![""]);
+    }
+
+    #[test]
+    fn consecutive_pipes() {
+        let result = parse_template_params("a||b");
+        assert_eq!(result, vec!["a", "", "b"]);
+    }
+
+    #[test]
+    fn wikilink_with_only_anchor() {
+        let result = parse_template_params("[[#section]]");
+        // Target is empty, anchor is "section", no display
+        assert_eq!(result, vec![""]);
+    }
+
+    #[test]
+    fn wikilink_with_empty_display() {
+        let result = parse_template_params("[[word|]]");
+        assert_eq!(result, vec![""]);
+    }
+
+    #[test]
+    fn special_characters_in_text() {
+        let result = parse_template_params("word's|don't|it-self");
+        assert_eq!(result, vec!["word's", "don't", "it-self"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Real-world examples
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn batsman_compound() {
+        // batsman: {{compound|en|bat|-s-|-man}}
+        let result = parse_template_params("bat|-s-|-man");
+        assert_eq!(result, vec!["bat", "-s-", "-man"]);
+    }
+
+    #[test]
+    fn affix_with_link() {
+        let result = parse_template_params("[[un-]]|[[happy]]");
+        assert_eq!(result, vec!["un-", "happy"]);
+    }
+
+    #[test]
+    fn suffix_template() {
+        let result = parse_template_params("beauty|-ful");
+        assert_eq!(result, vec!["beauty", "-ful"]);
+    }
+
+    #[test]
+    fn prefix_template() {
+        let result = parse_template_params("un-|happy");
+        assert_eq!(result, vec!["un-", "happy"]);
+    }
+
+    #[test]
+    fn confix_template() {
+        let result = parse_template_params("bio-|chemistry|-ist");
+        assert_eq!(result, vec!["bio-", "chemistry", "-ist"]);
+    }
+
+    #[test]
+    fn pictograph_style() {
+        // Pattern like pictograph: {{affix|en|la:pictus|-o-|graph}}
+        let result = parse_template_params("la:pictus|-o-|graph");
+        assert_eq!(result, vec!["la:pictus", "-o-", "graph"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Parser internal tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn parser_peek_multibyte() {
+        let parser = WikitextParser::new("café");
+        // Should handle multi-byte UTF-8 correctly
+        assert_eq!(parser.peek(1), "c");
+        assert_eq!(parser.peek(4), "café");
+    }
+
+    #[test]
+    fn parser_consume_multibyte() {
+        let mut parser = WikitextParser::new("café");
+        assert_eq!(parser.consume(1), "c");
+        assert_eq!(parser.consume(1), "a");
+        assert_eq!(parser.consume(1), "f");
+        assert_eq!(parser.consume(1), "é");
+        assert!(parser.at_end());
+    }
+
+    #[test]
+    fn parser_wikilink_all_parts() {
+        let mut parser = WikitextParser::new("[[Man#Etymology 2|Man]]");
+        let wl = parser.parse_wikilink();
+        assert_eq!(wl.target, "Man");
+        assert_eq!(wl.anchor, Some("Etymology 2".to_string()));
+        assert_eq!(wl.display, Some("Man".to_string()));
+    }
+
+    #[test]
+    fn parser_template_simple() {
+        let mut parser = WikitextParser::new("{{m|en|word}}");
+        let tmpl = parser.parse_template();
+        assert_eq!(tmpl.name, "m");
+        assert_eq!(tmpl.params, vec!["en", "word"]);
+    }
+
+    #[test]
+    fn parser_template_nested() {
+        let mut parser = WikitextParser::new("{{outer|{{inner|a|b}}}}");
+        let tmpl = parser.parse_template();
+        assert_eq!(tmpl.name, "outer");
+        // Inner template is parsed but its text is discarded
+        assert_eq!(tmpl.params, vec![""]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Named parameter tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn named_params_split_from_positional() {
+        let result = parse_template_params_named("en|cat| pl = cats |nocap=1");
+        assert_eq!(result.positional, vec!["en", "cat"]);
+        assert_eq!(result.named.get("pl").map(String::as_str), Some("cats"));
+        assert_eq!(result.named.get("nocap").map(String::as_str), Some("1"));
+    }
+
+    #[test]
+    fn equals_inside_link_is_not_a_key() {
+        let result = parse_template_params_named("[[a=b|x=y]]|t=[[gloss]]");
+        assert_eq!(result.positional, vec!["x=y"]);
+        assert_eq!(result.named.get("t").map(String::as_str), Some("gloss"));
+    }
+
+    #[test]
+    fn value_may_contain_equals() {
+        let result = parse_template_params_named("q=a=b");
+        assert_eq!(result.named.get("q").map(String::as_str), Some("a=b"));
+    }
+
+    #[test]
+    fn parser_template_named() {
+        let mut parser = WikitextParser::new("{{en-noun|s|pl2=cattle|head=[[cat]]}}");
+        let tmpl = parser.parse_template();
+        assert_eq!(tmpl.name, "en-noun");
+        assert_eq!(tmpl.params, vec!["s"]);
+        assert_eq!(tmpl.named.get("pl2").map(String::as_str), Some("cattle"));
+        assert_eq!(tmpl.named.get("head").map(String::as_str), Some("cat"));
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // HTML tag and entity tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn strip_inline_modifiers() {
+        // Dump text escapes the angle brackets of <id:...> and <t:...>
+        let result = parse_template_params("en|ice&lt;id:frozen water&gt;|-y&lt;t:adjective suffix&gt;");
+        assert_eq!(result, vec!["en", "ice", "-y"]);
+    }
+
+    #[test]
+    fn strip_formatting_keeps_inner_text() {
+        let result = parse_template_params("H<sub>2</sub>O|x<sup>2</sup>");
+        assert_eq!(result, vec!["H2O", "x2"]);
+    }
+
+    #[test]
+    fn strip_ref_with_body() {
+        // The ref body may contain pipes and equals signs of its own
+        let result = parse_template_params(
+            "word&lt;ref name=&quot;a&quot;&gt;{{cite-book|title=X|year=1900}}&lt;/ref&gt;|other<ref name=b />",
+        );
+        assert_eq!(result, vec!["word", "other"]);
+    }
+
+    #[test]
+    fn strip_decodes_entities() {
+        let result = parse_template_params("rock &amp; roll|caf&#233;|a&amp;nbsp;b|AT&T");
+        assert_eq!(result, vec!["rock & roll", "café", "a b", "AT&T"]);
+    }
+
+    #[test]
+    fn strip_decodes_amp_without_an_entity_after_it() {
+        let result = parse_template_params("a &amp; b; c|R&amp;D;");
+        assert_eq!(result, vec!["a & b; c", "R&D;"]);
+    }
+
+    #[test]
+    fn strip_leaves_lone_angle_brackets() {
+        let result = parse_template_params("a < b|c&gt;d");
+        assert_eq!(result, vec!["a < b", "c>d"]);
+    }
+
+    #[test]
+    fn preserve_keeps_markup_verbatim() {
+        let mut parser = WikitextParser::new("{{m|en|x<sup>2</sup>|t=a&amp;b<ref>r|s</ref>}}");
+        let tmpl = parser.parse_template();
+        assert_eq!(tmpl.params, vec!["en", "x<sup>2</sup>"]);
+        assert_eq!(tmpl.named.get("t").map(String::as_str), Some("a&amp;b<ref>r|s</ref>"));
+    }
+
+    #[test]
+    fn equals_inside_tag_is_not_a_key() {
+        let mut parser = WikitextParser::with_markup(
+            "{{m|en|word<ref name=x/>}}",
+            MarkupMode::Strip,
+        );
+        let tmpl = parser.parse_template();
+        assert_eq!(tmpl.params, vec!["en", "word"]);
+        assert!(tmpl.named.is_empty());
+    }
+}
+
+// ─────────────────────────────────────────────────────────────────────────────
+// Tests for Morphology Extraction
+// ─────────────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod morphology_tests {
+    use super::*;
+
+    // ─────────────────────────────────────────────────────────────
+    // classify_morphology tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn classify_suffixed() {
+        let result = classify_morphology(
+            vec!["happy".to_string(), "-ness".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.morph_type, "suffixed");
+        assert_eq!(result.base, Some("happy".to_string()));
+        assert_eq!(result.suffixes, vec!["-ness"]);
+        assert!(!result.is_compound);
+    }
+
+    #[test]
+    fn classify_prefixed() {
+        let result = classify_morphology(
+            vec!["un-".to_string(), "happy".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.morph_type, "prefixed");
+        assert_eq!(result.base, Some("happy".to_string()));
+        assert_eq!(result.prefixes, vec!["un-"]);
+        assert!(!result.is_compound);
+    }
+
+    #[test]
+    fn classify_affixed() {
+        let result = classify_morphology(
+            vec!["un-".to_string(), "break".to_string(), "-able".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.morph_type, "affixed");
+        assert_eq!(result.base, Some("break".to_string()));
+        assert_eq!(result.prefixes, vec!["un-"]);
+        assert_eq!(result.suffixes, vec!["-able"]);
+        assert!(!result.is_compound);
+    }
+
+    #[test]
+    fn classify_compound() {
+        let result = classify_morphology(
+            vec!["sun".to_string(), "flower".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.morph_type, "compound");
+        assert_eq!(result.base, None);
+        assert!(result.is_compound);
+    }
+
+    #[test]
+    fn classify_compound_with_interfix() {
+        let result = classify_morphology(
+            vec!["bee".to_string(), "-s-".to_string(), "wax".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.morph_type, "compound");
+        assert_eq!(result.base, None);
+        assert_eq!(result.interfixes, vec!["-s-"]);
+        assert!(result.is_compound);
+    }
+
+    #[test]
+    fn classify_multiple_suffixes() {
+        let result = classify_morphology(
+            vec!["dict".to_string(), "-ion".to_string(), "-ary".to_string()],
+            "{{test}}".to_string()
+        );
+        assert_eq!(result.suffixes, vec!["-ion", "-ary"]);
+        assert_eq!(result.base, Some("dict".to_string()));
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // extract_morphology tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn extract_suffix_template() {
+        let text = "===Etymology===\n{{suffix|en|happy|ness}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "suffixed");
+        assert_eq!(result.components, vec!["happy", "-ness"]);
+        assert_eq!(result.base, Some("happy".to_string()));
+    }
+
+    #[test]
+    fn extract_prefix_template() {
+        let text = "===Etymology===\n{{prefix|en|un|happy}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "prefixed");
+        assert_eq!(result.components, vec!["un-", "happy"]);
+        assert_eq!(result.base, Some("happy".to_string()));
+    }
+
+    #[test]
+    fn extract_confix_template() {
+        let text = "===Etymology===\n{{confix|en|en|light|ment}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "circumfixed");
+        assert_eq!(result.components, vec!["en-", "light", "-ment"]);
+        assert_eq!(result.base, Some("light".to_string()));
+    }
+
+    #[test]
+    fn extract_compound_template() {
+        let text = "===Etymology===\n{{compound|en|sun|flower}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "compound");
+        assert_eq!(result.components, vec!["sun", "flower"]);
+        assert!(result.is_compound);
+    }
+
+    #[test]
+    fn extract_affix_template_suffixed() {
+        let text = "===Etymology===\n{{af|en|happy|-ness}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "suffixed");
+        assert_eq!(result.components, vec!["happy", "-ness"]);
+    }
+
+    #[test]
+    fn extract_affix_template_prefixed() {
+        let text = "===Etymology===\n{{af|en|un-|happy}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "prefixed");
+        assert_eq!(result.components, vec!["un-", "happy"]);
+    }
+
+    #[test]
+    fn extract_affix_template_affixed() {
+        let text = "===Etymology===\n{{af|en|un-|break|-able}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "affixed");
+        assert_eq!(result.prefixes, vec!["un-"]);
+        assert_eq!(result.suffixes, vec!["-able"]);
+    }
+
+    #[test]
+    fn extract_affix_template_compound() {
+        let text = "===Etymology===\n{{af|en|sun|flower}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "compound");
+        assert!(result.is_compound);
+    }
+
+    #[test]
+    fn extract_surf_template() {
+        let text = "===Etymology===\n{{surf|en|heli|copter}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "compound");
+        assert_eq!(result.components, vec!["heli", "copter"]);
+    }
+
+    #[test]
+    fn extract_with_wikilinks() {
+        let text = "===Etymology===\n{{af|en|[[isle|Isle]]|of|[[Man#Etymology 2|Man]]}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.components, vec!["Isle", "of", "Man"]);
+    }
+
+    #[test]
+    fn extract_speedometer() {
+        let text = "===Etymology===\n{{af|en|speed|-o-|meter}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "compound");
+        assert_eq!(result.interfixes, vec!["-o-"]);
+    }
+
+    #[test]
+    fn foreign_components_keep_their_language() {
+        let text = "===Etymology===\n{{af|en|la:[[pictus]]|-o-|graph}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.components, vec!["-o-", "graph"]);
+        assert_eq!(result.foreign_components, vec![ForeignComponent { lang: "la".into(), term: "pictus".into() }]);
+
+        // Only English components count toward a segmentation's two parts
+        assert_eq!(extract_morphology("===Etymology===\n{{compound|en|grc:τῆλε|vision}}"), None);
+        let text = "===Etymology===\n{{blend|en|grc:τῆλε|vision}}";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.components, vec!["vision"]);
+        assert_eq!(result.foreign_components, vec![ForeignComponent { lang: "grc".into(), term: "τῆλε".into() }]);
+    }
+
+    #[test]
+    fn no_etymology_section() {
+        let text = "===Pronunciation===\nSome pronunciation info";
+        let result = extract_morphology(text);
+        assert!(result.is_none());
+    }
+
+    #[test]
+    fn extract_blend() {
+        let text = "===Etymology===\nBlend of {{blend|en|smoke|fog}}.";
+        let result = extract_morphology(text).unwrap();
+        assert_eq!(result.morph_type, "blend");
+        assert_eq!(result.components, vec!["smoke", "fog"]);
+        assert_eq!(result.base, None);
+    }
+
+    #[test]
+    fn extract_clipping_and_back_formation() {
+        let clipping = extract_morphology("===Etymology===\n{{clipping of|en|examination}}").unwrap();
+        assert_eq!(clipping.morph_type, "clipping");
+        assert_eq!(clipping.base, Some("examination".to_string()));
+
+        let back = extract_morphology("===Etymology===\n{{back-formation from|en|[[editor]]}}").unwrap();
+        assert_eq!(back.morph_type, "back-formation");
+        assert_eq!(back.components, vec!["editor"]);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Etymological relations
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn extract_calque_and_doublets() {
+        let text = "Calque of {{calque|en|fr|gratte-ciel|t=sky-scraper}}. \
+                    Doublet of {{doublet|en|[[hospital]]|hostel}} and {{dbt|en|hostel|hotel}}.";
+        let (calque_of, doublets) = extract_etymology_relations(&PageAst::parse(text), 0..text.len());
+        assert_eq!(
+            calque_of,
+            Some(CalqueSource { lang: "fr".to_string(), term: "gratte-ciel".to_string() })
+        );
+        assert_eq!(doublets, vec!["hospital", "hostel", "hotel"]);
+    }
+
+    #[test]
+    fn surface_and_etymological_analysis() {
+        let surf = extract_morphology("===Etymology===\n{{surf|en|dict|ion|ary}}").unwrap();
+        assert_eq!(surf.analysis, "surface");
+        let suffix = extract_morphology("===Etymology===\n{{suffix|en|happy|ness}}").unwrap();
+        assert_eq!(suffix.analysis, "etymological");
+    }
+
+    #[test]
+    fn components_concatenation() {
+        let parts = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(components_spell_word(&parts(&["speed", "-o-", "meter"]), "speedometer"));
+        assert!(components_spell_word(&parts(&["ice", "cream"]), "ice cream"));
+        assert!(!components_spell_word(&parts(&["happy", "-ness"]), "happiness"));
+    }
+
+    #[test]
+    fn morphology_per_etymology_section() {
+        let text = "===Etymology 1===\n{{suffix|en|bank|er}}\n\n====Noun====\n# A banker.\n\n\
+                    ===Etymology 2===\n{{compound|en|river|bank}}\n\n====Noun====\n# A riverbank.\n";
+        let etymologies = extract_etymologies(&PageAst::parse(text), false);
+        assert_eq!(etymologies.len(), 2);
+
+        let second_noun = text.rfind("====Noun").unwrap();
+        let morphology = etymology_for(&etymologies, second_noun).unwrap().morphology.as_ref().unwrap();
+        assert_eq!(morphology.morph_type, "compound");
+
+        let first_noun = text.find("====Noun").unwrap();
+        let morphology = etymology_for(&etymologies, first_noun).unwrap().morphology.as_ref().unwrap();
+        assert_eq!(morphology.morph_type, "suffixed");
+    }
+
+    #[test]
+    fn etymology_text_without_morphology_template() {
+        let text = "===Etymology===\nFrom {{inh|en|enm|hous}}, from {{inh|en|ang|hūs}}.\n\n===Noun===\n# A building.\n";
+        let families = PageFamilies::scan(text);
+        assert!(!families.has(TemplateFamily::Etymology));
+        assert!(page_etymologies(&PageAst::parse(text), &families, false).is_empty());
+
+        let etymologies = page_etymologies(&PageAst::parse(text), &families, true);
+        assert_eq!(etymologies.len(), 1);
+        let etymology = &etymologies[0].1;
+        assert_eq!(etymology.text.as_deref(), Some("From Middle English hous, from Old English hūs."));
+        assert!(etymology.morphology.is_none());
+    }
+
+    #[test]
+    fn no_morphology_template() {
+        let text = "===Etymology===\nFrom Old English word.";
+        let result = extract_morphology(text);
+        assert!(result.is_none());
+    }
+}
+
+#[cfg(test)]
+mod extraction_tests {
+    use super::*;
+
+    const TEST_LABELS: &str = r#"
+register_labels: [informal, slang, vulgar]
+temporal_labels: [archaic, obsolete, dated]
+domain_labels: [computing, medicine]
+region_labels: {us: en-US, uk: en-GB, scotland: en-GB-SCT, northern england: en-GB-north, southern us: en-US-south,
+                hong kong: en-HK}
+region_parents: {en-GB-north: en-GB, en-US-south: en-US}
+spelling_labels: {american spelling: en-US, british spelling: en-GB}
+dialect_labels: {scotland: Scottish English, aave: African-American Vernacular English, geordie: Geordie}
+special_page_prefixes: ["Appendix:"]
+"#;
+
+    /// Initialize the global schemas once per test binary; parse_page and the
+    /// label helpers read them
+    fn init_test_schemas() {
+        static INIT: std::sync::Once = std::sync::Once::new();
+        INIT.call_once(|| {
+            let _ = POS_MAP.set(pos_variant_map(&read_pos_schema(&PathBuf::from("../../schema/pos.yaml")).unwrap()));
+            let _ = IPA_INVENTORY.set(load_ipa_schema(&PathBuf::from("../../schema/ipa.yaml")).unwrap());
+            let _ = form_of::init_form_of(Some(&PathBuf::from("../../schema/form_of.yaml")));
+            let schema: LabelsSchema = serde_yaml::from_str(TEST_LABELS).unwrap();
+            let _ = REGISTER_LABELS_SET.set(schema.register_labels.into_iter().collect());
+            let _ = TEMPORAL_LABELS_SET.set(schema.temporal_labels.into_iter().collect());
+            let _ = DOMAIN_LABELS_SET.set(schema.domain_labels.into_iter().collect());
+            let _ = REGION_LABELS_MAP.set(schema.region_labels);
+            let _ = REGION_PARENTS_MAP.set(schema.region_parents);
+            let _ = SPELLING_LABELS_MAP.set(schema.spelling_labels);
+            let _ = DIALECT_LABELS_MAP.set(schema.dialect_labels);
+            let _ = SPECIAL_PREFIXES_VEC.set(schema.special_page_prefixes);
+        });
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // scan_pages tests
+    // ─────────────────────────────────────────────────────────────
+
+    /// Reader returning at most three bytes per read, so tags and UTF-8
+    /// characters are split across reads
+    struct Trickle<'a>(&'a [u8]);
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.0.len().min(buf.len()).min(3);
+            buf[..n].copy_from_slice(&self.0[..n]);
+            self.0 = &self.0[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn page_text_matches_text_pattern() {
+        let pages = [
+            r#"<page><text bytes="9" xml:space="preserve">==English==</text></page>"#,
+            "<page><text>é</text><text>second</text></page>",
+            "<page><text></text>x</text></page>",
+            "<page><text></text></page>",
+            r#"<page><text bytes="0" xml:space="preserve" /></page>"#,
+            "<page><textarea>a\nb</text></page>",
+            "<page><title>no text</title></page>",
+            "<page><text>unclosed",
+        ];
+        for page in pages {
+            let expected = TEXT_PATTERN.captures(page).map(|cap| cap.get(1).unwrap().as_str());
+            assert_eq!(page_text(page), expected, "{}", page);
+        }
+        assert!(has_english_section("==French==\n== english ==\n"));
+        assert!(!has_english_section("==French==\n{{l|en|English}}"));
+    }
+
+    #[test]
+    fn english_span_keeps_the_english_section() {
+        let text = "==Translingual==\n{{no entry|mul}}\n\n==English==\n===Noun===\n# A thing.\n\n==English==\n# Again.\n==French==\n# Chose.\n";
+        let span = english_span(text).unwrap();
+        assert_eq!(span, "==English==\n===Noun===\n# A thing.\n\n==English==\n# Again.\n");
+        assert_eq!(extract_english_section(span), extract_english_section(text));
+        assert_eq!(english_span("==French==\n# Chose."), None);
+    }
+
+    #[test]
+    fn pages_split_across_reads() {
+        let xml = "<mediawiki><siteinfo/>\n  <page><title>café</title></page>\n\
+                   junk <pag <page><title>naïve</title></page></mediawiki>";
+        let mut pages = Vec::new();
+        scan_pages(BufReader::new(Trickle(xml.as_bytes())), |page| {
+            pages.push(page);
+            true
+        })
+        .unwrap();
+        assert_eq!(
+            pages,
+            vec!["<page><title>café</title></page>", "<page><title>naïve</title></page>"]
+        );
+    }
+
+    #[test]
+    fn scan_stops_when_callback_declines() {
+        let xml = "<page>a</page><page>b</page><page>c</page>";
+        let mut count = 0;
+        scan_pages(xml.as_bytes(), |_| {
+            count += 1;
+            count < 2
+        })
+        .unwrap();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn verify_finds_first_differing_line() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| dir.join(format!("verify-{}-{}.jsonl", std::process::id(), name));
+        std::fs::write(path("a"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(path("b"), "one\ntwo\nthree\n").unwrap();
+        std::fs::write(path("c"), "one\nTWO\nthree\n").unwrap();
+        std::fs::write(path("d"), "one\ntwo\n").unwrap();
+        assert_eq!(first_difference(&path("a"), &path("b")).unwrap(), None);
+        assert_eq!(first_difference(&path("a"), &path("c")).unwrap(), Some(2));
+        assert_eq!(first_difference(&path("a"), &path("d")).unwrap(), Some(3));
+        for name in ["a", "b", "c", "d"] {
+            std::fs::remove_file(path(name)).unwrap();
+        }
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Template family pre-filter tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn families_found_only_when_present() {
+        let families = PageFamilies::scan(
+            "===Etymology===\n{{Af|en|un-|kind}}\n===Noun===\n# {{lb|en|slang}} {{gl|a thing}}",
+        );
+        assert!(families.has(TemplateFamily::Labels));
+        assert!(families.has(TemplateFamily::Glosses));
+        assert!(families.has(TemplateFamily::Etymology));
+        assert!(!families.has(TemplateFamily::NonGloss));
+        assert!(!families.has(TemplateFamily::Qualifiers));
+    }
+
+    #[test]
+    fn skipped_families_do_not_change_entries() {
+        init_test_schemas();
+        let text = "==English==\n===Etymology===\nFrom {{suffix|en|kind|ness}}.\n\
+                    ===Noun===\n# {{lb|en|informal}} {{misspelling of|en|kindness}}\n# Plain sense.";
+        let entries = parse_page("kindnes", text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].register_tags, vec!["informal"]);
+        assert_eq!(entries[0].variant_of.as_deref(), Some("kindness"));
+        assert!(entries[1].register_tags.is_empty());
+        assert!(entries.iter().all(|e| e.morphology.is_some()));
+    }
+
+    #[test]
+    fn page_meta_skips_revision_and_contributor_ids() {
+        let xml = "<page>\n<title>cat</title>\n<ns>0</ns>\n<id>5</id>\n<revision>\n<id>81234</id>\n\
+                   <timestamp>2024-09-27T12:53:27Z</timestamp>\n<contributor><id>7</id></contributor>\n</revision>\n</page>";
+        let meta = PageMeta::from_xml(xml);
+        assert_eq!(meta.page_id, Some(5));
+        assert_eq!(meta.rev_timestamp.as_deref(), Some("2024-09-27T12:53:27Z"));
+        assert_eq!(PageMeta::from_xml("<page><title>x</title></page>"), PageMeta::default());
+    }
+
+    #[test]
+    fn golden_cases_match() {
+        init_test_schemas();
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+        for case in golden::read_cases(&dir.join("cases.jsonl")).unwrap() {
+            let actual = golden::current_entries(&dir, &case.title).unwrap();
+            let diffs = golden::diff_entries(&case.entries, &actual);
+            assert!(diffs.is_empty(), "{}: {:#?}", case.title, diffs);
+        }
+    }
+
+    #[test]
+    fn english_page_without_entries_is_a_parse_anomaly() {
+        init_test_schemas();
+        let mut data = PageData::default();
+        let (entries, failures) =
+            parse_page_checked("empty", "{{also|Empty}}\n==English==\n===Etymology===\nUnknown.", &mut data);
+        assert!(entries.is_empty());
+        assert_eq!(failures[0].category, FailureCategory::ParseAnomaly);
+        assert!(failures[0].snippet.starts_with("==English=="));
+        let mut data = PageData::default();
+        let (entries, failures) = parse_page_checked("cat", "==English==\n===Noun===\n# A feline.", &mut data);
+        assert!(!entries.is_empty() && failures.is_empty());
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // extract_categories tests
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn categories_are_normalized_and_deduplicated() {
+        let text = "[[Category:English heteronyms]]\n\
+                    [[Category:English_nouns_with_irregular  plurals|mouse]]\n\
+                    [[:Category:English acronyms]]\n\
+                    {{cln|en|heteronyms|1-letter words|sort=a}}";
+        assert_eq!(
+            extract_categories(&PageAst::parse(text)),
+            vec!["heteronyms", "nouns with irregular plurals", "1-letter words"]
+        );
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Sense-level gloss templates
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn non_gloss_definitions_detected() {
+        assert!(NON_GLOSS_TEMPLATE.is_match("{{n-g|Used before a [[numeral]].}}"));
+        assert!(NON_GLOSS_TEMPLATE.is_match("{{non-gloss definition|A greeting.}}"));
+        assert!(!NON_GLOSS_TEMPLATE.is_match("A [[greeting]]."));
+    }
+
+    #[test]
+    fn gloss_qualifiers_extracted() {
+        let line = "{{lb|en|transitive}} To [[run]] {{gloss|of a [[machine#Noun|machine]]}} or {{gl|of water}}.";
+        assert_eq!(extract_glosses(line), vec!["of a machine", "of water"]);
+    }
+
+    #[test]
+    fn usage_qualifiers_extracted() {
+        let line = "{{q|chiefly|of [[horse]]s}} To [[canter]] {{qualifier|figuratively}}; {{i|rare}} {{quote-book|en|year=1900}} {{q|}}";
+        assert_eq!(extract_qualifiers(line), vec!["chiefly", "of horses", "figuratively", "rare"]);
+        let line = "{{q|especially with &quot;down&quot;}} {{qual|''see'' [[usage]]&lt;ref&gt;x&lt;/ref&gt;}}";
+        assert_eq!(extract_qualifiers(line), vec!["especially with \"down\"", "see usage"]);
+        let families = PageFamilies::scan("===Verb===\n# {{q|rare}} To canter.");
+        assert!(families.has(TemplateFamily::Qualifiers));
+        assert!(!families.has(TemplateFamily::Glosses));
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Label classification
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn dialect_labels_alongside_regions() {
+        init_test_schemas();
+        let labels = extract_labels_from_line("{{lb|en|Scotland|AAVE|slang}} A word.");
+        assert_eq!(labels.dialect, vec!["African-American Vernacular English", "Scottish English"]);
+        assert_eq!(labels.region, vec!["en-GB-SCT"]);
+        assert_eq!(labels.register, vec!["slang"]);
+    }
+
+    #[test]
+    fn subregions_carry_their_parent_region() {
+        init_test_schemas();
+        let labels = extract_labels_from_line("{{lb|en|Northern England|Southern_US|Hong Kong}} A word.");
+        assert_eq!(labels.region, vec!["en-GB", "en-GB-north", "en-HK", "en-US", "en-US-south"]);
+        // No parent listed for Scotland's code, so it stands alone
+        assert_eq!(region_with_parents("en-GB-SCT"), vec!["en-GB-SCT"]);
+    }
+
+    #[test]
+    fn valency_from_transitivity_labels() {
+        init_test_schemas();
+        let valency = |line: &str| extract_labels_from_line(line).valency;
+        assert_eq!(valency("{{lb|en|transitive|slang}} To nick."), Some("transitive".to_string()));
+        assert_eq!(valency("{{lb|en|usually|_|intransitive}} To sleep."), Some("intransitive".to_string()));
+        assert_eq!(valency("{{lb|en|transitive|also intransitive}} To eat."), Some("ambitransitive".to_string()));
+        assert_eq!(valency("{{lb|en|ditransitive}} To give."), Some("ditransitive".to_string()));
+        assert_eq!(valency("{{lb|en|informal}} To chill."), None);
+        assert_eq!(extract_labels_from_line("{{lb|en|transitive|slang}} To nick.").register, vec!["slang"]);
+    }
+
+    #[test]
+    fn labels_normalized_before_lookup() {
+        init_test_schemas();
+        assert_eq!(normalize_label(" chiefly  US "), "us");
+        assert_eq!(normalize_label("especially_British_spelling"), "british spelling");
+        assert_eq!(normalize_label("chiefly"), "chiefly");
+        assert_eq!(normalize_label("Old English"), "old english");
+        let labels = extract_labels_from_line("{{lb|en|US English|chiefly UK|especially_slang|mainly_archaic}} A word.");
+        assert_eq!(labels.region, vec!["en-GB", "en-US"]);
+        assert_eq!(labels.register, vec!["slang"]);
+        assert_eq!(labels.temporal, vec!["archaic"]);
+        let labels = extract_labels_from_line("{{lb|en|Scotland_English}} A word.");
+        assert_eq!(labels.region, vec!["en-GB-SCT"]);
+        assert_eq!(labels.dialect, vec!["Scottish English"]);
+    }
+
+    #[test]
+    fn unclassified_labels_pass_through() {
+        init_test_schemas();
+        let labels = extract_labels_from_line(
+            "{{lb|en|transitive|figuratively|slang|or|by extension|chiefly|_|US|Figuratively|sort=x}} A word.",
+        );
+        assert_eq!(labels.other, vec!["figuratively", "by extension"]);
+        assert_eq!(labels.register, vec!["slang"]);
+        assert!(extract_labels_from_line("{{lb|en|Scotland|informal}} A word.").other.is_empty());
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Spelling variants
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn spelling_variant_templates() {
+        init_test_schemas();
+        let variant = |line: &str| {
+            let entries = parse_page("x", &format!("==English==\n===Noun===\n# {}", line));
+            entries[0].variant_type.clone().zip(entries[0].variant_of.clone())
+        };
+        assert_eq!(
+            variant("{{misspelling of|en|receive}}"),
+            Some(("misspelling".to_string(), "receive".to_string()))
+        );
+        assert_eq!(
+            variant("{{lb|en|obsolete}} {{obsolete spelling of|en|[[musick#English|musick]]|nocap=1}}"),
+            Some(("obsolete_spelling".to_string(), "musick".to_string()))
+        );
+        assert_eq!(
+            variant("{{eye dialect of|en|was}}"),
+            Some(("eye_dialect".to_string(), "was".to_string()))
+        );
+        assert_eq!(
+            variant("{{lb|en|informal}} {{pronunciation spelling of|en|going to}}"),
+            Some(("pronunciation_spelling".to_string(), "going to".to_string()))
+        );
+        assert_eq!(variant("A [[domestic]] [[cat]]."), None);
+    }
+
+    #[test]
+    fn form_of_senses_carry_their_relation() {
+        init_test_schemas();
+        let text = "==English==\n===Noun===\n# {{acronym of|en|[[light]] [[amplification]] by ...}}\n\
+                    # {{lb|en|informal}} {{short for|en|[[laser]] [[beam]]}}\n# {{missp|en|lazer}}\n# A plain sense.";
+        let entries = parse_page("lazer", text);
+        let relations: Vec<_> = entries
+            .iter()
+            .map(|e| (e.relation_type.as_deref(), e.relation_target.as_deref(), e.variant_type.as_deref()))
+            .collect();
+        assert_eq!(relations, vec![
+            (Some("acronym"), Some("light amplification by ..."), None),
+            (Some("short_for"), Some("laser beam"), None),
+            (Some("misspelling"), Some("lazer"), Some("misspelling")),
+            (None, None, None),
+        ]);
+        // {{acronym of}} types the sense but, like before the schema, does not flag the word
+        assert!(entries.iter().all(|e| !e.is_abbreviation));
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Inflections
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn inflection_tags_from_templates() {
+        let lemma = |text: &str| extract_lemma(&PageAst::parse(text));
+        assert_eq!(lemma("# {{plural of|en|cat}}"), Some(("cat".to_string(), vec!["plural".to_string()])));
+        assert_eq!(
+            lemma("# {{past participle of|en|[[run]]}}").unwrap().1,
+            vec!["past", "participle"]
+        );
+        assert_eq!(
+            lemma("# {{inflection of|en|be||3|s|simple|pres|indc}}").unwrap(),
+            (
+                "be".to_string(),
+                ["third-person", "singular", "simple", "present", "indicative"].map(String::from).to_vec()
+            )
+        );
+        assert_eq!(
+            inflection_of_tags("{{inflection of|en|be||1|s|;|3|s|past}}"),
+            vec!["first-person", "singular", "third-person", "past"]
+        );
+        assert_eq!(lemma("# A [[domestic]] [[cat]]."), None);
+    }
+
+    // ─────────────────────────────────────────────────────────────
+    // Neologisms
+    // ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn hot_word_with_date() {
+        assert_eq!(
+            extract_neologism(&PageAst::parse("{{hot word|en|date=March 3 2023}}\n===Noun==="), &[]),
+            (true, Some("March 3 2023".to_string()))
+        );
+        assert_eq!(extract_neologism(&PageAst::parse("{{hotword}}"), &[]), (true, None));
+    }
+
+    #[test]
+    fn neologism_from_category() {
+        let categories = vec!["internet neologisms".to_string()];
+        assert_eq!(extract_neologism(&PageAst::parse("===Noun==="), &categories), (true, None));
+        assert_eq!(extract_neologism(&PageAst::parse("===Noun==="), &[]), (false, None));
+    }
+
+    #[test]
+    fn defdate_years() {
+        assert_eq!(extract_attested("{{lb|en|slang}} A thing. {{defdate|from 15th c.}}"), Some(1400));
+        assert_eq!(extract_attested("A thing. {{defdate|from the 1950s}}"), Some(1950));
+        assert_eq!(extract_attested("A thing. {{defdate|late 19th century}}"), Some(1867));
+        assert_eq!(extract_attested("A thing. {{defdt|c. 1837; earlier 17th c.}}"), Some(1600));
+        assert_eq!(extract_attested("A thing. {{defdate|8th–17th c.}}"), Some(700));
+        assert_eq!(extract_attested("A thing. {{defdate|Mid 19&lt;sup&gt;th&lt;/sup&gt; century.}}"), Some(1833));
+        assert_eq!(extract_attested("A thing. {{defdate|Old English}}"), None);
+        assert_eq!(extract_attested("A thing."), None);
+    }
+
+    #[test]
+    fn phrase_tokens_keep_apostrophes() {
+        assert_eq!(phrase_tokens("rock 'n' roll"), vec!["rock", "'n'", "roll"]);
+        assert_eq!(phrase_tokens("mother-in-law\u{2019}s tongue"), vec!["mother", "in", "law\u{2019}s", "tongue"]);
+        assert_eq!(phrase_tokens("easy come, easy go"), vec!["easy", "come", "easy", "go"]);
+        assert_eq!(phrase_tokens("St. Louis \u{2013} style"), vec!["St.", "Louis", "style"]);
+
+        init_test_schemas();
+        let entries = parse_page("o'clock position", "==English==\n===Noun===\n# A direction.\n");
+        assert_eq!(entries[0].tokens, vec!["o'clock", "position"]);
+        assert!(parse_page("o'clock", "==English==\n===Adverb===\n# Of the clock.\n")[0].tokens.is_empty());
+    }
+
+    #[test]
+    fn grapheme_types_from_headers() {
+        init_test_schemas();
+        let text = "==English==\n===Letter===\n# The second letter.\n\n\
+                    ===Numeral===\n# Two.\n\n===Noun===\n# A bee.\n";
+        let types: Vec<_> = parse_page("b", text).into_iter().map(|e| e.grapheme_type.map(String::from)).collect();
+        assert_eq!(types, vec![Some("letter".to_string()), Some("numeral".to_string()), None]);
+        assert_eq!(grapheme_type("SYM", "punctuation mark"), Some("symbol"));
+    }
+
+    #[test]
+    fn pos_from_headword_template_under_unmapped_header() {
+        init_test_schemas();
+        let text = "==English==\n===Nuon===\n{{en-noun}}\n\n# A small bird.\n\n\
+                    ===Usage notes===\n* Chiefly of songbirds.\n\n===Verb===\n{{en-verb}}\n# To chirp.\n";
+        let pos: Vec<_> = parse_page("tweet", text).into_iter().map(|e| (e.pos.into_owned(), e.pos_source)).collect();
+        assert_eq!(
+            pos,
+            vec![("NOU".to_string(), PosSource::Template), ("VRB".to_string(), PosSource::Header)]
+        );
+
+        let text = "==English==\n===Nuon===\n{{en-noun}}\n[[Category:English nouns]]\n";
+        let entries = parse_page("tweet", text);
+        assert_eq!((&*entries[0].pos, entries[0].pos_source), ("NOU", PosSource::Template));
+        let entries = parse_page("tweet", "==English==\n[[Category:English nouns]]\n");
+        assert_eq!((&*entries[0].pos, entries[0].pos_source), ("unknown", PosSource::Unknown));
+        assert!(serde_json::to_string(&entries[0]).unwrap().contains(r#""pos":"unknown","pos_source":"unknown""#));
+    }
+
+    #[test]
+    fn usage_notes_as_plain_text() {
+        let text = "===Adjective===\n# Not as many.\n\n====Usage notes====\n\
+                    * Traditionally '''fewer''' is used with [[count noun]]s, {{m|en|less}} with others.\n\
+                    {| class=\"wikitable\"\n|-\n| fewer || less\n|}\n\n\
+                    ====Synonyms====\n* {{l|en|less}}\n\n===Noun===\n# A few.\n\n\
+                    ====Usage notes====\n{{U:en:less-fewer}}\n: Rare.\n";
+        let ast = PageAst::parse(text);
+        assert_eq!(
+            extract_usage_notes(&ast).as_deref(),
+            Some("Traditionally fewer is used with count nouns, less with others.\nRare.")
+        );
+        assert_eq!(extract_usage_notes(&PageAst::parse("===Noun===\n# A few.\n")), None);
+    }
+
+    #[test]
+    fn syllable_validation_counts_ipa_variants() {
+        init_test_schemas();
+        let text = "==English==\n===Pronunciation===\n* {{IPA|en|/ˈdɪk.ʃə.nə.ɹi/|a=UK}}\n\
+                    * {{IPA|en|/ˈdɪk.ʃən.ɹi/|[ˈdɪk.ʃn̩.ɹi]|a=UK}}\n\n===Noun===\n# A word book.\n";
+        let validation = extract_syllable_validation("dictionary", text).unwrap();
+        assert_eq!((validation.ipa, validation.ipa_transcriptions, validation.ipa_counts_differ), (Some(4), 3, true));
+
+        let text = "==English==\n===Pronunciation===\n* {{IPA|en|/kæt/|[kʰæt]}}\n\n===Noun===\n# A feline.\n";
+        let validation = extract_syllable_validation("cat", text).unwrap();
+        assert_eq!((validation.ipa_transcriptions, validation.ipa_counts_differ), (2, false));
+    }
+
+    #[test]
+    fn heteronyms_from_pronunciation_sections() {
+        init_test_schemas();
+        let lead = "==English==\n===Etymology 1===\n====Pronunciation====\n* {{IPA|en|/lɛd/}}\n\n\
+                    ====Noun====\n# A heavy metal.\n\n===Etymology 2===\n====Pronunciation====\n\
+                    * {{IPA|en|/liːd/|a=UK}}\n\n====Verb====\n# To guide.\n";
+        let ipa: Vec<_> = parse_page("lead", lead).into_iter().map(|e| (e.is_heteronym, e.ipa)).collect();
+        assert_eq!(ipa, vec![(true, vec!["/lɛd/".to_string()]), (true, vec!["/liːd/".to_string()])]);
+
+        // A shared Pronunciation section, with lines qualified by part of speech
+        let record = "==English==\n===Pronunciation===\n* {{q|noun}} {{IPA|en|/ˈɹɛk.ɔːd/}}\n\
+                      * {{IPA|en|/ɹɪˈkɔːd/|q=verb}}\n\n===Etymology 1===\n====Noun====\n# A document.\n\n\
+                      ===Etymology 2===\n====Verb====\n# To write down.\n";
+        let ipa: Vec<_> = parse_page("record", record).into_iter().map(|e| e.ipa).collect();
+        assert_eq!(ipa, vec![vec!["/ˈɹɛk.ɔːd/".to_string()], vec!["/ɹɪˈkɔːd/".to_string()]]);
+
+        let beat = "==English==\n===Etymology 1===\n====Pronunciation====\n* {{IPA|en|/biːt/}}\n\n\
+                    ====Verb====\n# To hit.\n\n===Etymology 2===\n====Pronunciation====\n\
+                    * {{IPA|en|/biːt/|/bɛt/}}\n\n====Adjective====\n# Exhausted.\n";
+        assert!(parse_page("beat", beat).iter().all(|e| !e.is_heteronym && e.ipa.is_empty()));
+    }
+
+    #[test]
+    fn proper_noun_flag_is_per_sense() {
+        init_test_schemas();
+        let text = "==English==\n===Noun===\n{{en-noun}}\n# A sum of money owed.\n\n\
+                    ===Proper noun===\n{{en-prop}}\n# A male given name.\n\n\
+                    ===Noun===\n{{en-proper noun|head=Bill}}\n# A legislative proposal, the Bill.\n";
+        let flags: Vec<_> = parse_page("bill", text).into_iter().map(|e| (e.pos.into_owned(), e.is_proper_noun)).collect();
+        assert_eq!(
+            flags,
+            vec![("NOU".to_string(), false), ("NAM".to_string(), true), ("NOU".to_string(), true)]
+        );
+        assert_eq!(headword_line("\n{{wikipedia}}\n{{en-noun|~}}\n# A sense.\n{{en-verb}}"), Some("{{en-noun|~}}"));
+    }
+
+    #[test]
+    fn countability_from_headwords_and_categories() {
+        init_test_schemas();
+        let text = "==English==\n===Noun===\n{{en-noun|-}}\n# Nonsense.\n\n\
+                    ===Noun===\n{{en-noun|~}}\n# A tree.\n\n\
+                    ===Noun===\n{{en-plural noun}}\n# Trousers.\n";
+        let flags: Vec<_> = parse_page("bosh", text).into_iter().map(|e| (e.uncountable, e.plural_only)).collect();
+        assert_eq!(flags, vec![(true, false), (false, false), (false, true)]);
+
+        let text = "==English==\n===Noun===\n{{en-noun}}\n# Scissors.\n\n===Verb===\n# To cut.\n\n\
+                    [[Category:English pluralia tantum]]\n";
+        let flags: Vec<_> = parse_page("shears", text).into_iter().map(|e| e.plural_only).collect();
+        assert_eq!(flags, vec![true, false]);
+    }
+
+    #[test]
+    fn output_is_renamed_into_place_on_commit() {
+        let dir = std::env::temp_dir();
+        let path = dir.join(format!("wikt-output-{}.jsonl", std::process::id()));
+        let tmp_path = dir.join(format!("wikt-output-{}.jsonl.tmp", std::process::id()));
+
+        // Dropped without commit: nothing is left behind
+        let (mut out, file) = create_output(&path, false).unwrap();
+        out.write_all(b"partial").unwrap();
+        drop((out, file));
+        assert!(!path.exists() && !tmp_path.exists());
+
+        let (mut out, file) = create_output(&path, false).unwrap();
+        out.write_all(b"done\n").unwrap();
+        assert!(!path.exists());
+        drop(out);
+        file.commit().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "done\n");
+
+        let err = create_output(&path, false).err().unwrap();
+        assert_eq!(err.kind(), std::io::ErrorKind::AlreadyExists);
+        let (_, file) = create_output(&path, true).unwrap();
+        file.commit().unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "");
+        std::fs::remove_file(&path).ok();
+    }
+}
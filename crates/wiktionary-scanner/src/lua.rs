@@ -0,0 +1,604 @@
+//! Headword lines expanded by the dump's own Lua modules, with `--lua`.
+//!
+//! Without it, forms and labels come from the parameters of headword
+//! templates, which covers what the templates spell out but not what their
+//! modules work out (`{{en-noun}}` alone means "plural in -s", `{{en-verb}}`
+//! guesses the past tense from the spelling). With `--lua`, a first pass over
+//! the input keeps its `Module:` and `Template:` pages, and each POS section's
+//! headword line is expanded the way Wiktionary renders it: template
+//! parameters are substituted, `{{#invoke:}}` runs the module in Lua 5.1 (the
+//! version Scribunto runs), and the links in the result, other than the
+//! headword's own, are the section's `forms`:
+//!
+//! ```json
+//! {"id":"mouse","pos":"NOU",...,"forms":["mice"],...}
+//! ```
+//!
+//! Only the parts of Scribunto's `mw` library that headword modules use are
+//! provided (src/scribunto.lua), and each expansion has an instruction
+//! budget. A headword that needs anything more, or that fails, gets no
+//! `forms`; the rest of its entry is extracted as without `--lua`. The
+//! feature is off by default, as it builds Lua from source and slows the
+//! scan down: build with `--features lua`.
+
+use std::io;
+use std::path::PathBuf;
+
+#[cfg(feature = "lua")]
+pub use engine::{enabled, headword_forms, init_lua, lua_stats};
+
+#[cfg(not(feature = "lua"))]
+pub fn enabled() -> bool {
+    false
+}
+
+#[cfg(not(feature = "lua"))]
+pub fn init_lua(_inputs: &[PathBuf]) -> io::Result<usize> {
+    Err(io::Error::other("--lua needs a scanner built with --features lua"))
+}
+
+#[cfg(not(feature = "lua"))]
+pub fn headword_forms(_title: &str, _line: &str) -> Vec<String> {
+    Vec::new()
+}
+
+#[cfg(not(feature = "lua"))]
+pub fn lua_stats() -> (usize, usize) {
+    (0, 0)
+}
+
+#[cfg(feature = "lua")]
+mod engine {
+    use super::*;
+    use crate::page_ast::PageAst;
+    use crate::{open_inputs, scan_pages, unescape_xml, TEXT_PATTERN, TITLE_PATTERN};
+
+    use lazy_static::lazy_static;
+    use mlua::{Function, HookTriggers, Lua, LuaOptions, StdLib, Table, Value, Variadic};
+    use once_cell::sync::OnceCell;
+    use regex::Regex;
+    use std::cell::Cell;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use unicode_normalization::UnicodeNormalization;
+
+    /// Lua instructions between budget checks
+    const BUDGET_STEP: u32 = 1000;
+    /// Budget checks per headword: 20 million instructions
+    const BUDGET: u32 = 20_000;
+    /// Templates transcluded inside one another before giving up on a loop
+    const MAX_DEPTH: usize = 40;
+
+    const PRELUDE: &str = include_str!("scribunto.lua");
+
+    /// Source of every Module: and Template: page, by title
+    static PAGES: OnceCell<HashMap<String, String>> = OnceCell::new();
+    static EXPANDED: AtomicUsize = AtomicUsize::new(0);
+    static FAILED: AtomicUsize = AtomicUsize::new(0);
+
+    thread_local! {
+        // Each worker thread runs its own Lua, loading modules as they are required
+        static LUA: RefCell<Option<Lua>> = const { RefCell::new(None) };
+        static BUDGET_LEFT: Cell<u32> = const { Cell::new(0) };
+        static DEPTH: Cell<usize> = const { Cell::new(0) };
+    }
+
+    lazy_static! {
+        static ref COMMENT: Regex = Regex::new(r"(?s)<!--.*?(?:-->|$)").unwrap();
+        static ref NOINCLUDE: Regex = Regex::new(r"(?s)<noinclude>.*?(?:</noinclude>|$)").unwrap();
+        static ref ONLYINCLUDE: Regex = Regex::new(r"(?s)<onlyinclude>(.*?)</onlyinclude>").unwrap();
+        static ref INCLUDEONLY_TAG: Regex = Regex::new(r"</?includeonly>").unwrap();
+        static ref REDIRECT: Regex = Regex::new(r"(?i)^\s*#redirect\s*\[\[([^\]|#]+)").unwrap();
+        // The headword itself, which links to its parts in a multiword term
+        static ref HEADWORD_SPAN: Regex = Regex::new(r#"(?s)<strong[^>]*\bheadword\b[^>]*>.*?</strong>"#).unwrap();
+    }
+
+    /// Keep the Module: and Template: pages of `inputs` for `headword_forms`.
+    /// Returns the number of pages kept.
+    pub fn init_lua(inputs: &[PathBuf]) -> io::Result<usize> {
+        let mut pages = HashMap::new();
+        scan_pages(open_inputs(inputs)?, |page_xml| {
+            let Some(title) = TITLE_PATTERN.captures(&page_xml).map(|cap| unescape_xml(&cap[1])) else {
+                return true;
+            };
+            let wanted = title.starts_with("Module:") || title.starts_with("Template:");
+            if !wanted || title.ends_with("/documentation") || title.ends_with("/doc") {
+                return true;
+            }
+            if let Some(cap) = TEXT_PATTERN.captures(&page_xml) {
+                pages.insert(title, unescape_xml(&cap[1]));
+            }
+            true
+        })?;
+        let count = pages.len();
+        PAGES.set(pages).map_err(|_| io::Error::other("Lua pages already loaded"))?;
+        Ok(count)
+    }
+
+    pub fn enabled() -> bool {
+        PAGES.get().is_some()
+    }
+
+    /// Headwords expanded, and headwords whose expansion failed
+    pub fn lua_stats() -> (usize, usize) {
+        (EXPANDED.load(Ordering::Relaxed), FAILED.load(Ordering::Relaxed))
+    }
+
+    /// Forms linked from the rendered `headword_line` of the page `title`;
+    /// empty when it could not be rendered
+    pub fn headword_forms(title: &str, headword_line: &str) -> Vec<String> {
+        if PAGES.get().is_none() {
+            return Vec::new();
+        }
+        let rendered = LUA.with(|state| -> mlua::Result<String> {
+            let mut state = state.borrow_mut();
+            let lua = match state.take() {
+                Some(lua) => lua,
+                None => new_state()?,
+            };
+            BUDGET_LEFT.with(|budget| budget.set(BUDGET));
+            DEPTH.with(|depth| depth.set(0));
+            let result = lua
+                .globals()
+                .set("__title", title)
+                .and_then(|_| expand(&lua, headword_line, &Frame { args: &HashMap::new(), title }));
+            *state = Some(lua);
+            result
+        });
+        match rendered {
+            Ok(rendered) => {
+                EXPANDED.fetch_add(1, Ordering::Relaxed);
+                linked_forms(&rendered, title)
+            }
+            Err(_) => {
+                FAILED.fetch_add(1, Ordering::Relaxed);
+                Vec::new()
+            }
+        }
+    }
+
+    /// Link targets in rendered wikitext, leaving out the headword's own
+    /// links, namespaced pages (categories, the glossary) and `title`
+    fn linked_forms(rendered: &str, title: &str) -> Vec<String> {
+        let text = HEADWORD_SPAN.replace_all(rendered, "");
+        let ast = PageAst::parse(&text);
+        let mut forms: Vec<String> = Vec::new();
+        for target in ast.link_targets() {
+            let target = target.split('#').next().unwrap_or_default().trim();
+            if target.is_empty() || target.contains(':') || target == title || forms.iter().any(|f| f == target) {
+                continue;
+            }
+            forms.push(target.to_string());
+        }
+        forms
+    }
+
+    fn pages() -> &'static HashMap<String, String> {
+        static EMPTY: OnceCell<HashMap<String, String>> = OnceCell::new();
+        PAGES.get().unwrap_or_else(|| EMPTY.get_or_init(HashMap::new))
+    }
+
+    fn new_state() -> mlua::Result<Lua> {
+        let libs = StdLib::STRING | StdLib::TABLE | StdLib::MATH | StdLib::OS;
+        let lua = Lua::new_with(libs, LuaOptions::new())?;
+        lua.set_hook(HookTriggers::new().every_nth_instruction(BUDGET_STEP), |_, _| {
+            BUDGET_LEFT.with(|budget| match budget.get() {
+                0 => Err(mlua::Error::runtime("Lua instruction budget exceeded")),
+                left => {
+                    budget.set(left - 1);
+                    Ok(())
+                }
+            })
+        });
+
+        let globals = lua.globals();
+        globals.set(
+            "__module_source",
+            lua.create_function(|_, title: String| Ok(pages().get(&title).cloned()))?,
+        )?;
+        globals.set("__page_exists", lua.create_function(|_, title: String| Ok(pages().contains_key(&title)))?)?;
+        globals.set(
+            "__expand_template",
+            lua.create_function(|lua, (title, args): (String, Table)| {
+                let args = table_args(args)?;
+                let name = title.strip_prefix("Template:").unwrap_or(&title);
+                expand_template(lua, name, &args)
+            })?,
+        )?;
+        globals.set(
+            "__preprocess",
+            lua.create_function(|lua, text: String| {
+                let title: String = lua.globals().get("__title")?;
+                expand(lua, &text, &Frame { args: &HashMap::new(), title: &title })
+            })?,
+        )?;
+        globals.set("__ustring", ustring(&lua)?)?;
+        lua.load(PRELUDE).set_name("=scribunto").exec()?;
+        drop(globals);
+        Ok(lua)
+    }
+
+    /// The functions of mw.ustring that count in characters rather than bytes;
+    /// pattern functions fall back to the byte-wise string library
+    fn ustring(lua: &Lua) -> mlua::Result<Table<'_>> {
+        let ustring = lua.create_table()?;
+        ustring.set("len", lua.create_function(|_, s: String| Ok(s.chars().count()))?)?;
+        ustring.set(
+            "sub",
+            lua.create_function(|_, (s, i, j): (String, Option<i64>, Option<i64>)| {
+                let chars: Vec<char> = s.chars().collect();
+                Ok(match char_range(chars.len(), i.unwrap_or(1), j.unwrap_or(-1)) {
+                    Some(range) => chars[range].iter().collect(),
+                    None => String::new(),
+                })
+            })?,
+        )?;
+        ustring.set(
+            "codepoint",
+            lua.create_function(|_, (s, i, j): (String, Option<i64>, Option<i64>)| {
+                let chars: Vec<char> = s.chars().collect();
+                let i = i.unwrap_or(1);
+                let codes = char_range(chars.len(), i, j.unwrap_or(i)).map_or_else(Vec::new, |range| {
+                    chars[range].iter().map(|&ch| ch as u32).collect()
+                });
+                Ok(Variadic::from_iter(codes))
+            })?,
+        )?;
+        ustring.set(
+            "char",
+            lua.create_function(|_, codes: Variadic<u32>| Ok(codes.iter().filter_map(|&c| char::from_u32(c)).collect::<String>()))?,
+        )?;
+        ustring.set("lower", lua.create_function(|_, s: String| Ok(s.to_lowercase()))?)?;
+        ustring.set("upper", lua.create_function(|_, s: String| Ok(s.to_uppercase()))?)?;
+        ustring.set("toNFC", lua.create_function(|_, s: String| Ok(s.nfc().collect::<String>()))?)?;
+        ustring.set("toNFD", lua.create_function(|_, s: String| Ok(s.nfd().collect::<String>()))?)?;
+        ustring.set("toNFKC", lua.create_function(|_, s: String| Ok(s.nfkc().collect::<String>()))?)?;
+        ustring.set("toNFKD", lua.create_function(|_, s: String| Ok(s.nfkd().collect::<String>()))?)?;
+        ustring.set("isutf8", lua.create_function(|_, s: mlua::String| Ok(s.to_str().is_ok()))?)?;
+        Ok(ustring)
+    }
+
+    /// Zero-based range of the characters from `i` to `j`, counted from one
+    /// (negative counts from the end), as string.sub takes them
+    fn char_range(len: usize, i: i64, j: i64) -> Option<std::ops::Range<usize>> {
+        let len = len as i64;
+        let i = if i < 0 { (len + i + 1).max(1) } else { i.max(1) };
+        let j = if j < 0 { len + j + 1 } else { j.min(len) };
+        (i <= j).then(|| (i - 1) as usize..j as usize)
+    }
+
+    /// Arguments of a Lua args table, keyed as in wikitext ("1", "head", ...)
+    fn table_args(table: Table) -> mlua::Result<HashMap<String, String>> {
+        let mut args = HashMap::new();
+        for pair in table.pairs::<Value, Value>() {
+            let (key, value) = pair?;
+            if let (Some(key), Some(value)) = (value_text(&key), value_text(&value)) {
+                args.insert(key, value);
+            }
+        }
+        Ok(args)
+    }
+
+    fn value_text(value: &Value) -> Option<String> {
+        match value {
+            Value::String(s) => s.to_str().ok().map(str::to_string),
+            Value::Integer(n) => Some(n.to_string()),
+            Value::Number(n) if n.fract() == 0.0 => Some((*n as i64).to_string()),
+            Value::Number(n) => Some(n.to_string()),
+            _ => None,
+        }
+    }
+
+    fn args_table<'lua>(lua: &'lua Lua, args: &HashMap<String, String>) -> mlua::Result<Table<'lua>> {
+        let table = lua.create_table()?;
+        for (key, value) in args {
+            match key.parse::<i64>() {
+                Ok(index) => table.set(index, value.as_str())?,
+                Err(_) => table.set(key.as_str(), value.as_str())?,
+            }
+        }
+        Ok(table)
+    }
+
+    /// The template (or page) being expanded and the arguments it was called with
+    struct Frame<'a> {
+        args: &'a HashMap<String, String>,
+        title: &'a str,
+    }
+
+    /// Expand the parameters, templates and parser functions in `text`
+    fn expand(lua: &Lua, text: &str, frame: &Frame) -> mlua::Result<String> {
+        let mut out = String::new();
+        let mut pos = 0;
+        while let Some(ch) = text[pos..].chars().next() {
+            let rest = &text[pos..];
+            let (open, close) = if rest.starts_with("{{{") {
+                ("{{{", "}}}")
+            } else if rest.starts_with("{{") {
+                ("{{", "}}")
+            } else {
+                out.push(ch);
+                pos += ch.len_utf8();
+                continue;
+            };
+            let start = pos;
+            pos += open.len();
+            match raw_parts(text, &mut pos, close) {
+                Some(parts) if open == "{{{" => out.push_str(&parameter(lua, &parts, frame)?),
+                Some(parts) => out.push_str(&call(lua, &parts, frame)?),
+                None => out.push_str(&text[start..]),
+            }
+        }
+        Ok(out)
+    }
+
+    /// The unexpanded `|`-separated parts of a `{{...}}` or `{{{...}}}` whose
+    /// opening brackets end at `pos`, moving `pos` past its `close`; None if
+    /// it is never closed
+    fn raw_parts<'t>(text: &'t str, pos: &mut usize, close: &str) -> Option<Vec<&'t str>> {
+        let mut parts = Vec::new();
+        let mut start = *pos;
+        while let Some(ch) = text[*pos..].chars().next() {
+            let rest = &text[*pos..];
+            if rest.starts_with(close) {
+                parts.push(&text[start..*pos]);
+                *pos += close.len();
+                return Some(parts);
+            }
+            if ch == '|' {
+                parts.push(&text[start..*pos]);
+                *pos += 1;
+                start = *pos;
+            } else if rest.starts_with("{{{") {
+                *pos += 3;
+                raw_parts(text, pos, "}}}")?;
+            } else if rest.starts_with("{{") {
+                *pos += 2;
+                raw_parts(text, pos, "}}")?;
+            } else if rest.starts_with("[[") {
+                // A link's "|" separates its target and text, not parameters
+                *pos += 2;
+                raw_parts(text, pos, "]]")?;
+            } else {
+                *pos += ch.len_utf8();
+            }
+        }
+        None
+    }
+
+    /// `{{{name|default}}}`
+    fn parameter(lua: &Lua, parts: &[&str], frame: &Frame) -> mlua::Result<String> {
+        let name = expand(lua, parts[0], frame)?;
+        if let Some(value) = frame.args.get(name.trim()) {
+            return Ok(value.clone());
+        }
+        match parts.get(1) {
+            Some(default) => expand(lua, default, frame),
+            None => Ok(format!("{{{{{{{}}}}}}}", name)),
+        }
+    }
+
+    /// Arguments of a template call: `key=value` parts by key, trimmed, and
+    /// the others by position
+    fn call_args(lua: &Lua, parts: &[&str], frame: &Frame) -> mlua::Result<HashMap<String, String>> {
+        let mut args = HashMap::new();
+        let mut position = 0;
+        for part in parts {
+            match top_level_eq(part) {
+                Some(i) => {
+                    let key = expand(lua, &part[..i], frame)?;
+                    args.insert(key.trim().to_string(), expand(lua, &part[i + 1..], frame)?.trim().to_string());
+                }
+                None => {
+                    position += 1;
+                    args.insert(position.to_string(), expand(lua, part, frame)?);
+                }
+            }
+        }
+        Ok(args)
+    }
+
+    /// Offset of the first "=" outside nested templates and links
+    fn top_level_eq(part: &str) -> Option<usize> {
+        let bytes = part.as_bytes();
+        let mut depth = 0i32;
+        let mut i = 0;
+        while i < bytes.len() {
+            match (bytes[i], bytes.get(i + 1)) {
+                (b'{', Some(b'{')) | (b'[', Some(b'[')) => {
+                    depth += 1;
+                    i += 2;
+                    continue;
+                }
+                (b'}', Some(b'}')) | (b']', Some(b']')) => {
+                    depth -= 1;
+                    i += 2;
+                    continue;
+                }
+                (b'=', _) if depth == 0 => return Some(i),
+                _ => {}
+            }
+            i += 1;
+        }
+        None
+    }
+
+    /// `{{name|...}}`: a parser function, magic word or template
+    fn call(lua: &Lua, parts: &[&str], frame: &Frame) -> mlua::Result<String> {
+        let head = expand(lua, parts[0], frame)?;
+        let head = head.trim();
+        let part = |i: usize| -> mlua::Result<String> {
+            parts.get(i).map_or(Ok(String::new()), |part| expand(lua, part, frame).map(|s| s.trim().to_string()))
+        };
+
+        if let Some((function, first)) = head.split_once(':') {
+            let first = first.trim();
+            match function {
+                "#invoke" => {
+                    let args = call_args(lua, parts.get(2..).unwrap_or_default(), frame)?;
+                    let invoke: Function = lua.globals().get("__invoke")?;
+                    return invoke.call((
+                        first,
+                        part(1)?,
+                        args_table(lua, &args)?,
+                        args_table(lua, frame.args)?,
+                        frame.title,
+                    ));
+                }
+                "#if" => return part(if first.is_empty() { 2 } else { 1 }),
+                "#ifeq" => return part(if first == part(1)? { 2 } else { 3 }),
+                "#switch" => return switch(lua, first, &parts[1..], frame),
+                "lc" => return Ok(first.to_lowercase()),
+                "uc" => return Ok(first.to_uppercase()),
+                "lcfirst" | "ucfirst" => {
+                    let mut chars = first.chars();
+                    let initial: String = chars.next().into_iter().collect();
+                    let initial = if function == "lcfirst" { initial.to_lowercase() } else { initial.to_uppercase() };
+                    return Ok(initial + chars.as_str());
+                }
+                // Parser functions not needed by headword templates
+                _ if function.starts_with('#') => return Ok(String::new()),
+                _ => {}
+            }
+        }
+        match head {
+            "PAGENAME" | "FULLPAGENAME" | "PAGENAMEE" => {
+                let title: String = lua.globals().get("__title")?;
+                Ok(title)
+            }
+            "!" => Ok("|".to_string()),
+            "=" => Ok("=".to_string()),
+            _ => {
+                let args = call_args(lua, &parts[1..], frame)?;
+                expand_template(lua, head, &args)
+            }
+        }
+    }
+
+    /// `{{#switch:value|case=result|...|default}}`; cases without a result
+    /// fall through to the next one's
+    fn switch(lua: &Lua, value: &str, parts: &[&str], frame: &Frame) -> mlua::Result<String> {
+        let mut matched = false;
+        let mut default = None;
+        for (i, part) in parts.iter().enumerate() {
+            match top_level_eq(part) {
+                Some(eq) => {
+                    let case = expand(lua, &part[..eq], frame)?;
+                    let case = case.trim();
+                    if matched || case == value {
+                        return Ok(expand(lua, &part[eq + 1..], frame)?.trim().to_string());
+                    }
+                    if case == "#default" {
+                        default = Some(&part[eq + 1..]);
+                    }
+                }
+                None if i + 1 == parts.len() => default = Some(part),
+                None => matched |= expand(lua, part, frame)?.trim() == value,
+            }
+        }
+        default.map_or(Ok(String::new()), |part| Ok(expand(lua, part, frame)?.trim().to_string()))
+    }
+
+    /// A transclusion of Template:`name`; missing templates expand to nothing
+    fn expand_template(lua: &Lua, name: &str, args: &HashMap<String, String>) -> mlua::Result<String> {
+        let mut title = format!("Template:{}", name.trim());
+        let mut source = pages().get(&title);
+        if let Some(target) = source.and_then(|text| REDIRECT.captures(text)) {
+            title = target[1].trim().to_string();
+            source = pages().get(&title);
+        }
+        let Some(source) = source else {
+            return Ok(String::new());
+        };
+
+        let depth = DEPTH.with(|depth| depth.replace(depth.get() + 1));
+        if depth >= MAX_DEPTH {
+            return Err(mlua::Error::runtime(format!("template loop at {}", title)));
+        }
+        let result = expand(lua, &transcluded(source), &Frame { args, title: &title });
+        DEPTH.with(|d| d.set(depth));
+        result
+    }
+
+    /// The part of a template page that is transcluded
+    fn transcluded(source: &str) -> String {
+        let source = COMMENT.replace_all(source, "");
+        if ONLYINCLUDE.is_match(&source) {
+            return ONLYINCLUDE.captures_iter(&source).map(|cap| cap[1].to_string()).collect();
+        }
+        let source = NOINCLUDE.replace_all(&source, "");
+        INCLUDEONLY_TAG.replace_all(&source, "").into_owned()
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        const MODULE: &str = r##"
+local export = {}
+
+local function plural(word)
+    if word:find("[^aeiou]y$") then return (word:gsub("y$", "ies")) end
+    if word:find("[sxz]$") or word:find("[cs]h$") then return word .. "es" end
+    return word .. "s"
+end
+
+function export.show(frame)
+    local args = frame:getParent().args
+    local head = mw.title.getCurrentTitle().text
+    local pl = args[1] or plural(head)
+    local out = '<strong class="Latn headword" lang="en">' .. head .. '</strong>'
+    if pl == "-" then
+        return out .. " (''uncountable'')[[Category:English uncountable nouns]]"
+    end
+    return out .. " (''" .. frame.args[1] .. "'' <b>[[" .. pl .. "#English|" .. pl .. "]]</b>)"
+        .. "[[Category:English nouns]]"
+end
+
+function export.loop(frame)
+    while true do end
+end
+
+return export
+"##;
+
+        fn init_pages() {
+            let pages = [
+                ("Module:test-headword", MODULE),
+                ("Template:test-noun", "{{#invoke:test-headword|show|plural}}<noinclude>\n[[Category:Templates]]</noinclude>"),
+                ("Template:test-loop", "{{#invoke:test-headword|loop}}"),
+                ("Template:test-alias", "#REDIRECT [[Template:test-noun]]"),
+                ("Template:test-default", "<includeonly>{{{1|{{PAGENAME}}}}}</includeonly> docs"),
+            ];
+            let _ = PAGES.set(pages.iter().map(|(title, text)| (title.to_string(), text.to_string())).collect());
+        }
+
+        #[test]
+        fn headword_modules_give_forms() {
+            init_pages();
+            assert_eq!(headword_forms("berry", "{{test-noun}}"), ["berries"]);
+            assert_eq!(headword_forms("mouse", "{{test-noun|mice}}"), ["mice"]);
+            assert_eq!(headword_forms("box", "{{test-alias}}"), ["boxes"]);
+            assert_eq!(headword_forms("furniture", "{{test-noun|-}}"), Vec::<String>::new());
+
+            // A runaway module is stopped by the budget, and the thread's Lua still works
+            let (_, failed) = lua_stats();
+            assert_eq!(headword_forms("loop", "{{test-loop}}"), Vec::<String>::new());
+            assert!(lua_stats().1 > failed);
+            assert_eq!(headword_forms("cat", "{{test-noun}}"), ["cats"]);
+        }
+
+        #[test]
+        fn parameters_and_includeonly() {
+            init_pages();
+            let lua = new_state().unwrap();
+            lua.globals().set("__title", "cat").unwrap();
+            let frame = Frame { args: &HashMap::new(), title: "cat" };
+            assert_eq!(expand(&lua, "{{test-default}}", &frame).unwrap(), "cat docs");
+            assert_eq!(expand(&lua, "{{test-default|[[dog|a dog]]}}", &frame).unwrap(), "[[dog|a dog]] docs");
+            assert_eq!(expand(&lua, "{{#if:x|yes|no}} {{#switch:b|a|b=ab|c}}", &frame).unwrap(), "yes ab");
+            assert_eq!(expand(&lua, "{{unclosed|", &frame).unwrap(), "{{unclosed|");
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! `manifest.json`, written beside the output of every scan.
+//!
+//! The manifest says how to read the data files without looking inside them:
+//! the output shape and format, the compression, the version of the entry
+//! schema, the compact key table when `--compact-keys` was given, and each
+//! data shard and sidecar with its record and byte counts. The scan's stats
+//! come last.
+//!
+//! ```json
+//! {
+//!   "schema_version": "1.0",
+//!   "generator": "wiktionary-scanner-rust 0.1.0",
+//!   "shape": "flat",
+//!   "format": "jsonl",
+//!   "compression": "none",
+//!   "shards": [{"path": "wikt.jsonl", "records": 1672345, "bytes": 498011234}],
+//!   "stats": {"pages_processed": 9875123, ...}
+//! }
+//! ```
+
+use crate::compact;
+use crate::shape::{self, OutputShape};
+use crate::Stats;
+
+use serde::{Serialize, Serializer};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Version of the entry fields, raised when a field is removed or changes meaning
+pub const SCHEMA_VERSION: &str = "1.0";
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    schema_version: &'static str,
+    generator: String,
+    shape: OutputShape,
+    /// `jsonl`, or `json-array` with --json-array
+    format: &'static str,
+    compression: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keys: Option<KeyTable>,
+    shards: Vec<DataFile>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    sidecars: Vec<DataFile>,
+    stats: &'a Stats,
+}
+
+#[derive(Serialize)]
+struct DataFile {
+    /// Relative to the manifest when the file is beside it
+    path: String,
+    records: usize,
+    bytes: u64,
+}
+
+/// The compact keys, as an object from field name to key in table order
+struct KeyTable;
+
+impl Serialize for KeyTable {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(compact::KEYS.iter().copied())
+    }
+}
+
+fn data_file(dir: &Path, path: &Path, records: usize) -> io::Result<DataFile> {
+    Ok(DataFile {
+        path: path.strip_prefix(dir).unwrap_or(path).display().to_string(),
+        records,
+        bytes: std::fs::metadata(path)?.len(),
+    })
+}
+
+/// Records in a single output file: its lines, or array elements
+pub fn output_records(stats: &Stats) -> usize {
+    match shape::output_shape() {
+        OutputShape::Flat => stats.senses_written,
+        OutputShape::Nested => stats.words_written,
+    }
+}
+
+/// Write `manifest.json` into `dir`, replacing the one from an earlier scan
+/// into the same directory. Returns its path.
+pub fn write_manifest(
+    dir: &Path,
+    shards: &[(PathBuf, usize)],
+    sidecars: &[(PathBuf, usize)],
+    stats: &Stats,
+) -> io::Result<PathBuf> {
+    let manifest = Manifest {
+        schema_version: SCHEMA_VERSION,
+        generator: format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+        shape: shape::output_shape(),
+        format: if shape::json_array() { "json-array" } else { "jsonl" },
+        compression: "none",
+        keys: compact::enabled().then_some(KeyTable),
+        shards: shards.iter().map(|(path, records)| data_file(dir, path, *records)).collect::<io::Result<_>>()?,
+        sidecars: sidecars.iter().map(|(path, records)| data_file(dir, path, *records)).collect::<io::Result<_>>()?,
+        stats,
+    };
+
+    let mut json = serde_json::to_vec_pretty(&manifest)?;
+    json.push(b'\n');
+    // Concurrent scans into one directory each rename a whole file into place
+    let path = dir.join("manifest.json");
+    let tmp_path = dir.join(format!("manifest.json.{}.tmp", std::process::id()));
+    std::fs::write(&tmp_path, json)?;
+    std::fs::rename(&tmp_path, &path)?;
+    Ok(path)
+}
@@ -0,0 +1,137 @@
+//! Case-only doublets in scanner output, with `merge-case`.
+//!
+//! Wiktionary gives "internet" and "Internet", or "god" and "God", pages of
+//! their own, so a lexicon has both spellings with their own senses. This
+//! pass groups the words that differ only in case and adds `case_variant_of`
+//! after the `id` of every spelling but one, naming that one:
+//!
+//! ```json
+//! {"id":"Internet","case_variant_of":"internet","pos":"NAM",...}
+//! ```
+//!
+//! The spelling linked to is the lowercase one, or when there is none, the
+//! one with the fewest capitals ("Aids" for "AIDS"). Consumers can drop the
+//! linked spellings to deduplicate, or fold their senses into the other
+//! spelling; no sense is removed here.
+
+use crate::{create_output, is_stdio, open_input};
+
+use clap::Args as ClapArgs;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashMap};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+// The id is the first field in both output shapes
+const ID_KEY: &str = "{\"id\":";
+
+#[derive(ClapArgs, Debug)]
+pub struct MergeCaseArgs {
+    /// Scanner output JSONL (read twice, so not stdin)
+    pub input: PathBuf,
+
+    /// Where to write the output with case_variant_of (- for stdout)
+    pub output: PathBuf,
+}
+
+/// Only the field the pass needs
+#[derive(Deserialize)]
+struct IdRecord {
+    id: String,
+}
+
+/// Every spelling that has a case-only doublet, with the spelling it is a
+/// variant of
+pub fn case_variants<R: BufRead>(reader: R) -> io::Result<HashMap<String, String>> {
+    let mut spellings: HashMap<String, BTreeSet<String>> = HashMap::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: IdRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("Malformed line in input: {}", e)))?;
+        spellings.entry(record.id.to_lowercase()).or_default().insert(record.id);
+    }
+
+    let mut variants = HashMap::new();
+    for group in spellings.into_values().filter(|group| group.len() > 1) {
+        // BTreeSet order breaks ties between spellings with as many capitals
+        let capitals = |spelling: &String| spelling.chars().filter(|ch| ch.is_uppercase()).count();
+        let Some(canonical) = group.iter().min_by_key(|spelling| capitals(spelling)).cloned() else {
+            continue;
+        };
+        variants.extend(group.into_iter().filter(|spelling| *spelling != canonical).map(|s| (s, canonical.clone())));
+    }
+    Ok(variants)
+}
+
+/// `line` with `case_variant_of` after its id, if the id is a variant
+fn link_line(line: &str, variants: &HashMap<String, String>) -> io::Result<Option<String>> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "Malformed id in input");
+    let Some(rest) = line.strip_prefix(ID_KEY) else {
+        return Ok(None);
+    };
+    let mut values = serde_json::Deserializer::from_str(rest).into_iter::<String>();
+    let id = values.next().ok_or_else(malformed)?.map_err(|_| malformed())?;
+    let Some(canonical) = variants.get(&id) else {
+        return Ok(None);
+    };
+    let id_end = ID_KEY.len() + values.byte_offset();
+    let canonical = serde_json::to_string(canonical)?;
+    Ok(Some(format!("{},\"case_variant_of\":{}{}", &line[..id_end], canonical, &line[id_end..])))
+}
+
+/// Copy `input` to `output` with case-only doublets linked. Returns the number
+/// of lines and of distinct spellings given a `case_variant_of`.
+pub fn run_merge_case(args: &MergeCaseArgs, force: bool) -> io::Result<(usize, usize)> {
+    if is_stdio(&args.input) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, "merge-case reads its input twice and needs a file"));
+    }
+    let variants = case_variants(open_input(&args.input)?)?;
+
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut linked = 0;
+    for line in open_input(&args.input)?.lines() {
+        let line = line?;
+        match link_line(&line, &variants)? {
+            Some(line) => {
+                writeln!(writer, "{}", line)?;
+                linked += 1;
+            }
+            None => writeln!(writer, "{}", line)?,
+        }
+    }
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((linked, variants.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const JSONL: &str = concat!(
+        r#"{"id":"god","pos":"NOU","wc":1}"#, "\n",
+        r#"{"id":"God","pos":"NAM","wc":1}"#, "\n",
+        r#"{"id":"AIDS","pos":"NAM","wc":1}"#, "\n",
+        r#"{"id":"Aids","pos":"NAM","wc":1}"#, "\n",
+        r#"{"id":"cat","pos":"NOU","wc":1}"#, "\n",
+    );
+
+    #[test]
+    fn variants_link_to_the_fewest_capitals() {
+        let variants = case_variants(JSONL.as_bytes()).unwrap();
+        assert_eq!(variants.get("God").map(String::as_str), Some("god"));
+        assert_eq!(variants.get("AIDS").map(String::as_str), Some("Aids"));
+        assert_eq!(variants.len(), 2);
+
+        assert_eq!(
+            link_line(r#"{"id":"God","pos":"NAM","wc":1}"#, &variants).unwrap().as_deref(),
+            Some(r#"{"id":"God","case_variant_of":"god","pos":"NAM","wc":1}"#)
+        );
+        assert_eq!(link_line(r#"{"id":"god","pos":"NOU","wc":1}"#, &variants).unwrap(), None);
+    }
+}
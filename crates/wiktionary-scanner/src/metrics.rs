@@ -0,0 +1,276 @@
+//! Throughput and backpressure metrics for long scans, with `--metrics-every`
+//! and `--metrics-textfile`.
+//!
+//! The scan's threads add to a set of shared counters: pages read from the
+//! dump, pages and senses written, and the time spent in each stage (reading
+//! and splitting the dump, processing pages, writing entries). Processing
+//! time is summed over the workers, so on a parallel scan it can exceed the
+//! wall time. The channel pipeline also keeps gauges of its page and result
+//! queues and of its reorder buffer. A reporter thread prints them as a line
+//! on stderr, or rewrites a Prometheus textfile for node_exporter's textfile
+//! collector, every interval and once more when the scan ends.
+//!
+//! Without either option nothing is counted: `time` and the counters check
+//! that the metrics were initialized first.
+
+use crate::create_output;
+
+use once_cell::sync::OnceCell;
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, RecvTimeoutError, Sender};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+static METRICS: OnceCell<ScanMetrics> = OnceCell::new();
+
+/// A stage whose time is tracked
+#[derive(Debug, Clone, Copy)]
+pub enum Stage {
+    /// Reading the input and splitting it into pages
+    Read,
+    /// Parsing pages into entries
+    Process,
+    /// Serializing and writing entries
+    Write,
+}
+
+/// Stage names, in `Stage` order, for the textfile
+const STAGES: [&str; 3] = ["read", "process", "write"];
+
+#[derive(Debug, Default)]
+struct ScanMetrics {
+    pages_read: AtomicUsize,
+    pages_written: AtomicUsize,
+    senses_written: AtomicUsize,
+    page_queue: AtomicUsize,
+    result_queue: AtomicUsize,
+    reorder_buffer: AtomicUsize,
+    stage_nanos: [AtomicU64; 3],
+}
+
+/// Where the reporter sends its reports
+#[derive(Debug, Clone)]
+pub enum MetricsSink {
+    Stderr,
+    Textfile(PathBuf),
+}
+
+/// Whether the scan is counting
+pub fn enabled() -> bool {
+    METRICS.get().is_some()
+}
+
+/// Times a stage until dropped
+pub struct StageTimer(Option<(Stage, Instant)>);
+
+impl Drop for StageTimer {
+    fn drop(&mut self) {
+        if let (Some((stage, start)), Some(metrics)) = (self.0, METRICS.get()) {
+            metrics.stage_nanos[stage as usize].fetch_add(start.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Start timing `stage`; the time is added when the timer is dropped
+pub fn time(stage: Stage) -> StageTimer {
+    StageTimer(enabled().then(|| (stage, Instant::now())))
+}
+
+/// Add the time since `start` to `stage`
+pub fn add_time(stage: Stage, start: Instant) {
+    drop(StageTimer(Some((stage, start))));
+}
+
+pub fn page_read() {
+    if let Some(metrics) = METRICS.get() {
+        metrics.pages_read.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// A page the writer has handled, whether or not it had entries
+pub fn page_written() {
+    if let Some(metrics) = METRICS.get() {
+        metrics.pages_written.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+pub fn senses_written(senses: usize) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.senses_written.fetch_add(senses, Ordering::Relaxed);
+    }
+}
+
+/// The channel pipeline's queue depths and reorder buffer, as the writer sees them
+pub fn set_queues(page_queue: usize, result_queue: usize, reorder_buffer: usize) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.page_queue.store(page_queue, Ordering::Relaxed);
+        metrics.result_queue.store(result_queue, Ordering::Relaxed);
+        metrics.reorder_buffer.store(reorder_buffer, Ordering::Relaxed);
+    }
+}
+
+/// The counters at one moment
+#[derive(Debug, Default, Clone)]
+struct Snapshot {
+    elapsed: Duration,
+    pages_read: usize,
+    pages_written: usize,
+    senses_written: usize,
+    page_queue: usize,
+    result_queue: usize,
+    reorder_buffer: usize,
+    stage_seconds: [f64; 3],
+}
+
+impl Snapshot {
+    fn take(metrics: &ScanMetrics, start: Instant) -> Self {
+        Snapshot {
+            elapsed: start.elapsed(),
+            pages_read: metrics.pages_read.load(Ordering::Relaxed),
+            pages_written: metrics.pages_written.load(Ordering::Relaxed),
+            senses_written: metrics.senses_written.load(Ordering::Relaxed),
+            page_queue: metrics.page_queue.load(Ordering::Relaxed),
+            result_queue: metrics.result_queue.load(Ordering::Relaxed),
+            reorder_buffer: metrics.reorder_buffer.load(Ordering::Relaxed),
+            stage_seconds: metrics.stage_nanos.each_ref().map(|nanos| nanos.load(Ordering::Relaxed) as f64 / 1e9),
+        }
+    }
+
+    /// Pages written per second since `previous`
+    fn rate_since(&self, previous: &Snapshot) -> f64 {
+        let seconds = (self.elapsed - previous.elapsed).as_secs_f64();
+        (self.pages_written - previous.pages_written) as f64 / seconds.max(1e-9)
+    }
+
+    /// The stderr report
+    fn line(&self, previous: &Snapshot) -> String {
+        let [read, process, write] = self.stage_seconds;
+        format!(
+            "[metrics] {:.0}s: {} pages written ({:.0} pages/s, {:.0} overall), {} read, {} senses | \
+             queues: {} pages, {} results, {} reordering | stages: read {:.1}s, process {:.1}s, write {:.1}s",
+            self.elapsed.as_secs_f64(),
+            self.pages_written,
+            self.rate_since(previous),
+            self.rate_since(&Snapshot::default()),
+            self.pages_read,
+            self.senses_written,
+            self.page_queue,
+            self.result_queue,
+            self.reorder_buffer,
+            read,
+            process,
+            write,
+        )
+    }
+
+    /// The Prometheus textfile
+    fn textfile(&self, previous: &Snapshot) -> String {
+        let mut text = String::new();
+        let mut metric = |name: &str, kind: &str, help: &str, value: f64| {
+            let _ = writeln!(text, "# HELP wiktionary_scanner_{} {}", name, help);
+            let _ = writeln!(text, "# TYPE wiktionary_scanner_{} {}", name, kind);
+            let _ = writeln!(text, "wiktionary_scanner_{} {}", name, value);
+        };
+        metric("elapsed_seconds", "gauge", "Time since the scan started.", self.elapsed.as_secs_f64());
+        metric("pages_read_total", "counter", "Pages read from the dump.", self.pages_read as f64);
+        metric("pages_written_total", "counter", "Pages the writer has handled.", self.pages_written as f64);
+        metric("senses_written_total", "counter", "Senses written.", self.senses_written as f64);
+        metric("pages_per_second", "gauge", "Pages written per second over the last interval.", self.rate_since(previous));
+        metric("page_queue", "gauge", "Pages queued for the workers (channel pipeline).", self.page_queue as f64);
+        metric("result_queue", "gauge", "Results queued for the writer (channel pipeline).", self.result_queue as f64);
+        metric(
+            "reorder_buffer",
+            "gauge",
+            "Results held until their turn to be written (channel pipeline).",
+            self.reorder_buffer as f64,
+        );
+        let _ = writeln!(text, "# HELP wiktionary_scanner_stage_seconds_total Time spent in each stage, summed over threads.");
+        let _ = writeln!(text, "# TYPE wiktionary_scanner_stage_seconds_total counter");
+        for (name, seconds) in STAGES.iter().zip(self.stage_seconds) {
+            let _ = writeln!(text, "wiktionary_scanner_stage_seconds_total{{stage=\"{}\"}} {}", name, seconds);
+        }
+        text
+    }
+}
+
+/// The reporter thread; `finish` stops it after a last report
+pub struct Reporter {
+    stop: Sender<()>,
+    handle: JoinHandle<io::Result<()>>,
+}
+
+impl Reporter {
+    pub fn finish(self) -> io::Result<()> {
+        drop(self.stop);
+        self.handle.join().map_err(|_| io::Error::other("metrics reporter panicked"))?
+    }
+}
+
+/// Start counting, and report to `sink` every `interval`
+pub fn start_metrics(sink: MetricsSink, interval: Duration) -> Reporter {
+    let metrics = METRICS.get_or_init(ScanMetrics::default);
+    let start = Instant::now();
+    let (stop, stopped) = channel::<()>();
+    let handle = thread::spawn(move || {
+        let mut previous = Snapshot::default();
+        loop {
+            let last = matches!(stopped.recv_timeout(interval), Err(RecvTimeoutError::Disconnected));
+            let snapshot = Snapshot::take(metrics, start);
+            report(&sink, &snapshot, &previous)?;
+            if last {
+                return Ok(());
+            }
+            previous = snapshot;
+        }
+    });
+    Reporter { stop, handle }
+}
+
+fn report(sink: &MetricsSink, snapshot: &Snapshot, previous: &Snapshot) -> io::Result<()> {
+    match sink {
+        MetricsSink::Stderr => {
+            eprintln!("{}", snapshot.line(previous));
+            Ok(())
+        }
+        // Written aside and renamed, so the collector never reads half a file
+        MetricsSink::Textfile(path) => {
+            let (mut writer, output_file) = create_output(path, true)?;
+            writer.write_all(snapshot.textfile(previous).as_bytes())?;
+            writer.flush()?;
+            drop(writer);
+            output_file.commit()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reports_rates_and_stages() {
+        let previous = Snapshot { elapsed: Duration::from_secs(10), pages_written: 1000, ..Snapshot::default() };
+        let snapshot = Snapshot {
+            elapsed: Duration::from_secs(20),
+            pages_read: 5000,
+            pages_written: 4000,
+            senses_written: 9000,
+            page_queue: 800,
+            result_queue: 12,
+            reorder_buffer: 40,
+            stage_seconds: [3.0, 61.5, 1.5],
+        };
+        assert_eq!(
+            snapshot.line(&previous),
+            "[metrics] 20s: 4000 pages written (300 pages/s, 200 overall), 5000 read, 9000 senses | \
+             queues: 800 pages, 12 results, 40 reordering | stages: read 3.0s, process 61.5s, write 1.5s"
+        );
+        let textfile = snapshot.textfile(&previous);
+        assert!(textfile.contains("# TYPE wiktionary_scanner_pages_written_total counter\nwiktionary_scanner_pages_written_total 4000\n"));
+        assert!(textfile.contains("wiktionary_scanner_pages_per_second 300\n"));
+        assert!(textfile.contains("wiktionary_scanner_stage_seconds_total{stage=\"process\"} 61.5\n"));
+    }
+}
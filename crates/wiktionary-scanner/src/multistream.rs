@@ -0,0 +1,145 @@
+//! Seeking into multistream dumps for targeted extraction.
+//!
+//! `pages-articles-multistream.xml.bz2` is a series of independent bzip2
+//! streams of 100 pages each, and the companion
+//! `pages-articles-multistream-index.txt.bz2` lists `offset:page_id:title`
+//! for every page, where offset is the byte position of the page's stream.
+//! With a title filter, only the streams holding a matching title need to be
+//! decompressed, along with the header stream at the start of the file; the
+//! pages in them still go through the usual filters.
+
+use crate::open_input;
+
+use bzip2::read::BzDecoder;
+use std::collections::VecDeque;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+/// Byte offsets of the streams holding a title `wanted` accepts, in file
+/// order, and the number of streams in the index
+pub fn wanted_stream_offsets(index: impl BufRead, wanted: impl Fn(&str) -> bool) -> io::Result<(Vec<u64>, usize)> {
+    let mut offsets: Vec<u64> = Vec::new();
+    let mut streams = 0;
+    let mut last_offset = None;
+
+    for line in index.lines() {
+        let line = line?;
+        let mut fields = line.splitn(3, ':');
+        let (Some(offset), Some(_page_id), Some(title)) = (fields.next(), fields.next(), fields.next()) else {
+            continue;
+        };
+        let offset: u64 = offset
+            .parse()
+            .map_err(|_| io::Error::other(format!("Malformed multistream index line: {}", line)))?;
+
+        if last_offset != Some(offset) {
+            streams += 1;
+            last_offset = Some(offset);
+        }
+        if wanted(title) && offsets.last() != Some(&offset) {
+            offsets.push(offset);
+        }
+    }
+
+    Ok((offsets, streams))
+}
+
+/// Reads the decompressed pages of the chosen streams, one after another
+pub struct SelectedStreams {
+    dump: PathBuf,
+    offsets: VecDeque<u64>,
+    current: Option<BzDecoder<File>>,
+}
+
+impl SelectedStreams {
+    pub fn new(dump: &Path, offsets: Vec<u64>) -> Self {
+        SelectedStreams {
+            dump: dump.to_path_buf(),
+            offsets: offsets.into(),
+            current: None,
+        }
+    }
+}
+
+impl Read for SelectedStreams {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            if let Some(decoder) = self.current.as_mut() {
+                // A BzDecoder stops at the end of its stream
+                let n = decoder.read(buf)?;
+                if n > 0 {
+                    return Ok(n);
+                }
+            }
+            let Some(offset) = self.offsets.pop_front() else {
+                return Ok(0);
+            };
+            let mut file = File::open(&self.dump)?;
+            file.seek(SeekFrom::Start(offset))?;
+            self.current = Some(BzDecoder::new(file));
+        }
+    }
+}
+
+/// Open only the streams of `dump` that hold a title `wanted` accepts.
+/// Returns the reader with the numbers of streams selected and in total.
+pub fn open_selected_streams(
+    dump: &Path,
+    index: &Path,
+    wanted: impl Fn(&str) -> bool,
+) -> io::Result<(BufReader<SelectedStreams>, usize, usize)> {
+    let (mut offsets, total) = wanted_stream_offsets(open_input(index)?, wanted)?;
+    let selected = offsets.len();
+    // The first stream holds the <siteinfo> header rather than pages
+    if offsets.first() != Some(&0) {
+        offsets.insert(0, 0);
+    }
+    let reader = BufReader::with_capacity(256 * 1024, SelectedStreams::new(dump, offsets));
+    Ok((reader, selected, total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bzip2::write::BzEncoder;
+    use bzip2::Compression;
+    use std::io::Write;
+
+    fn compress(text: &str) -> Vec<u8> {
+        let mut encoder = BzEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(text.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn reads_only_streams_with_wanted_titles() {
+        let streams = [
+            "<page><title>apple</title></page>\n<page><title>bat</title></page>\n",
+            "<page><title>cat</title></page>\n<page><title>dog</title></page>\n",
+            "<page><title>eel</title></page>\n",
+        ];
+        let mut dump = Vec::new();
+        let mut index = String::new();
+        for (i, stream) in streams.iter().enumerate() {
+            let offset = dump.len();
+            for title in ["apple", "bat", "cat", "dog", "eel"] {
+                if stream.contains(&format!(">{}<", title)) {
+                    index.push_str(&format!("{}:{}:{}\n", offset, i, title));
+                }
+            }
+            dump.extend(compress(stream));
+        }
+        let path = std::env::temp_dir().join(format!("wikt-multistream-{}.xml.bz2", std::process::id()));
+        std::fs::write(&path, &dump).unwrap();
+
+        let (offsets, total) = wanted_stream_offsets(index.as_bytes(), |t| t == "dog" || t == "eel").unwrap();
+        assert_eq!(total, 3);
+        assert_eq!(offsets.len(), 2);
+
+        let mut text = String::new();
+        SelectedStreams::new(&path, offsets).read_to_string(&mut text).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(text, format!("{}{}", streams[1], streams[2]));
+    }
+}
@@ -0,0 +1,123 @@
+//! Unicode normalization and ASCII folding of the emitted word.
+//!
+//! Titles are written as they appear in the dump unless `--normalize` asks
+//! for NFC or NFKC. `--ascii` adds an `ascii` field holding the word with
+//! diacritics stripped and typographic apostrophes, quotes and dashes
+//! straightened (café → cafe, rock ’n’ roll → rock 'n' roll), so consumers
+//! can match typed input without reimplementing the folding, and
+//! `--sort-key` a dictionary-order key (see `collation`). All apply in
+//! `parse_page`, so every strategy and `update` see the same forms.
+
+use crate::collation::sort_key;
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+static WORD_FORMS: OnceCell<WordForms> = OnceCell::new();
+
+/// Normalization form for the emitted word
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Normalization {
+    /// Keep the title as it appears in the dump
+    #[default]
+    None,
+    /// Canonical composition
+    Nfc,
+    /// Compatibility composition (ligatures and fullwidth forms are also folded)
+    Nfkc,
+}
+
+#[derive(Debug, Default)]
+pub struct WordForms {
+    pub normalize: Normalization,
+    /// Add the `ascii` field
+    pub ascii: bool,
+    /// Add the `sort_key` field
+    pub sort_key: bool,
+}
+
+pub fn init_word_forms(forms: WordForms) -> Result<(), String> {
+    WORD_FORMS.set(forms).map_err(|_| "WORD_FORMS already initialized".to_string())
+}
+
+/// The word to emit for a title
+pub fn word_form(title: &str) -> String {
+    match WORD_FORMS.get().map_or(Normalization::None, |forms| forms.normalize) {
+        Normalization::None => title.to_string(),
+        Normalization::Nfc => title.nfc().collect(),
+        Normalization::Nfkc => title.nfkc().collect(),
+    }
+}
+
+/// The `ascii` field for a word: only with --ascii, and only when folding
+/// changes the word
+pub fn ascii_form(word: &str) -> Option<String> {
+    if !WORD_FORMS.get().is_some_and(|forms| forms.ascii) || word.is_ascii() {
+        return None;
+    }
+    fold_ascii(word)
+}
+
+/// The `sort_key` field for a word, with --sort-key
+pub fn sort_key_form(word: &str) -> Option<String> {
+    WORD_FORMS.get().is_some_and(|forms| forms.sort_key).then(|| sort_key(word))
+}
+
+/// The word in plain ASCII, or None if some character has no ASCII form
+/// (other scripts, most symbols)
+pub fn fold_ascii(word: &str) -> Option<String> {
+    let mut folded = String::with_capacity(word.len());
+    for ch in word.nfkd().filter(|&ch| !is_combining_mark(ch)) {
+        match ch {
+            _ if ch.is_ascii() => folded.push(ch),
+            '\u{2018}' | '\u{2019}' | '\u{201B}' | '\u{02BC}' | '\u{2032}' => folded.push('\''),
+            '\u{201C}' | '\u{201D}' | '\u{201F}' | '\u{2033}' => folded.push('"'),
+            '\u{2010}' | '\u{2011}' | '\u{2012}' | '\u{2013}' | '\u{2014}' | '\u{2212}' => folded.push('-'),
+            '\u{2044}' => folded.push('/'),
+            '\u{00A0}' | '\u{202F}' => folded.push(' '),
+            _ => folded.push_str(expand_letter(ch)?),
+        }
+    }
+    Some(folded)
+}
+
+/// ASCII spelling of a Latin letter that doesn't decompose into a base
+/// letter and a mark (æ → ae, ø → o)
+pub fn expand_letter(ch: char) -> Option<&'static str> {
+    Some(match ch {
+        'æ' => "ae",
+        'Æ' => "AE",
+        'œ' => "oe",
+        'Œ' => "OE",
+        'ß' => "ss",
+        'ø' => "o",
+        'Ø' => "O",
+        'đ' | 'ð' => "d",
+        'Đ' | 'Ð' => "D",
+        'ł' => "l",
+        'Ł' => "L",
+        'þ' => "th",
+        'Þ' => "Th",
+        'ı' => "i",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn folds_diacritics_and_punctuation() {
+        assert_eq!(fold_ascii("café").as_deref(), Some("cafe"));
+        assert_eq!(fold_ascii("rock \u{2019}n\u{2019} roll").as_deref(), Some("rock 'n' roll"));
+        assert_eq!(fold_ascii("Ærøskøbing").as_deref(), Some("AEroskobing"));
+        assert_eq!(fold_ascii("e\u{0301}clair").as_deref(), Some("eclair"));
+        assert_eq!(fold_ascii("ﬁnance").as_deref(), Some("finance"));
+        assert_eq!(fold_ascii("Ω"), None);
+    }
+}
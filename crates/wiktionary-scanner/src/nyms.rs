@@ -0,0 +1,198 @@
+//! Synonyms, antonyms, hypernyms and hyponyms of a sense.
+//!
+//! A nym template on a `#:` line under a definition belongs to that definition.
+//! A Synonyms (Antonyms, ...) section lists nyms for the whole part of speech,
+//! with a `{{sense|...}}` key naming the sense each line is for:
+//!
+//! ```text
+//! # A domesticated [[feline]].
+//! #: {{syn|en|kitty|puss}}
+//!
+//! ====Synonyms====
+//! * {{sense|feline}} {{l|en|moggy}}, [[pussycat]]
+//! ```
+//!
+//! The key is matched against the senses' `{{gloss}}` qualifiers and
+//! definition words, and kept on each nym as `sense`. A line without a key
+//! belongs to the only sense of its part of speech; lines that can't be tied
+//! to one sense are left out.
+
+use crate::page_ast::PageAst;
+use crate::{appendix, extract_glosses, unescape_xml, wordnet, MarkupMode, WikitextParser};
+
+use serde::{Deserialize, Serialize};
+use std::ops::Range;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Nym {
+    pub relation: String,
+    pub term: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sense: Option<String>,
+}
+
+/// Relation named by a nym template or section header
+pub fn relation(name: &str) -> Option<&'static str> {
+    match name.trim().to_lowercase().as_str() {
+        "syn" | "synonyms" | "syn-lite" => Some("synonym"),
+        "ant" | "antonyms" | "ant-lite" => Some("antonym"),
+        "hyper" | "hypernyms" => Some("hypernym"),
+        "hypo" | "hyponyms" => Some("hyponym"),
+        _ => None,
+    }
+}
+
+/// A linked term as plain text, or None for a link to another namespace
+/// (`Thesaurus:cat`, `w:Cat`)
+pub fn term(target: &str) -> Option<String> {
+    if target.contains(':') {
+        return None;
+    }
+    Some(appendix::clean_wikitext(target)).filter(|term| !term.is_empty())
+}
+
+/// Sense key and nyms of one line. Inside a nym section (`section` is its
+/// relation) `{{l|en|...}}` templates and plain links are terms too; elsewhere
+/// only nym templates are.
+fn parse_line(line: &str, section: Option<&'static str>) -> (Option<String>, Vec<Nym>) {
+    let ast = PageAst::parse(line);
+    // Templates, and links with their target; nested nodes are skipped
+    let mut nodes: Vec<(Range<usize>, Option<&Range<usize>>)> = ast
+        .templates
+        .iter()
+        .map(|template| (template.span.clone(), None))
+        .chain(ast.links.iter().map(|link| (link.span.clone(), Some(&link.target))))
+        .collect();
+    nodes.sort_by_key(|(span, _)| span.start);
+
+    let mut key: Option<String> = None;
+    let mut terms: Vec<(&'static str, String)> = Vec::new();
+    let mut covered = 0;
+    for (span, target) in nodes {
+        if span.start < covered {
+            continue;
+        }
+        covered = span.end;
+        if let Some(target) = target {
+            if let Some(relation) = section {
+                terms.extend(term(ast.slice(target)).map(|term| (relation, term)));
+            }
+            continue;
+        }
+        let template = WikitextParser::with_markup(ast.slice(&span), MarkupMode::Strip).parse_template();
+        let name = template.name.trim().to_lowercase();
+        let english = template.params.first().is_some_and(|lang| lang.trim() == "en");
+        let params = || template.params.iter().skip(1).filter_map(|param| term(param));
+        match (name.as_str(), relation(&name)) {
+            ("sense" | "s", _) => {
+                let sense: Vec<&str> =
+                    template.params.iter().map(|param| param.trim()).filter(|param| !param.is_empty()).collect();
+                if key.is_none() && !sense.is_empty() {
+                    key = Some(sense.join(", "));
+                }
+            }
+            (_, Some(relation)) if english => terms.extend(params().map(|term| (relation, term))),
+            ("l" | "link" | "l-self" | "ll", _) if english => {
+                if let Some(relation) = section {
+                    terms.extend(params().take(1).map(|term| (relation, term)));
+                }
+            }
+            _ => {}
+        }
+    }
+    let nyms = terms
+        .into_iter()
+        .map(|(relation, term)| Nym { relation: relation.to_string(), term, sense: key.clone() })
+        .collect();
+    (key, nyms)
+}
+
+/// Nyms on the `#:` lines between a definition and the next one
+pub fn inline_nyms(block: &str) -> Vec<Nym> {
+    block
+        .lines()
+        .filter_map(|line| line.strip_prefix("#:"))
+        .filter(|line| line.contains("{{"))
+        .flat_map(|line| parse_line(&unescape_xml(line), None).1)
+        .collect()
+}
+
+/// The list lines of a nym section, each with its sense key. Lines without
+/// terms are left out.
+pub fn section_nyms(relation: &'static str, body: &str) -> Vec<(Option<String>, Vec<Nym>)> {
+    body.lines()
+        .filter_map(|line| line.strip_prefix('*'))
+        .map(|line| parse_line(&unescape_xml(line), Some(relation)))
+        .filter(|(_, nyms)| !nyms.is_empty())
+        .collect()
+}
+
+/// Index of the definition a sense key names: one with the key as a
+/// `{{gloss}}`, else the one sharing the most words with the key. A tie
+/// names no definition.
+pub fn sense_for_key(key: &str, definitions: &[&str]) -> Option<usize> {
+    let glosses: Vec<Vec<String>> = definitions.iter().map(|line| extract_glosses(line)).collect();
+    if let Some(index) = glosses.iter().position(|glosses| glosses.iter().any(|gloss| gloss.eq_ignore_ascii_case(key))) {
+        return Some(index);
+    }
+    let key_words = wordnet::content_words(key);
+    let scores: Vec<usize> = definitions
+        .iter()
+        .zip(&glosses)
+        .map(|(line, glosses)| {
+            let text = format!("{} {}", appendix::clean_wikitext(&unescape_xml(line)), glosses.join(" "));
+            wordnet::content_words(&text).intersection(&key_words).count()
+        })
+        .collect();
+    let best = scores.iter().copied().max().filter(|&best| best > 0)?;
+    let mut best_senses = scores.iter().enumerate().filter(|(_, &score)| score == best);
+    match (best_senses.next(), best_senses.next()) {
+        (Some((index, _)), None) => Some(index),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn nym(relation: &str, term: &str, sense: Option<&str>) -> Nym {
+        Nym { relation: relation.into(), term: term.into(), sense: sense.map(str::to_string) }
+    }
+
+    #[test]
+    fn inline_and_section_lines() {
+        let block = "\n#: {{syn|en|kitty|puss<q:informal>|Thesaurus:cat}}\n#* {{quote-book|en|passage={{syn|en|no}}}}\n\
+                     #: {{ant|en|dog}}\n##: {{syn|en|subsense}}\n";
+        assert_eq!(
+            inline_nyms(block),
+            vec![nym("synonym", "kitty", None), nym("synonym", "puss", None), nym("antonym", "dog", None)]
+        );
+
+        let body = "\n* {{sense|physics|angstrom}} {{l|en|Å}}\n* {{sense|to abhor}} {{l|en|abhor}}, [[loathe]] {{q|rare}}\n\
+                    * See also [[Thesaurus:hate]]\n";
+        assert_eq!(
+            section_nyms("synonym", body),
+            vec![
+                (Some("physics, angstrom".into()), vec![nym("synonym", "Å", Some("physics, angstrom"))]),
+                (
+                    Some("to abhor".into()),
+                    vec![nym("synonym", "abhor", Some("to abhor")), nym("synonym", "loathe", Some("to abhor"))]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn sense_keys_name_definitions() {
+        let definitions = vec![
+            "A domesticated [[feline]] animal.",
+            "{{lb|en|slang}} {{gloss|person}} A fellow, a man.",
+            "A wild animal of the cat family.",
+        ];
+        assert_eq!(sense_for_key("person", &definitions), Some(1));
+        assert_eq!(sense_for_key("felines", &definitions), Some(0));
+        assert_eq!(sense_for_key("animal", &definitions), None);
+        assert_eq!(sense_for_key("vehicle", &definitions), None);
+    }
+}
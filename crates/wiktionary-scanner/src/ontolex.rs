@@ -0,0 +1,245 @@
+//! OntoLex-Lemon export.
+//!
+//! `export ontolex` writes scanner output as Turtle for triple stores. Each
+//! word and part of speech becomes a `ontolex:LexicalEntry` with its
+//! canonical `ontolex:Form`, and each sense line a `ontolex:LexicalSense`
+//! carrying the `definition` (or `glosses`) as `skos:definition`. IRIs are
+//! relative to `--base`: `<cat/NOU>`, `<cat/NOU#form>`, `<cat/NOU#sense1>`.
+
+use crate::{create_output, open_input};
+
+use clap::Args as ClapArgs;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+const PREFIXES: &str = "\
+@prefix ontolex: <http://www.w3.org/ns/lemon/ontolex#> .
+@prefix lexinfo: <http://www.lexinfo.net/ontology/3.0/lexinfo#> .
+@prefix skos: <http://www.w3.org/2004/02/skos/core#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix dct: <http://purl.org/dc/terms/> .
+";
+
+#[derive(ClapArgs, Debug)]
+pub struct OntolexArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the Turtle (- for stdout)
+    pub output: PathBuf,
+
+    /// Base IRI the entry IRIs are relative to
+    #[arg(long, default_value = "https://openword-lexicon.org/id/")]
+    pub base: String,
+}
+
+/// Only the fields the OntoLex export needs
+#[derive(Deserialize)]
+struct SenseRecord {
+    id: String,
+    pos: String,
+    definition: Option<String>,
+    #[serde(default)]
+    glosses: Vec<String>,
+    #[serde(default)]
+    wikidata_lexeme: Vec<String>,
+}
+
+/// lexinfo part of speech for a POS code
+fn lexinfo_pos(pos: &str) -> Option<&'static str> {
+    Some(match pos {
+        "NOU" => "noun",
+        "NAM" => "properNoun",
+        "VRB" => "verb",
+        "ADJ" => "adjective",
+        "ADV" => "adverb",
+        "PRN" => "pronoun",
+        "DET" => "determiner",
+        "ADP" => "adposition",
+        "CNJ" => "conjunction",
+        "PRT" => "particle",
+        "ITJ" => "interjection",
+        "NUM" => "numeral",
+        "SYM" => "symbol",
+        "AFX" => "affix",
+        "PRV" => "proverb",
+        "IDM" => "idiom",
+        "PPP" => "prepositionalPhrase",
+        "CTN" => "contraction",
+        _ => return None,
+    })
+}
+
+/// A word as an IRI path segment: everything but unreserved characters is
+/// percent-encoded, including '/' ("and/or" → and%2For)
+pub fn iri_segment(word: &str) -> String {
+    let mut segment = String::with_capacity(word.len());
+    for byte in word.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => segment.push(byte as char),
+            _ => {
+                let _ = write!(segment, "%{:02X}", byte);
+            }
+        }
+    }
+    segment
+}
+
+/// A Turtle string literal with an English language tag
+pub fn literal(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 5);
+    quoted.push('"');
+    for ch in text.chars() {
+        match ch {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push_str("\"@en");
+    quoted
+}
+
+/// Turtle for one word's senses, one entry per part of speech in the order
+/// first seen. `numbered` carries sense counts over from earlier lines for
+/// the same word and part of speech, so sense IRIs stay unique.
+fn word_turtle(senses: &[SenseRecord], numbered: &mut HashMap<(String, String), usize>) -> String {
+    let mut parts: Vec<&str> = Vec::new();
+    for sense in senses {
+        if !parts.contains(&sense.pos.as_str()) {
+            parts.push(&sense.pos);
+        }
+    }
+
+    let mut turtle = String::new();
+    for pos in parts {
+        let word = &senses[0].id;
+        let entry = format!("{}/{}", iri_segment(word), pos);
+        let class = if word.split_whitespace().nth(1).is_some() {
+            "ontolex:MultiwordExpression"
+        } else if pos == "AFX" {
+            "ontolex:Affix"
+        } else {
+            "ontolex:Word"
+        };
+        let pos_senses: Vec<&SenseRecord> = senses.iter().filter(|sense| sense.pos == pos).collect();
+        let count = numbered.entry((word.clone(), pos.to_string())).or_default();
+        let first = *count + 1;
+        *count += pos_senses.len();
+
+        let _ = writeln!(turtle, "<{}> a ontolex:LexicalEntry, {} ;", entry, class);
+        let _ = writeln!(turtle, "    rdfs:label {} ;", literal(word));
+        let _ = writeln!(turtle, "    dct:language <http://lexvo.org/id/iso639-3/eng> ;");
+        if let Some(lexinfo) = lexinfo_pos(pos) {
+            let _ = writeln!(turtle, "    lexinfo:partOfSpeech lexinfo:{} ;", lexinfo);
+        }
+        let mut lexemes: Vec<&String> = pos_senses.iter().flat_map(|sense| &sense.wikidata_lexeme).collect();
+        lexemes.sort();
+        lexemes.dedup();
+        for lexeme in lexemes {
+            let _ = writeln!(turtle, "    rdfs:seeAlso <http://www.wikidata.org/entity/{}> ;", lexeme);
+        }
+        let sense_iris: Vec<String> =
+            (first..first + pos_senses.len()).map(|n| format!("<{}#sense{}>", entry, n)).collect();
+        let _ = writeln!(turtle, "    ontolex:canonicalForm <{}#form> ;", entry);
+        let _ = writeln!(turtle, "    ontolex:sense {} .", sense_iris.join(", "));
+        let _ = writeln!(turtle, "<{}#form> a ontolex:Form ;\n    ontolex:writtenRep {} .", entry, literal(word));
+
+        for (sense, iri) in pos_senses.iter().zip(&sense_iris) {
+            let definition =
+                sense.definition.clone().or_else(|| (!sense.glosses.is_empty()).then(|| sense.glosses.join("; ")));
+            match definition {
+                Some(text) => {
+                    let _ = writeln!(turtle, "{} a ontolex:LexicalSense ;", iri);
+                    let _ = writeln!(turtle, "    skos:definition {} .", literal(&text));
+                }
+                None => {
+                    let _ = writeln!(turtle, "{} a ontolex:LexicalSense .", iri);
+                }
+            }
+        }
+        turtle.push('\n');
+    }
+    turtle
+}
+
+/// Write the input as OntoLex Turtle. Returns the numbers of lexical entries
+/// and senses written.
+pub fn run_export_ontolex(args: &OntolexArgs, force: bool) -> io::Result<(usize, usize)> {
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut numbered: HashMap<(String, String), usize> = HashMap::new();
+    let mut senses = 0;
+
+    writeln!(writer, "@base <{}> .\n{}", args.base, PREFIXES)?;
+    // Senses of a page are on consecutive lines, so one word is buffered at a time
+    let mut word_senses: Vec<SenseRecord> = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let sense: SenseRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        if word_senses.first().is_some_and(|first| first.id != sense.id) {
+            writer.write_all(word_turtle(&word_senses, &mut numbered).as_bytes())?;
+            word_senses.clear();
+        }
+        senses += 1;
+        word_senses.push(sense);
+    }
+    if !word_senses.is_empty() {
+        writer.write_all(word_turtle(&word_senses, &mut numbered).as_bytes())?;
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((numbered.len(), senses))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sense(json: &str) -> SenseRecord {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn iris_and_literals() {
+        assert_eq!(iri_segment("ice cream"), "ice%20cream");
+        assert_eq!(iri_segment("and/or"), "and%2For");
+        assert_eq!(iri_segment("café"), "caf%C3%A9");
+        assert_eq!(literal("a \"quoted\" \\ word"), r#""a \"quoted\" \\ word"@en"#);
+    }
+
+    #[test]
+    fn entries_per_part_of_speech() {
+        let senses = vec![
+            sense(r#"{"id":"cat","pos":"NOU","definition":"A feline.","wikidata_lexeme":["L7"]}"#),
+            sense(r#"{"id":"cat","pos":"VRB","glosses":["nautical"]}"#),
+            sense(r#"{"id":"cat","pos":"NOU"}"#),
+        ];
+        let mut numbered = HashMap::new();
+        let turtle = word_turtle(&senses, &mut numbered);
+        assert!(turtle.contains("<cat/NOU> a ontolex:LexicalEntry, ontolex:Word ;"));
+        assert!(turtle.contains("lexinfo:partOfSpeech lexinfo:noun ;"));
+        assert!(turtle.contains("rdfs:seeAlso <http://www.wikidata.org/entity/L7> ;"));
+        assert!(turtle.contains("ontolex:sense <cat/NOU#sense1>, <cat/NOU#sense2> ."));
+        assert!(turtle.contains("<cat/NOU#sense1> a ontolex:LexicalSense ;\n    skos:definition \"A feline.\"@en ."));
+        assert!(turtle.contains("<cat/NOU#sense2> a ontolex:LexicalSense ."));
+        assert!(turtle.contains("<cat/VRB#sense1> a ontolex:LexicalSense ;\n    skos:definition \"nautical\"@en ."));
+
+        // A later run of the same word keeps numbering its senses
+        let turtle = word_turtle(&senses[..1], &mut numbered);
+        assert!(turtle.contains("ontolex:sense <cat/NOU#sense3> ."));
+    }
+}
@@ -0,0 +1,278 @@
+//! Single-pass index of the templates, links and headers in a page section.
+//!
+//! Page-level extractors used to run one regex per template family over the
+//! whole English section. `PageAst::parse` walks the text once, recording the
+//! span of every `{{template}}`, `[[link]]` and `=== header ===`; extractors
+//! then look up nodes by name and only run their regex over each candidate's
+//! own span.
+
+use regex::{Captures, Regex};
+use std::ops::Range;
+
+/// A `{{...}}` template. Nested templates get nodes of their own.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemplateNode {
+    /// Text before the first "|", trimmed; not case-folded
+    pub name: Range<usize>,
+    /// From "{{" through the matching "}}" (or the end of text if unclosed)
+    pub span: Range<usize>,
+}
+
+/// A `[[...]]` link
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinkNode {
+    /// Text before the first "|", e.g. `Category:English nouns`
+    pub target: Range<usize>,
+    pub span: Range<usize>,
+}
+
+/// A header line with at least two "=" on each side
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeaderNode {
+    /// The smaller of the opening and closing "=" counts
+    pub level: usize,
+    /// Header text without the "=" markers, trimmed
+    pub title: Range<usize>,
+    /// The whole line, excluding its newline
+    pub span: Range<usize>,
+}
+
+#[derive(Debug)]
+pub struct PageAst<'a> {
+    text: &'a str,
+    pub templates: Vec<TemplateNode>,
+    pub links: Vec<LinkNode>,
+    pub headers: Vec<HeaderNode>,
+}
+
+impl<'a> PageAst<'a> {
+    /// Tokenize `text` in one pass. Nodes are listed in order of their start offset.
+    pub fn parse(text: &'a str) -> Self {
+        let bytes = text.as_bytes();
+        let mut templates: Vec<TemplateNode> = Vec::new();
+        let mut links: Vec<LinkNode> = Vec::new();
+        let mut headers = Vec::new();
+        // Indices of the nodes still waiting for their closing brackets
+        let mut open_templates: Vec<usize> = Vec::new();
+        let mut open_links: Vec<usize> = Vec::new();
+
+        let mut i = 0;
+        let mut line_start = true;
+        while i < bytes.len() {
+            if line_start && bytes[i] == b'=' {
+                let line_end = text[i..].find('\n').map_or(text.len(), |n| i + n);
+                if let Some(header) = scan_header(text, i..line_end) {
+                    headers.push(header);
+                }
+            }
+            line_start = bytes[i] == b'\n';
+
+            match (bytes[i], bytes.get(i + 1)) {
+                (b'{', Some(b'{')) => {
+                    open_templates.push(templates.len());
+                    templates.push(TemplateNode {
+                        name: field_before_pipe(text, i + 2, b'}'),
+                        span: i..text.len(),
+                    });
+                    i += 2;
+                }
+                (b'}', Some(b'}')) if !open_templates.is_empty() => {
+                    let index = open_templates.pop().unwrap_or_default();
+                    templates[index].span.end = i + 2;
+                    i += 2;
+                }
+                (b'[', Some(b'[')) => {
+                    open_links.push(links.len());
+                    links.push(LinkNode {
+                        target: field_before_pipe(text, i + 2, b']'),
+                        span: i..text.len(),
+                    });
+                    i += 2;
+                }
+                (b']', Some(b']')) if !open_links.is_empty() => {
+                    let index = open_links.pop().unwrap_or_default();
+                    links[index].span.end = i + 2;
+                    i += 2;
+                }
+                _ => i += 1,
+            }
+        }
+
+        PageAst { text, templates, links, headers }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn slice(&self, range: &Range<usize>) -> &'a str {
+        &self.text[range.clone()]
+    }
+
+    /// Source of every template named one of `names` (ASCII case-insensitive), in page order
+    pub fn templates<'s>(&'s self, names: &'s [&'s str]) -> impl Iterator<Item = &'a str> + 's {
+        self.templates_in(0..self.text.len(), names)
+    }
+
+    /// Like `templates`, restricted to templates starting inside `range`
+    pub fn templates_in<'s>(
+        &'s self,
+        range: Range<usize>,
+        names: &'s [&'s str],
+    ) -> impl Iterator<Item = &'a str> + 's {
+        self.templates
+            .iter()
+            .skip_while(move |t| t.span.start < range.start)
+            .take_while(move |t| t.span.start < range.end)
+            .filter(move |t| {
+                let name = self.slice(&t.name);
+                names.iter().any(|n| n.eq_ignore_ascii_case(name))
+            })
+            .map(move |t| self.slice(&t.span))
+    }
+
+    /// Source of the named templates inside `range` that `pattern` matches at their start
+    pub fn templates_matching_in<'s>(
+        &'s self,
+        range: Range<usize>,
+        names: &'s [&'s str],
+        pattern: &'s Regex,
+    ) -> impl Iterator<Item = &'a str> + 's {
+        self.templates_in(range, names)
+            .filter(move |source| pattern.find(source).is_some_and(|m| m.start() == 0))
+    }
+
+    /// Names of all templates, in page order
+    pub fn template_names(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.templates.iter().map(|t| self.slice(&t.name))
+    }
+
+    /// First match of `pattern` that starts at one of the named templates
+    pub fn captures(&self, names: &[&str], pattern: &Regex) -> Option<Captures<'a>> {
+        self.captures_in(0..self.text.len(), names, pattern)
+    }
+
+    pub fn captures_in(&self, range: Range<usize>, names: &[&str], pattern: &Regex) -> Option<Captures<'a>> {
+        self.templates_in(range, names).find_map(|source| anchored_captures(pattern, source))
+    }
+
+    /// Every match of `pattern` that starts at one of the named templates
+    pub fn captures_iter<'s>(
+        &'s self,
+        names: &'s [&'s str],
+        pattern: &'s Regex,
+    ) -> impl Iterator<Item = Captures<'a>> + 's {
+        self.templates(names).filter_map(move |source| anchored_captures(pattern, source))
+    }
+
+    /// Targets of all links, with any leading ":" (a link to the page rather
+    /// than membership, as in `[[:Category:...]]`) kept
+    pub fn link_targets(&self) -> impl Iterator<Item = &'a str> + '_ {
+        self.links.iter().map(|link| self.slice(&link.target))
+    }
+
+    /// Full source of the links whose target starts with `prefix` (ASCII case-insensitive)
+    pub fn links_with_prefix<'s>(&'s self, prefix: &'s str) -> impl Iterator<Item = &'a str> + 's {
+        self.links
+            .iter()
+            .filter(move |link| {
+                let target = self.slice(&link.target);
+                target.get(..prefix.len()).is_some_and(|head| head.eq_ignore_ascii_case(prefix))
+            })
+            .map(move |link| self.slice(&link.span))
+    }
+
+    /// Headers of at least `level`, e.g. 3 for the POS and Etymology headers
+    pub fn headers_from(&self, level: usize) -> impl Iterator<Item = &HeaderNode> + '_ {
+        self.headers.iter().filter(move |h| h.level >= level)
+    }
+}
+
+/// Pattern matches only count when they start at the template itself; a match
+/// on a nested template is found through that template's own node
+fn anchored_captures<'t>(pattern: &Regex, source: &'t str) -> Option<Captures<'t>> {
+    pattern.captures(source).filter(|cap| cap.get(0).is_some_and(|m| m.start() == 0))
+}
+
+/// Trimmed range from `start` up to the first "|", a doubled `close` bracket,
+/// or a newline
+fn field_before_pipe(text: &str, start: usize, close: u8) -> Range<usize> {
+    let bytes = text.as_bytes();
+    let mut end = start;
+    while end < bytes.len() {
+        match bytes[end] {
+            b'|' | b'\n' => break,
+            b if b == close && bytes.get(end + 1) == Some(&close) => break,
+            _ => end += 1,
+        }
+    }
+    let field = &text[start..end];
+    let leading = field.len() - field.trim_start().len();
+    let trimmed = field.trim().len();
+    start + leading..start + leading + trimmed
+}
+
+fn scan_header(text: &str, line: Range<usize>) -> Option<HeaderNode> {
+    let source = &text[line.clone()];
+    let trimmed = source.trim_end();
+    let opening = trimmed.len() - trimmed.trim_start_matches('=').len();
+    let closing = trimmed.len() - trimmed.trim_end_matches('=').len();
+    if opening == trimmed.len() || opening.min(closing) < 2 {
+        return None;
+    }
+
+    let inner = &trimmed[opening..trimmed.len() - closing];
+    let title_start = line.start + opening + (inner.len() - inner.trim_start().len());
+    let title = title_start..title_start + inner.trim().len();
+    if title.is_empty() {
+        return None;
+    }
+
+    Some(HeaderNode {
+        level: opening.min(closing),
+        title,
+        span: line,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nodes_with_nesting_and_spans() {
+        let text = "===Noun===\n{{en-noun}}\n# {{lb|en|[[w:UK|UK]]}} A [[cat]].\n{{unclosed|x";
+        let ast = PageAst::parse(text);
+
+        let names: Vec<&str> = ast.templates.iter().map(|t| ast.slice(&t.name)).collect();
+        assert_eq!(names, vec!["en-noun", "lb", "unclosed"]);
+        assert_eq!(ast.slice(&ast.templates[1].span), "{{lb|en|[[w:UK|UK]]}}");
+        assert_eq!(ast.templates[2].span.end, text.len());
+
+        let targets: Vec<&str> = ast.link_targets().collect();
+        assert_eq!(targets, vec!["w:UK", "cat"]);
+
+        assert_eq!(ast.headers.len(), 1);
+        assert_eq!(ast.headers[0].level, 3);
+        assert_eq!(ast.slice(&ast.headers[0].title), "Noun");
+    }
+
+    #[test]
+    fn header_levels_and_non_headers() {
+        let ast = PageAst::parse("==English==\n==== Verb ===  \n=== x === <!-- c -->\n=====\n");
+        let headers: Vec<(usize, &str)> =
+            ast.headers.iter().map(|h| (h.level, ast.slice(&h.title))).collect();
+        assert_eq!(headers, vec![(2, "English"), (3, "Verb")]);
+    }
+
+    #[test]
+    fn captures_start_at_named_template() {
+        let pattern = Regex::new(r"(?i)\{\{suffix\|en\|([^}|]+)\|([^}|]+)\}\}").unwrap();
+        let ast = PageAst::parse("From {{Suffix|en|kind|ness}} and {{suffix|en|x|y}}.");
+        let cap = ast.captures(&["suffix"], &pattern).unwrap();
+        assert_eq!(&cap[1], "kind");
+
+        let ranged = ast.captures_in(20..ast.text().len(), &["suffix"], &pattern).unwrap();
+        assert_eq!(&ranged[1], "x");
+    }
+}
@@ -4,18 +4,24 @@
 //! - Sequential (baseline)
 //! - Batch-parallel (std::thread on batches of pages)
 //! - Channel-pipeline (producer-consumer with mpsc channels)
-//! - Two-phase (read all pages, then process in parallel)
+//! - Two-phase (read all pages, then process in parallel; optionally in
+//!   memory-bounded chunks)
+
+use crate::{Entry, OutputLimit, PageData, PageMeta, Stats, TitleKind, classify_title, keep_grapheme_senses};
+use crate::error_log::{parse_page_checked, record_failure, FailureCategory, PageFailure};
+use crate::filters::filter_entries;
+use crate::metrics::{self, Stage};
+use crate::reorder::ReorderBuffer;
+use crate::select::selected;
+use crate::shape::{render_entries, write_entries, write_rendered, RenderedEntries};
+use crate::{TITLE_PATTERN, NS_PATTERN, REDIRECT_PATTERN, DICT_ONLY, get_special_prefixes, english_span, has_english_section, page_text};
 
-use crate::{Entry, Stats, parse_page, is_englishlike, classify_case, CaseForm};
-use crate::{TITLE_PATTERN, NS_PATTERN, TEXT_PATTERN, REDIRECT_PATTERN, ENGLISH_SECTION, DICT_ONLY, get_special_prefixes};
-
-use std::collections::BTreeMap;
 use std::io::{BufRead, Write, BufWriter};
 use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::{self, JoinHandle};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 /// Configuration for parallel processing
 #[derive(Debug, Clone)]
@@ -28,6 +34,16 @@ pub struct ParallelConfig {
     pub channel_buffer: usize,
     /// Number of worker threads for pipeline
     pub num_workers: usize,
+    /// Two-phase: bytes of page XML to hold at once (None = the whole dump)
+    pub memory_budget: Option<usize>,
+    /// Channel-pipeline: tune active workers and queue size from observed backpressure
+    pub autoscale: bool,
+    /// Channel-pipeline: out-of-order results held in memory before spilling to disk
+    pub reorder_limit: usize,
+    /// Channel-pipeline: consecutive pages handed to a worker at a time
+    pub chunk_pages: usize,
+    /// Suppress progress output
+    pub quiet: bool,
 }
 
 impl Default for ParallelConfig {
@@ -41,33 +57,81 @@ impl Default for ParallelConfig {
             batch_size: 1000,
             channel_buffer: 10000,
             num_workers: cpus.saturating_sub(1).max(1),
+            memory_budget: None,
+            autoscale: false,
+            reorder_limit: 10000,
+            chunk_pages: 100,
+            quiet: false,
         }
     }
 }
 
+/// Consecutive pages of the dump, handed to one worker
+struct PageChunk {
+    chunk_id: usize,
+    first_page_id: usize,
+    pages: Vec<String>,
+}
+
+/// A chunk's results, in page order. Sent even when no page had a result, so
+/// the writer sees every chunk id.
+#[derive(Debug)]
+pub struct ProcessedChunk {
+    pub chunk_id: usize,
+    pub pages: Vec<ProcessedPage>,
+}
+
 /// Parsed page ready for processing
 #[derive(Debug)]
 pub struct RawPage {
     pub title: String,
+    /// From a dump, the English section with its header; the rest of the
+    /// page's text is left in the XML
     pub text: String,
     pub page_id: usize,
+    /// The page's dump id and revision timestamp, stamped onto its entries
+    pub meta: PageMeta,
 }
 
 /// Result of page processing
 #[derive(Debug)]
 pub struct ProcessedPage {
-    pub entries: Vec<Entry>,
-    pub title: String,
+    pub entries: Vec<Entry<'static>>,
     pub page_id: usize,
-    pub was_english: bool,
-    pub was_redirect: bool,
-    pub was_special: bool,
-    pub was_non_latin: bool,
-    pub was_dict_only: bool,
+    /// The page's counts (how it was skipped, or its word and case, and the
+    /// senses the output filters dropped). Filled on the worker thread; the
+    /// writer adds the pages written and senses written, then merges it.
+    pub stats: Stats,
+    /// For the error log: why the page yielded no entries, or which of its
+    /// entries failed validation
+    pub failures: Vec<PageFailure>,
+    /// The entries serialized on the worker thread, which then leaves
+    /// `entries` empty (see `process_raw_page`)
+    pub rendered: Option<RenderedEntries>,
+}
+
+impl ProcessedPage {
+    /// A page without entries, counted by `count`
+    fn skipped(page_id: usize, count: fn(&mut Stats)) -> Self {
+        let mut stats = Stats::default();
+        count(&mut stats);
+        ProcessedPage { entries: vec![], page_id, stats, failures: Vec::new(), rendered: None }
+    }
+}
+
+/// A main-namespace page found in the XML
+#[derive(Debug)]
+pub enum ExtractedPage {
+    /// A page to parse, its text copied out of the XML
+    Raw(RawPage),
+    /// A redirect, a page without an English section or a dictionary-only
+    /// term, counted by the function. Most of the dump is one or the other,
+    /// and their text is never copied.
+    Skipped(fn(&mut Stats)),
 }
 
 /// Extract pages from XML stream into raw pages
-pub fn extract_pages_from_xml(page_xml: &str, page_id: usize) -> Option<RawPage> {
+pub fn extract_pages_from_xml(page_xml: &str, page_id: usize) -> Option<ExtractedPage> {
     // Extract title
     let title = TITLE_PATTERN.captures(page_xml)
         .map(|cap| cap[1].to_string())?;
@@ -84,201 +148,192 @@ pub fn extract_pages_from_xml(page_xml: &str, page_id: usize) -> Option<RawPage>
         return None;
     }
 
-    // Extract text
-    let text = TEXT_PATTERN.captures(page_xml)
-        .map(|cap| cap[1].to_string())?;
+    if !selected(&title) {
+        return None;
+    }
 
-    Some(RawPage { title, text, page_id })
+    // Redirects and other languages' pages are told apart on the XML itself
+    if REDIRECT_PATTERN.is_match(page_xml) {
+        return Some(ExtractedPage::Skipped(|stats| stats.redirects += 1));
+    }
+    let text = page_text(page_xml)?;
+    if !has_english_section(text) {
+        return Some(ExtractedPage::Skipped(|stats| stats.non_english += 1));
+    }
+    if DICT_ONLY.is_match(text) {
+        return Some(ExtractedPage::Skipped(|stats| stats.dict_only += 1));
+    }
+
+    // Only the English section is copied; on a page with many languages it
+    // can be a small part of the text
+    let text = english_span(text).unwrap_or(text).to_string();
+    Some(ExtractedPage::Raw(RawPage { title, text, page_id, meta: PageMeta::from_xml(page_xml) }))
 }
 
-/// Process a raw page into entries
-pub fn process_raw_page(raw: RawPage) -> ProcessedPage {
-    let title = raw.title.clone();
+/// Process a raw page into entries. With `render`, they are serialized while
+/// they still borrow from the page; otherwise they are copied out of it.
+pub fn process_raw_page(raw: RawPage, render: bool) -> ProcessedPage {
     let page_id = raw.page_id;
 
-    // Check for redirects
-    if REDIRECT_PATTERN.is_match(&raw.text) {
-        return ProcessedPage {
-            entries: vec![],
-            title,
-            page_id,
-            was_english: false,
-            was_redirect: true,
-            was_special: false,
-            was_non_latin: false,
-            was_dict_only: false,
-        };
-    }
-
-    // Check for English section
-    if !ENGLISH_SECTION.is_match(&raw.text) {
-        return ProcessedPage {
-            entries: vec![],
-            title,
-            page_id,
-            was_english: false,
-            was_redirect: false,
-            was_special: false,
-            was_non_latin: false,
-            was_dict_only: false,
-        };
+    // Check for English section (pages from the dump have been checked already)
+    if !has_english_section(&raw.text) {
+        return ProcessedPage::skipped(page_id, |stats| stats.non_english += 1);
     }
 
     // Check for dict-only
     if DICT_ONLY.is_match(&raw.text) {
-        return ProcessedPage {
-            entries: vec![],
-            title,
-            page_id,
-            was_english: true,
-            was_redirect: false,
-            was_special: false,
-            was_non_latin: false,
-            was_dict_only: true,
-        };
+        return ProcessedPage::skipped(page_id, |stats| stats.dict_only += 1);
     }
 
     // Check if English-like
-    if !is_englishlike(&raw.title) {
-        return ProcessedPage {
-            entries: vec![],
-            title,
-            page_id,
-            was_english: true,
-            was_redirect: false,
-            was_special: false,
-            was_non_latin: true,
-            was_dict_only: false,
-        };
+    let grapheme_title = match classify_title(&raw.title) {
+        TitleKind::Word => None,
+        TitleKind::Grapheme(unescaped) => Some(unescaped),
+        TitleKind::NonLatin => return non_latin_page(page_id),
+    };
+
+    // Parse page and apply output filters
+    let mut data = PageData::default();
+    let (mut entries, failures) =
+        parse_page_checked(grapheme_title.as_deref().unwrap_or(&raw.title), &raw.text, &mut data);
+    if entries.is_empty() {
+        return failed_page(failures, page_id);
     }
+    raw.meta.apply(&mut entries);
+    if grapheme_title.is_some() {
+        keep_grapheme_senses(&mut entries);
+        if entries.is_empty() {
+            return ProcessedPage { failures, ..non_latin_page(page_id) };
+        }
+    }
+    let (entries, senses_filtered) = filter_entries(entries);
 
-    // Parse page
-    let entries = parse_page(&raw.title, &raw.text);
+    let mut stats = Stats { senses_filtered, ..Stats::default() };
+    if entries.is_empty() {
+        stats.skipped += 1;
+    } else {
+        stats.count_word(&raw.title);
+    }
+    if render {
+        let rendered = Some(render_entries(entries));
+        return ProcessedPage { entries: Vec::new(), page_id, stats, failures, rendered };
+    }
+    let entries = entries.into_iter().map(Entry::into_owned).collect();
+    ProcessedPage { entries, page_id, stats, failures, rendered: None }
+}
+
+/// An English page whose title isn't English-like
+fn non_latin_page(page_id: usize) -> ProcessedPage {
+    ProcessedPage::skipped(page_id, |stats| stats.non_latin += 1)
+}
+
+/// A page that failed extraction; it counts as skipped in the stats
+fn failed_page(failures: Vec<PageFailure>, page_id: usize) -> ProcessedPage {
+    ProcessedPage { failures, ..ProcessedPage::skipped(page_id, |stats| stats.skipped += 1) }
+}
 
-    ProcessedPage {
-        entries,
-        title,
-        page_id,
-        was_english: true,
-        was_redirect: false,
-        was_special: false,
-        was_non_latin: false,
-        was_dict_only: false,
+/// Extract and process one page. Pages missing their title or text come back
+/// as failures, as in the sequential scan; other skipped pages yield None.
+///
+/// With `render`, the entries are serialized here too, taking serde_json off
+/// the single writer thread. Only a page that is written whole can be
+/// rendered, so `render` is off when --limit may cut a page's senses short.
+fn process_page_xml(page_xml: &str, page_id: usize, render: bool) -> Option<ProcessedPage> {
+    let _timer = metrics::time(Stage::Process);
+    match extract_pages_from_xml(page_xml, page_id) {
+        Some(ExtractedPage::Raw(raw)) => return Some(process_raw_page(raw, render)),
+        Some(ExtractedPage::Skipped(count)) => return Some(ProcessedPage::skipped(page_id, count)),
+        None => {}
+    }
+
+    let Some(cap) = TITLE_PATTERN.captures(page_xml) else {
+        let failure = PageFailure::new("", FailureCategory::NoTitle, page_xml);
+        return Some(failed_page(vec![failure], page_id));
+    };
+    let title = &cap[1];
+    let special = NS_PATTERN.captures(page_xml).is_some_and(|ns| &ns[1] != "0")
+        || get_special_prefixes().iter().any(|prefix| title.starts_with(prefix));
+    if special || !selected(title) || page_text(page_xml).is_some() {
+        return None;
     }
+    let failure = PageFailure::new(title, FailureCategory::NoText, page_xml);
+    Some(failed_page(vec![failure], page_id))
 }
 
+/// Count a page the writer is about to write: its partial, computed on the
+/// worker, is merged without classifying anything here
 fn update_stats_from_result(stats: &mut Stats, result: &ProcessedPage) {
-    if result.was_redirect {
-        stats.redirects += 1;
-    } else if result.was_special {
-        stats.special += 1;
-    } else if !result.was_english {
-        stats.non_english += 1;
-    } else if result.was_dict_only {
-        stats.dict_only += 1;
-    } else if result.was_non_latin {
-        stats.non_latin += 1;
-    } else if result.entries.is_empty() {
-        stats.skipped += 1;
-    } else {
-        stats.words_written += 1;
-        // Track case distribution for reporting
-        match classify_case(&result.title) {
-            CaseForm::Lower => stats.case_lower += 1,
-            CaseForm::Title => stats.case_title += 1,
-            CaseForm::Upper => stats.case_upper += 1,
-            CaseForm::Mixed => stats.case_mixed += 1,
-        }
-    }
+    stats.pages_processed += 1;
+    metrics::page_written();
+    stats.merge(&result.stats);
+    result.failures.iter().for_each(record_failure);
+}
+
+/// Count and write one page, copying its rendered lines if it has them.
+/// Returns true once the entry or word limit is reached.
+fn write_result<W: Write>(writer: &mut W, result: ProcessedPage, stats: &mut Stats, limit: OutputLimit) -> std::io::Result<bool> {
+    update_stats_from_result(stats, &result);
+    stats.senses_written += match result.rendered {
+        Some(rendered) => write_rendered(writer, rendered)?,
+        None => write_entries(writer, result.entries, limit.room(stats))?,
+    };
+    Ok(limit.reached(stats))
 }
 
 /// Strategy 1: Batch-Parallel Processing using std::thread
-/// Collects pages into batches, then processes each batch using a thread pool
+/// Collects pages into batches, then processes each batch using a thread pool.
+/// Batches are double-buffered: while one batch is processed in the background,
+/// the next one is read, and the finished batch is written once it is handed off.
 pub fn process_batch_parallel<W: Write>(
     reader: impl BufRead,
     writer: &mut BufWriter<W>,
     config: &ParallelConfig,
-    limit: Option<usize>,
+    limit: OutputLimit,
 ) -> std::io::Result<Stats> {
     let start_time = Instant::now();
     let mut stats = Stats::default();
     let mut batch: Vec<String> = Vec::with_capacity(config.batch_size);
     let mut page_id: usize = 0;
-
-    let mut buffer = String::new();
-    let mut chunk = vec![0u8; 1024 * 1024];
-    let mut reader = reader;
-
-    loop {
-        let bytes_read = reader.read(&mut chunk)?;
-        if bytes_read == 0 {
-            break;
+    // Batch being processed while the next one is read
+    let mut in_flight: Option<JoinHandle<Vec<ProcessedPage>>> = None;
+    let mut done = false;
+    let mut result = Ok(());
+
+    crate::scan_pages(reader, |page_xml| {
+        batch.push(page_xml);
+        page_id += 1;
+        if batch.len() < config.batch_size {
+            return true;
         }
 
-        buffer.push_str(&String::from_utf8_lossy(&chunk[..bytes_read]));
-
-        // Extract complete pages into batch
-        while let Some(start) = buffer.find("<page>") {
-            if let Some(end_offset) = buffer[start..].find("</page>") {
-                let end = start + end_offset + "</page>".len();
-                let page_xml = buffer[start..end].to_string();
-                buffer.drain(..end);
-
-                batch.push(page_xml);
-                page_id += 1;
-
-                // Process batch when full
-                if batch.len() >= config.batch_size {
-                    let base_id = page_id - batch.len();
-                    let results = process_batch_threaded(&batch, base_id, config.num_threads);
-                    batch.clear();
-
-                    for result in results {
-                        stats.pages_processed += 1;
-                        update_stats_from_result(&mut stats, &result);
-
-                        for entry in result.entries {
-                            if let Ok(json) = serde_json::to_string(&entry) {
-                                writeln!(writer, "{}", json)?;
-                                stats.senses_written += 1;
-
-                                if let Some(l) = limit {
-                                    if stats.senses_written >= l {
-                                        stats.elapsed = start_time.elapsed();
-                                        return Ok(stats);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            } else {
-                buffer.drain(..start);
-                break;
+        let full = std::mem::replace(&mut batch, Vec::with_capacity(config.batch_size));
+        let base_id = page_id - full.len();
+        let num_threads = config.num_threads;
+        let render = limit.whole_pages();
+        let next = thread::spawn(move || process_batch_threaded(&full, base_id, num_threads, render));
+
+        match write_batch(in_flight.replace(next), writer, &mut stats, limit) {
+            Ok(limit_reached) => {
+                done = limit_reached;
+                !limit_reached
+            }
+            Err(e) => {
+                result = Err(e);
+                false
             }
         }
+    })?;
+    result?;
 
-        if buffer.len() > 10 && !buffer.contains("<page>") {
-            buffer.drain(..buffer.len().saturating_sub(10));
-        }
+    if !done {
+        done = write_batch(in_flight.take(), writer, &mut stats, limit)?;
     }
 
     // Process remaining batch
-    if !batch.is_empty() {
+    if !done && !batch.is_empty() {
         let base_id = page_id - batch.len();
-        let results = process_batch_threaded(&batch, base_id, config.num_threads);
-
-        for result in results {
-            stats.pages_processed += 1;
-            update_stats_from_result(&mut stats, &result);
-
-            for entry in result.entries {
-                if let Ok(json) = serde_json::to_string(&entry) {
-                    writeln!(writer, "{}", json)?;
-                    stats.senses_written += 1;
-                }
-            }
-        }
+        let results = process_batch_threaded(&batch, base_id, config.num_threads, limit.whole_pages());
+        write_results(results, writer, &mut stats, limit)?;
     }
 
     writer.flush()?;
@@ -286,14 +341,28 @@ pub fn process_batch_parallel<W: Write>(
     Ok(stats)
 }
 
+/// Wait for a background batch, if any, and write its results
+fn write_batch<W: Write>(
+    handle: Option<JoinHandle<Vec<ProcessedPage>>>,
+    writer: &mut BufWriter<W>,
+    stats: &mut Stats,
+    limit: OutputLimit,
+) -> std::io::Result<bool> {
+    match handle.map(|handle| handle.join()) {
+        Some(Ok(results)) => write_results(results, writer, stats, limit),
+        Some(Err(_)) => Err(std::io::Error::other("batch worker thread panicked")),
+        None => Ok(false),
+    }
+}
+
 /// Process a batch of pages using multiple threads
-fn process_batch_threaded(batch: &[String], base_id: usize, num_threads: usize) -> Vec<ProcessedPage> {
+fn process_batch_threaded(batch: &[String], base_id: usize, num_threads: usize, render: bool) -> Vec<ProcessedPage> {
     if batch.is_empty() {
         return vec![];
     }
 
     let num_threads = num_threads.min(batch.len()).max(1);
-    let chunk_size = (batch.len() + num_threads - 1) / num_threads;
+    let chunk_size = batch.len().div_ceil(num_threads);
 
     // Split batch into chunks for each thread
     let chunks: Vec<Vec<(usize, String)>> = batch
@@ -312,9 +381,7 @@ fn process_batch_threaded(batch: &[String], base_id: usize, num_threads: usize)
             thread::spawn(move || {
                 chunk
                     .into_iter()
-                    .filter_map(|(pid, xml)| {
-                        extract_pages_from_xml(&xml, pid).map(process_raw_page)
-                    })
+                    .filter_map(|(pid, xml)| process_page_xml(&xml, pid, render))
                     .collect()
             })
         })
@@ -331,40 +398,113 @@ fn process_batch_threaded(batch: &[String], base_id: usize, num_threads: usize)
     results
 }
 
+/// Queue-depth counters shared by the channel pipeline threads, in pages
+#[derive(Debug, Default)]
+struct PipelineMetrics {
+    pages_sent: AtomicUsize,
+    pages_taken: AtomicUsize,
+    results_sent: AtomicUsize,
+    results_received: AtomicUsize,
+    peak_page_depth: AtomicUsize,
+    peak_result_depth: AtomicUsize,
+}
+
+impl PipelineMetrics {
+    fn page_depth(&self) -> usize {
+        self.pages_sent.load(Ordering::Relaxed).saturating_sub(self.pages_taken.load(Ordering::Relaxed))
+    }
+
+    fn result_depth(&self) -> usize {
+        self.results_sent.load(Ordering::Relaxed).saturating_sub(self.results_received.load(Ordering::Relaxed))
+    }
+}
+
+/// State shared by the reader, workers, writer and (with --autoscale) the controller
+#[derive(Debug)]
+struct PipelineShared {
+    metrics: PipelineMetrics,
+    autoscale: bool,
+    /// Workers with an index below this take pages; the rest stay parked
+    active_workers: AtomicUsize,
+    max_workers: usize,
+    /// Pages the reader may queue ahead of the workers
+    queue_limit: AtomicUsize,
+    max_queue: usize,
+    input_done: AtomicBool,
+    /// Whether workers serialize their pages' entries
+    render: bool,
+}
+
+impl PipelineShared {
+    fn new(config: &ParallelConfig, limit: OutputLimit) -> Self {
+        let (max_workers, max_queue) = if config.autoscale {
+            let cpus = thread::available_parallelism().map(|p| p.get()).unwrap_or(4);
+            (cpus.max(config.num_workers), config.channel_buffer * 8)
+        } else {
+            (config.num_workers, config.channel_buffer)
+        };
+        PipelineShared {
+            metrics: PipelineMetrics::default(),
+            autoscale: config.autoscale,
+            active_workers: AtomicUsize::new(config.num_workers),
+            max_workers,
+            queue_limit: AtomicUsize::new(config.channel_buffer),
+            max_queue,
+            input_done: AtomicBool::new(false),
+            render: limit.whole_pages(),
+        }
+    }
+}
+
 /// Strategy 2: Channel-Pipeline Processing using std::sync::mpsc
 /// Producer thread reads XML, worker threads process pages, writer collects results
-/// Results are buffered and sorted by page_id to ensure deterministic output order
+/// Pages travel in chunks of consecutive pages, and each worker returns a chunk's
+/// results in page order, so the writer only has to put whole chunks back in
+/// order: the reorder buffer holds a chunk per straggling worker rather than a
+/// result per page.
+///
+/// With `autoscale`, a controller thread samples the page queue: a queue that
+/// stays full means the workers are the bottleneck, so another worker is
+/// activated (or, with all active, the reader may queue further ahead); a queue
+/// that stays empty means reading is, so a worker is parked to leave the CPU
+/// to decompression.
 pub fn process_channel_pipeline<W: Write + Send + 'static>(
     reader: impl BufRead + Send + 'static,
     writer: W,
     config: &ParallelConfig,
-    limit: Option<usize>,
+    limit: OutputLimit,
 ) -> std::io::Result<Stats> {
-    // Channel now sends (page_id, xml) tuples to track original order
-    let (page_tx, page_rx): (SyncSender<(usize, String)>, Receiver<(usize, String)>) =
-        sync_channel(config.channel_buffer);
-    let (result_tx, result_rx): (SyncSender<ProcessedPage>, Receiver<ProcessedPage>) =
-        sync_channel(config.channel_buffer);
+    let shared = Arc::new(PipelineShared::new(config, limit));
+
+    // Queue sizes are given in pages; the channels hold chunks
+    let chunk_pages = config.chunk_pages.max(1);
+    let (page_tx, page_rx): (SyncSender<PageChunk>, Receiver<PageChunk>) =
+        sync_channel(shared.max_queue.div_ceil(chunk_pages));
+    let (result_tx, result_rx): (SyncSender<ProcessedChunk>, Receiver<ProcessedChunk>) =
+        sync_channel(config.channel_buffer.div_ceil(chunk_pages));
 
     let limit_reached = Arc::new(AtomicBool::new(false));
     let start_time = Instant::now();
 
     // Spawn reader thread
     let reader_limit_flag = Arc::clone(&limit_reached);
+    let reader_shared = Arc::clone(&shared);
     let reader_handle = thread::spawn(move || {
-        read_pages_to_channel(reader, page_tx, &reader_limit_flag)
+        let result = read_pages_to_channel(reader, page_tx, chunk_pages, &reader_limit_flag, &reader_shared);
+        reader_shared.input_done.store(true, Ordering::SeqCst);
+        result
     });
 
-    // Spawn worker threads
-    let num_workers = config.num_workers;
+    // Spawn worker threads; beyond the active count they start parked
     let page_rx = Arc::new(Mutex::new(page_rx));
-    let worker_handles: Vec<JoinHandle<()>> = (0..num_workers)
-        .map(|_| {
+    let worker_handles: Vec<JoinHandle<()>> = (0..shared.max_workers)
+        .map(|index| {
             let rx = Arc::clone(&page_rx);
             let tx = result_tx.clone();
             let limit_flag = Arc::clone(&limit_reached);
+            let worker_shared = Arc::clone(&shared);
             thread::spawn(move || {
-                process_pages_worker(rx, tx, &limit_flag)
+                process_pages_worker(index, rx, tx, &limit_flag, &worker_shared)
             })
         })
         .collect();
@@ -372,89 +512,182 @@ pub fn process_channel_pipeline<W: Write + Send + 'static>(
     // Drop extra sender so channel closes when workers finish
     drop(result_tx);
 
+    let controller_handle = shared.autoscale.then(|| {
+        let controller_shared = Arc::clone(&shared);
+        let limit_flag = Arc::clone(&limit_reached);
+        thread::spawn(move || autoscale_controller(&controller_shared, &limit_flag))
+    });
+
     // Writer in main thread - buffers and sorts results for deterministic output
-    let final_stats = write_results_sorted(result_rx, writer, limit, &limit_reached)?;
+    let mut pending = ReorderBuffer::new(config.reorder_limit);
+    let final_stats = write_results_sorted(result_rx, writer, limit, &limit_reached, &shared.metrics, &mut pending)?;
+    if pending.spilled() > 0 {
+        eprintln!("Reorder buffer: {} out-of-order pages spilled to disk", pending.spilled());
+    }
 
     // Wait for threads
     reader_handle.join().ok();
     for handle in worker_handles {
         handle.join().ok();
     }
+    if let Some(handle) = controller_handle {
+        handle.join().ok();
+        if !config.quiet {
+            eprintln!(
+                "Autoscale: {} of {} workers active, queue limit {}, peak page queue {}, peak result queue {}",
+                shared.active_workers.load(Ordering::Relaxed),
+                shared.max_workers,
+                shared.queue_limit.load(Ordering::Relaxed),
+                shared.metrics.peak_page_depth.load(Ordering::Relaxed),
+                shared.metrics.peak_result_depth.load(Ordering::Relaxed),
+            );
+        }
+    }
 
     let mut stats = final_stats;
     stats.elapsed = start_time.elapsed();
     Ok(stats)
 }
 
-fn read_pages_to_channel(
-    mut reader: impl BufRead,
-    tx: SyncSender<(usize, String)>,
-    limit_reached: &AtomicBool,
-) -> std::io::Result<usize> {
-    let mut buffer = String::new();
-    let mut chunk = vec![0u8; 1024 * 1024];
-    let mut page_id: usize = 0;
+/// Samples the page queue and adjusts the active workers and queue limit
+fn autoscale_controller(shared: &PipelineShared, limit_reached: &AtomicBool) {
+    const SAMPLE_INTERVAL: Duration = Duration::from_millis(50);
+    // Consecutive samples in one state before acting on it
+    const PATIENCE: usize = 3;
+
+    let mut full_samples = 0;
+    let mut empty_samples = 0;
 
     loop {
-        if limit_reached.load(Ordering::Relaxed) {
+        thread::sleep(SAMPLE_INTERVAL);
+        let input_done = shared.input_done.load(Ordering::SeqCst);
+        if limit_reached.load(Ordering::Relaxed) || input_done {
             break;
         }
 
-        let bytes_read = reader.read(&mut chunk)?;
-        if bytes_read == 0 {
-            break;
+        let depth = shared.metrics.page_depth();
+        let queue_limit = shared.queue_limit.load(Ordering::Relaxed);
+        if depth * 10 >= queue_limit * 9 {
+            full_samples += 1;
+            empty_samples = 0;
+        } else if depth == 0 {
+            empty_samples += 1;
+            full_samples = 0;
+        } else {
+            full_samples = 0;
+            empty_samples = 0;
         }
 
-        buffer.push_str(&String::from_utf8_lossy(&chunk[..bytes_read]));
+        if full_samples >= PATIENCE {
+            full_samples = 0;
+            let active = shared.active_workers.load(Ordering::Relaxed);
+            if active < shared.max_workers {
+                shared.active_workers.store(active + 1, Ordering::Relaxed);
+            } else if queue_limit < shared.max_queue {
+                shared.queue_limit.store((queue_limit * 2).min(shared.max_queue), Ordering::Relaxed);
+            }
+        } else if empty_samples >= PATIENCE {
+            empty_samples = 0;
+            let active = shared.active_workers.load(Ordering::Relaxed);
+            if active > 1 {
+                shared.active_workers.store(active - 1, Ordering::Relaxed);
+            }
+        }
+    }
+}
 
-        while let Some(start) = buffer.find("<page>") {
-            if let Some(end_offset) = buffer[start..].find("</page>") {
-                let end = start + end_offset + "</page>".len();
-                let page_xml = buffer[start..end].to_string();
-                buffer.drain(..end);
+fn read_pages_to_channel(
+    reader: impl BufRead,
+    tx: SyncSender<PageChunk>,
+    chunk_pages: usize,
+    limit_reached: &AtomicBool,
+    shared: &PipelineShared,
+) -> std::io::Result<usize> {
+    let metrics = &shared.metrics;
+    let mut page_id: usize = 0;
+    let mut chunk_id: usize = 0;
+    let mut pages: Vec<String> = Vec::with_capacity(chunk_pages);
+
+    let mut send = |pages: Vec<String>, first_page_id: usize| -> bool {
+        // The channel itself is sized for the largest queue autoscale allows
+        while shared.autoscale
+            && metrics.page_depth() >= shared.queue_limit.load(Ordering::Relaxed)
+            && !limit_reached.load(Ordering::Relaxed)
+        {
+            thread::sleep(Duration::from_millis(1));
+        }
 
-                if tx.send((page_id, page_xml)).is_err() {
-                    return Ok(page_id);
-                }
-                page_id += 1;
-            } else {
-                buffer.drain(..start);
-                break;
-            }
+        let count = pages.len();
+        if limit_reached.load(Ordering::Relaxed) || tx.send(PageChunk { chunk_id, first_page_id, pages }).is_err() {
+            return false;
         }
+        chunk_id += 1;
+        let sent = metrics.pages_sent.fetch_add(count, Ordering::Relaxed) + count;
+        let depth = sent.saturating_sub(metrics.pages_taken.load(Ordering::Relaxed));
+        metrics.peak_page_depth.fetch_max(depth, Ordering::Relaxed);
+        true
+    };
 
-        if buffer.len() > 10 && !buffer.contains("<page>") {
-            buffer.drain(..buffer.len().saturating_sub(10));
+    crate::scan_pages(reader, |page_xml| {
+        pages.push(page_xml);
+        page_id += 1;
+        if pages.len() < chunk_pages {
+            return true;
         }
-    }
+        let full = std::mem::replace(&mut pages, Vec::with_capacity(chunk_pages));
+        send(full, page_id - chunk_pages)
+    })?;
 
+    if !pages.is_empty() {
+        let first_page_id = page_id - pages.len();
+        send(pages, first_page_id);
+    }
     Ok(page_id)
 }
 
 fn process_pages_worker(
-    rx: Arc<Mutex<Receiver<(usize, String)>>>,
-    tx: SyncSender<ProcessedPage>,
+    index: usize,
+    rx: Arc<Mutex<Receiver<PageChunk>>>,
+    tx: SyncSender<ProcessedChunk>,
     limit_reached: &AtomicBool,
+    shared: &PipelineShared,
 ) {
+    let metrics = &shared.metrics;
     loop {
         if limit_reached.load(Ordering::Relaxed) {
             break;
         }
 
-        // Try to get next page from shared receiver
+        // Parked workers wait to be activated; once input ends the active ones drain the queue
+        if index >= shared.active_workers.load(Ordering::Relaxed) {
+            if shared.input_done.load(Ordering::SeqCst) {
+                break;
+            }
+            thread::sleep(Duration::from_millis(10));
+            continue;
+        }
+
+        // Try to get next chunk from shared receiver
         let item = {
             let lock = rx.lock().ok();
             lock.and_then(|guard| guard.recv().ok())
         };
 
         match item {
-            Some((page_id, xml)) => {
-                if let Some(raw) = extract_pages_from_xml(&xml, page_id) {
-                    let result = process_raw_page(raw);
-                    if tx.send(result).is_err() {
-                        break;
-                    }
+            Some(chunk) => {
+                metrics.pages_taken.fetch_add(chunk.pages.len(), Ordering::Relaxed);
+                let pages: Vec<ProcessedPage> = chunk
+                    .pages
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, xml)| process_page_xml(xml, chunk.first_page_id + i, shared.render))
+                    .collect();
+                let count = pages.len();
+                if tx.send(ProcessedChunk { chunk_id: chunk.chunk_id, pages }).is_err() {
+                    break;
                 }
+                metrics.results_sent.fetch_add(count, Ordering::Relaxed);
+                metrics.peak_result_depth.fetch_max(metrics.result_depth(), Ordering::Relaxed);
             }
             None => break,
         }
@@ -463,64 +696,54 @@ fn process_pages_worker(
 
 /// Write results in deterministic order using a streaming reorder buffer.
 ///
-/// Uses a BTreeMap to buffer out-of-order results while writing in-order results
-/// immediately. This minimizes memory usage when results arrive roughly in order,
-/// only buffering entries that arrive before their predecessors complete.
+/// Chunks that arrive before their turn wait in `pending` while the next chunk
+/// is written as soon as it arrives. This minimizes memory usage when chunks
+/// arrive roughly in order, only buffering those that finish before their
+/// predecessors; past its limit the buffer spills to disk (see reorder.rs).
 fn write_results_sorted<W: Write>(
-    rx: Receiver<ProcessedPage>,
+    rx: Receiver<ProcessedChunk>,
     writer: W,
-    limit: Option<usize>,
+    limit: OutputLimit,
     limit_reached: &AtomicBool,
+    metrics: &PipelineMetrics,
+    pending: &mut ReorderBuffer,
 ) -> std::io::Result<Stats> {
     let mut writer = BufWriter::with_capacity(256 * 1024, writer);
     let mut stats = Stats::default();
 
-    // Reorder buffer: holds results that arrived before their turn
-    let mut pending: BTreeMap<usize, ProcessedPage> = BTreeMap::new();
-    // Next page_id we're waiting to write
+    // Next chunk_id we're waiting to write
     let mut next_expected: usize = 0;
-    // Track max buffer size for debugging/monitoring
-    let mut _max_buffer_size: usize = 0;
 
-    // Helper closure to write a single result and update stats
+    // Helper closure to write a chunk's results and update stats
     // Returns true if limit was reached
-    let write_result = |result: ProcessedPage,
-                            stats: &mut Stats,
-                            writer: &mut BufWriter<W>| -> std::io::Result<bool> {
-        stats.pages_processed += 1;
-        update_stats_from_result(stats, &result);
-
-        for entry in result.entries {
-            if let Ok(json) = serde_json::to_string(&entry) {
-                writeln!(writer, "{}", json)?;
-                stats.senses_written += 1;
-
-                if let Some(l) = limit {
-                    if stats.senses_written >= l {
-                        return Ok(true); // limit reached
-                    }
-                }
+    let write_chunk = |chunk: ProcessedChunk,
+                           stats: &mut Stats,
+                           writer: &mut BufWriter<W>| -> std::io::Result<bool> {
+        for result in chunk.pages {
+            if write_result(writer, result, stats, limit)? {
+                return Ok(true);
             }
         }
         Ok(false)
     };
 
-    // Process results as they arrive
-    for result in rx {
-        let page_id = result.page_id;
+    // Process chunks as they arrive
+    for chunk in rx {
+        metrics.results_received.fetch_add(chunk.pages.len(), Ordering::Relaxed);
+        crate::metrics::set_queues(metrics.page_depth(), metrics.result_depth(), pending.len());
 
-        if page_id == next_expected {
-            // This is the next result we're waiting for - write it immediately
-            if write_result(result, &mut stats, &mut writer)? {
+        if chunk.chunk_id == next_expected {
+            // This is the next chunk we're waiting for - write it immediately
+            if write_chunk(chunk, &mut stats, &mut writer)? {
                 limit_reached.store(true, Ordering::SeqCst);
                 writer.flush()?;
                 return Ok(stats);
             }
             next_expected += 1;
 
-            // Drain any buffered results that are now ready
-            while let Some(buffered) = pending.remove(&next_expected) {
-                if write_result(buffered, &mut stats, &mut writer)? {
+            // Drain any buffered chunks that are now ready
+            while let Some(buffered) = pending.take(next_expected)? {
+                if write_chunk(buffered, &mut stats, &mut writer)? {
                     limit_reached.store(true, Ordering::SeqCst);
                     writer.flush()?;
                     return Ok(stats);
@@ -528,16 +751,17 @@ fn write_results_sorted<W: Write>(
                 next_expected += 1;
             }
         } else {
-            // This result arrived out of order - buffer it
-            pending.insert(page_id, result);
-            _max_buffer_size = _max_buffer_size.max(pending.len());
+            // This chunk arrived out of order - buffer it
+            pending.insert(chunk)?;
         }
     }
 
-    // Drain any remaining buffered results (shouldn't happen if all pages processed)
-    while let Some((&page_id, _)) = pending.first_key_value() {
-        if let Some(result) = pending.remove(&page_id) {
-            if write_result(result, &mut stats, &mut writer)? {
+    crate::metrics::set_queues(metrics.page_depth(), metrics.result_depth(), pending.len());
+
+    // Drain any remaining buffered chunks (only after a worker failed)
+    while let Some(chunk_id) = pending.first_id() {
+        if let Some(chunk) = pending.take(chunk_id)? {
+            if write_chunk(chunk, &mut stats, &mut writer)? {
                 limit_reached.store(true, Ordering::SeqCst);
                 writer.flush()?;
                 return Ok(stats);
@@ -552,46 +776,80 @@ fn write_results_sorted<W: Write>(
 /// Strategy 3: Two-Phase Processing
 /// Phase 1: Read all pages into memory
 /// Phase 2: Process all pages in parallel with multiple threads
+///
+/// With a memory budget the two phases repeat over bounded chunks of the dump:
+/// pages are read until their XML reaches the budget, then that chunk is
+/// processed and written before the next one is read.
 pub fn process_two_phase<W: Write>(
     reader: impl BufRead,
     writer: &mut BufWriter<W>,
     config: &ParallelConfig,
-    limit: Option<usize>,
+    limit: OutputLimit,
 ) -> std::io::Result<Stats> {
     let start_time = Instant::now();
+    let mut stats = Stats::default();
 
-    // Phase 1: Read all pages
-    eprintln!("Phase 1: Reading pages...");
-    let pages = read_all_pages(reader)?;
-    eprintln!("Read {} pages in {:?}", pages.len(), start_time.elapsed());
+    let Some(budget) = config.memory_budget else {
+        // Phase 1: Read all pages
+        eprintln!("Phase 1: Reading pages...");
+        let pages = read_all_pages(reader)?;
+        eprintln!("Read {} pages in {:?}", pages.len(), start_time.elapsed());
 
-    // Phase 2: Process in parallel
-    eprintln!("Phase 2: Processing in parallel with {} threads...", config.num_threads);
-    let process_start = Instant::now();
+        // Phase 2: Process in parallel
+        eprintln!("Phase 2: Processing in parallel with {} threads...", config.num_threads);
+        let process_start = Instant::now();
 
-    let results = process_all_pages_parallel(&pages, config.num_threads);
+        let results = process_all_pages_parallel(pages, 0, config.num_threads, limit.whole_pages());
 
-    eprintln!("Processed {} results in {:?}", results.len(), process_start.elapsed());
+        eprintln!("Processed {} results in {:?}", results.len(), process_start.elapsed());
 
-    // Phase 3: Write results
-    let mut stats = Stats::default();
-    for result in results {
-        stats.pages_processed += 1;
-        update_stats_from_result(&mut stats, &result);
-
-        for entry in result.entries {
-            if let Ok(json) = serde_json::to_string(&entry) {
-                writeln!(writer, "{}", json)?;
-                stats.senses_written += 1;
-
-                if let Some(l) = limit {
-                    if stats.senses_written >= l {
-                        stats.elapsed = start_time.elapsed();
-                        return Ok(stats);
-                    }
-                }
+        // Phase 3: Write results
+        write_results(results, writer, &mut stats, limit)?;
+        writer.flush()?;
+        stats.elapsed = start_time.elapsed();
+        return Ok(stats);
+    };
+
+    eprintln!("Two-phase in chunks of up to {} MB of page XML", budget / (1024 * 1024));
+    let mut pages: Vec<String> = Vec::new();
+    let mut held_bytes = 0;
+    let mut base_id = 0;
+    let mut chunk_count = 0;
+    let mut done = false;
+    let mut result = Ok(());
+
+    let mut run_chunk = |pages: &mut Vec<String>, base_id: &mut usize, stats: &mut Stats| -> std::io::Result<bool> {
+        chunk_count += 1;
+        let chunk_start = Instant::now();
+        let page_count = pages.len();
+        let results = process_all_pages_parallel(std::mem::take(pages), *base_id, config.num_threads, limit.whole_pages());
+        *base_id += page_count;
+        eprintln!("Chunk {}: {} pages processed in {:?}", chunk_count, page_count, chunk_start.elapsed());
+        write_results(results, writer, stats, limit)
+    };
+
+    crate::scan_pages(reader, |page_xml| {
+        held_bytes += page_xml.len();
+        pages.push(page_xml);
+        if held_bytes < budget {
+            return true;
+        }
+        held_bytes = 0;
+        match run_chunk(&mut pages, &mut base_id, &mut stats) {
+            Ok(limit_reached) => {
+                done = limit_reached;
+                !limit_reached
+            }
+            Err(e) => {
+                result = Err(e);
+                false
             }
         }
+    })?;
+    result?;
+
+    if !done && !pages.is_empty() {
+        run_chunk(&mut pages, &mut base_id, &mut stats)?;
     }
 
     writer.flush()?;
@@ -599,80 +857,62 @@ pub fn process_two_phase<W: Write>(
     Ok(stats)
 }
 
-fn read_all_pages(mut reader: impl BufRead) -> std::io::Result<Vec<String>> {
-    let mut pages = Vec::new();
-    let mut buffer = String::new();
-    let mut chunk = vec![0u8; 1024 * 1024];
-
-    loop {
-        let bytes_read = reader.read(&mut chunk)?;
-        if bytes_read == 0 {
-            break;
-        }
-
-        buffer.push_str(&String::from_utf8_lossy(&chunk[..bytes_read]));
-
-        while let Some(start) = buffer.find("<page>") {
-            if let Some(end_offset) = buffer[start..].find("</page>") {
-                let end = start + end_offset + "</page>".len();
-                let page_xml = buffer[start..end].to_string();
-                buffer.drain(..end);
-                pages.push(page_xml);
-            } else {
-                buffer.drain(..start);
-                break;
-            }
-        }
-
-        if buffer.len() > 10 && !buffer.contains("<page>") {
-            buffer.drain(..buffer.len().saturating_sub(10));
+/// Write a batch or phase of results in order. Returns true once the entry or word limit is reached.
+fn write_results<W: Write>(
+    results: Vec<ProcessedPage>,
+    writer: &mut BufWriter<W>,
+    stats: &mut Stats,
+    limit: OutputLimit,
+) -> std::io::Result<bool> {
+    for result in results {
+        if write_result(writer, result, stats, limit)? {
+            return Ok(true);
         }
     }
+    Ok(false)
+}
 
+fn read_all_pages(reader: impl BufRead) -> std::io::Result<Vec<String>> {
+    let mut pages = Vec::new();
+    crate::scan_pages(reader, |page_xml| {
+        pages.push(page_xml);
+        true
+    })?;
     Ok(pages)
 }
 
 /// Process all pages in parallel using std::thread
-fn process_all_pages_parallel(pages: &[String], num_threads: usize) -> Vec<ProcessedPage> {
+fn process_all_pages_parallel(pages: Vec<String>, base_id: usize, num_threads: usize, render: bool) -> Vec<ProcessedPage> {
     if pages.is_empty() {
         return vec![];
     }
 
-    let num_threads = num_threads.min(pages.len()).max(1);
-    let chunk_size = (pages.len() + num_threads - 1) / num_threads;
+    let page_count = pages.len();
+    let num_threads = num_threads.min(page_count).max(1);
+    let chunk_size = page_count.div_ceil(num_threads);
 
-    // Create indexed chunks
-    let chunks: Vec<Vec<(usize, &String)>> = pages
-        .iter()
-        .enumerate()
-        .collect::<Vec<_>>()
-        .chunks(chunk_size)
-        .map(|c| c.to_vec())
+    // Move the pages into indexed chunks, one per thread: the threads need
+    // 'static data, and a copy would hold every page twice
+    let mut indexed = pages.into_iter().enumerate().map(|(i, xml)| (base_id + i, xml));
+    let chunks: Vec<Vec<(usize, String)>> = (0..num_threads)
+        .map(|_| indexed.by_ref().take(chunk_size).collect())
         .collect();
 
     // Process chunks in parallel
-    // Note: We need to clone pages since threads need 'static lifetime
-    let chunks_owned: Vec<Vec<(usize, String)>> = chunks
-        .into_iter()
-        .map(|c| c.into_iter().map(|(i, s)| (i, s.clone())).collect())
-        .collect();
-
-    let handles: Vec<JoinHandle<Vec<ProcessedPage>>> = chunks_owned
+    let handles: Vec<JoinHandle<Vec<ProcessedPage>>> = chunks
         .into_iter()
         .map(|chunk| {
             thread::spawn(move || {
                 chunk
                     .into_iter()
-                    .filter_map(|(pid, xml)| {
-                        extract_pages_from_xml(&xml, pid).map(process_raw_page)
-                    })
+                    .filter_map(|(pid, xml)| process_page_xml(&xml, pid, render))
                     .collect()
             })
         })
         .collect();
 
     // Collect results preserving order
-    let mut all_results: Vec<ProcessedPage> = Vec::with_capacity(pages.len());
+    let mut all_results: Vec<ProcessedPage> = Vec::with_capacity(page_count);
     for handle in handles {
         if let Ok(chunk_results) = handle.join() {
             all_results.extend(chunk_results);
@@ -681,3 +921,47 @@ fn process_all_pages_parallel(pages: &[String], num_threads: usize) -> Vec<Proce
 
     all_results
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wait up to ten seconds (the controller acts every 150 ms) for `done`
+    fn wait_for(done: impl Fn() -> bool) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !done() {
+            if Instant::now() > deadline {
+                return false;
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+        true
+    }
+
+    #[test]
+    fn autoscale_follows_the_page_queue() {
+        let config = ParallelConfig { num_workers: 2, channel_buffer: 4, autoscale: true, ..ParallelConfig::default() };
+        let shared = Arc::new(PipelineShared::new(&config, OutputLimit::default()));
+        let limit_reached = Arc::new(AtomicBool::new(false));
+        let controller = {
+            let shared = Arc::clone(&shared);
+            let limit_reached = Arc::clone(&limit_reached);
+            thread::spawn(move || autoscale_controller(&shared, &limit_reached))
+        };
+        let active = || shared.active_workers.load(Ordering::Relaxed);
+        let queue_limit = || shared.queue_limit.load(Ordering::Relaxed);
+
+        // A full queue: the workers are the bottleneck, so one more is activated,
+        // or with no more to activate the reader may queue further ahead
+        shared.metrics.pages_sent.store(4, Ordering::Relaxed);
+        assert!(wait_for(|| active() > 2 || queue_limit() > 4), "no scaling up on a full queue");
+        assert!(active() <= shared.max_workers && queue_limit() <= shared.max_queue);
+
+        // An empty queue: reading is the bottleneck, so workers are parked down to one
+        shared.metrics.pages_taken.store(4, Ordering::Relaxed);
+        assert!(wait_for(|| active() == 1), "workers still active on an empty queue");
+
+        shared.input_done.store(true, Ordering::SeqCst);
+        controller.join().unwrap();
+    }
+}
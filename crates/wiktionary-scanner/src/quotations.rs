@@ -0,0 +1,153 @@
+//! Quotations illustrating a sense, with `--quotations`.
+//!
+//! A definition line is followed by its quotations on `#*` lines, written
+//! either with a quotation template or as a citation with the passage on
+//! the `#*:` line below:
+//!
+//! ```text
+//! #* {{quote-book|en|year=1851|author={{w|Herman Melville}}|title=Moby-Dick|passage=Call me '''Ishmael'''.}}
+//! #* '''1851''', Herman Melville, ''Moby-Dick'':
+//! #*: Call me '''Ishmael'''.
+//! ```
+//!
+//! Both become `{"year":1851,"author":"Herman Melville","text":"Call me Ishmael."}`.
+//! Quotations without passage text are left out.
+
+use crate::{appendix, unescape_xml, MarkupMode, WikitextParser};
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+lazy_static! {
+    // {{quote-book}}, {{quote-journal}}, {{quote-web}}, ... and {{RQ:...}} reference quotations
+    static ref QUOTE_TEMPLATE: Regex = Regex::new(r"(?i)^\{\{\s*(?:quote-[a-z-]+|RQ:[^|}]+)\s*[|}]").unwrap();
+    static ref YEAR: Regex = Regex::new(r"\b(\d{3,4})\b").unwrap();
+    static ref LINE_BREAK: Regex = Regex::new(r"(?i)<br\s*/?>").unwrap();
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Quotation {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub year: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub text: String,
+}
+
+fn first_year(text: &str) -> Option<u32> {
+    YEAR.captures(text).and_then(|cap| cap[1].parse().ok())
+}
+
+fn non_empty(text: String) -> Option<String> {
+    Some(text).filter(|text| !text.is_empty())
+}
+
+/// An author's name, without the Wikipedia prefix of a `[[w:...]]` link
+fn author_name(author: &str) -> Option<String> {
+    let author = appendix::clean_wikitext(author);
+    non_empty(author.strip_prefix("w:").unwrap_or(&author).trim().to_string())
+}
+
+/// A quotation from a template at the start of `source`, which may run over
+/// several lines. Positional parameters of `quote-*` templates are the
+/// language, date and author.
+fn from_template(source: &str) -> Quotation {
+    let source = appendix::inline_link_templates(source);
+    let template = WikitextParser::with_markup(&source, MarkupMode::Strip).parse_template();
+    let named = |keys: &[&str]| keys.iter().find_map(|key| template.named.get(*key).filter(|v| !v.trim().is_empty()));
+    let positional = |index: usize| template.params.get(index).filter(|v| !v.trim().is_empty());
+    let is_quote = template.name.to_lowercase().starts_with("quote-");
+
+    let year = named(&["year", "date"]).or(positional(1).filter(|_| is_quote)).and_then(|date| first_year(date));
+    let author = match (named(&["author"]), named(&["first"]), named(&["last"])) {
+        (Some(author), _, _) => Some(author.clone()),
+        (None, Some(first), Some(last)) => Some(format!("{} {}", first.trim(), last.trim())),
+        (None, None, Some(last)) => Some(last.clone()),
+        _ => positional(2).filter(|_| is_quote).cloned(),
+    };
+    let text = named(&["passage", "text"]).map(|passage| appendix::clean_wikitext(passage)).unwrap_or_default();
+    Quotation { year, author: author.and_then(|author| author_name(&author)), text }
+}
+
+/// A quotation from a citation line ("'''1851''', Herman Melville, ''Moby-Dick''"),
+/// whose passage follows on `#*:` lines
+fn from_citation(line: &str) -> Quotation {
+    let cleaned = appendix::clean_wikitext(line);
+    let mut fields = cleaned.split(',').map(str::trim);
+    let year = fields.next().and_then(first_year);
+    // The author follows the year; without a year the line's layout is unknown
+    let author = year.and(fields.next()).and_then(|author| author_name(author.trim_end_matches(':')));
+    Quotation { year, author, text: String::new() }
+}
+
+/// Quotations in the lines between a definition and the next one. Sub-sense
+/// quotations (`##*`) belong to the sub-sense and are not included.
+pub fn parse_quotations(block: &str) -> Vec<Quotation> {
+    // Verse and lyrics keep their line breaks as " / "
+    let block = LINE_BREAK.replace_all(&unescape_xml(block), " / ").into_owned();
+    let mut quotations: Vec<Quotation> = Vec::new();
+    let mut offset = 0;
+    for line in block.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        if let Some(passage) = line.strip_prefix("#*:") {
+            if let Some(quotation) = quotations.last_mut() {
+                let passage = appendix::clean_wikitext(passage);
+                if !passage.is_empty() {
+                    if !quotation.text.is_empty() {
+                        quotation.text.push(' ');
+                    }
+                    quotation.text.push_str(&passage);
+                }
+            }
+        } else if let Some(rest) = line.strip_prefix("#*") {
+            let rest_start = start + 2 + (rest.len() - rest.trim_start().len());
+            let rest = rest.trim();
+            if QUOTE_TEMPLATE.is_match(rest) {
+                quotations.push(from_template(&block[rest_start..]));
+            } else {
+                quotations.push(from_citation(rest));
+            }
+        }
+    }
+    quotations.retain(|quotation| !quotation.text.is_empty());
+    quotations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotation_templates() {
+        let block = "\n#* {{quote-book|en|year=1851|author={{w|Herman Melville}}|title=Moby-Dick|passage=Call me '''Ishmael'''.}}\n\
+                     #* {{quote-journal|en|date=March 3, 2012|last=Smith|first=Jane|title=Cats|text=The [[cat]] sat.<br>It purred.}}\n\
+                     #* {{quote-web|en|2019|{{w|Ann Other}}|title=No passage}}\n\
+                     #* {{RQ:Shakespeare Hamlet|passage=To be, or not to be}}\n";
+        assert_eq!(
+            parse_quotations(block),
+            vec![
+                Quotation { year: Some(1851), author: Some("Herman Melville".into()), text: "Call me Ishmael.".into() },
+                Quotation { year: Some(2012), author: Some("Jane Smith".into()), text: "The cat sat. / It purred.".into() },
+                Quotation { year: None, author: None, text: "To be, or not to be".into() },
+            ]
+        );
+    }
+
+    #[test]
+    fn citation_lines_and_multiline_templates() {
+        let block = "#* '''1851''', [[w:Herman Melville|Herman Melville]], ''Moby-Dick'', chapter 1:\n\
+                     #*: Call me '''Ishmael'''.\n\
+                     ##* '''1900''', Sub Sense, ''Not Here'':\n\
+                     ##*: Skipped.\n\
+                     #* {{quote-book|en\n|year=1922\n|author=[[w:James Joyce]]\n|passage=Stately, plump Buck Mulligan}}\n";
+        assert_eq!(
+            parse_quotations(block),
+            vec![
+                Quotation { year: Some(1851), author: Some("Herman Melville".into()), text: "Call me Ishmael.".into() },
+                Quotation { year: Some(1922), author: Some("James Joyce".into()), text: "Stately, plump Buck Mulligan".into() },
+            ]
+        );
+    }
+}
@@ -0,0 +1,244 @@
+//! The channel pipeline's reorder buffer, spilling to disk past a limit.
+//!
+//! Chunks of results that reach the writer before their turn wait here. A
+//! page that is slow to process holds back every chunk after its own, so
+//! behind one pathological page the buffer can grow to a large part of the
+//! dump. At most `--reorder-limit` pages' results are kept in memory: past
+//! that, the chunk furthest from its turn is serialized to a temp file, and
+//! only its offset is remembered. It is read back when the writer reaches it,
+//! so the output is the same whatever was spilled. The file is removed with
+//! the buffer.
+
+use crate::error_log::PageFailure;
+use crate::parallel::{ProcessedChunk, ProcessedPage};
+use crate::shape::RenderedEntries;
+use crate::sidecar::SidecarRecord;
+use crate::{Entry, Stats};
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Spill files made by this process, so concurrent buffers get their own
+static SPILL_FILES: AtomicUsize = AtomicUsize::new(0);
+
+/// A chunk as it is stored in the spill file
+#[derive(Serialize, Deserialize)]
+struct SpilledChunk {
+    chunk_id: usize,
+    pages: Vec<SpilledPage>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct SpilledPage {
+    page_id: usize,
+    entries: Vec<Entry<'static>>,
+    /// The first entry's sidecar records, which `Entry` doesn't serialize
+    sidecar: Vec<SidecarRecord>,
+    stats: Stats,
+    failures: Vec<PageFailure>,
+    rendered: Option<RenderedEntries>,
+}
+
+impl SpilledPage {
+    fn from_page(mut page: ProcessedPage) -> Self {
+        let sidecar = page.entries.first_mut().map(|entry| std::mem::take(&mut entry.sidecar)).unwrap_or_default();
+        SpilledPage {
+            page_id: page.page_id,
+            entries: page.entries,
+            sidecar,
+            stats: page.stats,
+            failures: page.failures,
+            rendered: page.rendered,
+        }
+    }
+
+    fn into_page(mut self) -> ProcessedPage {
+        if let Some(first) = self.entries.first_mut() {
+            first.sidecar = self.sidecar;
+        }
+        ProcessedPage {
+            entries: self.entries,
+            page_id: self.page_id,
+            stats: self.stats,
+            failures: self.failures,
+            rendered: self.rendered,
+        }
+    }
+}
+
+struct SpillFile {
+    path: PathBuf,
+    file: File,
+    /// Where each spilled chunk starts, its length, and its number of pages
+    index: BTreeMap<usize, (u64, usize, usize)>,
+    end: u64,
+}
+
+impl SpillFile {
+    fn create() -> io::Result<Self> {
+        let number = SPILL_FILES.fetch_add(1, Ordering::Relaxed);
+        let path = std::env::temp_dir().join(format!("wiktionary-scanner-{}-{}.reorder", std::process::id(), number));
+        let file = File::options().read(true).write(true).create_new(true).open(&path)?;
+        Ok(SpillFile { path, file, index: BTreeMap::new(), end: 0 })
+    }
+
+    fn write(&mut self, chunk: ProcessedChunk) -> io::Result<()> {
+        let (chunk_id, pages) = (chunk.chunk_id, chunk.pages.len());
+        let spilled = SpilledChunk { chunk_id, pages: chunk.pages.into_iter().map(SpilledPage::from_page).collect() };
+        let bytes = serde_json::to_vec(&spilled)?;
+        self.file.seek(SeekFrom::Start(self.end))?;
+        self.file.write_all(&bytes)?;
+        self.index.insert(chunk_id, (self.end, bytes.len(), pages));
+        self.end += bytes.len() as u64;
+        Ok(())
+    }
+
+    fn read(&mut self, chunk_id: usize) -> io::Result<Option<ProcessedChunk>> {
+        let Some((offset, len, _)) = self.index.remove(&chunk_id) else {
+            return Ok(None);
+        };
+        let mut bytes = vec![0; len];
+        self.file.seek(SeekFrom::Start(offset))?;
+        self.file.read_exact(&mut bytes)?;
+        let spilled: SpilledChunk = serde_json::from_slice(&bytes)?;
+        let pages = spilled.pages.into_iter().map(SpilledPage::into_page).collect();
+        Ok(Some(ProcessedChunk { chunk_id: spilled.chunk_id, pages }))
+    }
+
+    /// Pages of the chunks still spilled
+    fn pages(&self) -> usize {
+        self.index.values().map(|(_, _, pages)| pages).sum()
+    }
+}
+
+impl Drop for SpillFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Chunks waiting for their turn, by chunk id
+pub struct ReorderBuffer {
+    memory: BTreeMap<usize, ProcessedChunk>,
+    /// Pages of the chunks in memory
+    memory_pages: usize,
+    limit: usize,
+    /// Created on the first spill
+    spill: Option<SpillFile>,
+    spilled: usize,
+}
+
+impl ReorderBuffer {
+    /// A buffer holding at most `limit` pages' results in memory
+    pub fn new(limit: usize) -> Self {
+        ReorderBuffer { memory: BTreeMap::new(), memory_pages: 0, limit, spill: None, spilled: 0 }
+    }
+
+    pub fn insert(&mut self, chunk: ProcessedChunk) -> io::Result<()> {
+        self.memory_pages += chunk.pages.len();
+        self.memory.insert(chunk.chunk_id, chunk);
+        while self.memory_pages > self.limit {
+            // The last chunk in dump order is needed last
+            let Some((_, furthest)) = self.memory.pop_last() else {
+                break;
+            };
+            self.memory_pages -= furthest.pages.len();
+            self.spilled += furthest.pages.len();
+            let spill = match &mut self.spill {
+                Some(spill) => spill,
+                None => self.spill.insert(SpillFile::create()?),
+            };
+            spill.write(furthest)?;
+        }
+        Ok(())
+    }
+
+    /// The chunk `chunk_id`, if it has arrived
+    pub fn take(&mut self, chunk_id: usize) -> io::Result<Option<ProcessedChunk>> {
+        if let Some(chunk) = self.memory.remove(&chunk_id) {
+            self.memory_pages -= chunk.pages.len();
+            return Ok(Some(chunk));
+        }
+        match &mut self.spill {
+            Some(spill) => spill.read(chunk_id),
+            None => Ok(None),
+        }
+    }
+
+    /// The lowest chunk id waiting, in memory or spilled
+    pub fn first_id(&self) -> Option<usize> {
+        let spilled = self.spill.as_ref().and_then(|spill| spill.index.keys().next().copied());
+        self.memory.keys().next().copied().into_iter().chain(spilled).min()
+    }
+
+    /// Pages of the chunks waiting, in memory or spilled
+    pub fn len(&self) -> usize {
+        self.memory_pages + self.spill.as_ref().map_or(0, SpillFile::pages)
+    }
+
+    /// Pages spilled to disk so far
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error_log::FailureCategory;
+    use crate::sidecar::Sidecar;
+
+    fn page(page_id: usize) -> ProcessedPage {
+        let mut entry: Entry = serde_json::from_str(&format!(r#"{{"id":"w{}","pos":"NOU","wc":1}}"#, page_id)).unwrap();
+        entry.sidecar = vec![SidecarRecord { sidecar: Sidecar::Relations, line: format!("line {}", page_id) }];
+        ProcessedPage {
+            entries: vec![entry],
+            page_id,
+            stats: Stats { words_written: 1, case_lower: 1, ..Stats::default() },
+            failures: vec![PageFailure::new("w", FailureCategory::InvalidEntry, "{}")],
+            rendered: Some(RenderedEntries { lines: format!("{{\"id\":\"w{}\"}}\n", page_id), split: Vec::new(), senses: 1, sidecar: Vec::new() }),
+        }
+    }
+
+    /// Chunk `chunk_id` of two pages, or none for chunk 3
+    fn chunk(chunk_id: usize) -> ProcessedChunk {
+        let pages = if chunk_id == 3 { Vec::new() } else { vec![page(chunk_id * 2), page(chunk_id * 2 + 1)] };
+        ProcessedChunk { chunk_id, pages }
+    }
+
+    #[test]
+    fn spilled_chunks_come_back_in_order() {
+        let mut buffer = ReorderBuffer::new(4);
+        for chunk_id in [4, 2, 5, 1, 3] {
+            buffer.insert(chunk(chunk_id)).unwrap();
+        }
+        // Chunks 1 and 2 stay in memory
+        assert_eq!((buffer.len(), buffer.spilled()), (8, 4));
+        let path = buffer.spill.as_ref().unwrap().path.clone();
+        assert!(path.exists());
+
+        assert!(buffer.take(0).unwrap().is_none());
+        for chunk_id in 1..=5 {
+            assert_eq!(buffer.first_id(), Some(chunk_id));
+            let chunk = buffer.take(chunk_id).unwrap().unwrap();
+            let expected = self::chunk(chunk_id);
+            assert_eq!(chunk.chunk_id, chunk_id);
+            assert_eq!(chunk.pages.len(), expected.pages.len());
+            for (page, expected) in chunk.pages.iter().zip(&expected.pages) {
+                assert_eq!(page.page_id, expected.page_id);
+                assert_eq!(serde_json::to_string(&page.entries).unwrap(), serde_json::to_string(&expected.entries).unwrap());
+                assert_eq!(page.entries[0].sidecar, expected.entries[0].sidecar);
+                assert_eq!(page.rendered.as_ref().unwrap().lines, expected.rendered.as_ref().unwrap().lines);
+                assert_eq!((page.stats.words_written, page.stats.case_lower), (1, 1));
+                assert_eq!(page.failures[0].category, FailureCategory::InvalidEntry);
+            }
+        }
+        assert_eq!((buffer.len(), buffer.first_id()), (0, None));
+        drop(buffer);
+        assert!(!path.exists());
+    }
+}
@@ -0,0 +1,240 @@
+//! Summary report over scanner output.
+//!
+//! Reads the JSONL written by a scan and tallies the distributions a
+//! datasheet needs: parts of speech, tags and inflected senses per sense;
+//! syllable counts, word lengths and morphology types per word, since every
+//! sense of a page shares those. The report is Markdown, or one JSON object
+//! with `--json`.
+
+use crate::open_input;
+
+use clap::Args as ClapArgs;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+#[derive(ClapArgs, Debug)]
+pub struct ReportArgs {
+    /// Scanner output JSONL (use - for stdin)
+    pub input: PathBuf,
+
+    /// Write the report as JSON instead of Markdown
+    #[arg(long)]
+    pub json: bool,
+
+    /// Tags to list per kind in the Markdown report (0 = all)
+    #[arg(long, default_value_t = 20)]
+    pub top: usize,
+}
+
+/// Only the fields the report needs
+#[derive(Deserialize)]
+struct ReportRecord {
+    id: String,
+    pos: String,
+    nsyll: Option<usize>,
+    #[serde(default)]
+    is_inflected: bool,
+    #[serde(default)]
+    register_tags: Vec<String>,
+    #[serde(default)]
+    region_tags: Vec<String>,
+    #[serde(default)]
+    domain_tags: Vec<String>,
+    #[serde(default)]
+    temporal_tags: Vec<String>,
+    #[serde(default)]
+    dialect_tags: Vec<String>,
+    #[serde(default)]
+    other_labels: Vec<String>,
+    morphology: Option<MorphologyType>,
+}
+
+#[derive(Deserialize)]
+struct MorphologyType {
+    #[serde(rename = "type")]
+    morph_type: String,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct Report {
+    pub senses: usize,
+    pub words: usize,
+    pub inflected_senses: usize,
+    /// Inflected senses over lemma senses; None with no lemma senses
+    pub inflected_ratio: Option<f64>,
+    pub pos: BTreeMap<String, usize>,
+    /// Words by syllable count; words without one are left out
+    pub syllables: BTreeMap<usize, usize>,
+    /// Words by length in characters
+    pub lengths: BTreeMap<usize, usize>,
+    /// Senses per tag, by kind (register, region, domain, temporal, dialect, and
+    /// "other" for the labels the schema doesn't classify)
+    pub tags: BTreeMap<&'static str, BTreeMap<String, usize>>,
+    /// Words by morphology type, with "none" for words without morphology
+    pub morphology: BTreeMap<String, usize>,
+}
+
+pub fn collect_report<R: BufRead>(reader: R) -> io::Result<Report> {
+    let mut report = Report::default();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: ReportRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+
+        report.senses += 1;
+        report.inflected_senses += record.is_inflected as usize;
+        *report.pos.entry(record.pos).or_default() += 1;
+        let tags = [
+            ("register", record.register_tags),
+            ("region", record.region_tags),
+            ("domain", record.domain_tags),
+            ("temporal", record.temporal_tags),
+            ("dialect", record.dialect_tags),
+            ("other", record.other_labels),
+        ];
+        for (kind, list) in tags {
+            let counts = report.tags.entry(kind).or_default();
+            for tag in list {
+                *counts.entry(tag).or_default() += 1;
+            }
+        }
+
+        if seen.contains(&record.id) {
+            continue;
+        }
+        report.words += 1;
+        *report.lengths.entry(record.id.chars().count()).or_default() += 1;
+        if let Some(syllables) = record.nsyll {
+            *report.syllables.entry(syllables).or_default() += 1;
+        }
+        let morph_type = record.morphology.map_or_else(|| "none".to_string(), |m| m.morph_type);
+        *report.morphology.entry(morph_type).or_default() += 1;
+        seen.insert(record.id);
+    }
+
+    let lemmas = report.senses - report.inflected_senses;
+    report.inflected_ratio = (lemmas > 0).then(|| report.inflected_senses as f64 / lemmas as f64);
+    Ok(report)
+}
+
+/// Entries of a distribution, most frequent first
+fn by_count<K: Clone + Ord>(counts: &BTreeMap<K, usize>) -> Vec<(K, usize)> {
+    let mut entries: Vec<(K, usize)> = counts.iter().map(|(k, &n)| (k.clone(), n)).collect();
+    entries.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    entries
+}
+
+fn percent(count: usize, total: usize) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        100.0 * count as f64 / total as f64
+    }
+}
+
+fn write_table<W: Write, K: std::fmt::Display>(
+    out: &mut W,
+    heading: &str,
+    rows: &[(K, usize)],
+    total: usize,
+) -> io::Result<()> {
+    writeln!(out, "| {} | Count | % |", heading)?;
+    writeln!(out, "|---|---:|---:|")?;
+    for (key, count) in rows {
+        writeln!(out, "| {} | {} | {:.1} |", key, count, percent(*count, total))?;
+    }
+    writeln!(out)
+}
+
+pub fn write_markdown<W: Write>(out: &mut W, report: &Report, top: usize) -> io::Result<()> {
+    writeln!(out, "# Lexicon report\n")?;
+    writeln!(out, "- Senses: {}", report.senses)?;
+    writeln!(out, "- Words: {}", report.words)?;
+    let ratio = report.inflected_ratio.map_or_else(|| "n/a".to_string(), |r| format!("{:.3}", r));
+    writeln!(
+        out,
+        "- Inflected senses: {} ({:.1}%), {} inflected per lemma sense\n",
+        report.inflected_senses,
+        percent(report.inflected_senses, report.senses),
+        ratio
+    )?;
+
+    writeln!(out, "## Parts of speech\n")?;
+    write_table(out, "POS", &by_count(&report.pos), report.senses)?;
+
+    let with_syllables = report.syllables.values().sum();
+    writeln!(out, "## Syllables\n")?;
+    writeln!(out, "{} words have a syllable count.\n", with_syllables)?;
+    let rows: Vec<(usize, usize)> = report.syllables.iter().map(|(&k, &n)| (k, n)).collect();
+    write_table(out, "Syllables", &rows, with_syllables)?;
+
+    writeln!(out, "## Word lengths\n")?;
+    let rows: Vec<(usize, usize)> = report.lengths.iter().map(|(&k, &n)| (k, n)).collect();
+    write_table(out, "Characters", &rows, report.words)?;
+
+    writeln!(out, "## Tags\n")?;
+    for (kind, counts) in &report.tags {
+        if counts.is_empty() {
+            continue;
+        }
+        let mut rows = by_count(counts);
+        if top > 0 {
+            rows.truncate(top);
+        }
+        writeln!(out, "### {}\n", kind)?;
+        write_table(out, "Tag", &rows, report.senses)?;
+    }
+
+    writeln!(out, "## Morphology\n")?;
+    write_table(out, "Type", &by_count(&report.morphology), report.words)
+}
+
+pub fn run_report(args: &ReportArgs) -> io::Result<()> {
+    let report = collect_report(open_input(&args.input)?)?;
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+
+    if args.json {
+        serde_json::to_writer_pretty(&mut out, &report)?;
+        return writeln!(out);
+    }
+    write_markdown(&mut out, &report, args.top)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_senses_and_words() {
+        let jsonl = concat!(
+            r#"{"id":"cat","pos":"NOU","wc":1,"nsyll":1,"register_tags":["informal"]}"#, "\n",
+            r#"{"id":"cat","pos":"VRB","wc":1,"nsyll":1}"#, "\n",
+            r#"{"id":"cats","pos":"NOU","wc":1,"nsyll":1,"is_inflected":true,"lemma":"cat"}"#, "\n",
+            r#"{"id":"kindness","pos":"NOU","wc":1,"morphology":{"type":"suffixed"}}"#, "\n",
+        );
+        let report = collect_report(jsonl.as_bytes()).unwrap();
+        assert_eq!((report.senses, report.words, report.inflected_senses), (4, 3, 1));
+        assert_eq!(report.pos["NOU"], 3);
+        assert_eq!(report.syllables, BTreeMap::from([(1, 2)]));
+        assert_eq!(report.lengths, BTreeMap::from([(3, 1), (4, 1), (8, 1)]));
+        assert_eq!(report.tags["register"]["informal"], 1);
+        assert_eq!(report.morphology, BTreeMap::from([("none".to_string(), 2), ("suffixed".to_string(), 1)]));
+        assert_eq!(report.inflected_ratio, Some(1.0 / 3.0));
+
+        let mut markdown = Vec::new();
+        write_markdown(&mut markdown, &report, 0).unwrap();
+        let markdown = String::from_utf8(markdown).unwrap();
+        assert!(markdown.contains("| NOU | 3 | 75.0 |"));
+        assert!(markdown.contains("### register"));
+        assert!(!markdown.contains("### dialect"));
+    }
+}
@@ -0,0 +1,151 @@
+//! Rhyme dictionary builder.
+//!
+//! Reads a dump and groups English words by rhyme: the keys of their
+//! `{{rhymes|en|aɪm|s=1}}` templates, or, for pages without one, the end of
+//! the first IPA transcription from the stressed vowel on (/ˈkɹaɪm/ → aɪm).
+//! Keys derived from IPA follow the transcription's accent, so they can
+//! differ from the template keys, which Wiktionary gives in RP.
+
+use crate::page_ast::PageAst;
+use crate::{
+    count_syllables_from_ipa, create_output, extract_english_section, get_ipa_inventory, is_englishlike, open_inputs,
+    scan_pages, unescape_xml, WikitextParser, IPA_TEMPLATE, IPA_TRANSCRIPTION, NS_PATTERN, REDIRECT_PATTERN,
+    TEXT_PATTERN, TITLE_PATTERN,
+};
+
+use clap::Args as ClapArgs;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(ClapArgs, Debug)]
+pub struct BuildRhymesArgs {
+    /// Wiktionary dump (.xml or .xml.bz2, - for stdin)
+    pub input: PathBuf,
+
+    /// Where to write the rhyme index JSONL (- for stdout)
+    pub output: PathBuf,
+
+    /// Only use {{rhymes}} templates, not keys derived from IPA
+    #[arg(long)]
+    pub no_ipa: bool,
+}
+
+/// One line of the index
+#[derive(Debug, Serialize)]
+pub struct RhymeRecord<'a> {
+    pub rhyme: &'a str,
+    pub words: &'a BTreeSet<String>,
+}
+
+/// Keys of the page's {{rhymes|en|...}} templates
+pub fn template_rhymes(ast: &PageAst) -> Vec<String> {
+    ast.templates(&["rhymes", "rhyme"])
+        .flat_map(|template| {
+            let template = WikitextParser::new(template).parse_template();
+            template.params.into_iter().skip(1)
+        })
+        .map(|key| key.trim().to_string())
+        .filter(|key| !key.is_empty())
+        .collect()
+}
+
+/// Rhyme of an IPA transcription: from the vowel of the last stressed
+/// syllable to the end. Unstressed transcriptions only count when they have
+/// a single syllable.
+pub fn ipa_rhyme(ipa: &str) -> Option<String> {
+    let stressed = match ipa.rfind('ˈ') {
+        Some(at) => &ipa[at + 'ˈ'.len_utf8()..],
+        None if count_syllables_from_ipa(ipa) == 1 => ipa,
+        None => return None,
+    };
+    let syllable: String = stressed.chars().filter(|ch| !matches!(ch, '.' | 'ˌ' | ' ' | '‿')).collect();
+    let nucleus = syllable.find(|ch| get_ipa_inventory().vowels.contains(&ch))?;
+    Some(syllable[nucleus..].to_string())
+}
+
+fn page_rhymes(text: &str, use_ipa: bool) -> Vec<String> {
+    let Some(english) = extract_english_section(text) else {
+        return Vec::new();
+    };
+    let ast = PageAst::parse(english);
+    let rhymes = template_rhymes(&ast);
+    if !rhymes.is_empty() || !use_ipa {
+        return rhymes;
+    }
+    ast.captures(&["IPA"], &IPA_TEMPLATE)
+        .and_then(|cap| IPA_TRANSCRIPTION.captures(&cap[1]).and_then(|ipa| ipa_rhyme(&ipa[1])))
+        .into_iter()
+        .collect()
+}
+
+/// Build the index and write it, one rhyme per line in key order. Returns the
+/// numbers of rhymes and words indexed.
+pub fn run_build_rhymes(args: &BuildRhymesArgs, force: bool) -> io::Result<(usize, usize)> {
+    let mut index: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    let mut words = 0;
+
+    scan_pages(open_inputs(std::slice::from_ref(&args.input))?, |page_xml| {
+        let in_main = NS_PATTERN.captures(&page_xml).is_none_or(|cap| &cap[1] == "0");
+        if !in_main || REDIRECT_PATTERN.is_match(&page_xml) {
+            return true;
+        }
+        let (Some(title), Some(text)) = (TITLE_PATTERN.captures(&page_xml), TEXT_PATTERN.captures(&page_xml)) else {
+            return true;
+        };
+        if !is_englishlike(&title[1]) {
+            return true;
+        }
+        let rhymes = page_rhymes(&text[1], !args.no_ipa);
+        if !rhymes.is_empty() {
+            words += 1;
+        }
+        let word = unescape_xml(title[1].trim());
+        for rhyme in rhymes {
+            index.entry(rhyme).or_default().insert(word.clone());
+        }
+        true
+    })?;
+
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    for (rhyme, words) in &index {
+        writeln!(writer, "{}", serde_json::to_string(&RhymeRecord { rhyme, words })?)?;
+    }
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((index.len(), words))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::init_ipa_inventory;
+
+    // Vowels come from schema/ipa.yaml; another test may have loaded it already
+    fn init_ipa() {
+        let _ = init_ipa_inventory(None);
+    }
+
+    #[test]
+    fn rhymes_from_templates_then_ipa() {
+        init_ipa();
+        let text = "==English==\n===Pronunciation===\n* {{IPA|en|/kɹaɪm/}}\n* {{rhymes|en|aɪm|s=1}}\n";
+        assert_eq!(page_rhymes(text, true), vec!["aɪm"]);
+        let text = "==English==\n===Pronunciation===\n* {{IPA|en|/ɪnˈspaɪ.ɚ/}}\n";
+        assert_eq!(page_rhymes(text, true), vec!["aɪɚ"]);
+        assert!(page_rhymes(text, false).is_empty());
+    }
+
+    #[test]
+    fn ipa_rhymes() {
+        init_ipa();
+        assert_eq!(ipa_rhyme("ˈkæt").as_deref(), Some("æt"));
+        assert_eq!(ipa_rhyme("kæt").as_deref(), Some("æt"));
+        assert_eq!(ipa_rhyme("ˌʌndəˈstænd").as_deref(), Some("ænd"));
+        assert_eq!(ipa_rhyme("ˈwɔː.tə"), Some("ɔːtə".to_string()));
+        assert_eq!(ipa_rhyme("wɔːtə"), None);
+    }
+}
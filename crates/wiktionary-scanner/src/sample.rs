@@ -0,0 +1,138 @@
+//! Deterministic random sampling of mainspace pages for quick QA runs.
+//!
+//! Each title is hashed with the seed into a number in [0, 1). `--sample RATE`
+//! keeps the pages whose number is below RATE, which works as the pages stream
+//! past and keeps the same titles whatever the strategy or dump date.
+//! `--sample-n N` keeps the N pages with the smallest numbers; they are only
+//! known at the end of the input, so those pages are held in memory and then
+//! handed to the strategy in dump order.
+
+use crate::{get_special_prefixes, scan_pages, NS_PATTERN, TITLE_PATTERN};
+
+use once_cell::sync::OnceCell;
+use std::collections::BinaryHeap;
+use std::io::{self, BufRead, Cursor};
+
+static SAMPLER: OnceCell<Sampler> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy)]
+struct Sampler {
+    rate: f64,
+    seed: u64,
+}
+
+/// Keep roughly `rate` of the mainspace pages for the rest of the run
+pub fn init_sample_rate(rate: f64, seed: u64) {
+    if SAMPLER.set(Sampler { rate, seed }).is_err() {
+        panic!("Sampler already initialized");
+    }
+}
+
+/// Whether the page titled `title` is in the sample; true when not sampling
+pub fn sampled(title: &str) -> bool {
+    SAMPLER.get().is_none_or(|s| sample_point(s.seed, title) < s.rate)
+}
+
+/// Where `title` falls in [0, 1) for `seed`. FNV-1a with a splitmix64
+/// finalizer: unlike std's hashers, stable across Rust releases.
+pub fn sample_point(seed: u64, title: &str) -> f64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325 ^ seed;
+    for byte in title.bytes() {
+        hash ^= u64::from(byte);
+        hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+    }
+    hash ^= hash >> 30;
+    hash = hash.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash ^= hash >> 27;
+    hash = hash.wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Title of a mainspace page that isn't under a special prefix
+fn mainspace_title(page_xml: &str) -> Option<&str> {
+    let title = TITLE_PATTERN.captures(page_xml)?.get(1)?.as_str();
+    if NS_PATTERN.captures(page_xml).is_some_and(|cap| &cap[1] != "0")
+        || get_special_prefixes().iter().any(|prefix| title.starts_with(prefix))
+    {
+        return None;
+    }
+    Some(title)
+}
+
+/// Read the whole input and keep the `n` mainspace pages with the smallest
+/// sample points. Returns them as XML in dump order, with the number of
+/// mainspace pages seen.
+pub fn sample_n_pages(reader: impl BufRead, n: usize, seed: u64) -> io::Result<(Cursor<Vec<u8>>, usize)> {
+    // Max-heap on the sample point, so the worst kept page is on top
+    let mut kept: BinaryHeap<(u64, usize, String)> = BinaryHeap::with_capacity(n + 1);
+    let mut seen = 0;
+
+    scan_pages(reader, |page_xml| {
+        let Some(title) = mainspace_title(&page_xml) else {
+            return true;
+        };
+        // Points are compared as their bits; for non-negative floats that keeps the order
+        let point = sample_point(seed, title).to_bits();
+        seen += 1;
+        if kept.len() < n {
+            kept.push((point, seen, page_xml));
+        } else if kept.peek().is_some_and(|top| point < top.0) {
+            kept.pop();
+            kept.push((point, seen, page_xml));
+        }
+        true
+    })?;
+
+    let mut pages = kept.into_vec();
+    pages.sort_by_key(|(_, position, _)| *position);
+    let mut xml = Vec::new();
+    for (_, _, page) in pages {
+        xml.extend_from_slice(page.as_bytes());
+        xml.push(b'\n');
+    }
+    Ok((Cursor::new(xml), seen))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_points_are_stable_and_spread() {
+        assert_eq!(sample_point(0, "cat"), sample_point(0, "cat"));
+        assert_ne!(sample_point(0, "cat"), sample_point(1, "cat"));
+
+        let titles: Vec<String> = (0..10_000).map(|i| format!("word{}", i)).collect();
+        let below = titles.iter().filter(|t| sample_point(7, t) < 0.1).count();
+        assert!((800..1200).contains(&below), "{} of 10000 below 0.1", below);
+    }
+
+    #[test]
+    fn sample_n_keeps_smallest_points_in_dump_order() {
+        // Same prefixes as the main test schemas, whichever test sets them first
+        let _ = crate::SPECIAL_PREFIXES_VEC.set(vec!["Appendix:".to_string()]);
+        let titles = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let mut xml: String = titles
+            .iter()
+            .map(|t| format!("<page><title>{}</title><ns>0</ns><text>x</text></page>\n", t))
+            .collect();
+        xml.push_str("<page><title>Talk:a</title><ns>1</ns><text>x</text></page>\n");
+        xml.push_str("<page><title>Appendix:b</title><ns>0</ns><text>x</text></page>\n");
+        let (sample, seen) = sample_n_pages(xml.as_bytes(), 3, 42).unwrap();
+        assert_eq!(seen, titles.len());
+
+        let mut expected: Vec<&str> = titles.to_vec();
+        expected.sort_by(|a, b| sample_point(42, a).total_cmp(&sample_point(42, b)));
+        expected.truncate(3);
+        expected.sort();
+
+        let mut kept = Vec::new();
+        scan_pages(sample, |page| {
+            kept.push(TITLE_PATTERN.captures(&page).unwrap()[1].to_string());
+            true
+        })
+        .unwrap();
+        assert_eq!(kept, expected);
+    }
+}
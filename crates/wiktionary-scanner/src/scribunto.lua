@@ -0,0 +1,278 @@
+-- The parts of Scribunto's environment that headword modules use, for --lua.
+--
+-- Loaded once into each thread's Lua state, after the Rust side has set:
+--   __module_source(title)        source of a Module: page, or nil
+--   __page_exists(title)          whether the dump has the page
+--   __expand_template(title, args) a template call, expanded
+--   __preprocess(text)            wikitext with its templates expanded
+--   __ustring                     the UTF-8 aware mw.ustring functions
+--   __title                       the page being scanned (set per expansion)
+--
+-- Anything a module needs beyond this raises an error, and the scanner falls
+-- back to reading the headword template's parameters.
+
+local module_source = __module_source
+local page_exists = __page_exists
+local expand_template = __expand_template
+local preprocess = __preprocess
+
+-- Nothing from outside the dump
+dofile, loadfile, print = nil, nil, function() end
+os = { time = os.time, date = os.date, clock = os.clock, difftime = os.difftime }
+
+local loaded = {}
+
+local builtin = {
+	strict = {},
+	libraryUtil = {
+		checkType = function(name, argIdx, arg, expectType, nilOk)
+			if arg == nil and nilOk then return end
+			if type(arg) ~= expectType then
+				error(string.format("bad argument #%d to '%s' (%s expected, got %s)",
+					argIdx, name, expectType, type(arg)), 3)
+			end
+		end,
+		checkTypeMulti = function() end,
+		checkTypeForIndex = function() end,
+		checkTypeForNamedArg = function() end,
+		makeCheckSelfFunction = function() return function() end end,
+	},
+}
+
+local function load_module(name)
+	if builtin[name] then return builtin[name] end
+	if not name:find("^Module:") then name = "Module:" .. name end
+	if loaded[name] ~= nil then return loaded[name] end
+	local source = module_source(name)
+	if not source then error("module not found: " .. name, 2) end
+	local chunk, message = loadstring(source, "=" .. name)
+	if not chunk then error(message, 0) end
+	local value = chunk()
+	loaded[name] = value == nil and true or value
+	return loaded[name]
+end
+
+require = load_module
+
+local function clone(value, seen)
+	if type(value) ~= "table" then return value end
+	seen = seen or {}
+	if seen[value] then return seen[value] end
+	local copy = {}
+	seen[value] = copy
+	for k, v in pairs(value) do copy[clone(k, seen)] = clone(v, seen) end
+	return setmetatable(copy, getmetatable(value))
+end
+
+mw = {
+	clone = clone,
+	loadData = load_module,
+	log = function() end,
+	logObject = function() end,
+	addWarning = function() end,
+	isSubsting = function() return false end,
+	incrementExpensiveFunctionCount = function() end,
+	getCurrentFrame = function() return __frame end,
+}
+
+mw.ustring = setmetatable(__ustring, { __index = string })
+mw.ustring.maxPatternLength = math.huge
+mw.ustring.maxStringLength = math.huge
+mw.ustring.gcodepoint = function(s)
+	local i, n = 0, mw.ustring.len(s)
+	return function()
+		i = i + 1
+		if i <= n then return mw.ustring.codepoint(s, i) end
+	end
+end
+
+mw.text = {
+	trim = function(s, chars)
+		chars = chars or "\t\r\n\f "
+		return (s:gsub("^[" .. chars .. "]+", ""):gsub("[" .. chars .. "]+$", ""))
+	end,
+	nowiki = function(s) return s end,
+	encode = function(s) return s end,
+	decode = function(s) return s end,
+	unstrip = function(s) return s end,
+	unstripNoWiki = function(s) return s end,
+	killMarkers = function(s) return s end,
+	tag = function(name, attrs, content)
+		local parts = { "<" .. name }
+		for k, v in pairs(attrs or {}) do parts[#parts + 1] = string.format(' %s="%s"', k, v) end
+		if content == nil or content == false then return table.concat(parts) .. ">" end
+		return table.concat(parts) .. ">" .. content .. "</" .. name .. ">"
+	end,
+	gsplit = function(s, pattern, plain)
+		local start, done = 1, false
+		return function()
+			if done then return end
+			local first, last = s:find(pattern, start, plain)
+			if not first or last < first then
+				done = true
+				return s:sub(start)
+			end
+			local piece = s:sub(start, first - 1)
+			start = last + 1
+			return piece
+		end
+	end,
+	listToText = function(list, separator, conjunction)
+		separator, conjunction = separator or ", ", conjunction or " and "
+		if #list <= 1 then return list[1] or "" end
+		return table.concat(list, separator, 1, #list - 1) .. conjunction .. list[#list]
+	end,
+}
+mw.text.split = function(s, pattern, plain)
+	local pieces = {}
+	for piece in mw.text.gsplit(s, pattern, plain) do pieces[#pieces + 1] = piece end
+	return pieces
+end
+
+local function make_title(text, namespace)
+	if text == nil then return nil end
+	local prefix, rest = text:match("^([^:]+):(.+)$")
+	local ns_text = ""
+	if prefix and (prefix == "Template" or prefix == "Module" or prefix == "Category"
+			or prefix == "Appendix" or prefix == "Wiktionary" or prefix == "Reconstruction") then
+		ns_text, text = prefix, rest
+	elseif namespace and namespace ~= "" and namespace ~= 0 then
+		ns_text = namespace
+	end
+	local full = ns_text == "" and text or ns_text .. ":" .. text
+	local title = {
+		text = text,
+		nsText = ns_text,
+		namespace = ns_text == "" and 0 or -1,
+		prefixedText = full,
+		fullText = full,
+		baseText = text:match("^(.*)/[^/]*$") or text,
+		rootText = text:match("^([^/]*)") or text,
+		subpageText = text:match("/([^/]*)$") or text,
+		isContentPage = ns_text == "",
+		isRedirect = false,
+		fragment = "",
+		interwiki = "",
+	}
+	title.exists = page_exists(full)
+	title.getContent = function() return module_source(full) end
+	return title
+end
+
+mw.title = {
+	new = make_title,
+	makeTitle = function(namespace, text) return make_title(text, namespace) end,
+	getCurrentTitle = function() return make_title(__title) end,
+	equals = function(a, b) return a.fullText == b.fullText end,
+}
+
+local language = {
+	getCode = function() return "en" end,
+	lc = function(_, s) return mw.ustring.lower(s) end,
+	uc = function(_, s) return mw.ustring.upper(s) end,
+	lcfirst = function(_, s) return mw.ustring.lower(mw.ustring.sub(s, 1, 1)) .. mw.ustring.sub(s, 2) end,
+	ucfirst = function(_, s) return mw.ustring.upper(mw.ustring.sub(s, 1, 1)) .. mw.ustring.sub(s, 2) end,
+	formatNum = function(_, n) return tostring(n) end,
+	parseFormattedNumber = function(_, s) return tonumber((s:gsub(",", ""))) end,
+}
+mw.language = {
+	new = function() return language end,
+	getContentLanguage = function() return language end,
+	fetchLanguageName = function(code) return code end,
+	isKnownLanguageTag = function() return true end,
+}
+mw.getContentLanguage = mw.language.getContentLanguage
+mw.getLanguage = mw.language.new
+
+mw.site = { server = "https://en.wiktionary.org", siteName = "Wiktionary", namespaces = {} }
+
+-- Enough of mw.html for modules that build their output with it
+local Html = {}
+Html.__index = Html
+Html.__tostring = function(node)
+	local parts = {}
+	if node.name then
+		parts[#parts + 1] = "<" .. node.name
+		for _, attr in ipairs(node.attrs) do parts[#parts + 1] = string.format(' %s="%s"', attr[1], attr[2]) end
+		if #node.classes > 0 then parts[#parts + 1] = ' class="' .. table.concat(node.classes, " ") .. '"' end
+		if #node.styles > 0 then parts[#parts + 1] = ' style="' .. table.concat(node.styles, ";") .. '"' end
+		parts[#parts + 1] = ">"
+	end
+	for _, child in ipairs(node.children) do parts[#parts + 1] = tostring(child) end
+	if node.name and not node.selfClosing then parts[#parts + 1] = "</" .. node.name .. ">" end
+	return table.concat(parts)
+end
+
+local function new_node(name, parent)
+	return setmetatable({ name = name, parent = parent, attrs = {}, classes = {}, styles = {}, children = {} }, Html)
+end
+
+function Html:tag(name) local child = new_node(name, self); table.insert(self.children, child); return child end
+function Html:wikitext(...) for _, text in ipairs({ ... }) do table.insert(self.children, tostring(text)) end return self end
+function Html:newline() table.insert(self.children, "\n"); return self end
+function Html:node(child) if child then table.insert(self.children, child) end return self end
+function Html:attr(name, value)
+	if type(name) == "table" then for k, v in pairs(name) do self:attr(k, v) end return self end
+	if value ~= nil then table.insert(self.attrs, { name, value }) end
+	return self
+end
+function Html:addClass(class) if class then table.insert(self.classes, class) end return self end
+function Html:css(name, value)
+	if type(name) == "table" then for k, v in pairs(name) do self:css(k, v) end return self end
+	if value ~= nil then table.insert(self.styles, name .. ":" .. value) end
+	return self
+end
+function Html:cssText(css) if css then table.insert(self.styles, css) end return self end
+function Html:done() return self.parent or self end
+function Html:allDone() local node = self while node.parent do node = node.parent end return node end
+
+mw.html = {
+	create = function(name, args)
+		local node = new_node(name)
+		node.selfClosing = args and args.selfClosing
+		return node
+	end,
+}
+
+-- Call `module.func` the way {{#invoke:}} does: with a frame holding the
+-- invocation's arguments, whose parent holds the template call's
+function __invoke(module, func, args, parent_args, parent_title)
+	local function frame_for(frame_args, title, parent)
+		local frame = { args = frame_args }
+		function frame:getParent() return parent end
+		function frame:getTitle() return title end
+		function frame:expandTemplate(call) return expand_template(call.title, call.args or {}) end
+		function frame:preprocess(text)
+			if type(text) == "table" then text = text.text end
+			return preprocess(text)
+		end
+		function frame:extensionTag(name, content)
+			if type(name) == "table" then name, content = name.name, name.content end
+			return content or ""
+		end
+		function frame:callParserFunction() return "" end
+		function frame:getArgument(name)
+			local value = frame_args[name]
+			return value and { expand = function() return value end }
+		end
+		function frame:newChild(child)
+			return frame_for(child and child.args or {}, child and child.title or title, frame)
+		end
+		return frame
+	end
+
+	local parent = frame_for(parent_args, parent_title, nil)
+	local frame = frame_for(args, "Module:" .. module, parent)
+	local previous = __frame
+	__frame = frame
+	local exports = load_module(module)
+	local fn = type(exports) == "table" and exports[func]
+	if not fn then
+		__frame = previous
+		error("no function " .. func .. " in Module:" .. module, 0)
+	end
+	local ok, result = pcall(fn, frame)
+	__frame = previous
+	if not ok then error(result, 0) end
+	return result == nil and "" or tostring(result)
+end
@@ -0,0 +1,68 @@
+//! Which mainspace pages a scan processes.
+//!
+//! `--titles` and `--title-regex` restrict a scan to matching titles, and
+//! `--sample` to a random subset (see `sample`). Every strategy asks
+//! `selected` once a page's title and namespace have passed their checks;
+//! pages that aren't selected are dropped without being counted.
+
+use crate::sample::sampled;
+use crate::unescape_xml;
+
+use once_cell::sync::OnceCell;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::HashSet;
+
+static TITLE_FILTER: OnceCell<TitleFilter> = OnceCell::new();
+
+#[derive(Debug, Default)]
+pub struct TitleFilter {
+    /// Exact titles to keep (None = any title)
+    pub titles: Option<HashSet<String>>,
+    /// Pattern the title must match (None = any title)
+    pub pattern: Option<Regex>,
+}
+
+impl TitleFilter {
+    /// Whether an unescaped title passes both the list and the pattern
+    pub fn matches(&self, title: &str) -> bool {
+        self.titles.as_ref().is_none_or(|titles| titles.contains(title))
+            && self.pattern.as_ref().is_none_or(|pattern| pattern.is_match(title))
+    }
+}
+
+pub fn init_title_filter(filter: TitleFilter) {
+    if TITLE_FILTER.set(filter).is_err() {
+        panic!("Title filter already initialized");
+    }
+}
+
+pub fn get_title_filter() -> Option<&'static TitleFilter> {
+    TITLE_FILTER.get()
+}
+
+/// Whether to process the page with this title, as it appears in the dump XML
+pub fn selected(title: &str) -> bool {
+    let matches = TITLE_FILTER.get().is_none_or(|filter| {
+        let title = if title.contains('&') { Cow::Owned(unescape_xml(title)) } else { Cow::Borrowed(title) };
+        filter.matches(&title)
+    });
+    matches && sampled(title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn list_and_pattern_must_both_match() {
+        let filter = TitleFilter {
+            titles: Some(["unhappy", "undo", "cat"].iter().map(|s| s.to_string()).collect()),
+            pattern: Some(Regex::new("^un").unwrap()),
+        };
+        assert!(filter.matches("unhappy"));
+        assert!(!filter.matches("cat"));
+        assert!(!filter.matches("unkind"));
+        assert!(TitleFilter::default().matches("anything"));
+    }
+}
@@ -0,0 +1,467 @@
+//! Output shape: one line per sense, or one line per word.
+//!
+//! The default `flat` shape repeats the word-level fields on every sense
+//! line. `--shape nested` writes each word once, with those fields on the
+//! word and its senses in a `senses` array:
+//!
+//! ```json
+//! {"id":"cat","wc":1,"nsyll":1,"morphology":{...},"senses":[{"pos":"NOU",...},{"pos":"VRB",...}]}
+//! ```
+//!
+//! Page-level fields (forms of the word, provenance, `is_*` flags, `nsyll`,
+//! `tokens`, `lemma`, `categories`, `usage_notes`, ...) always move up to the word.
+//! Etymology fields (`calque_of`, `doublets`, `etymology_text`, `morphology`)
+//! move up only when every sense agrees on them; a page with several
+//! etymologies keeps them per sense.
+//!
+//! Either shape can be written as one JSON array instead of JSON lines, with
+//! `--json-array`. The elements stay one per line:
+//!
+//! ```json
+//! [
+//! {"id":"cat",...},
+//! {"id":"cat",...}
+//! ]
+//! ```
+
+use crate::compact;
+use crate::fast_json::to_writer;
+use crate::metrics::{self, Stage};
+use crate::sidecar::{write_records, SidecarRecord};
+use crate::split;
+use crate::{CalqueSource, Entry, Morphology};
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+static OUTPUT_SHAPE: OnceCell<OutputShape> = OnceCell::new();
+static JSON_ARRAY: OnceCell<bool> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OutputShape {
+    /// One JSON object per sense
+    #[default]
+    Flat,
+    /// One JSON object per word, with its senses in `senses`
+    Nested,
+}
+
+pub fn init_output_shape(shape: OutputShape) -> Result<(), String> {
+    OUTPUT_SHAPE.set(shape).map_err(|_| "OUTPUT_SHAPE already initialized".to_string())
+}
+
+pub fn output_shape() -> OutputShape {
+    OUTPUT_SHAPE.get().copied().unwrap_or_default()
+}
+
+pub fn init_json_array(on: bool) {
+    let _ = JSON_ARRAY.set(on);
+}
+
+pub fn json_array() -> bool {
+    JSON_ARRAY.get().is_some_and(|&on| on)
+}
+
+fn is_false(b: &bool) -> bool {
+    !*b
+}
+
+/// A word with the fields its senses share, in the flat shape's field order
+#[derive(Serialize)]
+struct NestedWord<'a> {
+    id: Cow<'a, str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    ascii: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sort_key: Option<Cow<'a, str>>,
+    wc: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    page_id: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rev_timestamp: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "is_false")]
+    is_abbreviation: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    is_heteronym: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    is_inflected: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    is_neologism: bool,
+    #[serde(skip_serializing_if = "is_false")]
+    is_phrase: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nsyll: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    phrase_type: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    tokens: Cow<'a, [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    neologism_date: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    lemma: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    inflection_tags: Cow<'a, [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    calque_of: Option<Cow<'a, CalqueSource>>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    doublets: Cow<'a, [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    etymology_text: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    spelling_region: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "<[String]>::is_empty")]
+    categories: Cow<'a, [String]>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    usage_notes: Option<Cow<'a, str>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    morphology: Option<Cow<'a, Morphology>>,
+    senses: Vec<Entry<'a>>,
+}
+
+/// Whether every entry has the same value for a field
+fn unanimous<'e, T: PartialEq + 'e>(entries: &'e [Entry<'e>], field: impl Fn(&'e Entry<'e>) -> &'e T) -> bool {
+    entries.windows(2).all(|pair| field(&pair[0]) == field(&pair[1]))
+}
+
+impl<'a> NestedWord<'a> {
+    /// Move the shared fields of one page's entries (at least one) up to the word.
+    /// Fields left at their defaults are skipped when the senses are written.
+    fn from_entries(mut entries: Vec<Entry<'a>>) -> Self {
+        let first = &entries[0];
+        let etymology_shared = unanimous(&entries, |e| &e.calque_of)
+            && unanimous(&entries, |e| &e.doublets)
+            && unanimous(&entries, |e| &e.etymology_text)
+            && unanimous(&entries, |e| &e.morphology);
+        let mut word = NestedWord {
+            id: first.word.clone(),
+            ascii: first.ascii.clone(),
+            sort_key: first.sort_key.clone(),
+            wc: first.word_count,
+            page_id: first.page_id,
+            rev_timestamp: first.rev_timestamp.clone(),
+            is_abbreviation: first.is_abbreviation,
+            is_heteronym: first.is_heteronym,
+            is_inflected: first.is_inflected,
+            is_neologism: first.is_neologism,
+            is_phrase: first.is_phrase,
+            nsyll: first.syllables,
+            phrase_type: first.phrase_type.clone(),
+            tokens: first.tokens.clone(),
+            neologism_date: first.neologism_date.clone(),
+            lemma: first.lemma.clone(),
+            inflection_tags: first.inflection_tags.clone(),
+            calque_of: None,
+            doublets: Cow::default(),
+            etymology_text: None,
+            spelling_region: first.spelling_region.clone(),
+            categories: first.categories.clone(),
+            usage_notes: first.usage_notes.clone(),
+            morphology: None,
+            senses: Vec::new(),
+        };
+        if etymology_shared {
+            word.calque_of = first.calque_of.clone();
+            word.doublets = first.doublets.clone();
+            word.etymology_text = first.etymology_text.clone();
+            word.morphology = first.morphology.clone();
+        }
+
+        for entry in &mut entries {
+            entry.word = Cow::default();
+            entry.ascii = None;
+            entry.sort_key = None;
+            entry.word_count = 0;
+            entry.page_id = None;
+            entry.rev_timestamp = None;
+            entry.is_abbreviation = false;
+            entry.is_heteronym = false;
+            entry.is_inflected = false;
+            entry.is_neologism = false;
+            entry.is_phrase = false;
+            entry.syllables = None;
+            entry.phrase_type = None;
+            entry.tokens = Cow::default();
+            entry.neologism_date = None;
+            entry.lemma = None;
+            entry.inflection_tags = Cow::default();
+            entry.spelling_region = None;
+            entry.categories = Cow::default();
+            entry.usage_notes = None;
+            if etymology_shared {
+                entry.calque_of = None;
+                entry.doublets = Cow::default();
+                entry.etymology_text = None;
+                entry.morphology = None;
+            }
+        }
+        word.senses = entries;
+        word
+    }
+}
+
+/// Write one page's entries in the output shape, at most `room` of them (what
+/// is left of --limit). Returns the number of senses written.
+pub fn write_entries<W: Write>(writer: &mut W, mut entries: Vec<Entry<'_>>, room: Option<usize>) -> io::Result<usize> {
+    if let Some(room) = room {
+        entries.truncate(room);
+    }
+    let written = entries.len();
+    if written == 0 {
+        return Ok(0);
+    }
+    let _timer = metrics::time(Stage::Write);
+    metrics::senses_written(written);
+    write_records(&std::mem::take(&mut entries[0].sidecar))?;
+    if split::enabled() {
+        for (pos, group) in group_by_pos(entries) {
+            let mut lines = Vec::new();
+            serialize_entries(&mut lines, group)?;
+            split::write_lines(&pos, &lines)?;
+        }
+    } else {
+        serialize_entries(writer, entries)?;
+    }
+    Ok(written)
+}
+
+/// A page's entries by part of speech, in the order each first appears
+fn group_by_pos(entries: Vec<Entry<'_>>) -> Vec<(String, Vec<Entry<'_>>)> {
+    let mut groups: Vec<(String, Vec<Entry>)> = Vec::new();
+    for entry in entries {
+        match groups.iter_mut().find(|(pos, _)| *pos == entry.pos) {
+            Some((_, group)) => group.push(entry),
+            None => groups.push((entry.pos.to_string(), vec![entry])),
+        }
+    }
+    groups
+}
+
+fn serialize_entries<W: Write>(writer: &mut W, entries: Vec<Entry<'_>>) -> io::Result<()> {
+    if compact::enabled() {
+        let mut lines = Vec::new();
+        serialize_shaped(&mut lines, entries)?;
+        return compact::write_compacted(writer, &lines);
+    }
+    serialize_shaped(writer, entries)
+}
+
+fn serialize_shaped<W: Write>(writer: &mut W, entries: Vec<Entry<'_>>) -> io::Result<()> {
+    match output_shape() {
+        OutputShape::Flat => {
+            for entry in &entries {
+                to_writer(writer, entry)?;
+                writer.write_all(b"\n")?;
+            }
+        }
+        OutputShape::Nested => {
+            to_writer(writer, &NestedWord::from_entries(entries))?;
+            writer.write_all(b"\n")?;
+        }
+    }
+    Ok(())
+}
+
+/// One page's entries, already serialized in the output shape. The parallel
+/// strategies render pages on the worker threads, so the writer only copies
+/// bytes; a page that --limit may cut short is written from its entries.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct RenderedEntries {
+    /// The page's output lines, each ending in a newline
+    pub lines: String,
+    /// With --split-by pos, the lines for each part of speech in place of `lines`
+    pub split: Vec<(String, String)>,
+    pub senses: usize,
+    /// The first entry's sidecar records, which the lines leave out
+    pub sidecar: Vec<SidecarRecord>,
+}
+
+pub fn render_entries(mut entries: Vec<Entry<'_>>) -> RenderedEntries {
+    let senses = entries.len();
+    let sidecar = entries.first_mut().map(|entry| std::mem::take(&mut entry.sidecar)).unwrap_or_default();
+    let serialize = |entries| {
+        let mut lines = Vec::new();
+        serialize_entries(&mut lines, entries).expect("serializing to memory");
+        String::from_utf8(lines).expect("JSON is UTF-8")
+    };
+    let mut rendered = RenderedEntries { senses, sidecar, ..Default::default() };
+    if senses == 0 {
+        return rendered;
+    }
+    if split::enabled() {
+        rendered.split = group_by_pos(entries).into_iter().map(|(pos, group)| (pos, serialize(group))).collect();
+    } else {
+        rendered.lines = serialize(entries);
+    }
+    rendered
+}
+
+/// Write a rendered page. Returns the number of senses written.
+pub fn write_rendered<W: Write>(writer: &mut W, rendered: RenderedEntries) -> io::Result<usize> {
+    if rendered.senses == 0 {
+        return Ok(0);
+    }
+    let _timer = metrics::time(Stage::Write);
+    metrics::senses_written(rendered.senses);
+    write_records(&rendered.sidecar)?;
+    writer.write_all(rendered.lines.as_bytes())?;
+    for (pos, lines) in &rendered.split {
+        split::write_lines(pos, lines.as_bytes())?;
+    }
+    Ok(rendered.senses)
+}
+
+/// Turns the JSON lines written through it into the elements of one array,
+/// keeping one element per line. Raw newlines only end lines, since JSON
+/// escapes them in strings. The strategies own the writer they are given, so
+/// the array is closed through the `JsonArrayEnd` handle once they return.
+pub struct JsonArrayWriter {
+    state: Arc<Mutex<JsonArray>>,
+}
+
+pub struct JsonArrayEnd {
+    state: Arc<Mutex<JsonArray>>,
+}
+
+struct JsonArray {
+    inner: Box<dyn Write + Send>,
+    elements: usize,
+    /// Whether an element has been started and its line not yet ended
+    in_element: bool,
+}
+
+impl JsonArrayWriter {
+    pub fn new(inner: Box<dyn Write + Send>) -> (Self, JsonArrayEnd) {
+        let state = Arc::new(Mutex::new(JsonArray { inner, elements: 0, in_element: false }));
+        (JsonArrayWriter { state: state.clone() }, JsonArrayEnd { state })
+    }
+}
+
+fn lock(state: &Mutex<JsonArray>) -> MutexGuard<'_, JsonArray> {
+    state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Write for JsonArrayWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let mut array = lock(&self.state);
+        let mut rest = buf;
+        while !rest.is_empty() {
+            if !array.in_element {
+                // The previous line's newline is held back until the comma after it
+                let separator: &[u8] = if array.elements == 0 { b"[\n" } else { b",\n" };
+                array.inner.write_all(separator)?;
+                array.elements += 1;
+                array.in_element = true;
+            }
+            match memchr::memchr(b'\n', rest) {
+                Some(end) => {
+                    array.inner.write_all(&rest[..end])?;
+                    array.in_element = false;
+                    rest = &rest[end + 1..];
+                }
+                None => {
+                    array.inner.write_all(rest)?;
+                    rest = &[];
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        lock(&self.state).inner.flush()
+    }
+}
+
+impl JsonArrayEnd {
+    /// Close the array, once everything has been written
+    pub fn finish(self) -> io::Result<()> {
+        let mut array = lock(&self.state);
+        let end: &[u8] = if array.elements == 0 { b"[]\n" } else { b"\n]\n" };
+        array.inner.write_all(end)?;
+        array.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(json: &str) -> Entry<'static> {
+        serde_json::from_str(json).unwrap()
+    }
+
+    #[test]
+    fn shared_fields_move_up_to_the_word() {
+        let morphology = r#"{"type":"suffixed","base":"hap","components":["hap","-y"],"prefixes":[],"suffixes":["-y"],"is_compound":false,"etymology_template":"{{af|en|hap|-y}}"}"#;
+        let entries = vec![
+            entry(&format!(r#"{{"id":"happy","pos":"ADJ","wc":1,"nsyll":2,"register_tags":["informal"],"morphology":{}}}"#, morphology)),
+            entry(&format!(r#"{{"id":"happy","pos":"NOU","wc":1,"nsyll":2,"morphology":{}}}"#, morphology)),
+        ];
+        let json = serde_json::to_string(&NestedWord::from_entries(entries)).unwrap();
+        assert!(json.starts_with(r#"{"id":"happy","wc":1,"nsyll":2,"morphology":{"type":"suffixed","base":"hap","#));
+        assert!(json.ends_with(r#""senses":[{"pos":"ADJ","register_tags":["informal"]},{"pos":"NOU"}]}"#));
+    }
+
+    #[test]
+    fn etymology_fields_stay_on_senses_when_they_differ() {
+        let entries = vec![
+            entry(r#"{"id":"bat","pos":"NOU","wc":1,"doublets":["baton"]}"#),
+            entry(r#"{"id":"bat","pos":"VRB","wc":1}"#),
+        ];
+        let json = serde_json::to_string(&NestedWord::from_entries(entries)).unwrap();
+        assert_eq!(json, r#"{"id":"bat","wc":1,"senses":[{"pos":"NOU","doublets":["baton"]},{"pos":"VRB"}]}"#);
+    }
+
+    #[test]
+    fn rendered_pages_write_like_entries() {
+        let entries = || vec![entry(r#"{"id":"bat","pos":"NOU","wc":1}"#), entry(r#"{"id":"bat","pos":"VRB","wc":1}"#)];
+        let mut written = Vec::new();
+        assert_eq!(write_entries(&mut written, entries(), None).unwrap(), 2);
+        let rendered = render_entries(entries());
+        assert_eq!(rendered.lines.as_bytes(), written);
+        let mut copied = Vec::new();
+        assert_eq!(write_rendered(&mut copied, rendered).unwrap(), 2);
+        assert_eq!(copied, written);
+        assert_eq!(render_entries(Vec::new()).lines, "");
+    }
+
+    /// A writer the test can read back after handing it over
+    #[derive(Clone, Default)]
+    struct Shared(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn array_of(chunks: &[&str]) -> String {
+        let out = Shared::default();
+        let (mut writer, end) = JsonArrayWriter::new(Box::new(out.clone()));
+        for chunk in chunks {
+            writer.write_all(chunk.as_bytes()).unwrap();
+        }
+        drop(writer);
+        end.finish().unwrap();
+        let bytes = out.0.lock().unwrap().clone();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn json_lines_become_array_elements() {
+        let array = array_of(&["{\"id\":\"a\"}\n{\"id\"", ":\"b\\nc\"}\n", "{\"id\":\"d\"}\n"]);
+        assert_eq!(array, "[\n{\"id\":\"a\"},\n{\"id\":\"b\\nc\"},\n{\"id\":\"d\"}\n]\n");
+        let elements: Vec<serde_json::Value> = serde_json::from_str(&array).unwrap();
+        assert_eq!(elements.len(), 3);
+        assert_eq!(array_of(&[]), "[]\n");
+    }
+}
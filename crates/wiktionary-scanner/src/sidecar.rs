@@ -0,0 +1,307 @@
+//! Sidecar outputs, with `--sidecars`.
+//!
+//! Some page data is too bulky or too loosely tied to a sense for the main
+//! entries, which stay one small object per sense. `--sidecars` writes it to
+//! JSONL streams of its own in the same run, one record per line:
+//!
+//! - `pronunciations.jsonl`: every English transcription on the page, with
+//!   the accents it is given for (`{{a|UK}}`, `a=`)
+//! - `relations.jsonl`: the page's derived, related and coordinate terms,
+//!   from `*` lines and `{{col}}`-style lists
+//!
+//! ```json
+//! {"id":"leisure","ipa":"/ˈlɛʒ.ə/","accents":["UK"]}
+//! {"id":"cat","relation":"derived","term":"catfish"}
+//! ```
+//!
+//! parse_page attaches a page's records to its first entry, and they are
+//! written alongside that page's entries, so the streams follow dump order
+//! with every strategy and cover the pages that are written.
+
+use crate::page_ast::PageAst;
+use crate::{create_output, nyms, unescape_xml, Entry, MarkupMode, OutputFile, WikitextParser, IPA_TRANSCRIPTION};
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+static SIDECARS: OnceCell<Mutex<Vec<SidecarStream>>> = OnceCell::new();
+// The sidecars being written, read by parse_page without taking the lock
+static SIDECAR_KINDS: OnceCell<Vec<Sidecar>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Sidecar {
+    /// Transcriptions and their accents (pronunciations.jsonl)
+    Pronunciations,
+    /// Derived, related and coordinate terms (relations.jsonl)
+    Relations,
+}
+
+impl Sidecar {
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Sidecar::Pronunciations => "pronunciations.jsonl",
+            Sidecar::Relations => "relations.jsonl",
+        }
+    }
+}
+
+/// One line of a sidecar stream
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SidecarRecord {
+    pub sidecar: Sidecar,
+    pub line: String,
+}
+
+#[derive(Serialize)]
+struct PronunciationRecord<'a> {
+    id: &'a str,
+    ipa: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    accents: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct RelationRecord<'a> {
+    id: &'a str,
+    relation: &'static str,
+    term: String,
+}
+
+struct SidecarStream {
+    sidecar: Sidecar,
+    path: PathBuf,
+    /// None once `finish_sidecars` has flushed and closed it
+    writer: Option<BufWriter<Box<dyn Write + Send>>>,
+    written: usize,
+}
+
+fn streams() -> Option<std::sync::MutexGuard<'static, Vec<SidecarStream>>> {
+    SIDECARS.get().map(|streams| streams.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+/// Open a stream in `dir` for each sidecar, for the rest of the run. The
+/// returned files are committed by the caller once the run has succeeded.
+pub fn open_sidecars(sidecars: &[Sidecar], dir: &Path, force: bool) -> io::Result<Vec<OutputFile>> {
+    let mut streams = Vec::new();
+    let mut files = Vec::new();
+    for &sidecar in sidecars {
+        if streams.iter().any(|stream: &SidecarStream| stream.sidecar == sidecar) {
+            continue;
+        }
+        let path = dir.join(sidecar.file_name());
+        let (writer, file) = create_output(&path, force)?;
+        streams.push(SidecarStream { sidecar, path, writer: Some(BufWriter::new(writer)), written: 0 });
+        files.push(file);
+    }
+    let _ = SIDECAR_KINDS.set(streams.iter().map(|stream| stream.sidecar).collect());
+    if SIDECARS.set(Mutex::new(streams)).is_err() {
+        panic!("Sidecars already initialized");
+    }
+    Ok(files)
+}
+
+fn enabled(sidecar: Sidecar) -> bool {
+    SIDECAR_KINDS.get().is_some_and(|kinds| kinds.contains(&sidecar))
+}
+
+/// Write a page's records to their streams, if they are open
+pub fn write_records(records: &[SidecarRecord]) -> io::Result<()> {
+    let Some(mut streams) = streams() else {
+        return Ok(());
+    };
+    for record in records {
+        if let Some(stream) = streams.iter_mut().find(|stream| stream.sidecar == record.sidecar) {
+            if let Some(writer) = stream.writer.as_mut() {
+                writeln!(writer, "{}", record.line)?;
+                stream.written += 1;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Flush and close the streams. Returns each one's path and record count.
+pub fn finish_sidecars() -> io::Result<Vec<(PathBuf, usize)>> {
+    let Some(mut streams) = streams() else {
+        return Ok(Vec::new());
+    };
+    let mut written = Vec::new();
+    for stream in streams.iter_mut() {
+        if let Some(mut writer) = stream.writer.take() {
+            writer.flush()?;
+        }
+        written.push((stream.path.clone(), stream.written));
+    }
+    Ok(written)
+}
+
+/// Keep the entries `keep` accepts, moving the page's records to the first
+/// one kept
+pub fn retain_entries(entries: &mut Vec<Entry<'_>>, keep: impl FnMut(&Entry<'_>) -> bool) {
+    let records = entries.first_mut().map(|entry| std::mem::take(&mut entry.sidecar)).unwrap_or_default();
+    entries.retain(keep);
+    if let Some(first) = entries.first_mut() {
+        first.sidecar = records;
+    }
+}
+
+/// The records of the open sidecars for one page
+pub fn page_records(word: &str, ast: &PageAst) -> Vec<SidecarRecord> {
+    let mut records = Vec::new();
+    if enabled(Sidecar::Pronunciations) {
+        records.extend(pronunciations(word, ast).into_iter().map(|record| SidecarRecord {
+            sidecar: Sidecar::Pronunciations,
+            line: serde_json::to_string(&record).unwrap_or_default(),
+        }));
+    }
+    if enabled(Sidecar::Relations) {
+        records.extend(relations(word, ast).into_iter().map(|record| SidecarRecord {
+            sidecar: Sidecar::Relations,
+            line: serde_json::to_string(&record).unwrap_or_default(),
+        }));
+    }
+    records
+}
+
+/// Bodies of the sections whose header satisfies `wanted`, with the header
+fn section_bodies<'a>(ast: &PageAst<'a>, wanted: impl Fn(&str) -> bool) -> Vec<(String, &'a str)> {
+    let text = ast.text();
+    let headers: Vec<_> = ast.headers_from(2).collect();
+    let mut bodies = Vec::new();
+    for (i, header) in headers.iter().enumerate() {
+        let title = ast.slice(&header.title).trim().to_lowercase();
+        if wanted(&title) {
+            let body_end = headers.get(i + 1).map_or(text.len(), |next| next.span.start);
+            bodies.push((title, &text[header.span.end..body_end]));
+        }
+    }
+    bodies
+}
+
+/// Each transcription once, with the accents of the lines it is on
+fn pronunciations<'a>(word: &'a str, ast: &PageAst) -> Vec<PronunciationRecord<'a>> {
+    let mut records: Vec<PronunciationRecord> = Vec::new();
+    for (_, body) in section_bodies(ast, |title| title.starts_with("pronunciation")) {
+        for line in body.lines() {
+            let line = unescape_xml(line);
+            let line_ast = PageAst::parse(&line);
+            let mut accents: Vec<String> = Vec::new();
+            let mut transcriptions: Vec<String> = Vec::new();
+            for source in line_ast.templates(&["IPA", "a", "accent"]) {
+                let template = WikitextParser::new(source).parse_template();
+                if !template.name.eq_ignore_ascii_case("IPA") {
+                    // Newer uses give the language first: {{a|en|UK}}
+                    let language = usize::from(template.params.first().is_some_and(|lang| lang.trim() == "en"));
+                    accents.extend(template.params.iter().skip(language).map(|accent| accent.trim().to_string()));
+                } else if template.params.first().is_some_and(|lang| lang == "en") {
+                    transcriptions.extend(template.params.iter().skip(1).filter(|p| IPA_TRANSCRIPTION.is_match(p)).cloned());
+                    let named = ["a", "aa"].iter().filter_map(|key| template.named.get(*key));
+                    accents.extend(named.flat_map(|value| value.split(',').map(|accent| accent.trim().to_string())));
+                }
+            }
+            accents.retain(|accent| !accent.is_empty());
+            for ipa in transcriptions {
+                match records.iter_mut().find(|record| record.ipa == ipa) {
+                    Some(record) => {
+                        for accent in &accents {
+                            if !record.accents.contains(accent) {
+                                record.accents.push(accent.clone());
+                            }
+                        }
+                    }
+                    None => records.push(PronunciationRecord { id: word, ipa, accents: accents.clone() }),
+                }
+            }
+        }
+    }
+    records
+}
+
+fn relation(title: &str) -> Option<&'static str> {
+    match title {
+        "derived terms" => Some("derived"),
+        "related terms" => Some("related"),
+        "coordinate terms" => Some("coordinate"),
+        _ => None,
+    }
+}
+
+/// Column list templates: {{col3|en|...}}, {{der3|en|...}}, {{rel-top}} lists and the like
+fn is_column_list(name: &str) -> bool {
+    let name = name.trim().to_lowercase();
+    ["col", "der", "rel"].iter().any(|prefix| {
+        name.strip_prefix(prefix).is_some_and(|rest| rest.is_empty() || rest == "-auto" || rest.chars().all(|ch| ch.is_ascii_digit()))
+    })
+}
+
+/// Each related term once per relation
+fn relations<'a>(word: &'a str, ast: &PageAst) -> Vec<RelationRecord<'a>> {
+    let mut records: Vec<RelationRecord> = Vec::new();
+    let mut push = |relation: &'static str, term: String| {
+        if !records.iter().any(|record| record.relation == relation && record.term == term) {
+            records.push(RelationRecord { id: word, relation, term });
+        }
+    };
+    for (title, body) in section_bodies(ast, |title| relation(title).is_some()) {
+        let Some(relation) = relation(&title) else {
+            continue;
+        };
+        for (_, terms) in nyms::section_nyms(relation, body) {
+            terms.into_iter().for_each(|nym| push(relation, nym.term));
+        }
+        let body = unescape_xml(body);
+        let body_ast = PageAst::parse(&body);
+        for template in &body_ast.templates {
+            // Strip drops inline modifiers such as <q:informal>
+            let template = WikitextParser::with_markup(body_ast.slice(&template.span), MarkupMode::Strip).parse_template();
+            if is_column_list(&template.name) && template.params.first().is_some_and(|lang| lang.trim() == "en") {
+                template.params.iter().skip(1).filter_map(|param| nyms::term(param)).for_each(|term| push(relation, term));
+            }
+        }
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pronunciations_with_accents() {
+        let text = "===Pronunciation===\n* {{a|UK}} {{IPA|en|/ˈlɛʒ.ə/}}\n* {{a|en|US}} {{IPA|en|/ˈliː.ʒɚ/|/ˈlɛʒ.ɚ/}}\n\
+                    * {{IPA|en|/ˈlɛʒ.ə/|a=Australia}}\n* {{rhymes|en|ɛʒə(ɹ)}}\n\n===Noun===\n# Free time.\n";
+        let lines: Vec<String> =
+            pronunciations("leisure", &PageAst::parse(text)).iter().map(|r| serde_json::to_string(r).unwrap()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                r#"{"id":"leisure","ipa":"/ˈlɛʒ.ə/","accents":["UK","Australia"]}"#,
+                r#"{"id":"leisure","ipa":"/ˈliː.ʒɚ/","accents":["US"]}"#,
+                r#"{"id":"leisure","ipa":"/ˈlɛʒ.ɚ/","accents":["US"]}"#,
+            ]
+        );
+    }
+
+    #[test]
+    fn relations_from_lists_and_columns() {
+        let text = "===Noun===\n# A feline.\n\n====Derived terms====\n{{col3|en|catfish|[[catnap]]|cat burglar<q:informal>}}\n\
+                    * {{l|en|catfish}}\n\n====Related terms====\n* [[kitten]], [[Thesaurus:cat]]\n\n====Translations====\n* [[Katze]]\n";
+        let terms: Vec<(&str, String)> =
+            relations("cat", &PageAst::parse(text)).into_iter().map(|r| (r.relation, r.term)).collect();
+        assert_eq!(
+            terms,
+            vec![
+                ("derived", "catfish".to_string()),
+                ("derived", "catnap".to_string()),
+                ("derived", "cat burglar".to_string()),
+                ("related", "kitten".to_string()),
+            ]
+        );
+        assert!(is_column_list("der3") && is_column_list("col-auto") && !is_column_list("color"));
+    }
+}
@@ -0,0 +1,117 @@
+//! Namespaces from the dump's `<siteinfo>` header.
+//!
+//! A dump starts with the wiki's namespace list:
+//!
+//! ```xml
+//! <namespaces>
+//!   <namespace key="0" case="case-sensitive" />
+//!   <namespace key="1" case="case-sensitive">Talk</namespace>
+//! ```
+//!
+//! Every named namespace becomes a special-page prefix ("Talk:"), replacing
+//! `special_page_prefixes` from the labels schema, so the skip list follows
+//! the wiki rather than a copy that can drift. Inputs without a header (page
+//! fragments, stdin from another tool) keep the YAML list.
+
+use crate::unescape_xml;
+
+use lazy_static::lazy_static;
+use memchr::memmem;
+use regex::Regex;
+use std::io::{self, BufRead, Cursor, Read};
+
+type Input = Box<dyn BufRead + Send>;
+
+/// Stop looking for the header after this much input
+const HEADER_LIMIT: usize = 1024 * 1024;
+
+lazy_static! {
+    static ref NAMESPACE: Regex =
+        Regex::new(r#"<namespace\s+key="(-?\d+)"[^>]*>([^<]*)</namespace>"#).unwrap();
+}
+
+/// "Name:" for each named namespace other than the main one, in header order
+pub fn namespace_prefixes(header: &str) -> Vec<String> {
+    NAMESPACE
+        .captures_iter(header)
+        .filter(|cap| &cap[1] != "0" && !cap[2].trim().is_empty())
+        .map(|cap| format!("{}:", unescape_xml(cap[2].trim())))
+        .collect()
+}
+
+/// Read the start of `reader` up to the end of `<siteinfo>` (or the first
+/// page) and return the namespace prefixes found there, with a reader that
+/// yields the whole input again.
+pub fn read_namespaces(reader: Input) -> io::Result<(Input, Option<Vec<String>>)> {
+    let (reader, head) = read_head(reader)?;
+    let prefixes = memmem::find(&head, b"</namespaces>").map(|end| {
+        namespace_prefixes(&String::from_utf8_lossy(&head[..end]))
+    });
+    Ok((reader, prefixes.filter(|p| !p.is_empty())))
+}
+
+/// Like `read_namespaces`, returning the whole `<siteinfo>` element instead
+pub fn read_siteinfo(reader: Input) -> io::Result<(Input, Option<String>)> {
+    let (reader, head) = read_head(reader)?;
+    let siteinfo = memmem::find(&head, b"<siteinfo>").and_then(|start| {
+        let end = memmem::find(&head[start..], b"</siteinfo>")? + start + b"</siteinfo>".len();
+        Some(String::from_utf8_lossy(&head[start..end]).into_owned())
+    });
+    Ok((reader, siteinfo))
+}
+
+/// The start of `reader` through `</siteinfo>` or the first `<page>`, with
+/// a reader that yields the whole input again
+fn read_head(mut reader: Input) -> io::Result<(Input, Vec<u8>)> {
+    let mut head = Vec::new();
+    let mut chunk = [0u8; 8192];
+    while head.len() < HEADER_LIMIT
+        && memmem::find(&head, b"</siteinfo>").is_none()
+        && memmem::find(&head, b"<page>").is_none()
+    {
+        let n = reader.read(&mut chunk)?;
+        if n == 0 {
+            break;
+        }
+        head.extend_from_slice(&chunk[..n]);
+    }
+    let reader: Input = Box::new(Cursor::new(head.clone()).chain(reader));
+    Ok((reader, head))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = r#"<mediawiki>
+  <siteinfo>
+    <sitename>Wiktionary</sitename>
+    <namespaces>
+      <namespace key="-2" case="case-sensitive">Media</namespace>
+      <namespace key="0" case="case-sensitive" />
+      <namespace key="1" case="case-sensitive">Talk</namespace>
+      <namespace key="100" case="case-sensitive">Appendix</namespace>
+      <namespace key="118" case="case-sensitive">Reconstruction</namespace>
+    </namespaces>
+  </siteinfo>
+"#;
+
+    #[test]
+    fn prefixes_from_named_namespaces() {
+        assert_eq!(namespace_prefixes(HEADER), vec!["Media:", "Talk:", "Appendix:", "Reconstruction:"]);
+    }
+
+    #[test]
+    fn input_is_replayed_after_the_header() {
+        let input = format!("{}<page><title>cat</title></page>\n</mediawiki>\n", HEADER);
+        let reader: Input = Box::new(io::BufReader::with_capacity(16, Cursor::new(input.clone())));
+        let (mut reader, prefixes) = read_namespaces(reader).unwrap();
+        assert_eq!(prefixes.unwrap().len(), 4);
+        let mut replayed = String::new();
+        reader.read_to_string(&mut replayed).unwrap();
+        assert_eq!(replayed, input);
+
+        let fragment: Input = Box::new(Cursor::new("<page><title>cat</title></page>"));
+        assert!(read_namespaces(fragment).unwrap().1.is_none());
+    }
+}
@@ -0,0 +1,148 @@
+//! Output split by part of speech, with `--split-by pos`.
+//!
+//! Consumers that want one part of speech would otherwise filter the whole
+//! lexicon. With `--split-by pos` the output path is a directory, and each
+//! part of speech is written to a file of its own there, named from its name
+//! in `schema/pos.yaml`: `nouns.jsonl`, `verbs.jsonl`, `proper-nouns.jsonl`, ...
+//! A file is opened when its first entry is written.
+//!
+//! A page's entries are grouped by part of speech as they are serialized (on
+//! the workers, with the parallel strategies), and the writer stage appends
+//! each group to its file, so every file follows dump order. In the nested
+//! shape a word with several parts of speech is written to each of their
+//! files, with the senses of that part of speech.
+
+use crate::shape::{self, JsonArrayEnd, JsonArrayWriter};
+use crate::{check_overwrite, create_output, pos_codes, pos_name, OutputFile};
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+
+static SPLIT: OnceCell<Mutex<SplitStreams>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SplitBy {
+    /// One file per part of speech (nouns.jsonl, verbs.jsonl, ...)
+    Pos,
+}
+
+struct SplitStreams {
+    dir: PathBuf,
+    force: bool,
+    /// By POS code
+    streams: HashMap<String, PosStream>,
+}
+
+struct PosStream {
+    path: PathBuf,
+    writer: BufWriter<Box<dyn Write + Send>>,
+    /// Closes the file's array, with --json-array
+    array_end: Option<JsonArrayEnd>,
+    file: OutputFile,
+    written: usize,
+}
+
+/// `nouns.jsonl` for NOU
+fn file_name(code: &str) -> String {
+    plural_file_name(pos_name(code).unwrap_or(code))
+}
+
+/// `proper-nouns.jsonl` for "Proper Noun"
+fn plural_file_name(name: &str) -> String {
+    let name = name.to_lowercase().replace(' ', "-");
+    let plural = if name.ends_with('s') || name.ends_with('x') { "es" } else { "s" };
+    format!("{}{}.jsonl", name, plural)
+}
+
+fn streams() -> Option<MutexGuard<'static, SplitStreams>> {
+    SPLIT.get().map(|streams| streams.lock().unwrap_or_else(|e| e.into_inner()))
+}
+
+pub fn enabled() -> bool {
+    SPLIT.get().is_some()
+}
+
+/// Split the rest of the run's output into `dir`, creating it if needed. The
+/// files are checked against `force` now, since they are opened mid-scan.
+pub fn open_split(dir: &Path, force: bool) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    for code in pos_codes() {
+        check_overwrite(&dir.join(file_name(code)), force)?;
+    }
+    let streams = SplitStreams { dir: dir.to_path_buf(), force, streams: HashMap::new() };
+    if SPLIT.set(Mutex::new(streams)).is_err() {
+        panic!("Split output already initialized");
+    }
+    Ok(())
+}
+
+fn open_stream(path: PathBuf, force: bool) -> io::Result<PosStream> {
+    let (mut writer, file) = create_output(&path, force)?;
+    let mut array_end = None;
+    if shape::json_array() {
+        let (array, end) = JsonArrayWriter::new(writer);
+        writer = Box::new(array);
+        array_end = Some(end);
+    }
+    Ok(PosStream { path, writer: BufWriter::new(writer), array_end, file, written: 0 })
+}
+
+/// Append one part of speech's lines to its file
+pub fn write_lines(pos: &str, lines: &[u8]) -> io::Result<()> {
+    let Some(mut split) = streams() else {
+        return Ok(());
+    };
+    if !split.streams.contains_key(pos) {
+        let stream = open_stream(split.dir.join(file_name(pos)), split.force)?;
+        split.streams.insert(pos.to_string(), stream);
+    }
+    let stream = split.streams.get_mut(pos).expect("opened above");
+    stream.writer.write_all(lines)?;
+    stream.written += memchr::memchr_iter(b'\n', lines).count();
+    Ok(())
+}
+
+/// Flush, close and commit the files. Returns each one's path and record
+/// count, by file name.
+pub fn finish_split() -> io::Result<Vec<(PathBuf, usize)>> {
+    let Some(mut split) = streams() else {
+        return Ok(Vec::new());
+    };
+    let mut written = Vec::new();
+    for (_, mut stream) in split.streams.drain() {
+        stream.writer.flush()?;
+        drop(stream.writer);
+        if let Some(end) = stream.array_end {
+            end.finish()?;
+        }
+        stream.file.commit()?;
+        written.push((stream.path, stream.written));
+    }
+    written.sort();
+    Ok(written)
+}
+
+/// Remove the files of a failed run
+pub fn discard_split() {
+    if let Some(mut split) = streams() {
+        split.streams.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files_are_named_for_the_plural() {
+        assert_eq!(plural_file_name("Noun"), "nouns.jsonl");
+        assert_eq!(plural_file_name("Proper Noun"), "proper-nouns.jsonl");
+        assert_eq!(plural_file_name("Affix"), "affixes.jsonl");
+    }
+}
@@ -0,0 +1,145 @@
+//! Settling disagreeing syllable counts, with `--syllable-rules`.
+//!
+//! A word's syllable count can come from its IPA, its hyphenation, an
+//! "N-syllable words" category or the `s=` of its rhymes. By default the
+//! first of those present wins, in that order. `--syllable-rules` tries rules
+//! first, in the order given, and falls back to that order when none applies:
+//!
+//! - `majority`: the count more than half of the sources give
+//! - `rhymes-near-ipa`: the rhymes count, when it is within one of the IPA
+//!   count (further apart, the rhymes are more likely wrong than the IPA)
+//!
+//! The settled count is the `nsyll` of the main output and the `final_value`
+//! of `--syllable-validation` records.
+
+use crate::page_ast::PageAst;
+use crate::{
+    extract_syllable_count_from_categories, extract_syllable_count_from_hyphenation, extract_syllable_count_from_ipa,
+    extract_syllable_count_from_rhymes,
+};
+
+use clap::ValueEnum;
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+
+static SYLLABLE_RULES: OnceCell<Vec<SyllableRule>> = OnceCell::new();
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum SyllableRule {
+    /// The count more than half of the sources give
+    Majority,
+    /// The rhymes count when it is within one of the IPA count
+    RhymesNearIpa,
+}
+
+pub fn init_syllable_rules(rules: Vec<SyllableRule>) -> Result<(), String> {
+    SYLLABLE_RULES.set(rules).map_err(|_| "SYLLABLE_RULES already initialized".to_string())
+}
+
+pub fn syllable_rules() -> &'static [SyllableRule] {
+    SYLLABLE_RULES.get().map_or(&[], Vec::as_slice)
+}
+
+/// Syllable counts from each source on a page
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SyllableSources {
+    pub rhymes: Option<usize>,
+    pub ipa: Option<usize>,
+    pub category: Option<usize>,
+    pub hyphenation: Option<usize>,
+}
+
+impl SyllableSources {
+    pub fn from_ast(ast: &PageAst) -> Self {
+        SyllableSources {
+            rhymes: extract_syllable_count_from_rhymes(ast),
+            ipa: extract_syllable_count_from_ipa(ast),
+            category: extract_syllable_count_from_categories(ast),
+            hyphenation: extract_syllable_count_from_hyphenation(ast),
+        }
+    }
+
+    fn counts(&self) -> impl Iterator<Item = usize> {
+        [self.rhymes, self.ipa, self.category, self.hyphenation].into_iter().flatten()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts().next().is_none()
+    }
+
+    pub fn has_disagreement(&self) -> bool {
+        let mut counts = self.counts();
+        counts.next().is_some_and(|first| counts.any(|count| count != first))
+    }
+
+    /// IPA (most reliable) > hyphenation > categories > rhymes
+    fn by_priority(&self) -> Option<usize> {
+        self.ipa.or(self.hyphenation).or(self.category).or(self.rhymes)
+    }
+
+    fn apply(&self, rule: SyllableRule) -> Option<usize> {
+        match rule {
+            SyllableRule::Majority => {
+                let counts: Vec<usize> = self.counts().collect();
+                counts.iter().copied().find(|&count| 2 * counts.iter().filter(|&&c| c == count).count() > counts.len())
+            }
+            SyllableRule::RhymesNearIpa => match (self.rhymes, self.ipa) {
+                (Some(rhymes), Some(ipa)) if rhymes.abs_diff(ipa) <= 1 => Some(rhymes),
+                _ => None,
+            },
+        }
+    }
+
+    /// When the sources disagree, the count the first applicable rule gives,
+    /// with that rule; otherwise the count by priority
+    pub fn resolve(&self, rules: &[SyllableRule]) -> Option<(usize, Option<SyllableRule>)> {
+        let rules = if self.has_disagreement() { rules } else { &[] };
+        rules
+            .iter()
+            .find_map(|&rule| self.apply(rule).map(|count| (count, Some(rule))))
+            .or_else(|| self.by_priority().map(|count| (count, None)))
+    }
+}
+
+/// Syllable count for a page's entries. Without rules only the sources up to
+/// the first one present are read.
+pub fn page_syllables(ast: &PageAst) -> Option<usize> {
+    let rules = syllable_rules();
+    if rules.is_empty() {
+        // Note: rhymes s= parameter was previously prioritized but has known errors in Wiktionary
+        // (e.g., "assassin" has s=2 but IPA shows 3 syllables)
+        return extract_syllable_count_from_ipa(ast)
+            .or_else(|| extract_syllable_count_from_hyphenation(ast))
+            .or_else(|| extract_syllable_count_from_categories(ast))
+            .or_else(|| extract_syllable_count_from_rhymes(ast));
+    }
+    SyllableSources::from_ast(ast).resolve(rules).map(|(count, _)| count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sources(rhymes: Option<usize>, ipa: Option<usize>, category: Option<usize>, hyphenation: Option<usize>) -> SyllableSources {
+        SyllableSources { rhymes, ipa, category, hyphenation }
+    }
+
+    #[test]
+    fn rules_apply_in_order_before_priority() {
+        use SyllableRule::*;
+        let outvoted_ipa = sources(Some(2), Some(3), Some(2), None);
+        assert_eq!(outvoted_ipa.resolve(&[]), Some((3, None)));
+        assert_eq!(outvoted_ipa.resolve(&[Majority]), Some((2, Some(Majority))));
+
+        // A tie has no majority; rhymes one off the IPA count are kept
+        let tie = sources(Some(2), Some(3), None, None);
+        assert_eq!(tie.resolve(&[Majority]), Some((3, None)));
+        assert_eq!(tie.resolve(&[Majority, RhymesNearIpa]), Some((2, Some(RhymesNearIpa))));
+        assert_eq!(sources(Some(1), Some(3), None, None).resolve(&[RhymesNearIpa]), Some((3, None)));
+
+        assert!(tie.has_disagreement() && !sources(None, Some(3), Some(3), None).has_disagreement());
+        assert_eq!(sources(None, Some(3), Some(3), None).resolve(&[Majority]), Some((3, None)));
+        assert_eq!(SyllableSources::default().resolve(&[Majority]), None);
+    }
+}
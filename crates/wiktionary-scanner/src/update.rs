@@ -0,0 +1,257 @@
+//! Incremental update of an existing scanner output.
+//!
+//! Given the JSONL from a previous run and a list of changed titles, only
+//! those pages are re-extracted (from a newer dump or adds-changes file, or
+//! from the live API) and spliced into the output. Replacement entries take
+//! the position of the first old entry for their title, so unchanged lines
+//! keep their order; titles new to the output are appended in sorted order.
+
+use crate::fetch;
+use crate::parallel::{extract_pages_from_xml, process_raw_page, ExtractedPage, RawPage};
+use crate::shape::write_entries;
+use crate::{create_output, open_input, scan_pages, Entry, PageMeta};
+
+use clap::Args as ClapArgs;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(ClapArgs, Debug)]
+pub struct UpdateArgs {
+    /// Existing JSONL output to update
+    pub existing: PathBuf,
+
+    /// File listing changed titles, one per line
+    #[arg(long)]
+    pub titles: PathBuf,
+
+    /// Dump (or adds-changes file) to re-extract pages from; defaults to the live API
+    #[arg(long)]
+    pub source: Option<PathBuf>,
+
+    /// MediaWiki API endpoint used when no --source is given
+    #[arg(long, default_value = "https://en.wiktionary.org/w/api.php")]
+    pub api: String,
+
+    /// Where to write the updated output (defaults to updating in place)
+    #[arg(long)]
+    pub output: Option<PathBuf>,
+}
+
+/// Only the identifier is needed to decide whether a line is replaced
+#[derive(Deserialize)]
+struct EntryId {
+    id: String,
+}
+
+#[derive(Debug, Default)]
+pub struct UpdateStats {
+    pub replaced: usize,
+    pub added: usize,
+    pub removed: usize,
+    pub lines_kept: usize,
+}
+
+/// Read a title list, ignoring blank lines and `#` comments
+pub fn read_title_list(path: &Path) -> io::Result<Vec<String>> {
+    let reader = BufReader::new(File::open(path)?);
+    let mut titles = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let title = line.trim();
+        if !title.is_empty() && !title.starts_with('#') {
+            titles.push(title.to_string());
+        }
+    }
+    Ok(titles)
+}
+
+/// Re-extract the wanted titles from a dump. Later occurrences of a page win,
+/// so an adds-changes file concatenated after an older dump behaves as expected.
+fn extract_from_dump(source: &Path, wanted: &HashSet<String>) -> io::Result<HashMap<String, Vec<Entry<'static>>>> {
+    let mut found = HashMap::new();
+    let mut page_id = 0;
+
+    scan_pages(open_input(source)?, |page_xml| {
+        page_id += 1;
+        if let Some(ExtractedPage::Raw(raw)) = extract_pages_from_xml(&page_xml, page_id) {
+            if wanted.contains(raw.title.trim()) {
+                let title = raw.title.trim().to_string();
+                found.insert(title, process_raw_page(raw, false).entries);
+            }
+        }
+        true
+    })?;
+
+    Ok(found)
+}
+
+fn extract_from_api(api: &str, titles: &[String]) -> io::Result<HashMap<String, Vec<Entry<'static>>>> {
+    let mut found = HashMap::new();
+    for (page_id, (title, wikitext)) in fetch::fetch_wikitext(api, titles)?.into_iter().enumerate() {
+        let raw = RawPage {
+            title: title.clone(),
+            text: fetch::escape_like_dump(&wikitext),
+            page_id,
+            meta: PageMeta::default(),
+        };
+        found.insert(title.trim().to_string(), process_raw_page(raw, false).entries);
+    }
+    Ok(found)
+}
+
+/// Splice fresh entries into an existing JSONL stream.
+///
+/// Every line whose `id` is in `changed` is dropped; the fresh entries for
+/// that title are written where its first old line was. Changed titles that
+/// were not in the old output are appended sorted by title.
+pub fn splice_entries<R: BufRead, W: Write>(
+    existing: R,
+    writer: &mut W,
+    changed: &HashSet<String>,
+    mut fresh: HashMap<String, Vec<Entry<'static>>>,
+) -> io::Result<UpdateStats> {
+    let mut stats = UpdateStats::default();
+    let mut seen: HashSet<String> = HashSet::new();
+
+    for line in existing.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let id = serde_json::from_str::<EntryId>(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in existing output: {}", e)))?
+            .id;
+
+        if !changed.contains(&id) {
+            writeln!(writer, "{}", line)?;
+            stats.lines_kept += 1;
+            continue;
+        }
+
+        if seen.insert(id.clone()) {
+            match fresh.remove(&id) {
+                Some(entries) if !entries.is_empty() => {
+                    write_entries(writer, entries, None)?;
+                    stats.replaced += 1;
+                }
+                _ => stats.removed += 1,
+            }
+        }
+    }
+
+    // Titles that did not exist before, in deterministic order
+    let added: BTreeMap<String, Vec<Entry<'static>>> = fresh
+        .into_iter()
+        .filter(|(title, entries)| !seen.contains(title) && !entries.is_empty())
+        .collect();
+    for entries in added.into_values() {
+        write_entries(writer, entries, None)?;
+        stats.added += 1;
+    }
+
+    Ok(stats)
+}
+
+pub fn run_update(args: &UpdateArgs, force: bool, quiet: bool) -> io::Result<()> {
+    let titles = read_title_list(&args.titles)?;
+    let changed: HashSet<String> = titles.iter().cloned().collect();
+
+    let fresh = match &args.source {
+        Some(source) => extract_from_dump(source, &changed)?,
+        None => extract_from_api(&args.api, &titles)?,
+    };
+
+    // Updating in place replaces the existing output: create_output writes beside it and renames
+    let (output, force) = match &args.output {
+        Some(output) => (output, force),
+        None => (&args.existing, true),
+    };
+    let (writer, output_file) = create_output(output, force)?;
+    let existing = BufReader::with_capacity(256 * 1024, File::open(&args.existing)?);
+    let mut writer = BufWriter::with_capacity(256 * 1024, writer);
+    let stats = splice_entries(existing, &mut writer, &changed, fresh)?;
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+
+    if !quiet {
+        println!("Changed titles: {}", changed.len());
+        println!("Replaced: {}", stats.replaced);
+        println!("Added: {}", stats.added);
+        println!("Removed: {}", stats.removed);
+        println!("Unchanged lines: {}", stats.lines_kept);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(word: &str, pos: &str) -> Entry<'static> {
+        serde_json::from_str(&format!(r#"{{"id":"{}","pos":"{}","wc":1}}"#, word, pos)).unwrap()
+    }
+
+    #[test]
+    fn splice_replaces_in_place_and_appends_new_titles() {
+        let existing = concat!(
+            r#"{"id":"apple","pos":"NOU","wc":1}"#, "\n",
+            r#"{"id":"cat","pos":"NOU","wc":1}"#, "\n",
+            r#"{"id":"cat","pos":"VRB","wc":1}"#, "\n",
+            r#"{"id":"gone","pos":"NOU","wc":1}"#, "\n",
+            r#"{"id":"zebra","pos":"NOU","wc":1}"#, "\n",
+        );
+        let changed: HashSet<String> = ["cat", "gone", "bat"].iter().map(|s| s.to_string()).collect();
+        let mut fresh = HashMap::new();
+        fresh.insert("cat".to_string(), vec![entry("cat", "ADJ")]);
+        fresh.insert("bat".to_string(), vec![entry("bat", "NOU")]);
+
+        let mut out = Vec::new();
+        let stats = splice_entries(existing.as_bytes(), &mut out, &changed, fresh).unwrap();
+        let ids: Vec<String> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<Entry>(l).unwrap())
+            .map(|e| format!("{}/{}", e.word, e.pos))
+            .collect();
+
+        assert_eq!(ids, vec!["apple/NOU", "cat/ADJ", "zebra/NOU", "bat/NOU"]);
+        assert_eq!((stats.replaced, stats.added, stats.removed), (1, 1, 1));
+    }
+
+    #[test]
+    fn output_replaced_only_with_force() {
+        let path = |name: &str| std::env::temp_dir().join(format!("update-{}-{}", std::process::id(), name));
+        fs::write(path("titles.txt"), "# nothing changed\n").unwrap();
+        fs::write(path("source.xml"), "<mediawiki>\n</mediawiki>\n").unwrap();
+        fs::write(path("existing.jsonl"), "{\"id\":\"cat\",\"pos\":\"NOU\",\"wc\":1}\n").unwrap();
+        fs::write(path("other.json"), "kept\n").unwrap();
+        let mut args = UpdateArgs {
+            existing: path("existing.jsonl"),
+            titles: path("titles.txt"),
+            source: Some(path("source.xml")),
+            api: String::new(),
+            output: Some(path("other.json")),
+        };
+
+        let err = run_update(&args, false, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(fs::read_to_string(path("other.json")).unwrap(), "kept\n");
+        assert!(!path("other.json.tmp").exists());
+
+        run_update(&args, true, true).unwrap();
+        assert_eq!(fs::read_to_string(path("other.json")).unwrap(), fs::read_to_string(path("existing.jsonl")).unwrap());
+
+        // Updating in place needs no --force
+        args.output = None;
+        run_update(&args, false, true).unwrap();
+        assert!(!path("existing.jsonl.tmp").exists());
+        for name in ["titles.txt", "source.xml", "existing.jsonl", "other.json"] {
+            fs::remove_file(path(name)).ok();
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Wikidata lexeme IDs.
+//!
+//! Entries get a `wikidata_lexeme` field from two sources: the page's own
+//! `{{wikidata lexeme|L1347}}` links, and, with `--wikidata-lexemes`, a join
+//! against the Wikidata lexeme dump (latest-lexemes.json[.bz2]) on the English
+//! lemma and the lexical category. Dump lexemes whose category has no POS code
+//! here (affixes, phrases) match any sense of their lemma.
+
+use crate::open_input;
+use crate::page_ast::PageAst;
+use crate::WikitextParser;
+
+use once_cell::sync::OnceCell;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::io::BufRead;
+use std::path::Path;
+
+/// Q-id of the English language item
+const ENGLISH: &str = "Q1860";
+
+static LEXEMES: OnceCell<LexemeIndex> = OnceCell::new();
+
+/// English lemma → (L-id, POS code of its lexical category)
+pub type LexemeIndex = HashMap<String, Vec<(String, Option<&'static str>)>>;
+
+#[derive(Deserialize)]
+struct Lexeme {
+    id: String,
+    #[serde(default)]
+    language: String,
+    #[serde(default, rename = "lexicalCategory")]
+    lexical_category: String,
+    #[serde(default)]
+    lemmas: HashMap<String, Lemma>,
+}
+
+#[derive(Deserialize)]
+struct Lemma {
+    value: String,
+}
+
+/// POS code for a Wikidata lexical category
+fn category_pos(qid: &str) -> Option<&'static str> {
+    Some(match qid {
+        "Q1084" => "NOU",
+        "Q147276" => "NAM",
+        "Q24905" => "VRB",
+        "Q34698" => "ADJ",
+        "Q380057" => "ADV",
+        "Q36224" => "PRN",
+        "Q576271" => "DET",
+        "Q4833830" => "ADP",
+        "Q36484" => "CNJ",
+        "Q83034" => "ITJ",
+        "Q63116" => "NUM",
+        _ => return None,
+    })
+}
+
+/// Index the English lexemes of a dump: a JSON array with one lexeme per line
+pub fn load_lexemes(path: &Path) -> Result<LexemeIndex, String> {
+    let reader = open_input(path).map_err(|e| format!("Failed to open {:?}: {}", path, e))?;
+    let mut index = LexemeIndex::new();
+    for (number, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| format!("Failed to read {:?}: {}", path, e))?;
+        let record = line.trim().trim_end_matches(',');
+        if record.is_empty() || record == "[" || record == "]" {
+            continue;
+        }
+        let lexeme: Lexeme = serde_json::from_str(record)
+            .map_err(|e| format!("Malformed lexeme on line {} of {:?}: {}", number + 1, path, e))?;
+        if lexeme.language != ENGLISH {
+            continue;
+        }
+        let pos = category_pos(&lexeme.lexical_category);
+        // Spelling variants (en-gb, en-us) are lemmas of the same lexeme
+        let mut spellings: Vec<String> = lexeme
+            .lemmas
+            .iter()
+            .filter(|(code, _)| *code == "en" || code.starts_with("en-"))
+            .map(|(_, lemma)| lemma.value.clone())
+            .collect();
+        spellings.sort();
+        spellings.dedup();
+        for spelling in spellings {
+            index.entry(spelling).or_default().push((lexeme.id.clone(), pos));
+        }
+    }
+    Ok(index)
+}
+
+pub fn init_lexemes(path: Option<&Path>) -> Result<(), String> {
+    let index = match path {
+        Some(path) => load_lexemes(path)?,
+        None => LexemeIndex::new(),
+    };
+    LEXEMES.set(index).map_err(|_| "LEXEMES already initialized".to_string())
+}
+
+/// L-ids from the page's {{wikidata lexeme}} links
+pub fn template_lexemes(ast: &PageAst) -> Vec<String> {
+    ast.templates(&["wikidata lexeme", "wikidata-lexeme"])
+        .flat_map(|template| WikitextParser::new(template).parse_template().params)
+        .map(|id| id.trim().to_string())
+        .filter(|id| is_lexeme_id(id))
+        .collect()
+}
+
+fn is_lexeme_id(id: &str) -> bool {
+    id.strip_prefix('L').is_some_and(|digits| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit()))
+}
+
+/// The `wikidata_lexeme` field for a sense: the page's links, then dump
+/// lexemes of the word with a matching (or unknown) category
+pub fn sense_lexemes(page_links: &[String], word: &str, pos: &str) -> Vec<String> {
+    let mut ids = page_links.to_vec();
+    let joined = LEXEMES.get().and_then(|index| index.get(word)).into_iter().flatten();
+    for (id, category) in joined {
+        if category.is_none_or(|code| code == pos) && !ids.contains(id) {
+            ids.push(id.clone());
+        }
+    }
+    ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_english_lexemes_from_dump() {
+        let dump = std::env::temp_dir().join(format!("lexemes-{}.json", std::process::id()));
+        std::fs::write(
+            &dump,
+            concat!(
+                "[\n",
+                r#"{"type":"lexeme","id":"L1347","lemmas":{"en-us":{"language":"en-us","value":"color"},"en-gb":{"language":"en-gb","value":"colour"}},"lexicalCategory":"Q1084","language":"Q1860"},"#,
+                "\n",
+                r#"{"type":"lexeme","id":"L7","lemmas":{"en":{"language":"en","value":"cat"}},"lexicalCategory":"Q1084","language":"Q1860"},"#,
+                "\n",
+                r#"{"type":"lexeme","id":"L99","lemmas":{"de":{"language":"de","value":"Katze"}},"lexicalCategory":"Q1084","language":"Q188"}"#,
+                "\n]\n"
+            ),
+        )
+        .unwrap();
+        let index = load_lexemes(&dump).unwrap();
+        std::fs::remove_file(&dump).unwrap();
+        assert_eq!(index["colour"], vec![("L1347".to_string(), Some("NOU"))]);
+        assert_eq!(index["color"], vec![("L1347".to_string(), Some("NOU"))]);
+        assert!(!index.contains_key("Katze"));
+    }
+
+    #[test]
+    fn lexeme_links_from_templates() {
+        let ast = PageAst::parse("==English==\n{{wikidata lexeme|L1347|L1348}}\n{{wikidata lexeme|Q5}}\n");
+        assert_eq!(template_lexemes(&ast), vec!["L1347", "L1348"]);
+    }
+}
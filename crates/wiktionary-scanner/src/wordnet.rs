@@ -0,0 +1,300 @@
+//! WordNet alignment export.
+//!
+//! `export wordnet-map` proposes Open English WordNet synsets for each sense
+//! of scanner output. Candidates are the synsets of WordNet entries with the
+//! same lemma and part of speech; each is scored by how many content words
+//! the sense's `definition` (or `glosses`) shares with the synset's
+//! definition. The scores are candidates for review, not a finished mapping:
+//!
+//! ```json
+//! {"id":"cat","pos":"NOU","sense":1,"candidates":[{"synset":"oewn-02124272-n","ili":"i46593","confidence":0.62,"definition":"feline mammal ..."}]}
+//! ```
+//!
+//! `sense` numbers a word's senses per part of speech from 1, as the OntoLex
+//! export does (`<cat/NOU#sense1>`). The WordNet file is the WN-LMF XML
+//! release of Open English WordNet, which puts each element on its own line.
+
+use crate::{create_output, open_input, unescape_xml};
+
+use clap::Args as ClapArgs;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::path::PathBuf;
+
+#[derive(ClapArgs, Debug)]
+pub struct WordnetMapArgs {
+    /// Scanner output JSONL, ideally made with --definitions (use - for stdin)
+    pub input: PathBuf,
+
+    /// Open English WordNet in WN-LMF XML (english-wordnet-2024.xml)
+    pub wordnet: PathBuf,
+
+    /// Where to write the candidate mappings as JSONL (- for stdout)
+    pub output: PathBuf,
+
+    /// Candidates to keep per sense, best first
+    #[arg(long, default_value_t = 3)]
+    pub candidates: usize,
+
+    /// Leave out candidates below this confidence (0 to 1)
+    #[arg(long, default_value_t = 0.1)]
+    pub min_confidence: f64,
+}
+
+lazy_static! {
+    static ref WRITTEN_FORM: Regex = Regex::new(r#"writtenForm="([^"]*)""#).unwrap();
+    static ref PART_OF_SPEECH: Regex = Regex::new(r#"partOfSpeech="([^"]*)""#).unwrap();
+    static ref SENSE_SYNSET: Regex = Regex::new(r#"\ssynset="([^"]*)""#).unwrap();
+    static ref ID: Regex = Regex::new(r#"\sid="([^"]*)""#).unwrap();
+    static ref ILI: Regex = Regex::new(r#"\sili="([^"]*)""#).unwrap();
+    static ref DEFINITION: Regex = Regex::new(r"<Definition[^>]*>([^<]*)</Definition>").unwrap();
+}
+
+/// Words too common to say anything about which sense is meant
+const STOPWORDS: &[&str] = &[
+    "a", "an", "and", "any", "are", "as", "at", "be", "by", "for", "from", "in", "into", "is", "it", "its", "of",
+    "on", "or", "that", "the", "their", "this", "to", "used", "usually", "which", "who", "with",
+];
+
+/// WordNet parts of speech for a POS code. Adjective satellites (`s`) are
+/// adjectives too.
+fn wordnet_pos(pos: &str) -> &'static [&'static str] {
+    match pos {
+        "NOU" | "NAM" => &["n"],
+        "VRB" => &["v"],
+        "ADJ" => &["a", "s"],
+        "ADV" => &["r"],
+        _ => &[],
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Synset {
+    pub ili: Option<String>,
+    pub definition: String,
+}
+
+/// The parts of a WordNet the alignment needs
+#[derive(Debug, Default)]
+pub struct Wordnet {
+    /// Synset IDs by (written form, part of speech), in file order
+    pub entries: HashMap<(String, String), Vec<String>>,
+    pub synsets: HashMap<String, Synset>,
+}
+
+fn attribute(pattern: &Regex, line: &str) -> Option<String> {
+    pattern.captures(line).map(|cap| unescape_xml(&cap[1]))
+}
+
+pub fn read_wordnet<R: BufRead>(reader: R) -> io::Result<Wordnet> {
+    let mut wordnet = Wordnet::default();
+    let mut lemma: Option<(String, String)> = None;
+    let mut synset: Option<String> = None;
+
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.starts_with("<Lemma ") {
+            lemma = attribute(&WRITTEN_FORM, line).zip(attribute(&PART_OF_SPEECH, line));
+        } else if line.starts_with("<Sense ") {
+            if let (Some(key), Some(id)) = (&lemma, attribute(&SENSE_SYNSET, line)) {
+                wordnet.entries.entry(key.clone()).or_default().push(id);
+            }
+        } else if line.starts_with("</LexicalEntry") {
+            lemma = None;
+        } else if line.starts_with("<Synset ") {
+            synset = attribute(&ID, line);
+            if let Some(id) = &synset {
+                let ili = attribute(&ILI, line).filter(|ili| !ili.is_empty() && ili != "in");
+                wordnet.synsets.insert(id.clone(), Synset { ili, definition: String::new() });
+            }
+        } else if let Some(cap) = DEFINITION.captures(line) {
+            if let Some(entry) = synset.as_ref().and_then(|id| wordnet.synsets.get_mut(id)) {
+                if entry.definition.is_empty() {
+                    entry.definition = unescape_xml(&cap[1]);
+                }
+            }
+        } else if line.starts_with("</Synset") {
+            synset = None;
+        }
+    }
+    Ok(wordnet)
+}
+
+/// Lowercased content words, with a plural "s" dropped
+pub(crate) fn content_words(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .map(str::to_lowercase)
+        .filter(|word| word.len() > 1 && !STOPWORDS.contains(&word.as_str()))
+        .map(|word| match word.strip_suffix('s') {
+            Some(stem) if stem.len() > 2 && !stem.ends_with('s') => stem.to_string(),
+            _ => word,
+        })
+        .collect()
+}
+
+/// Dice coefficient of two word sets
+fn overlap(a: &HashSet<String>, b: &HashSet<String>) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+/// Only the fields the alignment needs
+#[derive(Deserialize)]
+struct SenseRecord {
+    id: String,
+    pos: String,
+    definition: Option<String>,
+    #[serde(default)]
+    glosses: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Candidate {
+    pub synset: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ili: Option<String>,
+    pub confidence: f64,
+    pub definition: String,
+}
+
+#[derive(Serialize)]
+struct SenseMapping<'a> {
+    id: &'a str,
+    pos: &'a str,
+    sense: usize,
+    candidates: Vec<Candidate>,
+}
+
+/// Scored candidate synsets for one sense, best first. A lemma with a single
+/// synset for the part of speech is likely a match whatever the wording, so
+/// its confidence starts at one half.
+fn candidates(wordnet: &Wordnet, word: &str, pos: &str, text: &str) -> Vec<Candidate> {
+    let mut ids: Vec<&String> = Vec::new();
+    for wn_pos in wordnet_pos(pos) {
+        let key = (word.to_string(), wn_pos.to_string());
+        // WordNet writes common nouns in lowercase; Wiktionary keeps the title's case
+        let found = wordnet.entries.get(&key).or_else(|| wordnet.entries.get(&(word.to_lowercase(), key.1)));
+        for id in found.into_iter().flatten() {
+            if !ids.contains(&id) {
+                ids.push(id);
+            }
+        }
+    }
+
+    let sense_words = content_words(text);
+    let monosemous = ids.len() == 1;
+    let mut candidates: Vec<Candidate> = ids
+        .into_iter()
+        .filter_map(|id| {
+            let synset = wordnet.synsets.get(id)?;
+            let score = overlap(&sense_words, &content_words(&synset.definition));
+            let confidence = if monosemous { (1.0 + score) / 2.0 } else { score };
+            Some(Candidate {
+                synset: id.clone(),
+                ili: synset.ili.clone(),
+                confidence: (confidence * 100.0).round() / 100.0,
+                definition: synset.definition.clone(),
+            })
+        })
+        .collect();
+    candidates.sort_by(|a, b| b.confidence.total_cmp(&a.confidence));
+    candidates
+}
+
+/// Write candidate synsets for each sense of the input. Returns the number of
+/// senses with at least one candidate and the number of candidates written.
+pub fn run_export_wordnet_map(args: &WordnetMapArgs, force: bool) -> io::Result<(usize, usize)> {
+    let wordnet = read_wordnet(open_input(&args.wordnet)?)?;
+    let reader = open_input(&args.input)?;
+    let (output, output_file) = create_output(&args.output, force)?;
+    let mut writer = BufWriter::new(output);
+    let mut numbered: HashMap<(String, String), usize> = HashMap::new();
+    let (mut senses, mut written) = (0, 0);
+
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let record: SenseRecord = serde_json::from_str(&line)
+            .map_err(|e| io::Error::other(format!("Malformed line in input: {}", e)))?;
+        let number = numbered.entry((record.id.clone(), record.pos.clone())).or_default();
+        *number += 1;
+
+        let text = record.definition.unwrap_or_else(|| record.glosses.join("; "));
+        let mut found = candidates(&wordnet, &record.id, &record.pos, &text);
+        found.retain(|candidate| candidate.confidence >= args.min_confidence);
+        found.truncate(args.candidates);
+        if found.is_empty() {
+            continue;
+        }
+        senses += 1;
+        written += found.len();
+        let mapping = SenseMapping { id: &record.id, pos: &record.pos, sense: *number, candidates: found };
+        writeln!(writer, "{}", serde_json::to_string(&mapping)?)?;
+    }
+
+    writer.flush()?;
+    drop(writer);
+    output_file.commit()?;
+    Ok((senses, written))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WORDNET: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<LexicalResource xmlns:dc="https://globalwordnet.github.io/schemas/dc/">
+  <Lexicon id="oewn" label="Open English WordNet" language="en" version="2024">
+    <LexicalEntry id="oewn-cat-n">
+      <Lemma writtenForm="cat" partOfSpeech="n"/>
+      <Sense id="oewn-cat__1.05.00" synset="oewn-02124272-n"/>
+      <Sense id="oewn-cat__1.18.00" synset="oewn-10154186-n"/>
+    </LexicalEntry>
+    <LexicalEntry id="oewn-purr-v">
+      <Lemma writtenForm="purr" partOfSpeech="v"/>
+      <Sense id="oewn-purr__2.39.00" synset="oewn-01058574-v"/>
+    </LexicalEntry>
+    <Synset id="oewn-02124272-n" ili="i46593" partOfSpeech="n" members="oewn-cat-n">
+      <Definition>feline mammal usually having thick soft fur and no ability to roar</Definition>
+    </Synset>
+    <Synset id="oewn-10154186-n" ili="i90216" partOfSpeech="n" members="oewn-cat-n">
+      <Definition>an informal term for a youth or man &quot;a nice guy&quot;</Definition>
+    </Synset>
+    <Synset id="oewn-01058574-v" ili="i24105" partOfSpeech="v" members="oewn-purr-v">
+      <Definition>indicate pleasure by purring</Definition>
+    </Synset>
+  </Lexicon>
+</LexicalResource>
+"#;
+
+    #[test]
+    fn reads_entries_and_synsets() {
+        let wordnet = read_wordnet(WORDNET.as_bytes()).unwrap();
+        assert_eq!(wordnet.entries[&("cat".to_string(), "n".to_string())], vec!["oewn-02124272-n", "oewn-10154186-n"]);
+        let synset = &wordnet.synsets["oewn-10154186-n"];
+        assert_eq!(synset.ili.as_deref(), Some("i90216"));
+        assert_eq!(synset.definition, r#"an informal term for a youth or man "a nice guy""#);
+    }
+
+    #[test]
+    fn candidates_ranked_by_gloss_overlap() {
+        let wordnet = read_wordnet(WORDNET.as_bytes()).unwrap();
+        let found = candidates(&wordnet, "Cat", "NOU", "A domesticated feline mammal with soft fur.");
+        assert_eq!(found[0].synset, "oewn-02124272-n");
+        assert!(found[0].confidence > found[1].confidence);
+        assert_eq!(found[1].confidence, 0.0);
+
+        // The only verb synset for "purr" is a fair guess even without shared words
+        let found = candidates(&wordnet, "purr", "VRB", "To make a low, rumbling sound.");
+        assert_eq!((found.len(), found[0].confidence), (1, 0.5));
+        assert!(candidates(&wordnet, "cat", "ADJ", "Feline.").is_empty());
+    }
+}
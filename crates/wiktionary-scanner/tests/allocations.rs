@@ -0,0 +1,70 @@
+//! Allocation counts for the borrowed parse path. The batch pipeline parses
+//! with `parse_page_borrowed`, whose entries point into the page's word-level
+//! data instead of each holding a copy of it; `parse_page` copies it out.
+//!
+//! The counting allocator is global, so this file holds a single test.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use wiktionary_scanner_rust::{
+    init_form_of, init_ipa_inventory, init_labels, init_pos_map, parse_page, parse_page_borrowed, PageData,
+};
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+/// The allocations `f` makes, and what it returns
+fn allocations<T>(f: impl FnOnce() -> T) -> (usize, T) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let value = f();
+    (ALLOCATIONS.load(Ordering::Relaxed) - before, value)
+}
+
+#[test]
+fn borrowed_entries_share_the_page_data() {
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    init_pos_map(Some(&manifest_dir.join("../../schema/pos.yaml"))).unwrap();
+    init_labels(Some(&manifest_dir.join("tests/fixtures/labels.yaml"))).unwrap();
+    init_ipa_inventory(Some(&manifest_dir.join("../../schema/ipa.yaml"))).unwrap();
+    init_form_of(Some(&manifest_dir.join("../../schema/form_of.yaml"))).unwrap();
+    // A large page, escaped the way the XML dump escapes page text
+    let text = fs::read_to_string(manifest_dir.join("benches/pages/cat.wikitext"))
+        .unwrap()
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;");
+
+    // The first parse builds the lazily compiled patterns
+    parse_page("cat", &text);
+    let (owned, entries) = allocations(|| parse_page("cat", &text));
+    let mut data = PageData::default();
+    let (borrowed, borrowed_entries) = allocations(|| parse_page_borrowed("cat", &text, &mut data).len());
+    assert_eq!(borrowed_entries, entries.len());
+    assert!(entries.len() > 10);
+    eprintln!("{} entries: {} allocations borrowed, {} owned", entries.len(), borrowed, owned);
+    // At least the word, part of speech and category list of every sense
+    assert!(borrowed + 3 * entries.len() <= owned, "borrowed {} allocations, owned {}", borrowed, owned);
+}
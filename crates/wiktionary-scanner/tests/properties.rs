@@ -0,0 +1,134 @@
+//! Property tests for the title filters that decide what enters the lexicon.
+//!
+//! Strings are drawn from the kinds of characters titles actually contain:
+//! ASCII, Latin letters with diacritics, combining marks, curly quotes and
+//! dashes, odd whitespace, other scripts, vowel signs, letter numbers and
+//! emoji. The Python scanner's is_englishlike is the reference the Rust copy
+//! has to agree with.
+
+use proptest::prelude::*;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use unicode_normalization::UnicodeNormalization;
+use wiktionary_scanner_rust::{classify_case, is_englishlike, CaseForm};
+
+fn title_char() -> impl Strategy<Value = char> {
+    prop_oneof![
+        4 => prop::char::range('a', 'z'),
+        2 => prop::char::range('A', 'Z'),
+        1 => prop::sample::select(vec![' ', '\'', '-', '.', '/', '0', '7', '&', ';', '<', '>', '!']),
+        2 => prop::char::range('\u{C0}', '\u{24F}'),
+        1 => prop::char::range('\u{300}', '\u{36F}'),
+        1 => prop::sample::select(vec!['\u{2018}', '\u{2019}', '\u{2013}', '\u{2014}', '\u{2044}']),
+        1 => prop::sample::select(vec!['\t', '\n', '\u{1C}', '\u{85}', '\u{A0}', '\u{2009}', '\u{3000}']),
+        1 => prop::char::range('\u{391}', '\u{3C9}'),
+        1 => prop::char::range('\u{410}', '\u{44F}'),
+        1 => prop::char::range('\u{4E00}', '\u{4E2F}'),
+        1 => prop::char::range('\u{1F600}', '\u{1F64F}'),
+        1 => prop::char::range('\u{93E}', '\u{94C}'),
+        1 => prop::sample::select(vec!['\u{2B0}', '\u{2160}', '\u{24B6}', '\u{3007}', '\u{1F130}']),
+    ]
+}
+
+fn title() -> impl Strategy<Value = String> {
+    prop::collection::vec(title_char(), 0..12).prop_map(|chars| chars.into_iter().collect())
+}
+
+/// Words of cased Latin letters, lowercase
+fn lowercase_word() -> impl Strategy<Value = String> {
+    "[a-zà-öø-ÿ]{1,12}"
+}
+
+/// The Python scanner's verdicts (src/openword/scanner/v2/rules.py), or None
+/// when python3 can't run it here
+fn python_englishlike(titles: &[String]) -> Option<Vec<bool>> {
+    let src = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../src");
+    let script = "import json, sys\n\
+                  from openword.scanner.v2.rules import is_englishlike\n\
+                  for line in sys.stdin:\n    print(int(is_englishlike(json.loads(line))))\n";
+    let mut python = Command::new("python3")
+        .args(["-c", script])
+        .env("PYTHONPATH", &src)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .ok()?;
+    let input: String = titles.iter().map(|t| serde_json::to_string(t).unwrap() + "\n").collect();
+    python.stdin.take()?.write_all(input.as_bytes()).ok()?;
+    let output = python.wait_with_output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8(output.stdout).ok()?.lines().map(|line| line == "1").collect())
+}
+
+fn is_latin_letter(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ('\u{C0}'..='\u{24F}').contains(&ch) && ch.is_alphabetic()
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|first| first.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+proptest! {
+    #[test]
+    fn englishlike_ignores_normalization_form(title in title()) {
+        let verdict = is_englishlike(&title);
+        prop_assert_eq!(is_englishlike(&title.nfc().collect::<String>()), verdict);
+        prop_assert_eq!(is_englishlike(&title.nfd().collect::<String>()), verdict);
+    }
+
+    #[test]
+    fn englishlike_ignores_surrounding_spaces(title in title()) {
+        prop_assert_eq!(is_englishlike(&format!(" {} ", title)), is_englishlike(&title));
+    }
+
+    #[test]
+    fn englishlike_needs_a_latin_letter_and_no_markup(title in title()) {
+        if is_englishlike(&title) {
+            prop_assert!(title.nfc().any(is_latin_letter));
+            prop_assert!(!title.contains(['&', ';', '<', '>']));
+        }
+    }
+
+    #[test]
+    fn filters_never_panic(title in any::<String>()) {
+        is_englishlike(&title);
+        classify_case(&title);
+    }
+
+    #[test]
+    fn case_forms_of_cased_words(word in lowercase_word()) {
+        prop_assert_eq!(classify_case(&word), CaseForm::Lower);
+        prop_assert_eq!(classify_case(&word.to_uppercase()), CaseForm::Upper);
+        if word.chars().count() > 1 {
+            prop_assert_eq!(classify_case(&capitalize(&word)), CaseForm::Title);
+        }
+    }
+
+    #[test]
+    fn case_ignores_non_letters(word in lowercase_word(), noise in "[0-9 .'-]{0,4}") {
+        let title = capitalize(&word);
+        prop_assert_eq!(classify_case(&format!("{}{}", noise, title)), classify_case(&title));
+        prop_assert_eq!(classify_case(&format!("{}{}", title, noise)), classify_case(&title));
+    }
+}
+
+proptest! {
+    // One python3 run per case, so fewer, larger cases
+    #![proptest_config(ProptestConfig::with_cases(16))]
+
+    #[test]
+    fn englishlike_agrees_with_python_scanner(titles in prop::collection::vec(title(), 200)) {
+        let Some(python) = python_englishlike(&titles) else {
+            eprintln!("python3 can't run the Python scanner here; skipping");
+            return Ok(());
+        };
+        for (title, python_verdict) in titles.iter().zip(python) {
+            prop_assert_eq!(is_englishlike(title), python_verdict, "title {:?}", title);
+        }
+    }
+}
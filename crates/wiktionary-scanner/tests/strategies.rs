@@ -0,0 +1,206 @@
+//! Every strategy must write the same bytes for the same dump: the parallel
+//! ones reorder their results back into page order before writing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output};
+
+fn fixture(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// Scan the fixture dump with `args`, returning the scanner's output
+fn scan(output_name: &str, args: &[&str]) -> Vec<u8> {
+    scan_with_summary(output_name, &[&["--quiet"], args].concat()).0
+}
+
+/// Scan the fixture dump with `args`, returning the output and what the scanner printed
+fn scan_with_summary(output_name: &str, args: &[&str]) -> (Vec<u8>, String) {
+    let output = std::env::temp_dir().join(format!("strategies-{}-{}.jsonl", std::process::id(), output_name));
+    let summary = scan_to(&output, args);
+    let bytes = fs::read(&output).unwrap();
+    fs::remove_file(&output).unwrap();
+    (bytes, summary)
+}
+
+/// Scan the fixture dump with `args` into `output`, returning what the scanner printed
+fn scan_to(output: &Path, args: &[&str]) -> String {
+    String::from_utf8(run_scanner(&fixture("dump.xml"), output, args).stdout).unwrap()
+}
+
+/// Scan `input` with `args` into `output`
+fn run_scanner(input: &Path, output: &Path, args: &[&str]) -> Output {
+    let run = Command::new(env!("CARGO_BIN_EXE_wiktionary-scanner-rust"))
+        .args(["--force", "--schema"])
+        .arg(Path::new(env!("CARGO_MANIFEST_DIR")).join("../../schema/pos.yaml"))
+        .arg("--labels-schema")
+        .arg(fixture("labels.yaml"))
+        .args(args)
+        .arg(input)
+        .arg(output)
+        .output()
+        .expect("run scanner");
+    assert!(run.status.success(), "scan with {:?} failed", args);
+    run
+}
+
+#[test]
+fn parallel_strategies_match_sequential() {
+    let sequential = scan("sequential", &["--strategy", "sequential"]);
+    assert!(!sequential.is_empty());
+    for strategy in ["channel-pipeline", "batch-parallel", "two-phase"] {
+        // Tiny batches, chunks and buffers, so pages finish out of order and need
+        // reordering, and with the channel pipeline spill all but one waiting page to disk
+        let args = [
+            "--strategy", strategy, "--threads", "4", "--batch-size", "2", "--chunk-pages", "1", "--channel-buffer", "2",
+            "--reorder-limit", "1",
+        ];
+        let output = scan(strategy, &args);
+        assert!(output == sequential, "--strategy {} output differs from sequential", strategy);
+    }
+}
+
+#[test]
+fn two_phase_chunks_match_sequential() {
+    // The fixture's pages repeated past 2 MB, so a --memory-budget of 1 MB
+    // processes them in three chunks
+    let xml = fs::read_to_string(fixture("dump.xml")).unwrap();
+    let (first, last) = (xml.find("<page>").unwrap(), xml.rfind("</page>").unwrap() + "</page>".len());
+    let mut dump = xml[..first].to_string();
+    while dump.len() < 2 * 1024 * 1024 + 1024 {
+        dump.push_str(&xml[first..last]);
+    }
+    dump.push_str(&xml[last..]);
+    let input = std::env::temp_dir().join(format!("strategies-{}-chunks.xml", std::process::id()));
+    fs::write(&input, &dump).unwrap();
+
+    let output = std::env::temp_dir().join(format!("strategies-{}-chunks.jsonl", std::process::id()));
+    let scan_chunks = |args: &[&str]| {
+        let run = run_scanner(&input, &output, args);
+        (fs::read(&output).unwrap(), String::from_utf8(run.stderr).unwrap())
+    };
+    let (sequential, _) = scan_chunks(&["--quiet", "--strategy", "sequential"]);
+    let (chunked, progress) = scan_chunks(&["--strategy", "two-phase", "--threads", "4", "--memory-budget", "1"]);
+    fs::remove_file(&input).unwrap();
+    fs::remove_file(&output).unwrap();
+    assert!(progress.contains("Chunk 3:"), "{}", progress);
+    assert!(chunked == sequential, "--memory-budget output differs from sequential");
+}
+
+#[test]
+fn autoscale_matches_sequential() {
+    let sequential = scan("autoscale-sequential", &["--strategy", "sequential"]);
+    // Small queues, so the controller has a full or empty page queue to act on
+    let args = ["--strategy", "channel-pipeline", "--autoscale", "--threads", "2", "--chunk-pages", "1", "--channel-buffer", "2"];
+    let output = std::env::temp_dir().join(format!("strategies-{}-autoscale.jsonl", std::process::id()));
+    let quiet = run_scanner(&fixture("dump.xml"), &output, &[&["--quiet"], &args[..]].concat());
+    assert!(fs::read(&output).unwrap() == sequential, "--autoscale output differs from sequential");
+    assert!(!String::from_utf8_lossy(&quiet.stderr).contains("Autoscale:"));
+
+    let run = run_scanner(&fixture("dump.xml"), &output, &args);
+    fs::remove_file(&output).unwrap();
+    assert!(String::from_utf8_lossy(&run.stderr).contains("Autoscale:"));
+}
+
+#[test]
+fn parallel_strategies_count_like_sequential() {
+    // The counts, without the strategy, timing and rate lines
+    let counts = |summary: &str| -> Vec<String> {
+        let varying = ["Output", "Strategy", "Threads", "Time", "Rate"];
+        summary.lines().filter(|line| line.contains(": ") && !varying.iter().any(|prefix| line.starts_with(prefix)))
+            .map(str::to_string)
+            .collect()
+    };
+    let sequential = counts(&scan_with_summary("counts-sequential", &["--strategy", "sequential"]).1);
+    assert!(sequential.iter().any(|line| line.starts_with("Words written: ")));
+    for strategy in ["channel-pipeline", "batch-parallel", "two-phase"] {
+        let args = ["--strategy", strategy, "--threads", "4", "--batch-size", "2", "--channel-buffer", "2"];
+        let summary = scan_with_summary(&format!("counts-{}", strategy), &args).1;
+        assert_eq!(counts(&summary), sequential, "--strategy {} counts differ from sequential", strategy);
+    }
+}
+
+#[test]
+fn nested_shape_matches_sequential() {
+    let sequential = scan("nested-sequential", &["--strategy", "sequential", "--shape", "nested"]);
+    let output = scan(
+        "nested-batch-parallel",
+        &["--strategy", "batch-parallel", "--shape", "nested", "--threads", "4", "--batch-size", "2"],
+    );
+    assert!(output == sequential, "--shape nested output differs between strategies");
+    let lines = String::from_utf8(sequential).unwrap();
+    assert!(lines.lines().all(|line| line.starts_with(r#"{"id":"#) && line.contains(r#""senses":[{"pos":"#)));
+}
+
+#[test]
+fn word_limit_writes_whole_words() {
+    let full = String::from_utf8(scan("word-limit-full", &["--strategy", "sequential"])).unwrap();
+    let limited = String::from_utf8(scan("word-limit", &["--strategy", "sequential", "--word-limit", "3"])).unwrap();
+    let id = |line: &str| serde_json::from_str::<serde_json::Value>(line).unwrap()["id"].as_str().unwrap().to_string();
+    let mut words: Vec<String> = limited.lines().map(id).collect();
+    words.dedup();
+    assert_eq!(words.len(), 3);
+    // Every sense of the three words, and nothing after them
+    let expected: String = full.lines().filter(|line| words.contains(&id(line))).map(|line| format!("{}\n", line)).collect();
+    assert_eq!(limited, expected);
+    assert!(full.starts_with(&limited));
+}
+
+#[test]
+fn verify_flag_accepts_matching_output() {
+    let output = scan("verify", &["--strategy", "channel-pipeline", "--verify"]);
+    assert_eq!(output, scan("verify-sequential", &["--strategy", "sequential"]));
+}
+
+#[test]
+fn json_array_holds_the_same_records() {
+    let lines = String::from_utf8(scan("array-lines", &["--strategy", "sequential"])).unwrap();
+    let array = scan("array", &["--strategy", "channel-pipeline", "--threads", "4", "--chunk-pages", "1", "--json-array"]);
+    let records: Vec<serde_json::Value> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    let elements: Vec<serde_json::Value> = serde_json::from_slice(&array).unwrap();
+    assert_eq!(elements, records);
+}
+
+#[test]
+fn manifest_describes_the_output() {
+    // A directory of its own, since every scan replaces the manifest beside its output
+    let dir = std::env::temp_dir().join(format!("strategies-{}-manifest", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let output = dir.join("words.jsonl");
+    scan_to(&output, &["--quiet", "--shape", "nested", "--compact-keys"]);
+    let manifest: serde_json::Value = serde_json::from_slice(&fs::read(dir.join("manifest.json")).unwrap()).unwrap();
+    let lines = fs::read_to_string(&output).unwrap().lines().count();
+    fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!((manifest["shape"].as_str(), manifest["format"].as_str()), (Some("nested"), Some("jsonl")));
+    assert_eq!(manifest["keys"]["senses"], "s");
+    let shard = &manifest["shards"][0];
+    assert_eq!((shard["path"].as_str(), shard["records"].as_u64()), (Some("words.jsonl"), Some(lines as u64)));
+    assert_eq!(manifest["stats"]["words_written"], lines);
+}
+
+#[test]
+fn split_by_pos_writes_each_part_of_speech_in_order() {
+    let full = String::from_utf8(scan("split-full", &["--strategy", "sequential"])).unwrap();
+    let dir = std::env::temp_dir().join(format!("strategies-{}-split", std::process::id()));
+    scan_to(&dir, &["--quiet", "--strategy", "channel-pipeline", "--threads", "4", "--chunk-pages", "1", "--split-by", "pos"]);
+    let pos = |line: &str| serde_json::from_str::<serde_json::Value>(line).unwrap()["pos"].as_str().unwrap().to_string();
+
+    let mut files = 0;
+    let mut lines = 0;
+    for file in fs::read_dir(&dir).unwrap() {
+        let path = file.unwrap().path();
+        if path.extension().is_some_and(|ext| ext == "jsonl") {
+            let split = fs::read_to_string(&path).unwrap();
+            let first = pos(split.lines().next().unwrap());
+            let expected: String = full.lines().filter(|line| pos(line) == first).map(|line| format!("{}\n", line)).collect();
+            assert_eq!(split, expected, "{} differs from the {} lines of the full output", path.display(), first);
+            files += 1;
+            lines += split.lines().count();
+        }
+    }
+    let nouns = dir.join("nouns.jsonl").is_file();
+    fs::remove_dir_all(&dir).unwrap();
+    assert!(files > 1 && nouns);
+    assert_eq!(lines, full.lines().count());
+}
@@ -339,12 +339,13 @@ def sense_is_proper_noun(sense: dict) -> bool:
     """
     Check if sense is a proper noun (names, places, etc.).
 
-    Supports both v1 format (pos='proper') and v2 format (pos='NAM').
+    Supports both v1 format (pos='proper') and v2 format (pos='NAM'), and the
+    scanner's per-sense is_proper_noun flag (set by proper noun headword lines).
     """
     pos = sense.get("pos", "")
     # V2 format uses NAM for proper nouns
     # V1 format uses 'proper'
-    return pos in ("NAM", "proper")
+    return pos in ("NAM", "proper") or sense.get("is_proper_noun", False)
 
 
 # =============================================================================
@@ -0,0 +1,19 @@
+"""Tests for proper noun detection in filters.py."""
+
+
+class TestSenseIsProperNoun:
+    """sense_is_proper_noun() covers the POS codes and the scanner's flag."""
+
+    def test_proper_noun_pos(self):
+        from openword.filters import sense_is_proper_noun
+
+        assert sense_is_proper_noun({"id": "Paris", "pos": "NAM"})
+        assert sense_is_proper_noun({"id": "Paris", "pos": "proper"})
+        assert not sense_is_proper_noun({"id": "cat", "pos": "NOU"})
+
+    def test_is_proper_noun_flag(self):
+        """Nouns whose headword line is {{en-prop}} keep pos NOU but set the flag."""
+        from openword.filters import sense_is_proper_noun
+
+        assert sense_is_proper_noun({"id": "Xerox", "pos": "NOU", "is_proper_noun": True})
+        assert not sense_is_proper_noun({"id": "xerox", "pos": "NOU", "is_proper_noun": False})